@@ -121,7 +121,7 @@ async function buyTokens() {
 
   try {
     const tx = await program.methods
-      .buyTokens(solAmountLamports)
+      .buyTokens(solAmountLamports, 0, null, null, null, null)
       .accounts({
         buyer: buyerKeypair.publicKey,
         bondingCurve: bondingCurvePda,
@@ -1,7 +1,7 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { PublicKey, Keypair, SystemProgram, SYSVAR_RENT_PUBKEY, Connection } from "@solana/web3.js";
-import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID, getAssociatedTokenAddressSync } from "@solana/spl-token";
 import fs from "fs";
 import path from "path";
 
@@ -41,6 +41,25 @@ async function createTokenWithBondingCurve() {
     program.programId
   );
 
+  // New curves are validated against the protocol's global config, which also names the creation fee recipient
+  const [globalConfigPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("global_config")],
+    program.programId
+  );
+  const globalConfig: any = await (program.account as any).globalConfig.fetch(globalConfigPda);
+
+  // Each curve gets the next index in its creator's curve registry, created with their first curve
+  const [curveRegistryPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("curve_registry"), creatorKeypair.publicKey.toBuffer()],
+    program.programId
+  );
+  const curveRegistry: any = await (program.account as any).curveRegistry.fetchNullable(curveRegistryPda);
+  const curveCount = curveRegistry ? curveRegistry.curveCount : new anchor.BN(0);
+  const [registryEntryPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("curve_registry_entry"), creatorKeypair.publicKey.toBuffer(), curveCount.toArrayLike(Buffer, "le", 8)],
+    program.programId
+  );
+
   console.log("Bonding Curve PDA:", bondingCurvePda.toString());
   console.log("SOL Vault PDA:", solVaultPda.toString());
 
@@ -49,20 +68,61 @@ async function createTokenWithBondingCurve() {
   const slope = new anchor.BN(100); // Price increases by 100 lamports per token
   const name = "Test Token";
   const symbol = "TEST";
+  const uri = ""; // Off-chain metadata JSON, e.g. "https://example.com/token.json"
+  const curveType = { linear: {} }; // or { linearThenFlat: {} } to stop the price rising past flatThreshold
+  const flatThreshold = null; // Required for linearThenFlat curves, e.g. new anchor.BN(1_000_000), and
+  // constantProduct ones ({ constantProduct: {} }), where it is the virtual token reserve
+  const graduationMetric = { solRaised: {} }; // or { marketCap: {} } with a non-zero threshold
+  const graduationThreshold = null; // null = default SOL target
+  const salt = null; // A value adds a seed to the bonding curve PDA
+  const maxSupply = null; // A value caps the supply for a fixed-supply launch
+  const decimals = null; // e.g. 6 or 9; null creates a 0-decimal mint
+  const initialBuyLamports = null; // e.g. new anchor.BN(100_000_000) for a 0.1 SOL dev buy in the same instruction
+  const quoteMint: PublicKey | null = null; // e.g. the USDC mint to price the curve in USDC instead of SOL
+  const sellTaxRamp = null; // e.g. { initialSellTaxBps: 1000, finalSellTaxBps: 0, decaySlots: new anchor.BN(216_000) }
+  const dust = null; // e.g. { thresholdLamports: new anchor.BN(1000), policy: { sweepToFees: {} } }
+  const salePhases: any[] = []; // e.g. [{ kind: { presale: {} }, startTs, maxBuyLamports, supplyCap, feeBpsOverride: null, priceMultiplierBps: 9000 }, ...]
 
   console.log("\nInitializing bonding curve...");
 
   try {
     const tx = await program.methods
-      .initializeBondingCurve(initialPrice, slope, name, symbol)
+      .initializeBondingCurve(initialPrice, slope, name, symbol, uri, curveType, {
+        flatThreshold,
+        salePhases,
+        graduationMetric,
+        graduationThreshold,
+        salt,
+        maxSupply,
+        dust,
+        decimals,
+        initialBuyLamports,
+        quoteMint,
+        sellTaxRamp,
+      })
       .accounts({
         creator: creatorKeypair.publicKey,
         tokenMint: tokenMintKeypair.publicKey,
         bondingCurve: bondingCurvePda,
         solVault: solVaultPda,
+        globalConfig: globalConfigPda,
+        feeRecipient: globalConfig.feeRecipient,
         tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
         rent: SYSVAR_RENT_PUBKEY,
+        curveRegistry: curveRegistryPda,
+        registryEntry: registryEntryPda,
+        // The dev buy mints into the creator's associated token account
+        creatorTokenAccount: initialBuyLamports
+          ? getAssociatedTokenAddressSync(tokenMintKeypair.publicKey, creatorKeypair.publicKey)
+          : null,
+        associatedTokenProgram: initialBuyLamports ? ASSOCIATED_TOKEN_PROGRAM_ID : null,
+        // Quote-token curves hold their reserves in a token account owned by the curve
+        quoteTokenMint: quoteMint,
+        quoteVault: quoteMint
+          ? PublicKey.findProgramAddressSync([Buffer.from("quote_vault"), tokenMintKeypair.publicKey.toBuffer()], program.programId)[0]
+          : null,
+        quoteTokenProgram: quoteMint ? TOKEN_PROGRAM_ID : null,
       })
       .signers([creatorKeypair, tokenMintKeypair])
       .rpc();
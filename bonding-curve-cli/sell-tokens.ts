@@ -140,7 +140,7 @@ async function sellTokens() {
 
   try {
     const tx = await program.methods
-      .sellTokens(tokenAmountBN)
+      .sellTokens(tokenAmountBN, new anchor.BN(0), new anchor.BN(0), null, null)
       .accounts({
         seller: sellerKeypair.publicKey,
         bondingCurve: bondingCurvePda,
@@ -0,0 +1,87 @@
+//! Decoders for the program's accounts
+//!
+//! Each takes the raw account data as fetched from RPC, discriminator
+//! included, and fails if the data belongs to a different account type.
+
+use anchor_lang::{AccountDeserialize, Result};
+use bonding_curve_program::{
+    Airdrop, AirdropReceipt, BlacklistEntry, BondingCurve, CreatorVesting, CurveRegistry,
+    CurveRegistryEntry, CurveStats, GlobalConfig, HolderRewards, LimitOrder, PriceObservations,
+    TradingSession, UserTradeState,
+};
+
+/// Decode any of the program's accounts
+pub fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    let mut data = data;
+    T::try_deserialize(&mut data)
+}
+
+/// Decode a bonding curve's state
+pub fn bonding_curve(data: &[u8]) -> Result<BondingCurve> {
+    decode(data)
+}
+
+/// Decode the protocol's global config
+pub fn global_config(data: &[u8]) -> Result<GlobalConfig> {
+    decode(data)
+}
+
+/// Decode a trader's per-curve trade state
+pub fn user_trade_state(data: &[u8]) -> Result<UserTradeState> {
+    decode(data)
+}
+
+/// Decode a creator allocation lockup
+pub fn creator_vesting(data: &[u8]) -> Result<CreatorVesting> {
+    decode(data)
+}
+
+/// Decode a curve's price history ring buffer
+pub fn price_observations(data: &[u8]) -> Result<PriceObservations> {
+    decode(data)
+}
+
+/// Decode a curve's cumulative trade statistics
+pub fn curve_stats(data: &[u8]) -> Result<CurveStats> {
+    decode(data)
+}
+
+/// Decode a curve's Merkle airdrop
+pub fn airdrop(data: &[u8]) -> Result<Airdrop> {
+    decode(data)
+}
+
+/// Decode an airdrop claim receipt
+pub fn airdrop_receipt(data: &[u8]) -> Result<AirdropReceipt> {
+    decode(data)
+}
+
+/// Decode a resting limit order
+pub fn limit_order(data: &[u8]) -> Result<LimitOrder> {
+    decode(data)
+}
+
+/// Decode a curve's blacklist entry
+pub fn blacklist_entry(data: &[u8]) -> Result<BlacklistEntry> {
+    decode(data)
+}
+
+/// Decode a holder's reward checkpoint
+pub fn holder_rewards(data: &[u8]) -> Result<HolderRewards> {
+    decode(data)
+}
+
+/// Decode the curve registry
+pub fn curve_registry(data: &[u8]) -> Result<CurveRegistry> {
+    decode(data)
+}
+
+/// Decode a curve's registry entry
+pub fn curve_registry_entry(data: &[u8]) -> Result<CurveRegistryEntry> {
+    decode(data)
+}
+
+/// Decode a trading session
+pub fn trading_session(data: &[u8]) -> Result<TradingSession> {
+    decode(data)
+}
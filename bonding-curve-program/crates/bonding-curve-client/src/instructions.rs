@@ -0,0 +1,382 @@
+//! Instruction builders
+//!
+//! Each instruction comes in two parts: a `*_accounts` function filling in
+//! every required account from the mint and wallet, with all optional
+//! accounts left as `None`, and a builder turning those accounts plus the
+//! instruction's arguments into an [`Instruction`]. Callers that need an
+//! optional account (eligibility record, referral, quote-token accounts,
+//! price observations, ...) set it on the accounts struct in between.
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token::spl_token::{self, native_mint};
+use bonding_curve_program::{accounts, instruction, pda, ID};
+
+pub use bonding_curve_program::instruction::InitializeBondingCurve as InitializeArgs;
+pub use bonding_curve_program::{BuyLeg, CurveInitArgs};
+
+/// Curve PDA of a mint; salt 0 is the plain mint-derived address
+pub fn curve_address(token_mint: &Pubkey, salt: u64) -> Pubkey {
+    pda::salted_bonding_curve_address(token_mint, salt).0
+}
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Accounts of initialize_bonding_curve for a fresh mint keypair;
+/// `registry_index` is the creator's curve registry's current curve_count (0 for their first curve)
+pub fn initialize_bonding_curve_accounts(
+    creator: &Pubkey,
+    token_mint: &Pubkey,
+    salt: u64,
+    fee_recipient: &Pubkey,
+    token_program: &Pubkey,
+    registry_index: u64,
+) -> accounts::InitializeBondingCurve {
+    accounts::InitializeBondingCurve {
+        creator: *creator,
+        token_mint: *token_mint,
+        bonding_curve: curve_address(token_mint, salt),
+        sol_vault: pda::sol_vault_address(token_mint).0,
+        fee_vault: pda::fee_vault_address(token_mint).0,
+        metadata: pda::metadata_address(token_mint).0,
+        global_config: pda::global_config_address().0,
+        fee_recipient: *fee_recipient,
+        token_program: *token_program,
+        token_metadata_program: anchor_spl::metadata::ID,
+        system_program: system_program::ID,
+        rent: sysvar::rent::ID,
+        curve_registry: pda::curve_registry_address(creator).0,
+        registry_entry: pda::curve_registry_entry_address(creator, registry_index).0,
+        protocol_stats: None,
+        creator_token_account: None,
+        associated_token_program: None,
+        quote_token_mint: None,
+        quote_vault: None,
+        quote_token_program: None,
+    }
+}
+
+/// initialize_bonding_curve; the mint keypair must sign alongside the creator
+pub fn initialize_bonding_curve(
+    accounts: accounts::InitializeBondingCurve,
+    args: InitializeArgs,
+) -> Instruction {
+    build(accounts, args)
+}
+
+/// Accounts of buy_tokens and buy_exact_tokens, minting into the buyer's associated token account
+pub fn buy_accounts(
+    buyer: &Pubkey,
+    token_mint: &Pubkey,
+    salt: u64,
+    token_program: &Pubkey,
+) -> accounts::BuyTokens {
+    accounts::BuyTokens {
+        buyer: *buyer,
+        bonding_curve: curve_address(token_mint, salt),
+        token_mint: *token_mint,
+        buyer_token_account: get_associated_token_address_with_program_id(
+            buyer,
+            token_mint,
+            token_program,
+        ),
+        sol_vault: pda::sol_vault_address(token_mint).0,
+        fee_vault: pda::fee_vault_address(token_mint).0,
+        global_config: pda::global_config_address().0,
+        token_program: *token_program,
+        associated_token_program: anchor_spl::associated_token::ID,
+        system_program: system_program::ID,
+        rent: sysvar::rent::ID,
+        eligibility_record: None,
+        referral: None,
+        referrer_nft_account: None,
+        charity: None,
+        user_trade_state: None,
+        protocol_stats: None,
+        quote_vault: None,
+        buyer_quote_account: None,
+        quote_token_mint: None,
+        quote_token_program: None,
+        price_observations: None,
+        curve_stats: None,
+        instructions_sysvar: None,
+        session: None,
+        owner_token_account: None,
+        buyer_mark: None,
+        trade_nonces: None,
+    }
+}
+
+/// Buy accounts counting the buy in the curve's trade stats, which every buy
+/// must once the curve has them; `trader` is the buyer, or the session owner
+/// of a session buy
+pub fn with_buy_stats(accounts: accounts::BuyTokens, trader: &Pubkey) -> accounts::BuyTokens {
+    accounts::BuyTokens {
+        curve_stats: Some(pda::curve_stats_address(&accounts.bonding_curve).0),
+        buyer_mark: Some(pda::buyer_mark_address(&accounts.bonding_curve, trader).0),
+        ..accounts
+    }
+}
+
+/// Buy accounts of the session key `authority` buying for `owner`: the buy is
+/// paid from the session deposit, minted to `owner`'s associated token account
+/// and consumes a nonce of `owner`'s TradeNonces
+pub fn with_buy_session(
+    accounts: accounts::BuyTokens,
+    owner: &Pubkey,
+    token_program: &Pubkey,
+) -> accounts::BuyTokens {
+    accounts::BuyTokens {
+        session: Some(pda::session_address(owner, &accounts.buyer).0),
+        owner_token_account: Some(get_associated_token_address_with_program_id(
+            owner,
+            &accounts.token_mint,
+            token_program,
+        )),
+        trade_nonces: Some(pda::trade_nonces_address(owner).0),
+        ..accounts
+    }
+}
+
+/// buy_tokens: spend sol_amount lamports on the curve; session buys need a nonce
+pub fn buy_tokens(
+    accounts: accounts::BuyTokens,
+    sol_amount: u64,
+    flags: u8,
+    max_entry_price: Option<u64>,
+    min_tokens_out: Option<u64>,
+    tolerance_bps: Option<u16>,
+    nonce: Option<u64>,
+) -> Instruction {
+    build(
+        accounts,
+        instruction::BuyTokens {
+            sol_amount,
+            flags,
+            max_entry_price,
+            min_tokens_out,
+            tolerance_bps,
+            nonce,
+        },
+    )
+}
+
+/// buy_exact_tokens: buy token_amount tokens, paying at most max_sol_in; flags are BUY_FLAG_* bits
+pub fn buy_exact_tokens(
+    accounts: accounts::BuyTokens,
+    token_amount: u64,
+    max_sol_in: u64,
+    flags: u8,
+) -> Instruction {
+    build(
+        accounts,
+        instruction::BuyExactTokens {
+            token_amount,
+            max_sol_in,
+            flags,
+        },
+    )
+}
+
+/// buy_tokens_multi: one buy per (accounts, leg) pair, all in one atomic instruction;
+/// every leg's accounts must name the same buyer
+pub fn buy_tokens_multi(buyer: &Pubkey, legs: Vec<(accounts::BuyTokens, BuyLeg)>) -> Instruction {
+    let mut metas = accounts::BuyTokensMulti { buyer: *buyer }.to_account_metas(None);
+    let mut args = Vec::with_capacity(legs.len());
+    for (leg_accounts, leg) in legs {
+        metas.extend(leg_accounts.to_account_metas(None));
+        args.push(leg);
+    }
+    Instruction {
+        program_id: ID,
+        accounts: metas,
+        data: instruction::BuyTokensMulti { legs: args }.data(),
+    }
+}
+
+/// Accounts of sell_tokens, burning from the seller's associated token account
+pub fn sell_accounts(
+    seller: &Pubkey,
+    token_mint: &Pubkey,
+    salt: u64,
+    token_program: &Pubkey,
+) -> accounts::SellTokens {
+    accounts::SellTokens {
+        seller: *seller,
+        bonding_curve: curve_address(token_mint, salt),
+        token_mint: *token_mint,
+        seller_token_account: get_associated_token_address_with_program_id(
+            seller,
+            token_mint,
+            token_program,
+        ),
+        sol_vault: pda::sol_vault_address(token_mint).0,
+        fee_vault: pda::fee_vault_address(token_mint).0,
+        token_program: *token_program,
+        system_program: system_program::ID,
+        eligibility_record: None,
+        charity: None,
+        user_trade_state: None,
+        protocol_stats: None,
+        quote_vault: None,
+        seller_quote_account: None,
+        quote_token_mint: None,
+        quote_token_program: None,
+        price_observations: None,
+        curve_stats: None,
+        instructions_sysvar: None,
+        blacklist_entry: None,
+        wsol_mint: None,
+        seller_wsol_account: None,
+        wsol_token_program: None,
+        associated_token_program: None,
+        session: None,
+        trade_nonces: None,
+    }
+}
+
+/// Sell accounts of the session key `authority` selling for `owner`: the tokens
+/// burn from `owner`'s associated token account, on which the owner must have
+/// approved the session key as delegate, the proceeds go to the session deposit
+/// and a nonce of `owner`'s TradeNonces is consumed
+pub fn with_sell_session(
+    accounts: accounts::SellTokens,
+    owner: &Pubkey,
+    token_program: &Pubkey,
+) -> accounts::SellTokens {
+    accounts::SellTokens {
+        seller_token_account: get_associated_token_address_with_program_id(
+            owner,
+            &accounts.token_mint,
+            token_program,
+        ),
+        session: Some(pda::session_address(owner, &accounts.seller).0),
+        trade_nonces: Some(pda::trade_nonces_address(owner).0),
+        ..accounts
+    }
+}
+
+/// Sell accounts paying the proceeds into the seller's wSOL associated token
+/// account, for sell_tokens with settle_as_wsol
+pub fn with_wsol_settlement(accounts: accounts::SellTokens) -> accounts::SellTokens {
+    accounts::SellTokens {
+        wsol_mint: Some(native_mint::ID),
+        seller_wsol_account: Some(get_associated_token_address_with_program_id(
+            &accounts.seller,
+            &native_mint::ID,
+            &spl_token::ID,
+        )),
+        wsol_token_program: Some(spl_token::ID),
+        associated_token_program: Some(anchor_spl::associated_token::ID),
+        ..accounts
+    }
+}
+
+/// sell_tokens: sell token_amount tokens back to the curve; session sales need a nonce
+pub fn sell_tokens(
+    accounts: accounts::SellTokens,
+    token_amount: u64,
+    min_exit_price: Option<u64>,
+    min_sol_out: Option<u64>,
+    settle_as_wsol: Option<bool>,
+    nonce: Option<u64>,
+) -> Instruction {
+    build(
+        accounts,
+        instruction::SellTokens {
+            token_amount,
+            min_exit_price,
+            min_sol_out,
+            settle_as_wsol,
+            nonce,
+        },
+    )
+}
+
+/// create_session: let `authority` trade for `owner`, funding the session with deposit_lamports;
+/// its buys and sells then go through with_buy_session and with_sell_session
+pub fn create_session(
+    owner: &Pubkey,
+    authority: &Pubkey,
+    expiry_slot: u64,
+    max_sol_per_trade: u64,
+    deposit_lamports: u64,
+) -> Instruction {
+    build(
+        accounts::CreateSession {
+            owner: *owner,
+            session: pda::session_address(owner, authority).0,
+            system_program: system_program::ID,
+        },
+        instruction::CreateSession {
+            authority: *authority,
+            expiry_slot,
+            max_sol_per_trade,
+            deposit_lamports,
+        },
+    )
+}
+
+/// revoke_session: signed by the owner or the session key; the deposit goes back to the owner
+pub fn revoke_session(signer: &Pubkey, owner: &Pubkey, authority: &Pubkey) -> Instruction {
+    build(
+        accounts::RevokeSession {
+            signer: *signer,
+            owner: *owner,
+            session: pda::session_address(owner, authority).0,
+        },
+        instruction::RevokeSession {},
+    )
+}
+
+/// initialize_trade_nonces: create `user`'s nonce window, needed before their first session trade
+pub fn initialize_trade_nonces(user: &Pubkey) -> Instruction {
+    build(
+        accounts::InitializeTradeNonces {
+            user: *user,
+            trade_nonces: pda::trade_nonces_address(user).0,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeTradeNonces {},
+    )
+}
+
+/// consume_trade_nonce: burn one of `user`'s nonces, cancelling a session trade signed with it
+pub fn consume_trade_nonce(user: &Pubkey, nonce: u64) -> Instruction {
+    build(
+        accounts::ConsumeTradeNonce {
+            user: *user,
+            trade_nonces: pda::trade_nonces_address(user).0,
+        },
+        instruction::ConsumeTradeNonce { nonce },
+    )
+}
+
+/// quote_buy: simulate to read the TradeQuote from return data
+pub fn quote_buy(bonding_curve: &Pubkey, sol_amount: u64) -> Instruction {
+    build(
+        accounts::GetPrice {
+            bonding_curve: *bonding_curve,
+        },
+        instruction::QuoteBuy { sol_amount },
+    )
+}
+
+/// quote_sell: simulate to read the TradeQuote from return data
+pub fn quote_sell(bonding_curve: &Pubkey, token_amount: u64) -> Instruction {
+    build(
+        accounts::GetPrice {
+            bonding_curve: *bonding_curve,
+        },
+        instruction::QuoteSell { token_amount },
+    )
+}
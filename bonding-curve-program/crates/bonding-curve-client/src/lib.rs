@@ -0,0 +1,18 @@
+//! Off-chain client for the bonding curve program
+//!
+//! Builds the program's instructions, derives its PDAs, decodes its accounts
+//! and quotes trades with the program's own curve math, so services and
+//! tests never drift from what the program computes on-chain.
+//!
+//! - [`instructions`]: typed builders for initialize, buy, sell and quotes
+//! - [`accounts`]: account decoders
+//! - [`math`]: curve pricing, shared with the program
+//! - [`replay`]: rebuilds a curve's history from its events
+//! - [`pda`] and [`layout`]: re-exported from the program crate
+
+pub mod accounts;
+pub mod instructions;
+pub mod math;
+pub mod replay;
+
+pub use bonding_curve_program::{layout, pda, ID};
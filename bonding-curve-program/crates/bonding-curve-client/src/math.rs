@@ -0,0 +1,55 @@
+//! Curve math, computed exactly as the program does
+//!
+//! These call the program crate's own pricing functions rather than a
+//! reimplementation, so quotes match on-chain results to the lamport for
+//! every curve type. Amounts are in base units and lamports.
+
+use anchor_lang::Result;
+use bonding_curve_program::{
+    calculate_fee, calculate_sol_for_curve, calculate_sol_for_curve_ceil, calculate_tokens_for_curve,
+    BondingCurve, CurveParams,
+};
+
+/// Tokens that `sol_amount` buys on the curve starting at `supply`, before fees
+pub fn tokens_for_sol(params: &CurveParams, supply: u64, sol_amount: u64) -> Result<u64> {
+    calculate_tokens_for_curve(params, supply, sol_amount)
+}
+
+/// Lamports that `token_amount` tokens sitting just above `from_supply` are worth, before fees
+pub fn sol_for_tokens(params: &CurveParams, token_amount: u64, from_supply: u64) -> Result<u64> {
+    calculate_sol_for_curve(params, token_amount, from_supply)
+}
+
+/// Lamports minting `token_amount` tokens from `supply` costs before fees,
+/// rounded up as buy_exact_tokens charges it
+pub fn cost_for_tokens(params: &CurveParams, token_amount: u64, supply: u64) -> Result<u64> {
+    calculate_sol_for_curve_ceil(params, token_amount, supply)
+}
+
+/// Spot price in lamports per whole token at `supply`
+pub fn spot_price(params: &CurveParams, supply: u64) -> Result<u64> {
+    params.price_at_supply(supply)
+}
+
+/// Fee the program charges on `amount` at `fee_bps`, rounded as on-chain
+pub fn fee(amount: u64, fee_bps: u16) -> Result<u64> {
+    calculate_fee(amount, fee_bps)
+}
+
+/// Tokens a plain buy of `sol_amount` gets from a curve right now, outside
+/// any sale phase or fee bootstrap window: the trading fee and charity share
+/// are deducted first, as buy_tokens does
+pub fn buy_tokens_out(curve: &BondingCurve, sol_amount: u64) -> Result<u64> {
+    let fees = fee(sol_amount, curve.trade_fee_bps(None))? + fee(sol_amount, curve.charity_bps)?;
+    tokens_for_sol(&curve.params(), curve.current_supply, sol_amount.saturating_sub(fees))
+}
+
+/// Lamports a plain sale of `token_amount` pays the seller at `slot`,
+/// outside any sale phase: the sell fee (including any sell tax ramp) and
+/// charity share are withheld, as sell_tokens does
+pub fn sell_sol_out(curve: &BondingCurve, token_amount: u64, slot: u64) -> Result<u64> {
+    let from_supply = curve.current_supply.saturating_sub(token_amount);
+    let gross = sol_for_tokens(&curve.params(), token_amount, from_supply)?;
+    let fees = fee(gross, curve.sell_fee_bps(slot, None))? + fee(gross, curve.charity_bps)?;
+    Ok(gross.saturating_sub(fees))
+}
@@ -0,0 +1,383 @@
+//! Replaying a curve's history from its events
+//!
+//! Feeds the events a curve emitted, oldest first, through the program's own
+//! curve math and records the supply and reserves after each one, flagging
+//! every event the math disagrees with. Callers fetch the curve's
+//! transactions (getSignaturesForAddress, then getTransaction) and pass each
+//! one's log messages to [`Replay::apply_logs`]; the finished replay's
+//! trades and end state make a deterministic test fixture.
+//!
+//! Compact events carry neither the fee split nor the sell tax, so their
+//! reserve moves are the curve's own price for the tokens, and a taxed
+//! compact sell shows up as a price divergence. Trades priced under a sale
+//! phase's multiplier show up as divergences too, since the replay prices
+//! off the plain curve.
+
+use std::collections::BTreeMap;
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{Event, Result};
+use base64::Engine;
+use bonding_curve_program::{
+    AirdropClaimed, CompactTrade, CreatorAllocationCreated, CurveParams, ExitClaimSettled,
+    ExitQueued, LimitOrderExecuted, MmTradeExecuted, OrderSide, TokensBurnedPermanently,
+    TokensPurchased, TokensSold,
+};
+
+use crate::math;
+
+/// Prefix of the log lines emit! writes events to
+const PROGRAM_DATA: &str = "Program data: ";
+
+/// The events that move a curve's supply or reserves, or annotate a trade
+/// that does
+pub enum CurveEvent {
+    TokensPurchased(TokensPurchased),
+    TokensSold(TokensSold),
+    TokensBurnedPermanently(TokensBurnedPermanently),
+    AirdropClaimed(AirdropClaimed),
+    CompactTrade(CompactTrade),
+    CreatorAllocationCreated(CreatorAllocationCreated),
+    ExitQueued(ExitQueued),
+    ExitClaimSettled(ExitClaimSettled),
+    LimitOrderExecuted(LimitOrderExecuted),
+    MmTradeExecuted(MmTradeExecuted),
+}
+
+fn decode_as<T: Event>(data: &[u8]) -> Option<T> {
+    let mut body = data.strip_prefix(T::DISCRIMINATOR)?;
+    T::deserialize(&mut body).ok()
+}
+
+impl CurveEvent {
+    /// Decode an event from its bytes, discriminator included; None for
+    /// any other event
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        decode_as(data)
+            .map(Self::TokensPurchased)
+            .or_else(|| decode_as(data).map(Self::TokensSold))
+            .or_else(|| decode_as(data).map(Self::TokensBurnedPermanently))
+            .or_else(|| decode_as(data).map(Self::AirdropClaimed))
+            .or_else(|| decode_as(data).map(Self::CompactTrade))
+            .or_else(|| decode_as(data).map(Self::CreatorAllocationCreated))
+            .or_else(|| decode_as(data).map(Self::ExitQueued))
+            .or_else(|| decode_as(data).map(Self::ExitClaimSettled))
+            .or_else(|| decode_as(data).map(Self::LimitOrderExecuted))
+            .or_else(|| decode_as(data).map(Self::MmTradeExecuted))
+    }
+
+    /// Curve the event belongs to
+    pub fn bonding_curve(&self) -> Pubkey {
+        match self {
+            Self::TokensPurchased(event) => event.bonding_curve,
+            Self::TokensSold(event) => event.bonding_curve,
+            Self::TokensBurnedPermanently(event) => event.bonding_curve,
+            Self::AirdropClaimed(event) => event.bonding_curve,
+            Self::CompactTrade(event) => event.bonding_curve,
+            Self::CreatorAllocationCreated(event) => event.bonding_curve,
+            Self::ExitQueued(event) => event.bonding_curve,
+            Self::ExitClaimSettled(event) => event.bonding_curve,
+            Self::LimitOrderExecuted(event) => event.bonding_curve,
+            Self::MmTradeExecuted(event) => event.bonding_curve,
+        }
+    }
+}
+
+/// The replayed events in a transaction's log messages, in emission order
+pub fn events_from_logs<S: AsRef<str>>(logs: &[S]) -> Vec<CurveEvent> {
+    logs.iter()
+        .filter_map(|line| line.as_ref().strip_prefix(PROGRAM_DATA))
+        .filter_map(|data| base64::engine::general_purpose::STANDARD.decode(data).ok())
+        .filter_map(|data| CurveEvent::decode(&data))
+        .collect()
+}
+
+/// What a replayed event did
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TradeKind {
+    Buy,
+    Sell,
+    /// A buy filled from a limit order
+    LimitBuy,
+    /// A sale filled from a limit order
+    LimitSell,
+    Burn,
+    Airdrop,
+    CreatorAllocation,
+    /// Tokens burned into the exit queue; the reserves pay out at settlement
+    ExitQueued,
+    ExitSettled,
+    /// Trades against the market-maker vault, which move neither supply nor reserves
+    MmBuy,
+    MmSell,
+}
+
+/// One replayed event and the curve state after it
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReplayedTrade {
+    pub signature: String,
+    pub slot: u64,
+    pub kind: TradeKind,
+    pub trader: Pubkey,
+    /// Tokens minted, sold or burned, sell tax excluded
+    pub tokens: u64,
+    /// Tokens burned as sell tax on top of `tokens`
+    pub tax_tokens: u64,
+    /// Lamports paid in (buys) or the gross payout (sells), fees included
+    pub sol: u64,
+    pub fee: u64,
+    pub supply_after: u64,
+    pub reserves_after: u64,
+}
+
+/// An event the replayed state disagrees with
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Divergence {
+    pub signature: String,
+    pub slot: u64,
+    /// What disagreed: supply, price, payout, reserves, exit_claim or limit_fill
+    pub field: &'static str,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+/// A curve's history, replayed event by event
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub bonding_curve: Pubkey,
+    pub params: CurveParams,
+    pub supply: u64,
+    pub sol_reserves: u64,
+    pub trades: Vec<ReplayedTrade>,
+    pub divergences: Vec<Divergence>,
+    /// Gross payouts of exits queued during the replay, by queue position
+    pub queued_exits: BTreeMap<u64, u64>,
+}
+
+impl Replay {
+    /// Start replaying `bonding_curve` from `supply` and `sol_reserves`;
+    /// both are 0 for a curve's whole history
+    pub fn new(bonding_curve: Pubkey, params: CurveParams, supply: u64, sol_reserves: u64) -> Self {
+        Self {
+            bonding_curve,
+            params,
+            supply,
+            sol_reserves,
+            trades: Vec::new(),
+            divergences: Vec::new(),
+            queued_exits: BTreeMap::new(),
+        }
+    }
+
+    /// Replay the events in one transaction's log messages
+    pub fn apply_logs<S: AsRef<str>>(&mut self, signature: &str, slot: u64, logs: &[S]) -> Result<()> {
+        for event in events_from_logs(logs) {
+            self.apply(signature, slot, &event)?;
+        }
+        Ok(())
+    }
+
+    /// Replay one event; events of other curves are ignored
+    pub fn apply(&mut self, signature: &str, slot: u64, event: &CurveEvent) -> Result<()> {
+        if event.bonding_curve() != self.bonding_curve {
+            return Ok(());
+        }
+        let mut trade = ReplayedTrade {
+            signature: signature.to_string(),
+            slot,
+            kind: TradeKind::Buy,
+            trader: Pubkey::default(),
+            tokens: 0,
+            tax_tokens: 0,
+            sol: 0,
+            fee: 0,
+            supply_after: 0,
+            reserves_after: 0,
+        };
+        let mut found = Vec::new();
+        let mut diverge = |field, expected, actual| {
+            if expected != actual {
+                found.push((field, expected, actual));
+            }
+        };
+
+        match event {
+            CurveEvent::TokensPurchased(event) => {
+                let supply_before = event.new_supply.saturating_sub(event.tokens_minted);
+                diverge("supply", self.supply, supply_before);
+                diverge("price", math::spot_price(&self.params, event.new_supply)?, event.new_price);
+                // Buys credit at least what the curve charges for the tokens, rounding favouring the curve
+                let cost = math::sol_for_tokens(&self.params, event.tokens_minted, supply_before)?;
+                diverge("reserves", cost, cost.min(event.sol_to_reserves));
+
+                self.supply = event.new_supply;
+                self.sol_reserves = self.sol_reserves.saturating_add(event.sol_to_reserves);
+                trade.trader = event.buyer;
+                trade.tokens = event.tokens_minted;
+                trade.sol = event.sol_spent;
+                trade.fee = event.fee_lamports;
+            }
+            CurveEvent::TokensSold(event) => {
+                let supply_before = event.new_supply + event.tokens_burned + event.tax_tokens_burned;
+                diverge("supply", self.supply, supply_before);
+                diverge("price", math::spot_price(&self.params, event.new_supply)?, event.new_price);
+                // The seller, fee and charity shares always add back up to the gross payout
+                let gross = event.sol_received + event.fee_lamports + event.charity_lamports;
+                let expected = math::sol_for_tokens(
+                    &self.params,
+                    event.tokens_burned,
+                    supply_before - event.tokens_burned,
+                )?;
+                diverge("payout", expected, gross);
+
+                self.supply = event.new_supply;
+                self.withdraw_reserves(gross, &mut diverge);
+                trade.kind = TradeKind::Sell;
+                trade.trader = event.seller;
+                trade.tokens = event.tokens_burned;
+                trade.tax_tokens = event.tax_tokens_burned;
+                trade.sol = gross;
+                trade.fee = event.fee_lamports;
+            }
+            CurveEvent::TokensBurnedPermanently(event) => {
+                // Burns shrink supply but leave the reserves untouched
+                diverge("supply", self.supply, event.new_supply + event.amount);
+                self.supply = event.new_supply;
+                trade.kind = TradeKind::Burn;
+                trade.trader = event.holder;
+                trade.tokens = event.amount;
+            }
+            CurveEvent::AirdropClaimed(event) => {
+                // Airdrops mint without SOL, so only the supply moves
+                diverge("supply", self.supply, event.new_supply.saturating_sub(event.amount));
+                self.supply = event.new_supply;
+                trade.kind = TradeKind::Airdrop;
+                trade.trader = event.claimant;
+                trade.tokens = event.amount;
+            }
+            CurveEvent::CompactTrade(event) => {
+                let (supply_after, curve_sol) = if event.is_buy {
+                    let supply_after = self.supply + event.token_amount;
+                    (supply_after, math::cost_for_tokens(&self.params, event.token_amount, self.supply)?)
+                } else {
+                    if event.token_amount > self.supply {
+                        diverge("supply", self.supply, event.token_amount);
+                    }
+                    let supply_after = self.supply.saturating_sub(event.token_amount);
+                    (supply_after, math::sol_for_tokens(&self.params, event.token_amount, supply_after)?)
+                };
+                diverge("price", math::spot_price(&self.params, supply_after)?, event.new_price);
+
+                self.supply = supply_after;
+                if event.is_buy {
+                    self.sol_reserves = self.sol_reserves.saturating_add(curve_sol);
+                } else {
+                    self.withdraw_reserves(curve_sol, &mut diverge);
+                    trade.kind = TradeKind::Sell;
+                }
+                trade.trader = event.trader;
+                trade.tokens = event.token_amount;
+                trade.sol = if event.is_buy { event.sol_amount } else { curve_sol };
+            }
+            CurveEvent::CreatorAllocationCreated(event) => {
+                // Priced like buy_exact_tokens: the reserves take the curve's rounded-up cost
+                let cost = math::cost_for_tokens(&self.params, event.amount, self.supply)?;
+                self.supply += event.amount;
+                self.sol_reserves = self.sol_reserves.saturating_add(cost);
+                trade.kind = TradeKind::CreatorAllocation;
+                trade.trader = event.beneficiary;
+                trade.tokens = event.amount;
+                trade.sol = event.sol_paid;
+                trade.fee = event.sol_paid.saturating_sub(cost);
+            }
+            CurveEvent::ExitQueued(event) => {
+                // Supply drops now; the reserves only drop once the claim settles
+                if event.tokens_burned > self.supply {
+                    diverge("supply", self.supply, event.tokens_burned);
+                }
+                self.supply = self.supply.saturating_sub(event.tokens_burned);
+                let gross = event.sol_owed + event.fee_lamports;
+                self.queued_exits.insert(event.position, gross);
+                trade.kind = TradeKind::ExitQueued;
+                trade.trader = event.seller;
+                trade.tokens = event.tokens_burned;
+                trade.sol = gross;
+                trade.fee = event.fee_lamports;
+            }
+            CurveEvent::ExitClaimSettled(event) => {
+                let gross = event.sol_received + event.fee_lamports;
+                // Exits queued before the replay started have nothing to check against
+                if let Some(queued) = self.queued_exits.remove(&event.position) {
+                    diverge("exit_claim", queued, gross);
+                }
+                self.withdraw_reserves(gross, &mut diverge);
+                trade.kind = TradeKind::ExitSettled;
+                trade.trader = event.seller;
+                trade.sol = gross;
+                trade.fee = event.fee_lamports;
+            }
+            CurveEvent::LimitOrderExecuted(event) => {
+                // The fill's own TokensPurchased / TokensSold came just before; mark that trade
+                let fill = self
+                    .trades
+                    .last_mut()
+                    .filter(|last| last.signature == signature)
+                    .filter(|last| matches!(last.kind, TradeKind::Buy | TradeKind::Sell));
+                let (tokens, sol) = fill
+                    .as_ref()
+                    .map_or((0, 0), |fill| (fill.tokens + fill.tax_tokens, fill.sol));
+                diverge("limit_fill", event.tokens, tokens);
+                diverge("limit_fill", event.sol_amount, sol);
+                if let Some(fill) = fill {
+                    fill.kind = match event.side {
+                        OrderSide::Buy => TradeKind::LimitBuy,
+                        OrderSide::Sell => TradeKind::LimitSell,
+                    };
+                }
+            }
+            CurveEvent::MmTradeExecuted(event) => {
+                trade.kind = if event.is_buy { TradeKind::MmBuy } else { TradeKind::MmSell };
+                trade.trader = event.trader;
+                trade.tokens = event.token_amount;
+                trade.sol = event.sol_amount;
+            }
+        }
+
+        for (field, expected, actual) in found {
+            self.divergences.push(Divergence {
+                signature: signature.to_string(),
+                slot,
+                field,
+                expected,
+                actual,
+            });
+        }
+        if !matches!(event, CurveEvent::LimitOrderExecuted(_)) {
+            trade.supply_after = self.supply;
+            trade.reserves_after = self.sol_reserves;
+            self.trades.push(trade);
+        }
+        Ok(())
+    }
+
+    /// Compare the replayed supply with the live account's; with no upper
+    /// slot bound on the replay, the two must match exactly
+    pub fn check_live_supply(&mut self, current_supply: u64) {
+        if current_supply != self.supply {
+            self.divergences.push(Divergence {
+                signature: "live".to_string(),
+                slot: 0,
+                field: "supply",
+                expected: self.supply,
+                actual: current_supply,
+            });
+        }
+    }
+
+    fn withdraw_reserves(&mut self, lamports: u64, diverge: &mut impl FnMut(&'static str, u64, u64)) {
+        if lamports > self.sol_reserves {
+            diverge("reserves", self.sol_reserves, lamports);
+        }
+        self.sol_reserves = self.sol_reserves.saturating_sub(lamports);
+    }
+}
@@ -0,0 +1,106 @@
+//! Decoding accounts exactly as the program writes them
+
+use anchor_lang::error::{Error, ErrorCode};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{AccountDeserialize, AccountSerialize};
+use bonding_curve_client::accounts;
+use bonding_curve_program::{
+    BondingCurve, CurveType, GlobalConfig, LimitOrder, OrderSide, PriceObservation,
+    PriceObservations, UserTradeState, CURVE_VERSION,
+};
+
+/// Account data as the program leaves it: zero-initialised to `len`, edited,
+/// then serialized with its discriminator
+fn serialized<T: AccountDeserialize + AccountSerialize>(len: usize, edit: impl FnOnce(&mut T)) -> Vec<u8> {
+    let mut state = T::try_deserialize_unchecked(&mut &vec![0u8; len][..]).unwrap();
+    edit(&mut state);
+    let mut data = Vec::with_capacity(len);
+    state.try_serialize(&mut data).unwrap();
+    data.resize(len, 0);
+    data
+}
+
+fn is_code(error: Error, code: ErrorCode) -> bool {
+    error == Error::from(code)
+}
+
+#[test]
+fn accounts_decode_to_what_was_written() {
+    let (mint, creator) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let data = serialized::<BondingCurve>(BondingCurve::LEN, |curve| {
+        curve.token_mint = mint;
+        curve.creator = creator;
+        curve.curve_type = CurveType::Sigmoid;
+        curve.initial_price = 1_000_000;
+        curve.slope = 100_000;
+        curve.flat_threshold = 500_000_000_000;
+        curve.current_supply = 42_000_000;
+        curve.sol_reserves = 7_000;
+        curve.charity_bps = 25;
+        curve.version = CURVE_VERSION;
+    });
+    let curve = accounts::bonding_curve(&data).unwrap();
+    assert_eq!((curve.token_mint, curve.creator), (mint, creator));
+    assert_eq!(curve.curve_type, CurveType::Sigmoid);
+    assert_eq!(
+        (curve.initial_price, curve.slope, curve.flat_threshold),
+        (1_000_000, 100_000, 500_000_000_000)
+    );
+    assert_eq!((curve.current_supply, curve.sol_reserves), (42_000_000, 7_000));
+    assert_eq!((curve.charity_bps, curve.version), (25, CURVE_VERSION));
+
+    let admin = Pubkey::new_unique();
+    let data = serialized::<GlobalConfig>(GlobalConfig::LEN, |config| {
+        config.admin = admin;
+        config.protocol_fee_bps = 100;
+        config.max_slope = 1_000_000;
+    });
+    let config = accounts::global_config(&data).unwrap();
+    assert_eq!((config.admin, config.protocol_fee_bps, config.max_slope), (admin, 100, 1_000_000));
+
+    let user = Pubkey::new_unique();
+    let data = serialized::<UserTradeState>(UserTradeState::LEN, |state| {
+        state.user = user;
+        state.last_buy_ts = 1_700_000_000;
+        state.launch_tokens_bought = 9;
+        state.bump = 254;
+    });
+    let state = accounts::user_trade_state(&data).unwrap();
+    assert_eq!(
+        (state.user, state.last_buy_ts, state.launch_tokens_bought, state.bump),
+        (user, 1_700_000_000, 9, 254)
+    );
+
+    let data = serialized::<PriceObservations>(PriceObservations::LEN, |history| {
+        history.observations[3] = PriceObservation { slot: 11, price: 12, supply: 13 };
+        history.head = 4;
+        history.count = 4;
+    });
+    let history = accounts::price_observations(&data).unwrap();
+    let newest = history.observations[3];
+    assert_eq!((newest.slot, newest.price, newest.supply), (11, 12, 13));
+    assert_eq!((history.head, history.count), (4, 4));
+
+    let data = serialized::<LimitOrder>(LimitOrder::LEN, |order| {
+        order.side = OrderSide::Sell;
+        order.trigger_price = 5_000;
+        order.amount = 3;
+    });
+    let order = accounts::limit_order(&data).unwrap();
+    assert_eq!((order.side, order.trigger_price, order.amount), (OrderSide::Sell, 5_000, 3));
+}
+
+#[test]
+fn other_accounts_and_short_data_are_rejected() {
+    let data = serialized::<UserTradeState>(UserTradeState::LEN, |_| {});
+
+    let Err(error) = accounts::bonding_curve(&data) else {
+        panic!("a trade state decoded as a bonding curve");
+    };
+    assert!(is_code(error, ErrorCode::AccountDiscriminatorMismatch));
+    let Err(error) = accounts::user_trade_state(&data[..4]) else {
+        panic!("a truncated discriminator decoded");
+    };
+    assert!(is_code(error, ErrorCode::AccountDiscriminatorNotFound));
+    assert!(accounts::user_trade_state(&data[..UserTradeState::LEN - 1]).is_err());
+}
@@ -0,0 +1,68 @@
+//! Quoting trades off-chain with the program's own pricing
+
+use anchor_lang::AccountDeserialize;
+use bonding_curve_client::math;
+use bonding_curve_program::{
+    calculate_fee, calculate_tokens_for_curve, BondingCurve, CurveParams, CurveType,
+};
+
+const SOL: u64 = 1_000_000_000;
+
+fn curves() -> [CurveParams; 5] {
+    let params = |curve_type, initial_price, slope, flat_threshold| CurveParams {
+        curve_type,
+        initial_price,
+        slope,
+        flat_threshold,
+        decimals: 6,
+    };
+    [
+        params(CurveType::Linear, 100, 1, 0),
+        params(CurveType::LinearThenFlat, 100, 1, 500_000_000_000),
+        params(CurveType::Exponential, 1_000, 1_000_000, 0),
+        params(CurveType::Sigmoid, 1_000_000, 100_000, 500_000_000_000),
+        params(CurveType::ConstantProduct, 28, 0, 1_073_000_000_000_000),
+    ]
+}
+
+/// A curve with `params`, `supply` sold and the given trading fees
+fn curve(params: &CurveParams, supply: u64, fee_bps: u16, charity_bps: u16) -> BondingCurve {
+    let mut curve = BondingCurve::try_deserialize_unchecked(&mut &vec![0u8; BondingCurve::LEN][..]).unwrap();
+    curve.curve_type = params.curve_type;
+    curve.initial_price = params.initial_price;
+    curve.slope = params.slope;
+    curve.flat_threshold = params.flat_threshold;
+    curve.decimals = params.decimals;
+    curve.current_supply = supply;
+    curve.protocol_fee_bps = fee_bps;
+    curve.charity_bps = charity_bps;
+    curve
+}
+
+#[test]
+fn buys_price_what_is_left_after_fees() {
+    for params in curves() {
+        for (supply, sol) in [(0, SOL), (1_000_000_000, SOL / 3), (250_000_000_000, 7 * SOL)] {
+            let expected = calculate_tokens_for_curve(
+                &params,
+                supply,
+                sol - calculate_fee(sol, 100).unwrap() - calculate_fee(sol, 25).unwrap(),
+            )
+            .unwrap();
+            assert_eq!(
+                math::buy_tokens_out(&curve(&params, supply, 100, 25), sol).unwrap(),
+                expected,
+                "{:?}: {sol} lamports at {supply}",
+                params.curve_type
+            );
+
+            // A fully backed curve charges no trading fee, only the charity share
+            let mut backed = curve(&params, supply, 100, 25);
+            backed.fully_backed = true;
+            assert_eq!(
+                math::buy_tokens_out(&backed, sol).unwrap(),
+                calculate_tokens_for_curve(&params, supply, sol - calculate_fee(sol, 25).unwrap()).unwrap()
+            );
+        }
+    }
+}
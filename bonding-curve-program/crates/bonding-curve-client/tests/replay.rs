@@ -0,0 +1,189 @@
+//! Replaying curve histories from the log lines the program writes
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::Event;
+use base64::Engine;
+use bonding_curve_client::math;
+use bonding_curve_client::replay::{Replay, TradeKind};
+use bonding_curve_program::{
+    CompactTrade, CurveParams, CurveType, ExitClaimSettled, ExitQueued, LimitOrderExecuted,
+    MmTradeExecuted, OrderSide, TokensPurchased, TokensSold,
+};
+
+const PARAMS: CurveParams = CurveParams {
+    curve_type: CurveType::Linear,
+    initial_price: 1_000,
+    slope: 10,
+    flat_threshold: 0,
+    decimals: 6,
+};
+
+const TOKENS: u64 = 5_000_000;
+
+/// The log line emit! writes `event` to
+fn log(event: &impl Event) -> String {
+    format!(
+        "Program data: {}",
+        base64::engine::general_purpose::STANDARD.encode(event.data())
+    )
+}
+
+fn purchase(curve: Pubkey, supply_before: u64, tokens: u64) -> TokensPurchased {
+    let cost = math::cost_for_tokens(&PARAMS, tokens, supply_before).unwrap();
+    let new_supply = supply_before + tokens;
+    TokensPurchased {
+        buyer: Pubkey::new_unique(),
+        bonding_curve: curve,
+        tokens_minted: tokens,
+        sol_spent: cost + 10,
+        fee_lamports: 10,
+        charity_lamports: 0,
+        sol_to_reserves: cost,
+        new_supply,
+        new_price: PARAMS.price_at_supply(new_supply).unwrap(),
+        referrer: None,
+        referral_lamports: 0,
+        sol_unfilled: 0,
+        price_before: PARAMS.price_at_supply(supply_before).unwrap(),
+        trade_index: 0,
+        timestamp: 0,
+        event_version: 0,
+    }
+}
+
+fn sale(curve: Pubkey, supply_before: u64, tokens: u64) -> TokensSold {
+    let gross = math::sol_for_tokens(&PARAMS, tokens, supply_before - tokens).unwrap();
+    let new_supply = supply_before - tokens;
+    TokensSold {
+        seller: Pubkey::new_unique(),
+        bonding_curve: curve,
+        tokens_burned: tokens,
+        tax_tokens_burned: 0,
+        sol_received: gross - 5,
+        fee_lamports: 5,
+        charity_lamports: 0,
+        new_supply,
+        new_price: PARAMS.price_at_supply(new_supply).unwrap(),
+        price_before: PARAMS.price_at_supply(supply_before).unwrap(),
+        trade_index: 0,
+        timestamp: 0,
+        event_version: 0,
+    }
+}
+
+#[test]
+fn every_trade_path_replays_onto_the_curve_math() {
+    let curve = Pubkey::new_unique();
+    let mut replay = Replay::new(curve, PARAMS, 0, 0);
+
+    // A buy, and another curve's buy in the same transaction
+    let buy = purchase(curve, 0, 3 * TOKENS);
+    replay
+        .apply_logs("buy", 1, &[log(&buy), log(&purchase(Pubkey::new_unique(), 0, TOKENS))])
+        .unwrap();
+
+    // A limit order's sale, annotated by LimitOrderExecuted
+    let sold = sale(curve, 3 * TOKENS, TOKENS);
+    let fill = LimitOrderExecuted {
+        limit_order: Pubkey::new_unique(),
+        bonding_curve: curve,
+        owner: sold.seller,
+        side: OrderSide::Sell,
+        tokens: TOKENS,
+        sol_amount: sold.sol_received + sold.fee_lamports,
+        fee_lamports: sold.fee_lamports,
+        cranker: Pubkey::new_unique(),
+        tip_lamports: 0,
+    };
+    replay.apply_logs("limit", 2, &[log(&sold), log(&fill)]).unwrap();
+
+    // A compact buy carries only the tokens and the price after it
+    let compact = CompactTrade {
+        trader: Pubkey::new_unique(),
+        bonding_curve: curve,
+        is_buy: true,
+        sol_amount: 0,
+        token_amount: TOKENS,
+        new_price: PARAMS.price_at_supply(3 * TOKENS).unwrap(),
+    };
+    // The market-maker vault trades its inventory without touching the curve
+    let mm = MmTradeExecuted {
+        bonding_curve: curve,
+        trader: Pubkey::new_unique(),
+        is_buy: true,
+        token_amount: TOKENS,
+        sol_amount: 1,
+        spot_price: 0,
+    };
+    replay.apply_logs("compact", 3, &[log(&compact), log(&mm)]).unwrap();
+
+    // An exit burns its tokens when queued and pays out when settled
+    let gross = math::sol_for_tokens(&PARAMS, TOKENS, 2 * TOKENS).unwrap();
+    let queued = ExitQueued {
+        seller: Pubkey::new_unique(),
+        bonding_curve: curve,
+        position: 0,
+        ahead_in_queue: 0,
+        tokens_burned: TOKENS,
+        sol_owed: gross - 7,
+        fee_lamports: 7,
+        queue_lamports: gross,
+    };
+    let settled = ExitClaimSettled {
+        seller: queued.seller,
+        bonding_curve: curve,
+        position: 0,
+        sol_received: gross - 7,
+        fee_lamports: 7,
+        remaining_in_queue: 0,
+    };
+    replay.apply_logs("queue", 4, &[log(&queued)]).unwrap();
+    let reserves_queued = replay.sol_reserves;
+    replay.apply_logs("settle", 5, &[log(&settled)]).unwrap();
+
+    assert_eq!(replay.divergences, vec![]);
+    let kinds: Vec<_> = replay.trades.iter().map(|trade| trade.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            TradeKind::Buy,
+            TradeKind::LimitSell,
+            TradeKind::Buy,
+            TradeKind::MmBuy,
+            TradeKind::ExitQueued,
+            TradeKind::ExitSettled,
+        ]
+    );
+    assert_eq!(replay.supply, 2 * TOKENS);
+    assert_eq!(replay.sol_reserves, reserves_queued - gross);
+    // Replayed reserves are what the curve math says the remaining supply is worth, rounding aside
+    let backing = math::sol_for_tokens(&PARAMS, 2 * TOKENS, 0).unwrap();
+    assert!(replay.sol_reserves >= backing && replay.sol_reserves <= backing + 3);
+    replay.check_live_supply(2 * TOKENS);
+    assert_eq!(replay.divergences, vec![]);
+}
+
+#[test]
+fn events_the_math_disagrees_with_are_flagged() {
+    let curve = Pubkey::new_unique();
+    let mut replay = Replay::new(curve, PARAMS, 0, 0);
+    replay.apply_logs("buy", 1, &[log(&purchase(curve, 0, 2 * TOKENS))]).unwrap();
+
+    // A sale paying out more than the curve math allows
+    let mut overpaid = sale(curve, 2 * TOKENS, TOKENS);
+    overpaid.sol_received += 1;
+    replay.apply_logs("sell", 2, &[log(&overpaid)]).unwrap();
+    // A buy starting from a supply the replay never reached
+    replay.apply_logs("gap", 3, &[log(&purchase(curve, 2 * TOKENS, TOKENS))]).unwrap();
+    replay.check_live_supply(0);
+
+    let flagged: Vec<_> = replay
+        .divergences
+        .iter()
+        .map(|divergence| (divergence.signature.as_str(), divergence.field))
+        .collect();
+    assert_eq!(
+        flagged,
+        [("sell", "payout"), ("gap", "supply"), ("live", "supply")]
+    );
+}
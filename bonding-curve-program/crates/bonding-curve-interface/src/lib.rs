@@ -0,0 +1,160 @@
+//! CPI interface for programs composing with the bonding curve
+//!
+//! Aggregators and routers depend on this crate to buy and sell through a
+//! curve from their own programs. [`buy_tokens`] and [`sell_tokens`] take
+//! just the accounts every trade needs and leave the program's optional
+//! accounts (eligibility record, referral, charity, trade state, stats,
+//! buyer mark, quote-token accounts, price observations, blacklist entry,
+//! instructions sysvar) out; curves that require one of those need the full generated
+//! bindings in [`cpi`] instead.
+//!
+//! PDAs are derived with the re-exported [`pda`] module, never from
+//! hardcoded seed strings:
+//!
+//! | Account       | Seeds                                        |
+//! |---------------|----------------------------------------------|
+//! | Bonding curve | `["bonding_curve", mint]` (+ salt bytes)     |
+//! | SOL vault     | `["sol_vault", mint]`                        |
+//! | Fee vault     | `["fee_vault", mint]`                        |
+//! | Quote vault   | `["quote_vault", mint]`                      |
+//! | Global config | `["global_config"]`                          |
+//!
+//! The buyer or seller may be a PDA of the calling program; pass its signer
+//! seeds, or an empty slice when the wallet signed the outer transaction.
+
+use anchor_lang::prelude::*;
+
+pub use bonding_curve_program::program::BondingCurveProgram;
+pub use bonding_curve_program::{cpi, pda, BuyResult, ID};
+
+/// Accounts of a buy through the curve
+pub struct Buy<'info> {
+    /// The curve program itself
+    pub bonding_curve_program: AccountInfo<'info>,
+    /// Pays the SOL and owns the minted tokens
+    pub buyer: AccountInfo<'info>,
+    pub bonding_curve: AccountInfo<'info>,
+    pub token_mint: AccountInfo<'info>,
+    /// Buyer's associated token account, created by the curve if needed
+    pub buyer_token_account: AccountInfo<'info>,
+    pub sol_vault: AccountInfo<'info>,
+    pub fee_vault: AccountInfo<'info>,
+    pub global_config: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub associated_token_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: AccountInfo<'info>,
+}
+
+/// Accounts of a sale back to the curve
+pub struct Sell<'info> {
+    /// The curve program itself
+    pub bonding_curve_program: AccountInfo<'info>,
+    /// Owns the tokens sold and receives the SOL
+    pub seller: AccountInfo<'info>,
+    pub bonding_curve: AccountInfo<'info>,
+    pub token_mint: AccountInfo<'info>,
+    pub seller_token_account: AccountInfo<'info>,
+    pub sol_vault: AccountInfo<'info>,
+    pub fee_vault: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+/// Buy on the curve with sol_amount lamports; arguments as in the program's buy_tokens
+pub fn buy_tokens<'info>(
+    accounts: Buy<'info>,
+    signer_seeds: &[&[&[u8]]],
+    sol_amount: u64,
+    flags: u8,
+    max_entry_price: Option<u64>,
+    min_tokens_out: Option<u64>,
+    tolerance_bps: Option<u16>,
+) -> Result<BuyResult> {
+    let cpi_accounts = cpi::accounts::BuyTokens {
+        buyer: accounts.buyer,
+        bonding_curve: accounts.bonding_curve,
+        token_mint: accounts.token_mint,
+        buyer_token_account: accounts.buyer_token_account,
+        sol_vault: accounts.sol_vault,
+        fee_vault: accounts.fee_vault,
+        global_config: accounts.global_config,
+        token_program: accounts.token_program,
+        associated_token_program: accounts.associated_token_program,
+        system_program: accounts.system_program,
+        rent: accounts.rent,
+        eligibility_record: None,
+        referral: None,
+        referrer_nft_account: None,
+        charity: None,
+        user_trade_state: None,
+        protocol_stats: None,
+        quote_vault: None,
+        buyer_quote_account: None,
+        quote_token_mint: None,
+        quote_token_program: None,
+        price_observations: None,
+        curve_stats: None,
+        instructions_sysvar: None,
+        session: None,
+        owner_token_account: None,
+        buyer_mark: None,
+        trade_nonces: None,
+    };
+    let result = cpi::buy_tokens(
+        CpiContext::new_with_signer(accounts.bonding_curve_program, cpi_accounts, signer_seeds),
+        sol_amount,
+        flags,
+        max_entry_price,
+        min_tokens_out,
+        tolerance_bps,
+        None,
+    )?;
+    Ok(result.get())
+}
+
+/// Sell token_amount tokens back to the curve; arguments as in the program's sell_tokens
+pub fn sell_tokens<'info>(
+    accounts: Sell<'info>,
+    signer_seeds: &[&[&[u8]]],
+    token_amount: u64,
+    min_exit_price: Option<u64>,
+    min_sol_out: Option<u64>,
+) -> Result<()> {
+    let cpi_accounts = cpi::accounts::SellTokens {
+        seller: accounts.seller,
+        bonding_curve: accounts.bonding_curve,
+        token_mint: accounts.token_mint,
+        seller_token_account: accounts.seller_token_account,
+        sol_vault: accounts.sol_vault,
+        fee_vault: accounts.fee_vault,
+        token_program: accounts.token_program,
+        system_program: accounts.system_program,
+        eligibility_record: None,
+        charity: None,
+        user_trade_state: None,
+        protocol_stats: None,
+        quote_vault: None,
+        seller_quote_account: None,
+        quote_token_mint: None,
+        quote_token_program: None,
+        price_observations: None,
+        curve_stats: None,
+        instructions_sysvar: None,
+        blacklist_entry: None,
+        wsol_mint: None,
+        seller_wsol_account: None,
+        wsol_token_program: None,
+        associated_token_program: None,
+        session: None,
+        trade_nonces: None,
+    };
+    cpi::sell_tokens(
+        CpiContext::new_with_signer(accounts.bonding_curve_program, cpi_accounts, signer_seeds),
+        token_amount,
+        min_exit_price,
+        min_sol_out,
+        None,
+        None,
+    )
+}
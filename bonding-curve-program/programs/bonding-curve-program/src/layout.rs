@@ -0,0 +1,503 @@
+//! Raw account layouts for clients decoding accounts without the IDL toolchain
+//!
+//! Every account starts with its 8-byte Anchor discriminator, followed by its
+//! Borsh-encoded fields. The offsets here are byte positions from the start
+//! of the account data. Borsh writes `None` as a single tag byte, so offsets
+//! stop at an account's first variable-size field (PREFIX_LEN); decode
+//! anything past that sequentially. Fully fixed-size accounts are checked
+//! against their allocated size at compile time.
+
+use anchor_lang::Discriminator;
+
+/// Declares consecutive field offsets, starting right after the discriminator,
+/// and PREFIX_LEN as the end of the last listed field
+macro_rules! field_offsets {
+    ($($field:ident: $size:expr),* $(,)?) => {
+        field_offsets!(@next 8usize; $($field: $size),*);
+    };
+    (@next $offset:expr; $field:ident: $size:expr $(, $rest:ident: $rest_size:expr)*) => {
+        pub const $field: usize = $offset;
+        field_offsets!(@next $field + $size; $($rest: $rest_size),*);
+    };
+    (@next $offset:expr;) => {
+        pub const PREFIX_LEN: usize = $offset;
+    };
+}
+
+/// BondingCurve; sale_phases (variable size) starts at PREFIX_LEN
+pub mod bonding_curve {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::BondingCurve::DISCRIMINATOR;
+
+    field_offsets! {
+        CREATOR: 32,
+        TOKEN_MINT: 32,
+        CURRENT_SUPPLY: 8,
+        SOL_RESERVES: 8,
+        INITIAL_PRICE: 8,
+        SLOPE: 8,
+        CURVE_TYPE: 1,
+        FLAT_THRESHOLD: 8,
+        BUMP: 1,
+        NAME: 32,
+        SYMBOL: 8,
+        PHASE_COUNT: 1,
+    }
+}
+
+pub mod insurance_fund {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::InsuranceFund::DISCRIMINATOR;
+
+    field_offsets! {
+        AUTHORITY: 32,
+        TOTAL_DEPOSITED: 8,
+        TOTAL_PAID_OUT: 8,
+        REMEDIATION_COUNT: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::InsuranceFund::LEN);
+}
+
+pub mod eligibility_record {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::EligibilityRecord::DISCRIMINATOR;
+
+    field_offsets! {
+        ATTESTOR: 32,
+        WALLET: 32,
+        ISSUED_AT: 8,
+        REVOKED: 1,
+        BUMP: 1,
+        REJECTION_CODE: 2,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::EligibilityRecord::LEN);
+}
+
+pub mod referral_code {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::ReferralCode::DISCRIMINATOR;
+
+    field_offsets! {
+        NFT_MINT: 32,
+        CREATOR: 32,
+        CODE: 16,
+        REFERRED_VOLUME: 8,
+        REFERRAL_COUNT: 8,
+        REWARDS_UNCLAIMED: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::ReferralCode::LEN);
+}
+
+pub mod user_trade_state {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::UserTradeState::DISCRIMINATOR;
+
+    field_offsets! {
+        BONDING_CURVE: 32,
+        USER: 32,
+        LAST_BUY_TS: 8,
+        LAUNCH_TOKENS_BOUGHT: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::UserTradeState::LEN);
+}
+
+pub mod raffle_ticket {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::RaffleTicket::DISCRIMINATOR;
+
+    field_offsets! {
+        BONDING_CURVE: 32,
+        OWNER: 32,
+        INDEX: 4,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::RaffleTicket::LEN);
+}
+
+pub mod exit_claim {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::ExitClaim::DISCRIMINATOR;
+
+    field_offsets! {
+        BONDING_CURVE: 32,
+        SELLER: 32,
+        POSITION: 8,
+        SOL_AMOUNT: 8,
+        FEE_LAMPORTS: 8,
+        QUEUED_TS: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::ExitClaim::LEN);
+}
+
+pub mod mm_vault {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::MmVault::DISCRIMINATOR;
+
+    field_offsets! {
+        BONDING_CURVE: 32,
+        HALF_SPREAD_BPS: 2,
+        ENABLED: 1,
+        SOL_BALANCE: 8,
+        VOLUME_LAMPORTS: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::MmVault::LEN);
+}
+
+pub mod upgrade_snapshot {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::UpgradeSnapshot::DISCRIMINATOR;
+
+    field_offsets! {
+        ADMIN: 32,
+        TAKEN_SLOT: 8,
+        CONFIG_HASH: 32,
+        CURVE_COUNT: 1,
+        CURVES: 32 * crate::MAX_UPGRADE_SAMPLE_CURVES,
+        CURVE_HASHES: 32 * crate::MAX_UPGRADE_SAMPLE_CURVES,
+        VERIFIED_SLOT: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::UpgradeSnapshot::LEN);
+}
+
+pub mod creator_vesting {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::CreatorVesting::DISCRIMINATOR;
+
+    field_offsets! {
+        BONDING_CURVE: 32,
+        BENEFICIARY: 32,
+        TOTAL_AMOUNT: 8,
+        CLAIMED_AMOUNT: 8,
+        START_SLOT: 8,
+        CLIFF_SLOTS: 8,
+        VESTING_SLOTS: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::CreatorVesting::LEN);
+}
+
+/// PriceObservations; each observation is (slot, price, supply) as three u64s
+pub mod price_observations {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::PriceObservations::DISCRIMINATOR;
+    pub const OBSERVATION_LEN: usize = 24;
+
+    field_offsets! {
+        BONDING_CURVE: 32,
+        HEAD: 2,
+        COUNT: 2,
+        OBSERVATIONS: OBSERVATION_LEN * crate::MAX_PRICE_OBSERVATIONS,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::PriceObservations::LEN);
+}
+
+/// LimitOrder; side is one byte (0 = Buy, 1 = Sell)
+pub mod limit_order {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::LimitOrder::DISCRIMINATOR;
+
+    field_offsets! {
+        BONDING_CURVE: 32,
+        OWNER: 32,
+        ORDER_ID: 8,
+        SIDE: 1,
+        TRIGGER_PRICE: 8,
+        AMOUNT: 8,
+        TIP_LAMPORTS: 8,
+        CREATED_TS: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::LimitOrder::LEN);
+}
+
+pub mod blacklist_entry {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::BlacklistEntry::DISCRIMINATOR;
+
+    field_offsets! {
+        BONDING_CURVE: 32,
+        HOLDER: 32,
+        ADDED_TS: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::BlacklistEntry::LEN);
+}
+
+pub mod holder_rewards {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::HolderRewards::DISCRIMINATOR;
+
+    field_offsets! {
+        BONDING_CURVE: 32,
+        HOLDER: 32,
+        STAKED: 8,
+        REWARD_INDEX: 16,
+        TOTAL_CLAIMED: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::HolderRewards::LEN);
+}
+
+pub mod curve_registry {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::CurveRegistry::DISCRIMINATOR;
+
+    field_offsets! {
+        CREATOR: 32,
+        CURVE_COUNT: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::CurveRegistry::LEN);
+}
+
+pub mod curve_registry_entry {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::CurveRegistryEntry::DISCRIMINATOR;
+
+    field_offsets! {
+        INDEX: 8,
+        BONDING_CURVE: 32,
+        TOKEN_MINT: 32,
+        CREATOR: 32,
+        CREATED_TS: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::CurveRegistryEntry::LEN);
+}
+
+pub mod trading_session {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::TradingSession::DISCRIMINATOR;
+
+    field_offsets! {
+        OWNER: 32,
+        AUTHORITY: 32,
+        EXPIRY_SLOT: 8,
+        MAX_SOL_PER_TRADE: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::TradingSession::LEN);
+}
+
+pub mod curve_stats {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::CurveStats::DISCRIMINATOR;
+
+    field_offsets! {
+        BONDING_CURVE: 32,
+        VOLUME_LAMPORTS: 8,
+        BUY_COUNT: 8,
+        SELL_COUNT: 8,
+        UNIQUE_BUYERS: 8,
+        ATH_PRICE: 8,
+        ATH_SLOT: 8,
+        LAST_TRADE_SLOT: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::CurveStats::LEN);
+}
+
+pub mod airdrop {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::Airdrop::DISCRIMINATOR;
+
+    field_offsets! {
+        BONDING_CURVE: 32,
+        MERKLE_ROOT: 32,
+        TOTAL_AMOUNT: 8,
+        CLAIMED_AMOUNT: 8,
+        CLAIM_COUNT: 4,
+        CLAIM_DEADLINE_TS: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::Airdrop::LEN);
+}
+
+pub mod airdrop_receipt {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::AirdropReceipt::DISCRIMINATOR;
+
+    field_offsets! {
+        AIRDROP: 32,
+        CLAIMANT: 32,
+        AMOUNT: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::AirdropReceipt::LEN);
+}
+
+pub mod bootstrap_deposit {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::BootstrapDeposit::DISCRIMINATOR;
+
+    field_offsets! {
+        BONDING_CURVE: 32,
+        DEPOSITOR: 32,
+        AMOUNT: 8,
+        CLAIMED: 1,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::BootstrapDeposit::LEN);
+}
+
+/// ProtocolStats; each totals block is four u64s
+/// (curves_created, curves_graduated, volume_lamports, fees_lamports)
+pub mod protocol_stats {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::ProtocolStats::DISCRIMINATOR;
+
+    field_offsets! {
+        TOTALS: 32,
+        LAST_SUMMARY: 32,
+        LAST_SUMMARY_EPOCH: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::ProtocolStats::LEN);
+}
+
+pub mod global_config {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::GlobalConfig::DISCRIMINATOR;
+
+    field_offsets! {
+        ADMIN: 32,
+        PENDING_ADMIN: 32,
+        FEE_RECIPIENT: 32,
+        CREATION_FEE_LAMPORTS: 8,
+        PROTOCOL_FEE_BPS: 2,
+        REFERRAL_FEE_BPS: 2,
+        MAX_NAME_LEN: 2,
+        MAX_SYMBOL_LEN: 2,
+        MIN_INITIAL_PRICE: 8,
+        MAX_INITIAL_PRICE: 8,
+        MIN_SLOPE: 8,
+        MAX_SLOPE: 8,
+        MIN_GRADUATION_THRESHOLD: 8,
+        DEFAULT_GRADUATION_THRESHOLD: 8,
+        MAX_FEE_BPS: 2,
+        MAX_SOL_PER_TRADE: 8,
+        RENT_BUFFER: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::GlobalConfig::LEN);
+}
+
+/// LaunchQueue; each entry is a 32-byte curve followed by an i64 launch_ts
+pub mod launch_queue {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::LaunchQueue::DISCRIMINATOR;
+    pub const ENTRY_LEN: usize = 40;
+
+    field_offsets! {
+        LEN: 2,
+        ENTRIES: ENTRY_LEN * crate::LAUNCH_QUEUE_CAPACITY,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::LaunchQueue::LEN);
+}
+
+pub mod trade_nonces {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::TradeNonces::DISCRIMINATOR;
+
+    field_offsets! {
+        USER: 32,
+        BASE: 8,
+        BITMAP: 8 * crate::NONCE_WINDOW_WORDS,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::TradeNonces::LEN);
+}
+
+pub mod migration_state {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::MigrationState::DISCRIMINATOR;
+
+    field_offsets! {
+        BONDING_CURVE: 32,
+        FINAL_PRICE: 8,
+        SOL_TOTAL: 8,
+        TOKENS_TOTAL: 8,
+        SOL_MIGRATED: 8,
+        TOKENS_MIGRATED: 8,
+        TRANCHES: 2,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::MigrationState::LEN);
+}
+
+pub mod curve_index {
+    use super::*;
+
+    pub const DISCRIMINATOR: &[u8] = crate::CurveIndex::DISCRIMINATOR;
+
+    field_offsets! {
+        INDEX_ID: 4,
+        LEN: 1,
+        CURVES: 32 * crate::CURVE_INDEX_CAPACITY,
+        LAST_PRICE: 8,
+        CUMULATIVE_PRICE: 16,
+        LAST_UPDATE_TS: 8,
+        BUMP: 1,
+    }
+
+    const _: () = assert!(PREFIX_LEN == crate::CurveIndex::LEN);
+}
@@ -1,7 +1,39 @@
+// The generated CPI bindings mirror each instruction's full argument list
+#![cfg_attr(feature = "cpi", allow(clippy::too_many_arguments))]
+
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT};
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
 use anchor_lang::system_program;
-use anchor_spl::token::{self, Token, TokenAccount, Mint};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+use anchor_spl::token_interface::spl_token_2022::instruction::AuthorityType;
+use anchor_spl::token_interface::spl_token_2022::{
+    self,
+    extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+};
 use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{spl_token::native_mint, Token};
+use anchor_spl::metadata::{self, mpl_token_metadata::types::DataV2, Metadata};
+use std::collections::BTreeSet;
+
+pub mod layout;
+pub mod pda;
+
+use pda::*;
+
+/// Emits a trade event to the logs and, in builds with the cpi-events
+/// feature, also as a self-CPI whose instruction data indexers can read even
+/// when the logs are truncated. The instruction's accounts must carry
+/// `#[cfg_attr(feature = "cpi-events", event_cpi)]`
+macro_rules! emit_trade {
+    ($ctx:expr, $event:expr) => {{
+        let event = $event;
+        emit!(event);
+        #[cfg(feature = "cpi-events")]
+        emit_event_cpi(&$ctx.accounts.event_authority, $ctx.bumps.event_authority, &event)?;
+    }};
+}
 
 // Program ID
 declare_id!("GQQQNJZdqKnFwB6di7u2PnsJZLX7hzaYW4g4b5BeQ3nE");
@@ -13,7 +45,7 @@ declare_id!("GQQQNJZdqKnFwB6di7u2PnsJZLX7hzaYW4g4b5BeQ3nE");
  * demonstrating core concepts similar to Pump.fun's functionality.
  * 
  * Key Features:
- * - Linear bonding curve pricing
+ * - Linear bonding curve pricing (optionally flattening past a supply threshold)
  * - Token minting/burning based on SOL deposits/withdrawals  
  * - Automated price discovery
  * - Educational comments explaining each step
@@ -33,11 +65,46 @@ pub mod bonding_curve_program {
      * accounts for managing token sales/purchases through the curve.
      * 
      * Parameters:
-     * - initial_price: Starting price in lamports per token (multiplied by 10^decimals)
-     * - slope: How much the price increases per token minted (linear curve)
+     * - initial_price: Starting price in lamports per whole token (10^decimals base units);
+     *   the ceiling price for Sigmoid curves
+     * - slope: How much the price per whole token increases per whole token minted
+     *   (linear curves); the doubling supply for Exponential and the width for
+     *   Sigmoid, in whole tokens
      * - name: Token name (for metadata)
      * - symbol: Token symbol (for metadata)
-     * - uri: Metadata URI (can be empty for educational purposes)
+     * - uri: Metadata URI, stored in the token's Metaplex metadata (can be empty)
+     * - curve_type: Shape of the price curve (Linear, LinearThenFlat, Exponential or Sigmoid)
+     * - args: The optional settings, each a field of CurveInitArgs:
+     *
+     * - flat_threshold: Supply after which a LinearThenFlat curve stops rising, or a Sigmoid's midpoint
+     *   (required for LinearThenFlat)
+     * - sale_phases: Optional schedule of up to MAX_SALE_PHASES phases (empty = always public)
+     * - graduation_metric: Whether graduation tracks SOL raised or market cap
+     * - graduation_threshold: Target for the metric (None = default SOL target);
+     *   quote-token curves never graduate and take None
+     * - salt: Optional extra seed of the bonding curve PDA, so several curves can
+     *   coexist at predictable addresses and a failed init can be retried at a
+     *   fresh one; None keeps the plain [BONDING_CURVE_SEED, mint] address
+     * - max_supply: Optional hard cap on minted supply for fixed-supply launches
+     * - dust: Optional floor on sell payouts, up to MAX_DUST_THRESHOLD_LAMPORTS,
+     *   and whether smaller sells are rejected or have their payout swept
+     *   into the fee vault
+     * - decimals: Mint decimals, up to MAX_TOKEN_DECIMALS (None = 0). Supplies,
+     *   thresholds and token amounts are always in base units
+     * - initial_buy_lamports: Optional dev buy for the creator, made in this
+     *   same instruction so nobody can trade ahead of it. It pays the normal
+     *   buy fees and needs creator_token_account, which is created here
+     * - quote_mint: Optional SPL mint (e.g. USDC) the curve trades against
+     *   instead of SOL. Needs quote_token_mint, quote_vault and
+     *   quote_token_program; prices, reserves and per-trade limits are then
+     *   in the quote mint's base units
+     * - sell_tax_ramp: Optional extra sell fee that starts at
+     *   initial_sell_tax_bps and decays linearly to final_sell_tax_bps over
+     *   decay_slots from this slot, paid into the fees like the trading fee
+     *
+     * Optional settings are Options rather than zero sentinels so that a
+     * plain launch costs one byte each in the instruction data, leaving room
+     * for a buy, memo and priority fee in the same transaction.
      */
     pub fn initialize_bonding_curve(
         ctx: Context<InitializeBondingCurve>,
@@ -45,21 +112,118 @@ pub mod bonding_curve_program {
         slope: u64,              // Price increase per token minted
         name: String,            // Token name
         symbol: String,          // Token symbol
+        uri: String,             // Metadata URI
+        curve_type: CurveType,   // Curve shape
+        args: CurveInitArgs,     // Optional settings (Default = a plain launch)
     ) -> Result<()> {
-        // Validate input parameters to prevent common mistakes
-        require!(initial_price > 0, BondingCurveError::InvalidPrice);
-        require!(slope > 0, BondingCurveError::InvalidSlope);
-        require!(name.len() <= 32, BondingCurveError::NameTooLong);
-        require!(symbol.len() <= 10, BondingCurveError::SymbolTooLong);
+        let CurveInitArgs {
+            flat_threshold,
+            sale_phases,
+            graduation_metric,
+            graduation_threshold,
+            salt,
+            max_supply,
+            dust,
+            decimals,
+            initial_buy_lamports,
+            quote_mint,
+            sell_tax_ramp,
+        } = args;
+
+        // Validate input parameters against the protocol's configured ranges
+        let config = &ctx.accounts.global_config;
+        config.check_curve_params(initial_price, slope, &name, &symbol)?;
+        require!(uri.len() <= MAX_URI_LEN, BondingCurveError::UriTooLong);
+        let decimals = decimals.unwrap_or(0);
+        require!(decimals <= MAX_TOKEN_DECIMALS, BondingCurveError::InvalidDecimals);
+        let dust = dust.unwrap_or_default();
+        require!(
+            dust.threshold_lamports <= MAX_DUST_THRESHOLD_LAMPORTS,
+            BondingCurveError::InvalidDustThreshold
+        );
+
+        let sell_tax_ramp = sell_tax_ramp.unwrap_or_default();
+        require!(
+            sell_tax_ramp.initial_sell_tax_bps <= MAX_SELL_TAX_BPS
+                && sell_tax_ramp.final_sell_tax_bps <= sell_tax_ramp.initial_sell_tax_bps,
+            BondingCurveError::InvalidSellTaxRamp
+        );
+
+        // Quote-token curves need their vault, and the dev buy is SOL-only
+        let quote_mint = quote_mint.unwrap_or_default();
+        let quote_token_program = match quote_mint == Pubkey::default() {
+            true => Pubkey::default(),
+            false => {
+                let quote_token_mint = ctx
+                    .accounts
+                    .quote_token_mint
+                    .as_ref()
+                    .ok_or(BondingCurveError::MissingQuoteAccount)?;
+                require_keys_eq!(quote_token_mint.key(), quote_mint, BondingCurveError::QuoteMintMismatch);
+                // Trades move exact amounts through the quote vault, which a transfer fee would short
+                require!(
+                    !has_transfer_fee(&quote_token_mint.to_account_info())?,
+                    BondingCurveError::QuoteMintTransferFee
+                );
+                require!(ctx.accounts.quote_vault.is_some(), BondingCurveError::MissingQuoteAccount);
+                require!(initial_buy_lamports.is_none(), BondingCurveError::QuoteCurveUnsupported);
+                ctx.accounts
+                    .quote_token_program
+                    .as_ref()
+                    .ok_or(BondingCurveError::MissingQuoteAccount)?
+                    .key()
+            }
+        };
 
-        // Initialize bonding curve state
+        let flat_threshold = curve_flat_threshold(curve_type, flat_threshold)?;
+
+        // Phases must fit in the account and start in strictly increasing order
+        require!(sale_phases.len() <= MAX_SALE_PHASES, BondingCurveError::TooManyPhases);
+        for phase in sale_phases.iter() {
+            if let Some(fee_bps) = phase.fee_bps_override {
+                require!(fee_bps <= MAX_FEE_BPS, BondingCurveError::InvalidFee);
+            }
+            require!(phase.price_multiplier_bps > 0, BondingCurveError::InvalidPriceMultiplier);
+        }
+        for pair in sale_phases.windows(2) {
+            require!(pair[0].start_ts < pair[1].start_ts, BondingCurveError::InvalidPhaseSchedule);
+        }
+        // A market-cap target has no sensible default; SOL targets fall back to the protocol one.
+        // Migration is SOL-only, so quote-token curves never graduate and keep trading on the curve
+        let graduation_threshold = match (graduation_metric, graduation_threshold) {
+            (_, threshold) if quote_mint != Pubkey::default() => {
+                require!(threshold.is_none(), BondingCurveError::QuoteCurveUnsupported);
+                u64::MAX
+            }
+            (GraduationMetric::SolRaised, None) => config.default_graduation_threshold,
+            (GraduationMetric::MarketCap, None) => {
+                return err!(BondingCurveError::InvalidGraduationThreshold)
+            }
+            (_, Some(threshold)) => {
+                require!(
+                    threshold >= config.min_graduation_threshold,
+                    BondingCurveError::InvalidGraduationThreshold
+                );
+                threshold
+            }
+        };
+
+        let salt = salt.unwrap_or(0);
+        let protocol_fee_bps = config.protocol_fee_bps;
+        let creation_fee = config.creation_fee_lamports;
+
+        let mut phase_slots = [SalePhase::default(); MAX_SALE_PHASES];
+        phase_slots[..sale_phases.len()].copy_from_slice(&sale_phases);
+
+        // Initialize bonding curve state; the new account starts zeroed, so
+        // every field left unset here starts at zero, false or None
         let bonding_curve = &mut ctx.accounts.bonding_curve;
         bonding_curve.creator = ctx.accounts.creator.key();
         bonding_curve.token_mint = ctx.accounts.token_mint.key();
-        bonding_curve.current_supply = 0;
-        bonding_curve.sol_reserves = 0;
         bonding_curve.initial_price = initial_price;
         bonding_curve.slope = slope;
+        bonding_curve.curve_type = curve_type;
+        bonding_curve.flat_threshold = flat_threshold;
         bonding_curve.bump = ctx.bumps.bonding_curve;
 
         // Convert name and symbol to fixed-size arrays (further optimized)
@@ -75,10 +239,44 @@ pub mod bonding_curve_program {
 
         bonding_curve.name = name_bytes;
         bonding_curve.symbol = symbol_bytes;
+        bonding_curve.phase_count = sale_phases.len() as u8;
+        bonding_curve.sale_phases = phase_slots;
+        bonding_curve.graduation_metric = graduation_metric;
+        bonding_curve.graduation_threshold = graduation_threshold;
+        bonding_curve.protocol_fee_bps = protocol_fee_bps;
+        bonding_curve.creator_last_action_ts = Clock::get()?.unix_timestamp;
+        bonding_curve.salt = salt;
+        bonding_curve.token_program = ctx.accounts.token_program.key();
+        bonding_curve.max_supply = max_supply.unwrap_or(0);
+        bonding_curve.dust_threshold = dust.threshold_lamports;
+        bonding_curve.dust_policy = dust.policy;
+        bonding_curve.decimals = decimals;
+        bonding_curve.quote_mint = quote_mint;
+        bonding_curve.quote_token_program = quote_token_program;
+        bonding_curve.sell_tax_initial_bps = sell_tax_ramp.initial_sell_tax_bps;
+        bonding_curve.sell_tax_final_bps = sell_tax_ramp.final_sell_tax_bps;
+        bonding_curve.sell_tax_decay_slots = sell_tax_ramp.decay_slots;
+        bonding_curve.sell_tax_start_slot = Clock::get()?.slot;
+        bonding_curve.version = CURVE_VERSION;
+        bonding_curve.check_sell_fee_cap()?;
+        bonding_curve.check_phase_multipliers()?;
+
+        // Charge the protocol's curve creation fee, if any
+        if creation_fee > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.creator.to_account_info(),
+                        to: ctx.accounts.fee_recipient.to_account_info(),
+                    },
+                ),
+                creation_fee,
+            )?;
+        }
 
-        // Transfer initial rent to SOL vault
-        let rent = Rent::get()?;
-        let rent_lamports = rent.minimum_balance(0);
+        // Transfer initial rent (plus the dust buffer) to SOL vault
+        let rent_lamports = vault_rent_floor()?;
         
         anchor_lang::system_program::transfer(
             CpiContext::new(
@@ -91,21 +289,96 @@ pub mod bonding_curve_program {
             rent_lamports,
         )?;
 
+        // Fund the fee vault's rent exemption so fees of any size can land in it
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.creator.to_account_info(),
+                    to: ctx.accounts.fee_vault.to_account_info(),
+                },
+            ),
+            Rent::get()?.minimum_balance(0),
+        )?;
+
+        // Create the Metaplex metadata so wallets show the token's name and symbol;
+        // the curve PDA signs as both mint and update authority
+        let token_mint_key = ctx.accounts.token_mint.key();
+        metadata::create_metadata_accounts_v3(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                metadata::CreateMetadataAccountsV3 {
+                    metadata: ctx.accounts.metadata.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    mint_authority: ctx.accounts.bonding_curve.to_account_info(),
+                    payer: ctx.accounts.creator.to_account_info(),
+                    update_authority: ctx.accounts.bonding_curve.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+                &[&[
+                    BONDING_CURVE_SEED,
+                    token_mint_key.as_ref(),
+                    &salt_seed(salt),
+                    &[ctx.bumps.bonding_curve],
+                ]],
+            ),
+            DataV2 {
+                name,
+                symbol,
+                uri,
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            },
+            true,
+            true,
+            None,
+        )?;
+
+        // Number the curve so clients can enumerate the creator's curves by index
+        let registry_index = register_in_curve_registry(
+            &mut ctx.accounts.curve_registry,
+            ctx.bumps.curve_registry,
+            &mut ctx.accounts.registry_entry,
+            ctx.bumps.registry_entry,
+            &mut ctx.accounts.bonding_curve,
+        )?;
+
         // Emit an event for tracking and analytics
+        let bonding_curve = &ctx.accounts.bonding_curve;
         emit!(BondingCurveInitialized {
             bonding_curve: bonding_curve.key(),
             token_mint: ctx.accounts.token_mint.key(),
             creator: ctx.accounts.creator.key(),
             initial_price,
             slope,
+            curve_type,
+            flat_threshold,
+            phase_count: sale_phases.len() as u8,
+            graduation_metric,
+            graduation_threshold,
+            quote_mint,
+            registry_index,
         });
 
+        if let Some(stats) = ctx.accounts.protocol_stats.as_mut() {
+            stats.totals.curves_created += 1;
+        }
+
+        // The creator's dev buy lands before anyone else can see the curve
+        if let Some(sol_amount) = initial_buy_lamports {
+            execute_initial_buy(ctx.accounts, ctx.bumps.sol_vault, sol_amount)?;
+        }
+
         msg!("Bonding curve initialized for token: {}", ctx.accounts.token_mint.key());
         Ok(())
     }
 
     /**
      * Buy tokens using SOL through the bonding curve
+     * (or the quote token, on quote-token curves; sol_amount is then in its base units)
      * 
      * This function implements the core bonding curve logic:
      * 1. Calculate how many tokens can be bought with the given SOL
@@ -113,84 +386,338 @@ pub mod bonding_curve_program {
      * 3. Mint tokens to the buyer's associated token account
      * 
      * The price increases as more tokens are minted, creating scarcity.
+     *
+     * Returns a BuyResult separating the gross SOL sent, fees deducted,
+     * SOL credited to reserves and tokens minted.
+     *
+     * With BUY_FLAG_ALLOW_PARTIAL set in flags, a buy that would break a per-trade, phase,
+     * launch-window or max-supply limit fills as much as the limits allow
+     * instead of failing, and the unspent SOL is reported as sol_unfilled and
+     * never pulled from the buyer. Without it such a buy fails, past
+     * max_supply with MaxSupplyExceeded. The buy that reaches max_supply
+     * marks the curve sold out.
+     *
+     * max_entry_price bounds the spot price (phase-adjusted) the buy leaves
+     * the curve at, i.e. the price of the last token bought. min_tokens_out
+     * reverts the buy if the curve moved against the client's quote.
+     * Clients without a fresh quote can pass tolerance_bps instead: the buy
+     * then also reverts if it gets more than tolerance_bps fewer tokens than
+     * its SOL would buy at the spot price found at execution time.
+     *
+     * A session key buying for its trading session must pass a nonce from
+     * the owner's TradeNonces window, so a relayed buy executes at most once.
      */
     pub fn buy_tokens(
         ctx: Context<BuyTokens>,
-        sol_amount: u64,  // Amount of SOL to spend (in lamports)
-    ) -> Result<()> {
-        // Validate input
-        require!(sol_amount > 0, BondingCurveError::InvalidAmount);
+        sol_amount: u64,      // Amount of SOL to spend (in lamports)
+        flags: u8,            // BUY_FLAG_* bits
+        max_entry_price: Option<u64>, // Highest acceptable spot price after the buy
+        min_tokens_out: Option<u64>,  // Fewest tokens the buyer accepts
+        tolerance_bps: Option<u16>,   // Allowed shortfall against the spot price at execution
+        nonce: Option<u64>,           // Replay-protection nonce (required for session buys)
+    ) -> Result<BuyResult> {
+        execute_buy(
+            ctx,
+            sol_amount,
+            flags,
+            max_entry_price,
+            min_tokens_out,
+            tolerance_bps,
+            nonce,
+            false,
+        )
+    }
 
-        let bonding_curve = &ctx.accounts.bonding_curve;
-        
-        // Calculate how many tokens can be purchased with the given SOL
-        let tokens_to_mint = calculate_tokens_for_sol(
+    /**
+     * Buy tokens during the allowlist period with a Merkle proof
+     *
+     * Same as buy_tokens, but while the curve's allowlist is active the
+     * buyer must prove their wallet is a leaf of whitelist_root. Leaves are
+     * the SHA-256 hash of the wallet's public key, and each proof step
+     * hashes the sorted pair, so proofs carry no left/right flags.
+     */
+    pub fn buy_tokens_whitelisted(
+        ctx: Context<BuyTokens>,
+        sol_amount: u64,      // Amount of SOL to spend (in lamports)
+        flags: u8,            // BUY_FLAG_* bits
+        max_entry_price: Option<u64>, // Highest acceptable spot price after the buy
+        min_tokens_out: Option<u64>,  // Fewest tokens the buyer accepts
+        tolerance_bps: Option<u16>,   // Allowed shortfall against the spot price at execution
+        proof: Vec<[u8; 32]>,         // Sibling hashes from the buyer's leaf up to the root
+        nonce: Option<u64>,           // Replay-protection nonce (required for session buys)
+    ) -> Result<BuyResult> {
+        require!(
+            verify_whitelist_proof(
+                &ctx.accounts.bonding_curve.whitelist_root,
+                &ctx.accounts.buyer.key(),
+                &proof,
+            ),
+            BondingCurveError::NotWhitelisted
+        );
+        execute_buy(
+            ctx,
             sol_amount,
-            bonding_curve.current_supply,
-            bonding_curve.initial_price,
-            bonding_curve.slope,
-        )?;
+            flags,
+            max_entry_price,
+            min_tokens_out,
+            tolerance_bps,
+            nonce,
+            true,
+        )
+    }
 
-        // Transfer SOL to vault
-        let cpi_context = CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            system_program::Transfer {
-                from: ctx.accounts.buyer.to_account_info(),
-                to: ctx.accounts.sol_vault.to_account_info(),
-            },
+    /**
+     * Buy on several curves in one transaction
+     *
+     * Lets aggregators split a SOL amount across curves atomically. Each leg
+     * is a plain buy_tokens with its own amount and slippage bounds, run
+     * against its own group of remaining accounts: the full BuyTokens
+     * account list in declaration order, with the program id standing in
+     * for unused optional accounts, just as a standalone buy_tokens would
+     * pass them. Every group must name the signing buyer. Legs run in order
+     * and a failing leg reverts them all; the result has one BuyResult per
+     * leg.
+     */
+    pub fn buy_tokens_multi<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BuyTokensMulti<'info>>,
+        legs: Vec<BuyLeg>, // One entry per curve, in remaining-account order
+    ) -> Result<Vec<BuyResult>> {
+        require!(
+            !legs.is_empty() && legs.len() <= MAX_MULTI_BUY_LEGS,
+            BondingCurveError::InvalidBuyLegs
         );
-        system_program::transfer(cpi_context, sol_amount)?;
 
-        // Mint tokens to buyer
-        let cpi_context = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            token::MintTo {
-                mint: ctx.accounts.token_mint.to_account_info(),
-                to: ctx.accounts.buyer_token_account.to_account_info(),
-                authority: ctx.accounts.bonding_curve.to_account_info(),
+        let program_id = ctx.program_id;
+        let mut remaining = ctx.remaining_accounts;
+        let mut results = Vec::with_capacity(legs.len());
+        for leg in legs {
+            let mut bumps = BuyTokensBumps::default();
+            let mut accounts = BuyTokens::try_accounts(
+                program_id,
+                &mut remaining,
+                &[],
+                &mut bumps,
+                &mut BTreeSet::new(),
+            )?;
+            require_keys_eq!(
+                accounts.buyer.key(),
+                ctx.accounts.buyer.key(),
+                BondingCurveError::InvalidBuyLegs
+            );
+            let result = execute_buy(
+                Context::new(program_id, &mut accounts, &[], bumps),
+                leg.sol_amount,
+                leg.flags,
+                leg.max_entry_price,
+                leg.min_tokens_out,
+                leg.tolerance_bps,
+                leg.nonce,
+                false,
+            )?;
+            // Persist this leg's account changes so a later leg on the same curve sees them
+            accounts.exit(program_id)?;
+            results.push(result);
+        }
+        require!(remaining.is_empty(), BondingCurveError::InvalidBuyLegs);
+
+        msg!("Bought on {} curves", results.len());
+        Ok(results)
+    }
+
+    /**
+     * Buy exactly token_amount tokens, paying at most max_sol_in
+     *
+     * The SOL cost comes from the curve integral, grossed up for fees and
+     * the charity share the same way buy_tokens deducts them. Only that
+     * exact cost is pulled from the buyer, so there is never dust to refund;
+     * the untouched part of max_sol_in is reported as sol_unfilled. Past
+     * max_supply the buy fails with MaxSupplyExceeded, unless
+     * BUY_FLAG_ALLOW_PARTIAL is set in flags: the order then shrinks to the
+     * tokens still left to mint.
+     */
+    pub fn buy_exact_tokens(
+        ctx: Context<BuyTokens>,
+        token_amount: u64,  // Exact number of tokens to mint
+        max_sol_in: u64,    // Most the buyer will spend, fees included
+        flags: u8,          // BUY_FLAG_* bits
+    ) -> Result<BuyResult> {
+        let buyer = ctx.accounts.buyer.key();
+        let accounts = &mut *ctx.accounts;
+        let fill = execute_exact_buy(
+            ExactBuyAccounts {
+                payer: accounts.buyer.to_account_info(),
+                bonding_curve: &mut accounts.bonding_curve,
+                token_mint: accounts.token_mint.to_account_info(),
+                destination: accounts.buyer_token_account.to_account_info(),
+                sol_vault: &accounts.sol_vault,
+                sol_vault_bump: ctx.bumps.sol_vault,
+                fee_vault: &accounts.fee_vault,
+                token_program: accounts.token_program.to_account_info(),
+                system_program: &accounts.system_program,
+                global_config: Some(&*accounts.global_config),
+                eligibility_record: &accounts.eligibility_record,
+                referral: &mut accounts.referral,
+                referrer_nft_account: &accounts.referrer_nft_account,
+                charity: &accounts.charity,
+                user_trade_state: &mut accounts.user_trade_state,
+                protocol_stats: &mut accounts.protocol_stats,
+                price_observations: &mut accounts.price_observations,
+                curve_stats: &mut accounts.curve_stats,
+                instructions_sysvar: &accounts.instructions_sysvar,
+                buyer_mark: &accounts.buyer_mark,
             },
-        );
-        token::mint_to(
-            cpi_context.with_signer(&[&[
-                b"bonding_curve",
-                ctx.accounts.token_mint.key().as_ref(),
-                &[bonding_curve.bump],
-            ]]),
-            tokens_to_mint,
+            buyer,
+            token_amount,
+            max_sol_in,
+            flags,
         )?;
 
-        // Update bonding curve state
-        let bonding_curve = &mut ctx.accounts.bonding_curve;
-        bonding_curve.current_supply = bonding_curve.current_supply.checked_add(tokens_to_mint).unwrap();
-        bonding_curve.sol_reserves = bonding_curve.sol_reserves.checked_add(sol_amount).unwrap();
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        if bonding_curve.compact_events {
+            emit!(CompactTrade {
+                trader: buyer,
+                bonding_curve: bonding_curve.key(),
+                is_buy: true,
+                sol_amount: fill.gross_sol,
+                token_amount: fill.token_amount,
+                new_price: fill.new_price,
+            });
+        } else {
+            emit_trade!(ctx, TokensPurchased {
+                buyer,
+                bonding_curve: bonding_curve.key(),
+                tokens_minted: fill.token_amount,
+                sol_spent: fill.gross_sol,
+                fee_lamports: fill.fee_lamports,
+                charity_lamports: fill.charity_lamports,
+                sol_to_reserves: fill.sol_to_reserves,
+                new_supply: bonding_curve.current_supply,
+                new_price: fill.new_price,
+                referrer: fill.referrer,
+                referral_lamports: fill.referral_lamports,
+                sol_unfilled: max_sol_in - fill.gross_sol,
+                price_before: fill.price_before,
+                trade_index: bonding_curve.trade_index,
+                timestamp: fill.timestamp,
+                event_version: TRADE_EVENT_VERSION,
+            });
+        }
 
-        // Calculate the new price after the purchase
-        let new_price = bonding_curve.initial_price
-            .checked_add(bonding_curve.current_supply.checked_mul(bonding_curve.slope).unwrap())
-            .unwrap();
+        if fill.large_trade {
+            emit!(LargeTrade {
+                trader: buyer,
+                bonding_curve: bonding_curve.key(),
+                is_buy: true,
+                sol_amount: fill.sol_to_reserves,
+                token_amount: fill.token_amount,
+                reserves_before: fill.reserves_before,
+                supply_before: fill.supply_before,
+            });
+        }
+
+        if !bonding_curve.compact_events {
+            msg!(
+                "Tokens purchased: {} tokens for {} lamports ({} fees, {} to reserves)",
+                fill.token_amount,
+                fill.gross_sol,
+                fill.fee_lamports,
+                fill.sol_to_reserves
+            );
+        }
+
+        Ok(BuyResult {
+            gross_sol: fill.gross_sol,
+            fee_lamports: fill.fee_lamports,
+            charity_lamports: fill.charity_lamports,
+            sol_to_reserves: fill.sol_to_reserves,
+            tokens_minted: fill.token_amount,
+            sol_unfilled: max_sol_in - fill.gross_sol,
+        })
+    }
+
+    /**
+     * Buy an exact number of tokens on behalf of a recipient
+     *
+     * The payer covers the curve cost plus fees (bounded by max_sol_cost)
+     * and exactly token_amount tokens are minted to the recipient, whose
+     * token account is created if needed. Meant for airdrop-style
+     * distributions driven by other programs via CPI. Eligibility and hold
+     * periods apply to the recipient, who ends up holding the tokens.
+     *
+     * Otherwise it is a buy like buy_exact_tokens: it pays and credits a
+     * referral passed with the global config, and counts in the protocol
+     * and curve stats under the recipient's buyer mark.
+     */
+    pub fn buy_exact_tokens_for(
+        ctx: Context<BuyExactTokensFor>,
+        recipient: Pubkey,   // Wallet receiving the tokens
+        token_amount: u64,   // Exact number of tokens to mint
+        max_sol_cost: u64,   // Most the payer will spend, fees included
+        flags: u8,           // BUY_FLAG_* bits
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let fill = execute_exact_buy(
+            ExactBuyAccounts {
+                payer: accounts.payer.to_account_info(),
+                bonding_curve: &mut accounts.bonding_curve,
+                token_mint: accounts.token_mint.to_account_info(),
+                destination: accounts.recipient_token_account.to_account_info(),
+                sol_vault: &accounts.sol_vault,
+                sol_vault_bump: ctx.bumps.sol_vault,
+                fee_vault: &accounts.fee_vault,
+                token_program: accounts.token_program.to_account_info(),
+                system_program: &accounts.system_program,
+                global_config: accounts.global_config.as_deref().map(|config| &**config),
+                eligibility_record: &accounts.eligibility_record,
+                referral: &mut accounts.referral,
+                referrer_nft_account: &accounts.referrer_nft_account,
+                charity: &accounts.charity,
+                user_trade_state: &mut accounts.user_trade_state,
+                protocol_stats: &mut accounts.protocol_stats,
+                price_observations: &mut accounts.price_observations,
+                curve_stats: &mut accounts.curve_stats,
+                instructions_sysvar: &accounts.instructions_sysvar,
+                buyer_mark: &accounts.buyer_mark,
+            },
+            recipient,
+            token_amount,
+            max_sol_cost,
+            flags,
+        )?;
 
-        // Emit purchase event for tracking and analytics
-        emit!(TokensPurchased {
-            buyer: ctx.accounts.buyer.key(),
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        emit_trade!(ctx, TokensPurchased {
+            buyer: recipient,
             bonding_curve: bonding_curve.key(),
-            tokens_minted: tokens_to_mint,
-            sol_spent: sol_amount,
+            tokens_minted: fill.token_amount,
+            sol_spent: fill.gross_sol,
+            fee_lamports: fill.fee_lamports,
+            charity_lamports: fill.charity_lamports,
+            sol_to_reserves: fill.sol_to_reserves,
             new_supply: bonding_curve.current_supply,
-            new_price,
+            new_price: fill.new_price,
+            referrer: fill.referrer,
+            referral_lamports: fill.referral_lamports,
+            sol_unfilled: max_sol_cost - fill.gross_sol,
+            price_before: fill.price_before,
+            trade_index: bonding_curve.trade_index,
+            timestamp: fill.timestamp,
+            event_version: TRADE_EVENT_VERSION,
         });
 
-        // Log the purchase details
         msg!(
-            "Tokens purchased: {} tokens for {} lamports",
-            tokens_to_mint,
-            sol_amount
+            "{} bought {} tokens for {} for {} lamports",
+            ctx.accounts.payer.key(),
+            fill.token_amount,
+            recipient,
+            fill.gross_sol
         );
-
         Ok(())
     }
 
     /**
      * Sell tokens back to the bonding curve for SOL
+     * (or the quote token, on quote-token curves)
      * 
      * This function allows users to sell their tokens back to the curve:
      * 1. Calculate how much SOL the tokens are worth at current price
@@ -198,15 +725,85 @@ pub mod bonding_curve_program {
      * 3. Transfer SOL from reserves to the seller
      * 
      * The price decreases as tokens are burned, maintaining the curve.
+     *
+     * If the curve has a sell burn tax, an extra sell_burn_tax_bps share of
+     * token_amount is burned from the seller with no SOL paid for it.
+     * A sell tax ramp adds its current rate to the trading fee.
+     *
+     * min_exit_price bounds the spot price (phase-adjusted) the sale leaves
+     * the curve at, i.e. the price of the last token sold. min_sol_out
+     * reverts the sale if the seller would net less than quoted.
+     *
+     * With settle_as_wsol the proceeds are paid as wSOL into the seller's
+     * native-mint associated token account, created if needed, for sellers
+     * such as vault PDAs that can't take system transfers cleanly.
+     *
+     * A session key selling for its trading session passes the session and
+     * the owner's token account, on which the owner has approved the session
+     * key as delegate; the tokens burn from there, the sale counts as the
+     * owner's, and the proceeds are added to the session's deposit. Like a
+     * session buy, it must consume a nonce from the owner's TradeNonces.
      */
     pub fn sell_tokens(
         ctx: Context<SellTokens>,
-        token_amount: u64,  // Amount of tokens to sell
+        token_amount: u64,    // Amount of tokens to sell
+        min_exit_price: Option<u64>, // Lowest acceptable spot price after the sale
+        min_sol_out: Option<u64>,    // Fewest lamports the seller accepts, after fees
+        settle_as_wsol: Option<bool>, // Pay out as wSOL instead of lamports (SOL curves only)
+        nonce: Option<u64>,           // Replay-protection nonce (required for session sales)
     ) -> Result<()> {
         // Validate input
         require!(token_amount > 0, BondingCurveError::InvalidAmount);
+        let settle_as_wsol = settle_as_wsol.unwrap_or(false);
 
-        let bonding_curve = &ctx.accounts.bonding_curve;
+        // One clock read and one mutable borrow of the curve for the whole sale
+        let clock = Clock::get()?;
+        let (now, slot) = (clock.unix_timestamp, clock.slot);
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_trade_in_slot(slot)?;
+        require!(bonding_curve.status == CurveStatus::Active, BondingCurveError::CurveNotActive);
+        require!(bonding_curve.selling_open(now), BondingCurveError::TradingClosed);
+        require!(
+            !(settle_as_wsol && bonding_curve.has_quote_mint()),
+            BondingCurveError::QuoteCurveUnsupported
+        );
+        // A session key sells for the session's owner, burning from the owner's
+        // token account as its approved delegate
+        let trader = match &ctx.accounts.session {
+            Some(session) => {
+                require!(!bonding_curve.has_quote_mint(), BondingCurveError::QuoteCurveUnsupported);
+                require!(!settle_as_wsol, BondingCurveError::SessionPaysDeposit);
+                require!(
+                    ctx.accounts.seller_token_account.owner == session.owner,
+                    BondingCurveError::InvalidSessionTokenAccount
+                );
+                session.owner
+            }
+            None => ctx.accounts.seller.key(),
+        };
+        check_trade_nonce(
+            &mut ctx.accounts.trade_nonces,
+            trader,
+            nonce,
+            ctx.accounts.session.is_some(),
+        )?;
+        check_strict_mode(bonding_curve, &ctx.accounts.instructions_sysvar)?;
+        check_eligibility(
+            bonding_curve,
+            trader,
+            &ctx.accounts.eligibility_record,
+        )?;
+        check_not_blacklisted(
+            bonding_curve,
+            trader,
+            &ctx.accounts.blacklist_entry,
+        )?;
+        check_hold_period(
+            &ctx.accounts.user_trade_state,
+            bonding_curve,
+            trader,
+            now,
+        )?;
         
         // Calculate SOL to return based on bonding curve
         // For selling, we calculate the value of tokens being sold based on their position in the curve
@@ -214,77 +811,231 @@ pub mod bonding_curve_program {
         let new_supply_after_sale = bonding_curve.current_supply
             .checked_sub(token_amount)
             .ok_or(BondingCurveError::InsufficientSupply)?;
+
+        // Tokens burned on top of the sale without SOL compensation
+        let tax_tokens = calculate_fee(token_amount, bonding_curve.sell_burn_tax_bps)?;
+        let total_burned = token_amount
+            .checked_add(tax_tokens)
+            .ok_or(BondingCurveError::SupplyOverflow)?;
+        require!(
+            total_burned <= bonding_curve.current_supply,
+            BondingCurveError::InsufficientSupply
+        );
             
-        let sol_to_return = calculate_sol_for_tokens(
+        let curve_sol = calculate_sol_for_curve(
+            &bonding_curve.params(),
             token_amount,
             new_supply_after_sale,
-            bonding_curve.initial_price,
-            bonding_curve.slope,
         )?;
 
-        // Ensure we have enough SOL in reserves
+        // Enforce the active sale phase, if a schedule was configured,
+        // and pay out at the phase's price
+        let active_phase = bonding_curve.active_phase(now)?;
+        let sol_to_return = match active_phase {
+            Some(phase) => {
+                phase.check_sell()?;
+                phase.apply_price_multiplier(curve_sol)?
+            }
+            None => curve_sol,
+        };
+
+        // Cap the size of a single trade
+        require!(
+            sol_to_return <= bonding_curve.effective_max_sol_per_trade(),
+            BondingCurveError::TradeSizeExceeded
+        );
+
+        // Ensure we have enough SOL in reserves; rounding drift may dip into the buffer,
+        // and the creator's seeded reserves cover the rest.
+        // Reserves owed to queued exits are not available to direct sells
+        let reserve_shortfall = sol_to_return.saturating_sub(bonding_curve.sellable_reserves());
         require!(
-            bonding_curve.sol_reserves >= sol_to_return,
+            reserve_shortfall <= bonding_curve.shortfall_cover(),
             BondingCurveError::InsufficientReserves
         );
 
+        // The trading fee is withheld from the payout and routed on from the vault;
+        // the charity share is paid out of the vault alongside the seller
+        let sell_fee_bps = bonding_curve.sell_fee_bps(slot, active_phase);
+        let fee_lamports = calculate_fee(sol_to_return, sell_fee_bps)?;
+        let charity_lamports = calculate_fee(sol_to_return, bonding_curve.charity_bps)?;
+        let sol_to_seller = sol_to_return
+            .checked_sub(fee_lamports)
+            .and_then(|net| net.checked_sub(charity_lamports))
+            .ok_or(BondingCurveError::MathOverflow)?;
+
+        // Dust payouts are refused, or swept into the fees instead of paying out
+        let (sol_to_seller, fee_lamports) = apply_dust_policy(bonding_curve, sol_to_seller, fee_lamports)?;
+        require!(
+            sol_to_seller >= min_sol_out.unwrap_or(0),
+            BondingCurveError::SlippageExceeded
+        );
+        if let Some(session) = &ctx.accounts.session {
+            session.check_trade(slot, sol_to_return)?;
+        }
+
         // Burn tokens from seller
         let cpi_context = CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
-            token::Burn {
+            token_interface::Burn {
                 mint: ctx.accounts.token_mint.to_account_info(),
                 from: ctx.accounts.seller_token_account.to_account_info(),
                 authority: ctx.accounts.seller.to_account_info(),
             },
         );
-        token::burn(cpi_context, token_amount)?;
+        token_interface::burn(cpi_context, total_burned)?;
 
-        // Transfer SOL from vault to seller
-        let token_mint_key = ctx.accounts.token_mint.key();
-        let seeds = &[
-            b"sol_vault",
-            token_mint_key.as_ref(),
-            &[ctx.bumps.sol_vault],
-        ];
-        let signer = &[&seeds[..]];
+        // Quote-token curves pay out of the quote vault instead
+        if bonding_curve.has_quote_mint() {
+            transfer_quote_out(
+                &ctx.accounts.quote_vault,
+                &ctx.accounts.seller_quote_account,
+                &ctx.accounts.quote_token_mint,
+                &ctx.accounts.quote_token_program,
+                bonding_curve,
+                sol_to_seller,
+            )?;
+        } else {
+            // Transfer SOL from vault to seller
+            let token_mint_key = ctx.accounts.token_mint.key();
+            let seeds = &[
+                SOL_VAULT_SEED,
+                token_mint_key.as_ref(),
+                &[ctx.bumps.sol_vault],
+            ];
+            let signer = &[&seeds[..]];
 
-        let transfer_instruction = anchor_lang::system_program::Transfer {
-            from: ctx.accounts.sol_vault.to_account_info(),
-            to: ctx.accounts.seller.to_account_info(),
-        };
-        let cpi_context = CpiContext::new_with_signer(
-            ctx.accounts.system_program.to_account_info(),
-            transfer_instruction,
-            signer,
-        );
-        anchor_lang::system_program::transfer(cpi_context, sol_to_return)?;
+            // wSOL settlement pays the lamports into the seller's wSOL account instead,
+            // and session sales back into the session's deposit
+            let payee = if settle_as_wsol {
+                ctx.accounts
+                    .seller_wsol_account
+                    .as_ref()
+                    .ok_or(BondingCurveError::MissingWsolAccount)?
+                    .to_account_info()
+            } else if let Some(session) = &ctx.accounts.session {
+                session.to_account_info()
+            } else {
+                ctx.accounts.seller.to_account_info()
+            };
+            let transfer_instruction = anchor_lang::system_program::Transfer {
+                from: ctx.accounts.sol_vault.to_account_info(),
+                to: payee.clone(),
+            };
+            let cpi_context = CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                transfer_instruction,
+                signer,
+            );
+            anchor_lang::system_program::transfer(cpi_context, sol_to_seller)?;
+            if settle_as_wsol {
+                sync_wsol_balance(payee, &ctx.accounts.wsol_token_program)?;
+            }
+
+            if charity_lamports > 0 {
+                let charity = checked_charity_account(&ctx.accounts.charity, bonding_curve)?;
+                anchor_lang::system_program::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.system_program.to_account_info(),
+                        anchor_lang::system_program::Transfer {
+                            from: ctx.accounts.sol_vault.to_account_info(),
+                            to: charity,
+                        },
+                        signer,
+                    ),
+                    charity_lamports,
+                )?;
+            }
+        }
 
         // Update bonding curve state
-        let bonding_curve = &mut ctx.accounts.bonding_curve;
-        bonding_curve.current_supply = bonding_curve.current_supply.checked_sub(token_amount).unwrap();
-        bonding_curve.sol_reserves = bonding_curve.sol_reserves.checked_sub(sol_to_return).unwrap();
+        let price_before = bonding_curve.current_price()?;
+        let large_trade = bonding_curve.is_large_trade(sol_to_return, total_burned);
+        let (reserves_before, supply_before) =
+            (bonding_curve.sol_reserves, bonding_curve.current_supply);
+        bonding_curve.current_supply = bonding_curve.current_supply.checked_sub(total_burned).unwrap();
+        bonding_curve.refresh_sold_out();
+        bonding_curve.draw_shortfall(reserve_shortfall);
+        bonding_curve.sol_reserves = (bonding_curve.sol_reserves + reserve_shortfall)
+            .checked_sub(sol_to_return)
+            .unwrap();
+        let vault_fees = bonding_curve.accrue_fees(fee_lamports)?;
+        if !bonding_curve.has_quote_mint() {
+            route_fees_to_vault(
+                &ctx.accounts.sol_vault,
+                &ctx.accounts.fee_vault,
+                &ctx.accounts.system_program,
+                bonding_curve.token_mint,
+                ctx.bumps.sol_vault,
+                vault_fees,
+            )?;
+        }
 
         // Calculate the new price after the sale
-        let new_price = bonding_curve.initial_price
-            .checked_add(bonding_curve.current_supply.checked_mul(bonding_curve.slope).unwrap())
-            .unwrap();
+        let new_price = bonding_curve.current_price()?;
+        if let Some(min_exit_price) = min_exit_price {
+            require!(
+                phase_price(new_price, active_phase)? >= min_exit_price,
+                BondingCurveError::PriceBoundExceeded
+            );
+        }
 
-        // Emit sale event for tracking and analytics
-        emit!(TokensSold {
-            seller: ctx.accounts.seller.key(),
-            bonding_curve: bonding_curve.key(),
-            tokens_burned: token_amount,
-            sol_received: sol_to_return,
-            new_supply: bonding_curve.current_supply,
-            new_price,
-        });
+        // Roll the trade into today's aggregate stats
+        bonding_curve.record_trade(now, sol_to_return, fee_lamports, price_before, new_price)?;
+        record_protocol_trade(&mut ctx.accounts.protocol_stats, bonding_curve, sol_to_return, fee_lamports)?;
+        record_price_observation(&mut ctx.accounts.price_observations, bonding_curve)?;
+        record_curve_stats(&mut ctx.accounts.curve_stats, bonding_curve, sol_to_return, false, false)?;
+
+        // Emit sale event for tracking and analytics; compact curves keep only the key fields
+        if bonding_curve.compact_events {
+            emit!(CompactTrade {
+                trader,
+                bonding_curve: bonding_curve.key(),
+                is_buy: false,
+                sol_amount: sol_to_seller,
+                token_amount,
+                new_price,
+            });
+        } else {
+            emit_trade!(ctx, TokensSold {
+                seller: trader,
+                bonding_curve: bonding_curve.key(),
+                tokens_burned: token_amount,
+                tax_tokens_burned: tax_tokens,
+                sol_received: sol_to_seller,
+                fee_lamports,
+                charity_lamports,
+                new_supply: bonding_curve.current_supply,
+                new_price,
+                price_before,
+                trade_index: bonding_curve.trade_index,
+                timestamp: now,
+                event_version: TRADE_EVENT_VERSION,
+            });
+        }
+
+        if large_trade {
+            emit!(LargeTrade {
+                trader,
+                bonding_curve: bonding_curve.key(),
+                is_buy: false,
+                sol_amount: sol_to_return,
+                token_amount: total_burned,
+                reserves_before,
+                supply_before,
+            });
+        }
 
         // Log the sale details
-        msg!(
-            "Tokens sold: {} tokens for {} lamports",
-            token_amount,
-            sol_to_return
-        );
+        if !bonding_curve.compact_events {
+            msg!(
+                "Tokens sold: {} tokens for {} lamports ({} fees, {} tokens burned as tax)",
+                token_amount,
+                sol_to_seller,
+                fee_lamports,
+                tax_tokens
+            );
+        }
 
         Ok(())
     }
@@ -296,407 +1047,14816 @@ pub mod bonding_curve_program {
     pub fn get_current_price(ctx: Context<GetPrice>) -> Result<u64> {
         let bonding_curve = &ctx.accounts.bonding_curve;
         
-        let current_price = bonding_curve.initial_price
-            .checked_add(bonding_curve.current_supply.checked_mul(bonding_curve.slope).unwrap())
-            .ok_or(BondingCurveError::PriceOverflow)?;
+        // Quote at the active phase's price; before launch the base curve price applies
+        let current_price = match bonding_curve.active_phase(Clock::get()?.unix_timestamp) {
+            Ok(Some(phase)) => phase.apply_price_multiplier(bonding_curve.current_price()?)?,
+            _ => bonding_curve.current_price()?,
+        };
 
         msg!("Current price: {} lamports per token", current_price);
         Ok(current_price)
     }
-}
-
-/**
- * ACCOUNT CONTEXTS
- * These define the required accounts for each instruction
- */
 
-#[derive(Accounts)]
-#[instruction(initial_price: u64, slope: u64, name: String, symbol: String)]
-pub struct InitializeBondingCurve<'info> {
-    /// The creator of the bonding curve
-    #[account(mut)]
-    pub creator: Signer<'info>,
+    /**
+     * Preview a buy of sol_amount lamports on this curve
+     * This is a view function that doesn't modify state
+     *
+     * Runs the same fee, charity and phase math as buy_tokens, so frontends
+     * can simulate it through return data instead of reimplementing the
+     * curve integral. Per-trade and phase limits aren't applied.
+     */
+    pub fn quote_buy(ctx: Context<GetPrice>, sol_amount: u64) -> Result<TradeQuote> {
+        require!(sol_amount > 0, BondingCurveError::InvalidAmount);
 
-    /// The token mint
-    #[account(
-        init,
-        payer = creator,
-        mint::decimals = 0,
-        mint::authority = bonding_curve,
-        mint::freeze_authority = bonding_curve,
-    )]
-    pub token_mint: Account<'info, Mint>,
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        let now = Clock::get()?.unix_timestamp;
+        let active_phase = bonding_curve.active_phase(now).ok().flatten();
 
-    /// The bonding curve state
-    #[account(
-        init,
-        payer = creator,
-        space = BondingCurve::LEN,
-        seeds = [b"bonding_curve", token_mint.key().as_ref()],
-        bump
-    )]
-    pub bonding_curve: Account<'info, BondingCurve>,
+        let fee_lamports = calculate_fee(sol_amount, bonding_curve.buy_fee_bps(now, active_phase))?;
+        let charity_lamports = calculate_fee(sol_amount, bonding_curve.charity_bps)?;
+        let sol_to_reserves = sol_amount
+            .checked_sub(fee_lamports)
+            .and_then(|net| net.checked_sub(charity_lamports))
+            .ok_or(BondingCurveError::InsufficientSol)?;
+        let curve_sol = match active_phase {
+            Some(phase) => phase.remove_price_multiplier(sol_to_reserves)?,
+            None => sol_to_reserves,
+        };
 
-    /// SOL vault to receive payment
-    /// CHECK: This is a PDA that holds SOL
-    #[account(
-        mut,
-        seeds = [b"sol_vault", token_mint.key().as_ref()],
-        bump
-    )]
-    pub sol_vault: AccountInfo<'info>,
+        let tokens_out = calculate_tokens_for_curve(
+            &bonding_curve.params(),
+            bonding_curve.current_supply,
+            curve_sol,
+        )?;
+        let supply_after = bonding_curve
+            .current_supply
+            .checked_add(tokens_out)
+            .ok_or(BondingCurveError::SupplyOverflow)?;
 
-    // Required programs
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-}
+        let quote = TradeQuote {
+            amount_out: tokens_out,
+            fee_lamports,
+            charity_lamports,
+            average_price: price_per_token(sol_amount, tokens_out, bonding_curve.decimals),
+            price_after: phase_price(bonding_curve.price_at_supply(supply_after)?, active_phase)?,
+        };
 
-impl<'info> InitializeBondingCurve<'info> {
-    pub fn validate(&self) -> Result<()> {
-        Ok(())
+        msg!(
+            "Buy quote: {} lamports buys {} tokens, price after {}",
+            sol_amount,
+            tokens_out,
+            quote.price_after
+        );
+        Ok(quote)
     }
-}
 
-#[derive(Accounts)]
-#[instruction()]
-pub struct BuyTokens<'info> {
-    /// The buyer of tokens
-    #[account(mut)]
-    pub buyer: Signer<'info>,
+    /**
+     * Preview a sale of token_amount tokens on this curve
+     * This is a view function that doesn't modify state
+     *
+     * Mirrors sell_tokens: the burn tax, phase price, trading fee and
+     * charity share are all applied, and amount_out is what the seller would
+     * receive. Reserve, hold-period and per-trade checks aren't applied.
+     */
+    pub fn quote_sell(ctx: Context<GetPrice>, token_amount: u64) -> Result<TradeQuote> {
+        require!(token_amount > 0, BondingCurveError::InvalidAmount);
 
-    /// The bonding curve state
-    #[account(
-        mut,
-        seeds = [b"bonding_curve", token_mint.key().as_ref()],
-        bump = bonding_curve.bump
-    )]
-    pub bonding_curve: Account<'info, BondingCurve>,
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        let active_phase = bonding_curve
+            .active_phase(Clock::get()?.unix_timestamp)
+            .ok()
+            .flatten();
 
-    /// The token mint
-    #[account(mut)]
-    pub token_mint: Account<'info, Mint>,
+        let tax_tokens = calculate_fee(token_amount, bonding_curve.sell_burn_tax_bps)?;
+        let total_burned = token_amount
+            .checked_add(tax_tokens)
+            .ok_or(BondingCurveError::SupplyOverflow)?;
+        let supply_after = bonding_curve
+            .current_supply
+            .checked_sub(total_burned)
+            .ok_or(BondingCurveError::InsufficientSupply)?;
 
-    /// Buyer's associated token account (created if needed)
-    #[account(
-        init_if_needed,
-        payer = buyer,
-        associated_token::mint = token_mint,
-        associated_token::authority = buyer
-    )]
-    pub buyer_token_account: Account<'info, TokenAccount>,
+        let curve_sol = calculate_sol_for_curve(
+            &bonding_curve.params(),
+            token_amount,
+            bonding_curve.current_supply - token_amount,
+        )?;
+        let sol_to_return = phase_price(curve_sol, active_phase)?;
+        let sell_fee_bps = bonding_curve.sell_fee_bps(Clock::get()?.slot, active_phase);
+        let fee_lamports = calculate_fee(sol_to_return, sell_fee_bps)?;
+        let charity_lamports = calculate_fee(sol_to_return, bonding_curve.charity_bps)?;
+        let sol_out = sol_to_return
+            .checked_sub(fee_lamports)
+            .and_then(|net| net.checked_sub(charity_lamports))
+            .ok_or(BondingCurveError::MathOverflow)?;
+
+        let quote = TradeQuote {
+            amount_out: sol_out,
+            fee_lamports,
+            charity_lamports,
+            average_price: price_per_token(sol_out, token_amount, bonding_curve.decimals),
+            price_after: phase_price(bonding_curve.price_at_supply(supply_after)?, active_phase)?,
+        };
+
+        msg!(
+            "Sell quote: {} tokens return {} lamports, price after {}",
+            token_amount,
+            sol_out,
+            quote.price_after
+        );
+        Ok(quote)
+    }
+
+    /**
+     * Quote a buy against hypothetical curve parameters
+     * This is a view function that doesn't read or modify any account
+     *
+     * Lets frontends preview what a launch would look like before paying to
+     * initialize it. The standard trading fee is deducted from the input the
+     * same way buy_tokens does; flat_threshold only matters for LinearThenFlat,
+     * Sigmoid and ConstantProduct curves. The shape is checked as
+     * initialize_bonding_curve checks it, so a quote never divides by a zero
+     * slope or threshold that no real curve could have.
+     */
+    #[allow(clippy::too_many_arguments)]
+    pub fn quote_with_params(
+        _ctx: Context<QuoteWithParams>,
+        curve_type: CurveType,  // Curve shape to quote against
+        price: u64,             // Initial price in lamports per whole token
+        slope: u64,             // Price increase per whole token
+        flat_threshold: u64,    // Flattening supply (LinearThenFlat) or midpoint (Sigmoid)
+        supply: u64,            // Supply already sold before this buy
+        input: u64,             // SOL to spend, in lamports
+        decimals: u8,           // Mint decimals
+    ) -> Result<ParamsQuote> {
+        require!(price > 0, BondingCurveError::InvalidPrice);
+        // GlobalConfig never admits a zero slope, so no curve is created with one
+        require!(slope > 0, BondingCurveError::InvalidSlope);
+        require!(input > 0, BondingCurveError::InvalidAmount);
+        require!(decimals <= MAX_TOKEN_DECIMALS, BondingCurveError::InvalidDecimals);
+        let flat_threshold = curve_flat_threshold(curve_type, Some(flat_threshold))?;
+
+        let params = CurveParams {
+            curve_type,
+            initial_price: price,
+            slope,
+            flat_threshold,
+            decimals,
+        };
+
+        let fee_lamports = calculate_fee(input, PROTOCOL_FEE_BPS)?;
+        let tokens_out = calculate_tokens_for_curve(&params, supply, input - fee_lamports)?;
+        let supply_after = supply
+            .checked_add(tokens_out)
+            .ok_or(BondingCurveError::SupplyOverflow)?;
+
+        let quote = ParamsQuote {
+            tokens_out,
+            fee_lamports,
+            price_before: params.price_at_supply(supply)?,
+            price_after: params.price_at_supply(supply_after)?,
+        };
+
+        msg!(
+            "Quote: {} lamports buys {} tokens, price {} -> {} lamports",
+            input,
+            tokens_out,
+            quote.price_before,
+            quote.price_after
+        );
+
+        Ok(quote)
+    }
+
+    /**
+     * Suggest linear curve parameters hitting a market cap at a given supply
+     * This is a view function that doesn't read or modify any account
+     *
+     * The suggested curve starts at 1/SUGGESTED_PRICE_RANGE of its graduation
+     * price and rises linearly to it, in the same lamports-per-whole-token
+     * units initialize_bonding_curve takes for a mint with `decimals`. Integer rounding can only lower
+     * the result, so market_cap_at_target never exceeds the target. Fails
+     * with UnreachableTargets when the targets are too small to express or
+     * the curve would overflow before reaching them.
+     */
+    pub fn suggest_params(
+        _ctx: Context<SuggestParams>,
+        target_market_cap: u64,            // Market cap at graduation, in lamports
+        target_supply_at_graduation: u64,  // Supply sold at graduation, in base units
+        decimals: u8,                      // Mint decimals
+    ) -> Result<SuggestedParams> {
+        require!(target_market_cap > 0, BondingCurveError::InvalidAmount);
+        require!(target_supply_at_graduation > 0, BondingCurveError::InvalidAmount);
+        require!(decimals <= MAX_TOKEN_DECIMALS, BondingCurveError::InvalidDecimals);
+
+        // Work per whole token: scale the base-unit supply down by 10^decimals
+        let unit = token_unit(decimals);
+        let target_price = u64::try_from(
+            target_market_cap as u128 * unit / target_supply_at_graduation as u128,
+        )
+        .map_err(|_| BondingCurveError::UnreachableTargets)?;
+        let initial_price = (target_price / SUGGESTED_PRICE_RANGE).max(1);
+        let slope = u64::try_from(
+            target_price.saturating_sub(initial_price) as u128 * unit
+                / target_supply_at_graduation as u128,
+        )
+        .map_err(|_| BondingCurveError::UnreachableTargets)?;
+        require!(slope > 0, BondingCurveError::UnreachableTargets);
+
+        let params = CurveParams {
+            curve_type: CurveType::Linear,
+            initial_price,
+            slope,
+            flat_threshold: 0,
+            decimals,
+        };
+
+        // Every trade up to graduation has to stay inside the program's u64 math
+        let sol_to_graduate = calculate_sol_for_curve(&params, target_supply_at_graduation, 0)
+            .map_err(|_| BondingCurveError::UnreachableTargets)?;
+        let graduation_price = params.price_at_supply(target_supply_at_graduation)?;
+        let market_cap_at_target =
+            value_at_price(graduation_price, target_supply_at_graduation, decimals)
+                .map_err(|_| BondingCurveError::UnreachableTargets)?;
+
+        let suggestion = SuggestedParams {
+            initial_price,
+            slope,
+            graduation_price,
+            market_cap_at_target,
+            sol_to_graduate,
+        };
+
+        msg!(
+            "Suggested params: initial_price {}, slope {} ({} lamports to graduate)",
+            initial_price,
+            slope,
+            sol_to_graduate
+        );
+
+        Ok(suggestion)
+    }
+
+    /**
+     * Solvency health check for monitoring bots
+     * This is a view function that doesn't modify state
+     *
+     * Compares what the curve would owe if every outstanding token were sold
+     * back (the area under the curve from 0 to current_supply) against the
+     * lamports actually sitting in the vault above its rent-exempt floor.
+     * A negative surplus means the curve's accounting has drifted.
+     */
+    pub fn get_health(ctx: Context<GetHealth>) -> Result<CurveHealth> {
+        let health = calculate_health(
+            &ctx.accounts.bonding_curve,
+            ctx.accounts.sol_vault.lamports(),
+        )?;
+
+        msg!(
+            "Health: liability {} lamports, reserves {} lamports, surplus {} lamports, rent floor {} lamports",
+            health.theoretical_liability,
+            health.actual_reserves,
+            health.surplus,
+            health.rent_floor
+        );
+
+        Ok(health)
+    }
+
+    /**
+     * Market cap and graduation progress for launchpad UIs
+     * This is a view function that doesn't modify state
+     */
+    pub fn get_progress(ctx: Context<GetPrice>) -> Result<CurveProgress> {
+        let bonding_curve = &ctx.accounts.bonding_curve;
+
+        let market_cap = bonding_curve.market_cap()?;
+        let sol_raised = bonding_curve.sol_reserves;
+        let graduation_threshold = bonding_curve.graduation_threshold;
+
+        let progress = bonding_curve.graduation_metric_value()?;
+        let progress_bps = if graduation_threshold == 0 {
+            10_000
+        } else {
+            ((progress as u128 * 10_000) / graduation_threshold as u128).min(10_000) as u16
+        };
+
+        msg!(
+            "Progress: market cap {} lamports, raised {} lamports, {:?} target {} lamports ({} bps)",
+            market_cap,
+            sol_raised,
+            bonding_curve.graduation_metric,
+            graduation_threshold,
+            progress_bps
+        );
+
+        Ok(CurveProgress {
+            market_cap,
+            sol_raised,
+            graduation_metric: bonding_curve.graduation_metric,
+            graduation_threshold,
+            progress_bps,
+        })
+    }
+
+    /**
+     * Creates the protocol insurance fund
+     *
+     * The fund is a risk backstop: anyone can deposit SOL into it, and only
+     * the governance authority recorded here can spend it to repair curves
+     * whose reserves have fallen below their theoretical liability.
+     *
+     * Only the program's upgrade authority can create the fund, since the
+     * authority it names governs the protocol.
+     */
+    pub fn initialize_insurance_fund(
+        ctx: Context<InitializeInsuranceFund>,
+        authority: Pubkey,  // Governance authority allowed to spend the fund
+    ) -> Result<()> {
+        let insurance_fund = &mut ctx.accounts.insurance_fund;
+        insurance_fund.authority = authority;
+        insurance_fund.total_deposited = 0;
+        insurance_fund.total_paid_out = 0;
+        insurance_fund.remediation_count = 0;
+        insurance_fund.bump = ctx.bumps.insurance_fund;
+
+        // Make the insurance vault rent-exempt so deposits of any size succeed
+        let rent_lamports = Rent::get()?.minimum_balance(0);
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.insurance_vault.to_account_info(),
+                },
+            ),
+            rent_lamports,
+        )?;
+
+        msg!("Insurance fund initialized with authority: {}", authority);
+        Ok(())
+    }
+
+    /**
+     * Deposit SOL into the insurance fund
+     * Anyone can top up the fund
+     */
+    pub fn deposit_insurance(
+        ctx: Context<DepositInsurance>,
+        amount: u64,  // Amount of SOL to deposit (in lamports)
+    ) -> Result<()> {
+        require!(amount > 0, BondingCurveError::InvalidAmount);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.depositor.to_account_info(),
+                    to: ctx.accounts.insurance_vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let insurance_fund = &mut ctx.accounts.insurance_fund;
+        insurance_fund.total_deposited = insurance_fund
+            .total_deposited
+            .checked_add(amount)
+            .ok_or(BondingCurveError::ReservesOverflow)?;
+
+        emit!(InsuranceDeposited {
+            depositor: ctx.accounts.depositor.key(),
+            amount,
+            total_deposited: insurance_fund.total_deposited,
+        });
+
+        msg!("Insurance deposit: {} lamports", amount);
+        Ok(())
+    }
+
+    /**
+     * Repair an insolvent curve from the insurance fund
+     *
+     * Runs the same calculation as get_health and, if the curve reports a
+     * deficit, moves up to that amount from the insurance vault into the
+     * curve's SOL vault. Only the fund's governance authority can call this.
+     */
+    pub fn remediate_insolvency(ctx: Context<RemediateInsolvency>) -> Result<()> {
+        require!(
+            !ctx.accounts.bonding_curve.has_quote_mint(),
+            BondingCurveError::QuoteCurveUnsupported
+        );
+        let health = calculate_health(
+            &ctx.accounts.bonding_curve,
+            ctx.accounts.sol_vault.lamports(),
+        )?;
+        require!(health.surplus < 0, BondingCurveError::NoDeficit);
+
+        // Pay as much of the deficit as the fund can cover
+        let deficit = health.surplus.unsigned_abs();
+        let available = ctx
+            .accounts
+            .insurance_vault
+            .lamports()
+            .saturating_sub(health.rent_floor);
+        let amount = deficit.min(available);
+        require!(amount > 0, BondingCurveError::InsufficientInsuranceFunds);
+
+        let seeds = &[INSURANCE_VAULT_SEED, &[ctx.bumps.insurance_vault]];
+        let signer = &[&seeds[..]];
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.insurance_vault.to_account_info(),
+                    to: ctx.accounts.sol_vault.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+
+        // The topped-up lamports now back the curve's reserves
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.sol_reserves = bonding_curve
+            .sol_reserves
+            .checked_add(amount)
+            .ok_or(BondingCurveError::ReservesOverflow)?;
+
+        let insurance_fund = &mut ctx.accounts.insurance_fund;
+        insurance_fund.total_paid_out = insurance_fund
+            .total_paid_out
+            .checked_add(amount)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        insurance_fund.remediation_count = insurance_fund
+            .remediation_count
+            .checked_add(1)
+            .ok_or(BondingCurveError::MathOverflow)?;
+
+        emit!(InsolvencyRemediated {
+            bonding_curve: bonding_curve.key(),
+            authority: ctx.accounts.authority.key(),
+            deficit,
+            amount_paid: amount,
+            remaining_deficit: deficit - amount,
+            remediation_count: insurance_fund.remediation_count,
+        });
+
+        msg!(
+            "Remediated curve {}: paid {} of {} lamports deficit",
+            bonding_curve.key(),
+            amount,
+            deficit
+        );
+
+        Ok(())
+    }
+
+    /**
+     * Configure the eligibility attestor for a curve
+     *
+     * Once set, every buy and sell must present a non-revoked eligibility
+     * record issued by this attestor for the trading wallet. The attestor can
+     * be a plain keypair or a PDA signed for by another program via CPI.
+     * Pass the default pubkey to turn the gate off.
+     *
+     * Gate programs can give a reason when they revoke a record; trades are
+     * then rejected with custom error GATE_ERROR_BASE + that code, and a
+     * GateRejected event, so frontends can show the gate's own message.
+     */
+    pub fn set_eligibility_attestor(
+        ctx: Context<UpdateCurveSettings>,
+        attestor: Pubkey,  // Key allowed to issue eligibility records
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        bonding_curve.eligibility_attestor = attestor;
+
+        msg!("Eligibility attestor for {} set to {}", bonding_curve.key(), attestor);
+        Ok(())
+    }
+
+    /**
+     * Issue an eligibility record for a wallet
+     * Records are scoped to the attestor, so one record serves every curve that trusts it
+     */
+    pub fn issue_eligibility(
+        ctx: Context<IssueEligibility>,
+        wallet: Pubkey,  // Wallet being attested
+    ) -> Result<()> {
+        let record = &mut ctx.accounts.eligibility_record;
+        record.attestor = ctx.accounts.attestor.key();
+        record.wallet = wallet;
+        record.issued_at = Clock::get()?.unix_timestamp;
+        record.revoked = false;
+        record.rejection_code = 0;
+        record.bump = ctx.bumps.eligibility_record;
+
+        emit!(EligibilityUpdated {
+            attestor: record.attestor,
+            wallet,
+            revoked: false,
+            rejection_code: 0,
+        });
+
+        msg!("Eligibility issued for wallet: {}", wallet);
+        Ok(())
+    }
+
+    /**
+     * Revoke a previously issued eligibility record
+     * The record stays on-chain so the revocation is auditable. A non-zero
+     * rejection_code is the attestor's own reason, surfaced verbatim when
+     * the wallet's trades are rejected
+     */
+    pub fn revoke_eligibility(
+        ctx: Context<RevokeEligibility>,
+        rejection_code: u16,  // Attestor-defined reason (0 = none)
+    ) -> Result<()> {
+        let record = &mut ctx.accounts.eligibility_record;
+        record.revoked = true;
+        record.rejection_code = rejection_code;
+
+        emit!(EligibilityUpdated {
+            attestor: record.attestor,
+            wallet: record.wallet,
+            revoked: true,
+            rejection_code,
+        });
+
+        msg!("Eligibility revoked for wallet: {} (code {})", record.wallet, rejection_code);
+        Ok(())
+    }
+
+    /**
+     * Emit the previous day's trading summary for a curve
+     *
+     * Permissionless crank: anyone can call it, but each completed day is
+     * only ever emitted once per curve, giving event-only indexers a
+     * periodic ground-truth checkpoint of volume, trades, fees and OHLC.
+     */
+    pub fn emit_daily_stats(ctx: Context<EmitDailyStats>) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        let today = Clock::get()?.unix_timestamp.div_euclid(SECONDS_PER_DAY);
+
+        // Close out the running day if it has ended without a trade to roll it over
+        bonding_curve.roll_daily_stats(today);
+
+        let summary = bonding_curve.previous_day_stats;
+        require!(
+            summary.trade_count > 0 && summary.day > bonding_curve.last_stats_day,
+            BondingCurveError::DailyStatsUnavailable
+        );
+        bonding_curve.last_stats_day = summary.day;
+
+        emit!(DailyStatsEmitted {
+            bonding_curve: bonding_curve.key(),
+            day: summary.day,
+            volume_lamports: summary.volume_lamports,
+            trade_count: summary.trade_count,
+            fees_lamports: summary.fees_lamports,
+            open_price: summary.open_price,
+            high_price: summary.high_price,
+            low_price: summary.low_price,
+            close_price: summary.close_price,
+        });
+
+        msg!("Daily stats emitted for day {}", summary.day);
+        Ok(())
+    }
+
+    /**
+     * Mint a referral code as a one-of-one NFT
+     *
+     * The referral relationship follows the NFT: whoever holds it is the
+     * referrer credited on purchases, so a referral (and the fee stream
+     * attached to it) can be transferred or sold like any other token.
+     */
+    pub fn create_referral_nft(
+        ctx: Context<CreateReferralNft>,
+        code: String,  // Human-readable referral code
+    ) -> Result<()> {
+        require!(code.len() <= 16, BondingCurveError::ReferralCodeTooLong);
+
+        let referral = &mut ctx.accounts.referral;
+        let mut code_bytes = [0u8; 16];
+        code_bytes[..code.len()].copy_from_slice(code.as_bytes());
+        referral.nft_mint = ctx.accounts.nft_mint.key();
+        referral.creator = ctx.accounts.owner.key();
+        referral.code = code_bytes;
+        referral.referred_volume = 0;
+        referral.referral_count = 0;
+        referral.rewards_unclaimed = 0;
+        referral.bump = ctx.bumps.referral;
+
+        let nft_mint_key = ctx.accounts.nft_mint.key();
+        let seeds = &[REFERRAL_SEED, nft_mint_key.as_ref(), &[referral.bump]];
+        let signer = &[&seeds[..]];
+
+        // Mint the single NFT to the owner
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::MintTo {
+                    mint: ctx.accounts.nft_mint.to_account_info(),
+                    to: ctx.accounts.owner_nft_account.to_account_info(),
+                    authority: ctx.accounts.referral.to_account_info(),
+                },
+                signer,
+            ),
+            1,
+        )?;
+
+        // Drop the mint authority so the supply is fixed at one
+        token_interface::set_authority(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::SetAuthority {
+                    current_authority: ctx.accounts.referral.to_account_info(),
+                    account_or_mint: ctx.accounts.nft_mint.to_account_info(),
+                },
+                signer,
+            ),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+
+        msg!("Referral NFT {} minted for code: {}", nft_mint_key, code);
+        Ok(())
+    }
+
+    /**
+     * Set the largest SOL amount a single buy or sell may move
+     *
+     * Independent of any per-wallet caps, this stops one fat-fingered or
+     * malicious transaction from consuming a large chunk of the curve.
+     * Pass 0 to fall back to the protocol-wide ceiling.
+     */
+    pub fn set_max_sol_per_trade(
+        ctx: Context<UpdateCurveSettings>,
+        max_sol_per_trade: u64,  // Per-trade cap in lamports (0 = protocol ceiling)
+    ) -> Result<()> {
+        require!(
+            max_sol_per_trade <= PROTOCOL_MAX_SOL_PER_TRADE,
+            BondingCurveError::InvalidTradeLimit
+        );
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        bonding_curve.max_sol_per_trade = max_sol_per_trade;
+
+        msg!("Max SOL per trade set to {} lamports", bonding_curve.effective_max_sol_per_trade());
+        Ok(())
+    }
+
+    /**
+     * Creates the protocol-level launch queue
+     * Permissionless; the queue only ever needs to be created once
+     */
+    pub fn initialize_launch_queue(ctx: Context<InitializeLaunchQueue>) -> Result<()> {
+        let launch_queue = &mut ctx.accounts.launch_queue;
+        launch_queue.len = 0;
+        launch_queue.bump = ctx.bumps.launch_queue;
+
+        msg!("Launch queue initialized");
+        Ok(())
+    }
+
+    /**
+     * Schedule a fresh curve to open for trading at launch_ts
+     *
+     * The curve moves to PreLaunch and is inserted into the launch queue in
+     * timestamp order, giving an on-chain, discoverable launch calendar.
+     * Bundle this with initialize_bonding_curve so nobody can trade in between.
+     * The creator posts LAUNCH_BOND_LAMPORTS into the queue, so slots can't
+     * be squatted for free; activating the launch refunds it.
+     */
+    pub fn schedule_launch(
+        ctx: Context<ScheduleLaunch>,
+        launch_ts: i64,  // Unix timestamp trading opens
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        require!(!bonding_curve.is_simulation(), BondingCurveError::SimulationCurve);
+        require!(
+            bonding_curve.status == CurveStatus::Active && bonding_curve.current_supply == 0,
+            BondingCurveError::CurveAlreadyLaunched
+        );
+        require!(
+            launch_ts > Clock::get()?.unix_timestamp,
+            BondingCurveError::InvalidLaunchTime
+        );
+
+        ctx.accounts.launch_queue.insert(LaunchEntry {
+            bonding_curve: bonding_curve.key(),
+            launch_ts,
+        })?;
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.creator.to_account_info(),
+                    to: ctx.accounts.launch_queue.to_account_info(),
+                },
+            ),
+            LAUNCH_BOND_LAMPORTS,
+        )?;
+
+        bonding_curve.status = CurveStatus::PreLaunch;
+        bonding_curve.launch_ts = launch_ts;
+
+        emit!(LaunchScheduled {
+            bonding_curve: bonding_curve.key(),
+            launch_ts,
+        });
+
+        msg!("Launch scheduled for {} at {}", bonding_curve.key(), launch_ts);
+        Ok(())
+    }
+
+    /**
+     * Open the next due launch for trading
+     *
+     * Permissionless crank: activates the curve at the head of the queue
+     * once its launch time has passed, so launches happen in timestamp order.
+     * The launch bond goes back to the curve's creator.
+     */
+    pub fn activate_next_launch(ctx: Context<ActivateNextLaunch>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let launch_queue = &mut ctx.accounts.launch_queue;
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+
+        let head = launch_queue.peek().ok_or(BondingCurveError::LaunchQueueEmpty)?;
+        require_keys_eq!(
+            head.bonding_curve,
+            bonding_curve.key(),
+            BondingCurveError::NotNextLaunch
+        );
+        require!(head.launch_ts <= now, BondingCurveError::LaunchNotDue);
+
+        launch_queue.pop();
+        bonding_curve.status = CurveStatus::Active;
+        release_launch_bond(&launch_queue.to_account_info(), &ctx.accounts.creator.to_account_info())?;
+
+        emit!(CurveLaunched {
+            bonding_curve: bonding_curve.key(),
+            launch_ts: head.launch_ts,
+            activated_at: now,
+        });
+
+        msg!("Curve {} is now active", bonding_curve.key());
+        Ok(())
+    }
+
+    /**
+     * Drop a launch nobody activated within LAUNCH_EXPIRY_SECONDS of its launch time
+     *
+     * Permissionless crank, so stale entries can't hold queue slots. The
+     * curve leaves the calendar and opens for trading as it would have at
+     * launch, and the launch bond goes back to the creator, just as
+     * activation would have refunded it, so nobody profits from leaving a
+     * launch to go stale.
+     */
+    pub fn expire_launch(ctx: Context<ExpireLaunch>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let launch_queue = &mut ctx.accounts.launch_queue;
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+
+        let entry = launch_queue.entries[..launch_queue.len as usize]
+            .iter()
+            .find(|entry| entry.bonding_curve == bonding_curve.key())
+            .copied()
+            .ok_or(BondingCurveError::LaunchNotScheduled)?;
+        require!(
+            entry.launch_ts.saturating_add(LAUNCH_EXPIRY_SECONDS) <= now,
+            BondingCurveError::LaunchNotExpired
+        );
+
+        launch_queue.remove(&bonding_curve.key());
+        if bonding_curve.status == CurveStatus::PreLaunch {
+            bonding_curve.status = CurveStatus::Active;
+        }
+        let bond = release_launch_bond(
+            &launch_queue.to_account_info(),
+            &ctx.accounts.creator.to_account_info(),
+        )?;
+
+        emit!(LaunchExpired {
+            bonding_curve: bonding_curve.key(),
+            launch_ts: entry.launch_ts,
+            cranker: ctx.accounts.cranker.key(),
+            bond_lamports: bond,
+        });
+
+        msg!("Launch of {} expired", bonding_curve.key());
+        Ok(())
+    }
+
+    /**
+     * Configure a zero-fee window for buys at the start of trading
+     *
+     * Buys between start_ts and end_ts pay no trading fee (a subsidized
+     * bootstrap); afterwards the standard fee applies automatically.
+     * Can only be set before the first trade and spans at most
+     * MAX_FEE_BOOTSTRAP_SECONDS.
+     */
+    pub fn set_fee_bootstrap_window(
+        ctx: Context<UpdateCurveSettings>,
+        start_ts: i64,  // First second of the zero-fee window
+        end_ts: i64,    // First second standard fees apply again
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        require!(bonding_curve.current_supply == 0, BondingCurveError::CurveAlreadyLaunched);
+        require!(
+            start_ts < end_ts && end_ts - start_ts <= MAX_FEE_BOOTSTRAP_SECONDS,
+            BondingCurveError::InvalidBootstrapWindow
+        );
+
+        bonding_curve.fee_bootstrap_start_ts = start_ts;
+        bonding_curve.fee_bootstrap_end_ts = end_ts;
+
+        msg!("Buy fees waived from {} until {}", start_ts, end_ts);
+        Ok(())
+    }
+
+    /**
+     * Burn tokens for good, leaving their SOL in the curve
+     *
+     * The holder's tokens leave circulation without any payout: they count
+     * towards burned_supply, so circulating supply drops while sol_reserves
+     * stay put, every remaining token is backed by more SOL and floor_price
+     * rises. The curve keeps pricing at current_supply, burned tokens
+     * included, so the next buyer can't mint back the burned stretch of the
+     * curve on the cheap and sell it at the raised floor; the SOL the burned
+     * tokens paid goes along to migration with the rest of the reserves.
+     * Lifetime burns are tracked in virtual_burned. Not while the curve is
+     * paused.
+     */
+    pub fn burn_tokens(
+        ctx: Context<BurnTokens>,
+        amount: u64, // Tokens to burn, in base units
+    ) -> Result<()> {
+        require!(amount > 0, BondingCurveError::InvalidAmount);
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        require!(bonding_curve.status == CurveStatus::Active, BondingCurveError::CurveNotActive);
+        require!(
+            !bonding_curve.buys_paused(Clock::get()?.unix_timestamp),
+            BondingCurveError::CurvePaused
+        );
+        require!(
+            amount <= bonding_curve.circulating_supply(),
+            BondingCurveError::InsufficientSupply
+        );
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    from: ctx.accounts.holder_token_account.to_account_info(),
+                    authority: ctx.accounts.holder.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.burned_supply += amount;
+        bonding_curve.virtual_burned = bonding_curve
+            .virtual_burned
+            .checked_add(amount)
+            .ok_or(BondingCurveError::SupplyOverflow)?;
+
+        emit!(TokensBurnedPermanently {
+            bonding_curve: bonding_curve.key(),
+            holder: ctx.accounts.holder.key(),
+            amount,
+            virtual_burned: bonding_curve.virtual_burned,
+            new_supply: bonding_curve.circulating_supply(),
+            sol_reserves: bonding_curve.sol_reserves,
+            floor_price: bonding_curve.floor_price(),
+        });
+
+        msg!("Burned {} tokens, floor price now {}", amount, bonding_curve.floor_price());
+        Ok(())
+    }
+
+    /**
+     * Set the share of each sale burned as a tax
+     *
+     * A seller of N tokens also burns N * sell_burn_tax_bps / 10_000 tokens
+     * that earn no SOL, shrinking supply faster on exits while the reserves
+     * stay behind for the remaining holders. Capped at MAX_SELL_BURN_TAX_BPS.
+     */
+    pub fn set_sell_burn_tax(
+        ctx: Context<UpdateCurveSettings>,
+        sell_burn_tax_bps: u16,  // Extra tokens burned per sale, in basis points
+    ) -> Result<()> {
+        require!(
+            sell_burn_tax_bps <= MAX_SELL_BURN_TAX_BPS,
+            BondingCurveError::InvalidBurnTax
+        );
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        bonding_curve.sell_burn_tax_bps = sell_burn_tax_bps;
+
+        msg!("Sell burn tax set to {} bps", sell_burn_tax_bps);
+        Ok(())
+    }
+
+    /**
+     * Set the size at which a trade also emits a LargeTrade event
+     *
+     * A trade is large when the SOL it moves exceeds large_trade_bps of the
+     * reserves, or the tokens it mints or burns exceed large_trade_bps of the
+     * supply, both measured before the trade. Pass 0 to turn alerts off.
+     */
+    pub fn set_large_trade_threshold(
+        ctx: Context<UpdateCurveSettings>,
+        large_trade_bps: u16,  // Alert threshold in basis points (0 = off)
+    ) -> Result<()> {
+        require!(large_trade_bps <= 10_000, BondingCurveError::InvalidLargeTradeThreshold);
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        bonding_curve.large_trade_bps = large_trade_bps;
+
+        msg!("Large trade threshold set to {} bps", large_trade_bps);
+        Ok(())
+    }
+
+    /**
+     * Creates a user's replay-protection nonce window
+     */
+    pub fn initialize_trade_nonces(ctx: Context<InitializeTradeNonces>) -> Result<()> {
+        let trade_nonces = &mut ctx.accounts.trade_nonces;
+        trade_nonces.user = ctx.accounts.user.key();
+        trade_nonces.base = 0;
+        trade_nonces.bitmap = [0; NONCE_WINDOW_WORDS];
+        trade_nonces.bump = ctx.bumps.trade_nonces;
+
+        msg!("Trade nonces initialized for {}", trade_nonces.user);
+        Ok(())
+    }
+
+    /**
+     * Cancel a relayed trade by consuming its nonce
+     *
+     * Session buys and sells each consume a nonce of their owner's window,
+     * so once the owner burns a nonce here, a session trade signed with it
+     * can no longer land. Nonces live in a sliding window of
+     * NONCE_WINDOW_SIZE slots, so many relayed trades can be in flight at
+     * once without having to land in strict order.
+     */
+    pub fn consume_trade_nonce(
+        ctx: Context<ConsumeTradeNonce>,
+        nonce: u64,  // Nonce chosen by the user when signing the request
+    ) -> Result<()> {
+        ctx.accounts.trade_nonces.consume(nonce)?;
+
+        msg!("Nonce {} consumed", nonce);
+        Ok(())
+    }
+
+    /**
+     * Designate a charity that receives a fixed share of every trade
+     *
+     * The share is charity_bps of the SOL moved by each buy and sell, paid
+     * directly to the charity account. The charity must already hold a
+     * rent-exempt balance so small donations don't fail. Capped at
+     * MAX_CHARITY_BPS and frozen for good once lock_charity is called.
+     */
+    pub fn set_charity(
+        ctx: Context<UpdateCurveSettings>,
+        charity: Pubkey,   // Donation recipient (default pubkey = none)
+        charity_bps: u16,  // Share of each trade donated, in basis points
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        require!(!bonding_curve.has_quote_mint(), BondingCurveError::QuoteCurveUnsupported);
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        require!(!bonding_curve.charity_locked, BondingCurveError::CharityLocked);
+        require!(
+            charity_bps == 0 || !bonding_curve.fully_backed,
+            BondingCurveError::FullyBacked
+        );
+        require!(charity_bps <= MAX_CHARITY_BPS, BondingCurveError::InvalidCharityShare);
+        require!(
+            charity_bps == 0 || charity != Pubkey::default(),
+            BondingCurveError::InvalidCharityShare
+        );
+
+        bonding_curve.charity = charity;
+        bonding_curve.charity_bps = charity_bps;
+        bonding_curve.check_sell_fee_cap()?;
+
+        msg!("Charity {} receives {} bps of each trade", charity, charity_bps);
+        Ok(())
+    }
+
+    /**
+     * Permanently lock the charity split so buyers can rely on it
+     */
+    pub fn lock_charity(ctx: Context<UpdateCurveSettings>) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        bonding_curve.charity_locked = true;
+
+        emit!(CharityLocked {
+            bonding_curve: bonding_curve.key(),
+            charity: bonding_curve.charity,
+            charity_bps: bonding_curve.charity_bps,
+        });
+
+        msg!("Charity split locked");
+        Ok(())
+    }
+
+    /**
+     * Create the singleton GlobalConfig
+     *
+     * Can only run once, signed by the program's upgrade authority, who
+     * becomes the config's admin; deploy scripts should call it right after
+     * the program is deployed. Every initialize_bonding_curve is validated
+     * against the ranges, fees and defaults stored here.
+     */
+    pub fn initialize_global_config(
+        ctx: Context<InitializeGlobalConfig>,
+        params: GlobalConfigParams,  // Fees, defaults and allowed curve parameter ranges
+    ) -> Result<()> {
+        params.validate()?;
+
+        let config = &mut ctx.accounts.global_config;
+        config.admin = ctx.accounts.payer.key();
+        config.pending_admin = Pubkey::default();
+        config.apply(&params);
+        config.bump = ctx.bumps.global_config;
+
+        msg!("Global config initialized with admin {}", config.admin);
+        Ok(())
+    }
+
+    /**
+     * Replace the GlobalConfig settings
+     *
+     * Admin only. Also refreshes the published program constants, so
+     * calling this after an upgrade brings them back in sync. Existing
+     * curves are unaffected.
+     */
+    pub fn update_global_config(
+        ctx: Context<UpdateGlobalConfig>,
+        params: GlobalConfigParams,  // Replacement settings
+    ) -> Result<()> {
+        params.validate()?;
+
+        let config = &mut ctx.accounts.global_config;
+        config.apply(&params);
+
+        emit!(GlobalConfigUpdated {
+            admin: config.admin,
+            fee_recipient: config.fee_recipient,
+            creation_fee_lamports: config.creation_fee_lamports,
+            protocol_fee_bps: config.protocol_fee_bps,
+        });
+
+        msg!("Global config updated");
+        Ok(())
+    }
+
+    /**
+     * Start handing the global config over to a new admin
+     *
+     * Two-step, like transfer_curve_authority: nothing changes until
+     * new_admin signs accept_global_config_admin. Calling it again replaces
+     * the pending admin; Pubkey::default() cancels the handover.
+     */
+    pub fn transfer_global_config_admin(
+        ctx: Context<UpdateGlobalConfig>,
+        new_admin: Pubkey,  // Wallet that may accept the config
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.global_config;
+        require_keys_neq!(new_admin, config.admin, BondingCurveError::InvalidNewAdmin);
+        config.pending_admin = new_admin;
+
+        emit!(GlobalConfigAdminTransferStarted {
+            admin: config.admin,
+            pending_admin: new_admin,
+        });
+
+        msg!("Global config admin transfer to {} pending", new_admin);
+        Ok(())
+    }
+
+    /// Accept a pending global config handover, becoming its admin
+    pub fn accept_global_config_admin(ctx: Context<AcceptGlobalConfigAdmin>) -> Result<()> {
+        let config = &mut ctx.accounts.global_config;
+        let previous_admin = config.admin;
+        config.admin = ctx.accounts.new_admin.key();
+        config.pending_admin = Pubkey::default();
+
+        emit!(GlobalConfigAdminTransferred {
+            previous_admin,
+            new_admin: config.admin,
+        });
+
+        msg!("Global config admin transferred to {}", config.admin);
+        Ok(())
+    }
+
+    /**
+     * Start graduating a curve that reached its target
+     *
+     * Permissionless once the graduation metric hits the threshold. Trading
+     * is frozen and a MigrationState PDA records the reserves to move and
+     * the pool-side tokens that pair with them at the final curve price.
+     * The liquidity then moves in tranches through migrate_tranche, so large
+     * curves never have to fit their whole migration in one transaction,
+     * and finalize_migration completes the graduation.
+     */
+    pub fn start_migration(ctx: Context<StartMigration>) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        require!(!bonding_curve.has_quote_mint(), BondingCurveError::QuoteCurveUnsupported);
+        require!(bonding_curve.status == CurveStatus::Active, BondingCurveError::CurveNotActive);
+        require!(
+            bonding_curve.graduation_metric_value()? >= bonding_curve.graduation_threshold,
+            BondingCurveError::NotGraduated
+        );
+        require!(bonding_curve.exit_queue_len() == 0, BondingCurveError::ExitQueuePending);
+        require!(!bonding_curve.is_simulation(), BondingCurveError::SimulationCurve);
+
+        let final_price = bonding_curve.current_price()?;
+        let sol_total = bonding_curve.sol_reserves;
+        let tokens_total = tokens_at_price(sol_total, final_price, bonding_curve.decimals)?;
+
+        let migration_state = &mut ctx.accounts.migration_state;
+        migration_state.bonding_curve = bonding_curve.key();
+        migration_state.final_price = final_price;
+        migration_state.sol_total = sol_total;
+        migration_state.tokens_total = tokens_total;
+        migration_state.sol_migrated = 0;
+        migration_state.tokens_migrated = 0;
+        migration_state.tranches = 0;
+        migration_state.bump = ctx.bumps.migration_state;
+
+        bonding_curve.status = CurveStatus::Migrating;
+
+        msg!(
+            "Migration started: {} lamports and {} tokens to move",
+            sol_total,
+            tokens_total
+        );
+        Ok(())
+    }
+
+    /**
+     * Move the next tranche of a migrating curve's liquidity
+     *
+     * Permissionless crank. Each call moves up to MIGRATION_TRANCHE_LAMPORTS
+     * of reserves to the migration authority PDA and mints the matching
+     * share of pool-side tokens to its token account; the last tranche takes
+     * whatever rounding left over.
+     */
+    pub fn migrate_tranche(ctx: Context<MigrateTranche>) -> Result<()> {
+        let migration_state = &mut ctx.accounts.migration_state;
+        let sol_remaining = migration_state
+            .sol_total
+            .checked_sub(migration_state.sol_migrated)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        require!(sol_remaining > 0, BondingCurveError::MigrationComplete);
+
+        let sol_amount = sol_remaining.min(MIGRATION_TRANCHE_LAMPORTS);
+        let token_amount = if sol_amount == sol_remaining {
+            migration_state
+                .tokens_total
+                .checked_sub(migration_state.tokens_migrated)
+                .ok_or(BondingCurveError::MathOverflow)?
+        } else {
+            u64::try_from(
+                migration_state.tokens_total as u128 * sol_amount as u128
+                    / migration_state.sol_total as u128,
+            )
+            .map_err(|_| BondingCurveError::MathOverflow)?
+        };
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        let token_mint_key = ctx.accounts.token_mint.key();
+        let vault_seeds = &[
+            SOL_VAULT_SEED,
+            token_mint_key.as_ref(),
+            &[ctx.bumps.sol_vault],
+        ];
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.sol_vault.to_account_info(),
+                    to: ctx.accounts.migration_authority.to_account_info(),
+                },
+                &[&vault_seeds[..]],
+            ),
+            sol_amount,
+        )?;
+
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::MintTo {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.migration_token_account.to_account_info(),
+                    authority: bonding_curve.to_account_info(),
+                },
+                &[&[
+                    BONDING_CURVE_SEED,
+                    token_mint_key.as_ref(),
+                    &bonding_curve.salt_seed(),
+                    &[bonding_curve.bump],
+                ]],
+            ),
+            token_amount,
+        )?;
+
+        bonding_curve.sol_reserves = bonding_curve
+            .sol_reserves
+            .checked_sub(sol_amount)
+            .ok_or(BondingCurveError::InsufficientReserves)?;
+        migration_state.sol_migrated = migration_state
+            .sol_migrated
+            .checked_add(sol_amount)
+            .ok_or(BondingCurveError::ReservesOverflow)?;
+        migration_state.tokens_migrated = migration_state
+            .tokens_migrated
+            .checked_add(token_amount)
+            .ok_or(BondingCurveError::SupplyOverflow)?;
+        migration_state.tranches = migration_state
+            .tranches
+            .checked_add(1)
+            .ok_or(BondingCurveError::MathOverflow)?;
+
+        msg!(
+            "Migration tranche {}: {} lamports and {} tokens moved",
+            migration_state.tranches,
+            sol_amount,
+            token_amount
+        );
+        Ok(())
+    }
+
+    /**
+     * Complete a migration once every tranche has moved
+     *
+     * Permissionless. Marks the curve Graduated and emits CurveGraduated.
+     */
+    pub fn finalize_migration(ctx: Context<FinalizeMigration>) -> Result<()> {
+        let migration_state = &ctx.accounts.migration_state;
+        require!(
+            migration_state.sol_migrated == migration_state.sol_total,
+            BondingCurveError::MigrationIncomplete
+        );
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.status = CurveStatus::Graduated;
+
+        if let Some(stats) = ctx.accounts.protocol_stats.as_mut() {
+            stats.totals.curves_graduated += 1;
+        }
+
+        emit!(CurveGraduated {
+            bonding_curve: bonding_curve.key(),
+            token_mint: bonding_curve.token_mint,
+            migration_authority: migration_authority_address().0,
+            sol_migrated: migration_state.sol_migrated,
+            tokens_migrated: migration_state.tokens_migrated,
+            final_price: migration_state.final_price,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Curve graduated: {} lamports and {} tokens migrated in {} tranches",
+            migration_state.sol_migrated,
+            migration_state.tokens_migrated,
+            migration_state.tranches
+        );
+        Ok(())
+    }
+
+    /**
+     * Hand the token's mint authority off from the curve PDA
+     *
+     * Only valid once the curve has graduated and its liquidity has been
+     * migrated. The authority can only be revoked (None) or passed to the
+     * program's governance PDA, never to a wallet that could mint at will.
+     */
+    pub fn handoff_mint_authority(
+        ctx: Context<HandoffMintAuthority>,
+        target: Option<Pubkey>,  // New mint authority (None = revoke)
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        require!(!bonding_curve.is_simulation(), BondingCurveError::SimulationCurve);
+        require!(
+            !bonding_curve.mint_authority_handed_off,
+            BondingCurveError::MintAuthorityHandedOff
+        );
+        require!(
+            bonding_curve.status == CurveStatus::Graduated,
+            BondingCurveError::NotGraduated
+        );
+        if let Some(target) = target {
+            require_keys_eq!(
+                target,
+                governance_address().0,
+                BondingCurveError::InvalidMintAuthorityTarget
+            );
+        }
+
+        let token_mint_key = ctx.accounts.token_mint.key();
+        let salt = bonding_curve.salt_seed();
+        let seeds = &[
+            BONDING_CURVE_SEED,
+            token_mint_key.as_ref(),
+            &salt,
+            &[bonding_curve.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        token_interface::set_authority(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::SetAuthority {
+                    current_authority: bonding_curve.to_account_info(),
+                    account_or_mint: ctx.accounts.token_mint.to_account_info(),
+                },
+                signer,
+            ),
+            AuthorityType::MintTokens,
+            target,
+        )?;
+
+        bonding_curve.mint_authority_handed_off = true;
+        bonding_curve.mint_authority_target = target;
+
+        emit!(MintAuthorityHandedOff {
+            bonding_curve: bonding_curve.key(),
+            token_mint: token_mint_key,
+            new_authority: target,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Mint authority handed off to {:?}", target);
+        Ok(())
+    }
+
+    /**
+     * Open a deposit-only bootstrap phase on a fresh curve
+     *
+     * Until end_ts backers deposit SOL without receiving tokens. At
+     * activation the combined deposits buy from the curve in one go, and
+     * every depositor gets tokens pro rata at the same clearing price, so
+     * nobody gains from landing first in the very first block.
+     */
+    pub fn start_deposit_bootstrap(
+        ctx: Context<UpdateCurveSettings>,
+        end_ts: i64,  // Unix timestamp deposits close
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        require!(!bonding_curve.has_quote_mint(), BondingCurveError::QuoteCurveUnsupported);
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        require!(
+            bonding_curve.status == CurveStatus::Active && bonding_curve.current_supply == 0,
+            BondingCurveError::CurveAlreadyLaunched
+        );
+        require!(
+            end_ts > Clock::get()?.unix_timestamp,
+            BondingCurveError::InvalidLaunchTime
+        );
+
+        bonding_curve.status = CurveStatus::Bootstrap;
+        bonding_curve.bootstrap_end_ts = end_ts;
+
+        msg!("Deposit bootstrap open until {}", end_ts);
+        Ok(())
+    }
+
+    /**
+     * Deposit SOL into a curve's bootstrap phase
+     *
+     * The SOL goes straight to the vault and is recorded against the
+     * depositor; tokens are claimed after activation.
+     */
+    pub fn deposit_bootstrap(
+        ctx: Context<DepositBootstrap>,
+        amount: u64,  // Lamports to deposit
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        require!(amount > 0, BondingCurveError::InvalidAmount);
+        require!(
+            bonding_curve.status == CurveStatus::Bootstrap,
+            BondingCurveError::BootstrapNotOpen
+        );
+        require!(
+            Clock::get()?.unix_timestamp < bonding_curve.bootstrap_end_ts,
+            BondingCurveError::BootstrapNotOpen
+        );
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.depositor.to_account_info(),
+                    to: ctx.accounts.sol_vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let deposit = &mut ctx.accounts.bootstrap_deposit;
+        deposit.bonding_curve = bonding_curve.key();
+        deposit.depositor = ctx.accounts.depositor.key();
+        deposit.amount = deposit
+            .amount
+            .checked_add(amount)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        deposit.bump = ctx.bumps.bootstrap_deposit;
+
+        bonding_curve.bootstrap_deposits = bonding_curve
+            .bootstrap_deposits
+            .checked_add(amount)
+            .ok_or(BondingCurveError::MathOverflow)?;
+
+        emit!(BootstrapDeposited {
+            bonding_curve: bonding_curve.key(),
+            depositor: deposit.depositor,
+            amount,
+            total_deposits: bonding_curve.bootstrap_deposits,
+        });
+
+        msg!("Bootstrap deposit of {} lamports", amount);
+        Ok(())
+    }
+
+    /**
+     * Close the bootstrap phase and open the curve for trading
+     *
+     * Permissionless crank once end_ts has passed. All deposits buy from
+     * the curve as a single order; the resulting tokens are minted to
+     * depositors through claim_bootstrap_allocation. If the deposits would
+     * buy past max_supply, the order stops there and the SOL it didn't
+     * spend is refunded pro rata with each claim.
+     */
+    pub fn activate_bootstrap(ctx: Context<ActivateBootstrap>) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            bonding_curve.status == CurveStatus::Bootstrap,
+            BondingCurveError::BootstrapNotOpen
+        );
+        require!(
+            now >= bonding_curve.bootstrap_end_ts,
+            BondingCurveError::LaunchNotDue
+        );
+
+        let deposits = bonding_curve.bootstrap_deposits;
+        let params = bonding_curve.params();
+        let mut tokens = calculate_tokens_for_curve(&params, 0, deposits)?;
+        let mut spent = deposits;
+        if tokens > bonding_curve.remaining_supply() {
+            tokens = bonding_curve.remaining_supply();
+            spent = calculate_sol_for_curve_ceil(&params, tokens, 0)?.min(deposits);
+        }
+
+        bonding_curve.bootstrap_tokens = tokens;
+        bonding_curve.bootstrap_refund_lamports = deposits
+            .checked_sub(spent)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        bonding_curve.current_supply = tokens;
+        bonding_curve.sol_reserves = spent;
+        bonding_curve.status = CurveStatus::Active;
+
+        if bonding_curve.refresh_sold_out() {
+            emit!(CurveSoldOut {
+                bonding_curve: bonding_curve.key(),
+                token_mint: bonding_curve.token_mint,
+                max_supply: bonding_curve.max_supply,
+                sol_reserves: bonding_curve.sol_reserves,
+                timestamp: now,
+            });
+        }
+        latch_raise_target(bonding_curve, now);
+        record_protocol_trade(&mut ctx.accounts.protocol_stats, bonding_curve, spent, 0)?;
+
+        let clearing_price = price_per_token(spent, tokens, bonding_curve.decimals);
+
+        emit!(BootstrapActivated {
+            bonding_curve: bonding_curve.key(),
+            total_deposits: deposits,
+            total_tokens: tokens,
+            clearing_price,
+            refund_lamports: bonding_curve.bootstrap_refund_lamports,
+        });
+
+        msg!(
+            "Bootstrap closed: {} lamports bought {} tokens at {} lamports each",
+            spent,
+            tokens,
+            clearing_price
+        );
+        Ok(())
+    }
+
+    /**
+     * Mint a depositor's share of the bootstrap allocation
+     *
+     * Shares are rounded down, so a few dust tokens may stay counted in the
+     * supply without being minted; the reserves still fully back them. The
+     * same share of any deposits activation didn't spend is refunded.
+     */
+    pub fn claim_bootstrap_allocation(ctx: Context<ClaimBootstrapAllocation>) -> Result<()> {
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        require!(
+            bonding_curve.bootstrap_end_ts > 0
+                && bonding_curve.status != CurveStatus::Bootstrap,
+            BondingCurveError::BootstrapNotOpen
+        );
+
+        let deposit = &mut ctx.accounts.bootstrap_deposit;
+        require!(!deposit.claimed, BondingCurveError::BootstrapAlreadyClaimed);
+
+        let tokens = (deposit.amount as u128 * bonding_curve.bootstrap_tokens as u128
+            / bonding_curve.bootstrap_deposits as u128) as u64;
+        let refund = (deposit.amount as u128 * bonding_curve.bootstrap_refund_lamports as u128
+            / bonding_curve.bootstrap_deposits as u128) as u64;
+        deposit.claimed = true;
+
+        if refund > 0 {
+            let vault_seeds = &[
+                SOL_VAULT_SEED,
+                bonding_curve.token_mint.as_ref(),
+                &[ctx.bumps.sol_vault],
+            ];
+            system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.sol_vault.to_account_info(),
+                        to: ctx.accounts.depositor.to_account_info(),
+                    },
+                    &[&vault_seeds[..]],
+                ),
+                refund,
+            )?;
+        }
+
+        let token_mint_key = ctx.accounts.token_mint.key();
+        let salt = bonding_curve.salt_seed();
+        let seeds = &[
+            BONDING_CURVE_SEED,
+            token_mint_key.as_ref(),
+            &salt,
+            &[bonding_curve.bump],
+        ];
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::MintTo {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.depositor_token_account.to_account_info(),
+                    authority: bonding_curve.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            tokens,
+        )?;
+
+        msg!("Claimed {} bootstrap tokens and {} lamports refunded", tokens, refund);
+        Ok(())
+    }
+
+    /**
+     * Make the curve fully backed, permanently
+     *
+     * From here on the program guarantees 100% of buy SOL lands in the
+     * reserves: trading fees drop to zero and no charity share can be set,
+     * so holders can always redeem against the full amount paid in. The
+     * flag is public state, distinguishing these curves from fee-taking ones.
+     */
+    pub fn enable_full_backing(ctx: Context<UpdateCurveSettings>) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        require!(bonding_curve.charity_bps == 0, BondingCurveError::FullyBacked);
+
+        bonding_curve.fully_backed = true;
+
+        emit!(FullBackingEnabled {
+            bonding_curve: bonding_curve.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Curve is now fully backed");
+        Ok(())
+    }
+
+    /**
+     * Require wallets to hold for a while after buying before they sell
+     *
+     * An anti-PvP speed bump: each buy restarts the wallet's hold period in
+     * its UserTradeState, and sells fail until it has elapsed. Capped at
+     * MAX_HOLD_SECONDS; pass 0 to turn it off.
+     */
+    pub fn set_min_hold_period(
+        ctx: Context<UpdateCurveSettings>,
+        min_hold_seconds: i64,  // Seconds between a buy and the next sell
+    ) -> Result<()> {
+        require!(
+            (0..=MAX_HOLD_SECONDS).contains(&min_hold_seconds),
+            BondingCurveError::InvalidHoldPeriod
+        );
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        bonding_curve.min_hold_seconds = min_hold_seconds;
+
+        msg!("Minimum hold period set to {} seconds", min_hold_seconds);
+        Ok(())
+    }
+
+    /**
+     * Creates a user's trade state on a curve
+     */
+    pub fn initialize_user_trade_state(ctx: Context<InitializeUserTradeState>) -> Result<()> {
+        let user_trade_state = &mut ctx.accounts.user_trade_state;
+        user_trade_state.bonding_curve = ctx.accounts.bonding_curve.key();
+        user_trade_state.user = ctx.accounts.user.key();
+        user_trade_state.last_buy_ts = 0;
+        user_trade_state.launch_tokens_bought = 0;
+        user_trade_state.bump = ctx.bumps.user_trade_state;
+
+        msg!("Trade state initialized for {}", user_trade_state.user);
+        Ok(())
+    }
+
+    /**
+     * Open a launch raffle on a fresh curve
+     *
+     * Instead of first-come-first-served, early buyers get raffle tickets at
+     * a fixed price until end_ts. Once sales close, commit_raffle_draw fixes
+     * a future slot and draw_raffle picks up to max_winners tickets from that
+     * slot's hash; winners all receive tokens at one uniform price and losers
+     * are refunded. A raffle that closed without selling a ticket can be
+     * opened again.
+     */
+    pub fn start_launch_raffle(
+        ctx: Context<UpdateCurveSettings>,
+        end_ts: i64,        // Unix timestamp ticket sales close
+        ticket_price: u64,  // Lamports per ticket
+        max_winners: u32,   // Most tickets that can win
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        let now = Clock::get()?.unix_timestamp;
+        require!(!bonding_curve.has_quote_mint(), BondingCurveError::QuoteCurveUnsupported);
+        bonding_curve.record_creator_action(now)?;
+        let unsold_raffle = bonding_curve.status == CurveStatus::Raffle
+            && bonding_curve.raffle.ticket_count == 0
+            && now >= bonding_curve.raffle.end_ts;
+        require!(
+            (bonding_curve.status == CurveStatus::Active && bonding_curve.current_supply == 0)
+                || unsold_raffle,
+            BondingCurveError::CurveAlreadyLaunched
+        );
+        require!(end_ts > now, BondingCurveError::InvalidLaunchTime);
+        require!(ticket_price > 0 && max_winners > 0, BondingCurveError::InvalidRaffle);
+
+        bonding_curve.status = CurveStatus::Raffle;
+        bonding_curve.raffle = LaunchRaffle {
+            end_ts,
+            ticket_price,
+            max_winners,
+            ..LaunchRaffle::default()
+        };
+        bonding_curve.raffle_draw_slot = 0;
+
+        msg!(
+            "Launch raffle open until {}: {} lamports per ticket, {} winners",
+            end_ts,
+            ticket_price,
+            max_winners
+        );
+        Ok(())
+    }
+
+    /**
+     * Buy one ticket in a curve's launch raffle
+     */
+    pub fn buy_raffle_ticket(ctx: Context<BuyRaffleTicket>) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        require!(
+            bonding_curve.status == CurveStatus::Raffle
+                && Clock::get()?.unix_timestamp < bonding_curve.raffle.end_ts,
+            BondingCurveError::RaffleNotOpen
+        );
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.buyer.to_account_info(),
+                    to: ctx.accounts.sol_vault.to_account_info(),
+                },
+            ),
+            bonding_curve.raffle.ticket_price,
+        )?;
+
+        let ticket = &mut ctx.accounts.raffle_ticket;
+        ticket.bonding_curve = bonding_curve.key();
+        ticket.owner = ctx.accounts.buyer.key();
+        ticket.index = bonding_curve.raffle.ticket_count;
+        ticket.bump = ctx.bumps.raffle_ticket;
+
+        bonding_curve.raffle.ticket_count = bonding_curve
+            .raffle
+            .ticket_count
+            .checked_add(1)
+            .ok_or(BondingCurveError::MathOverflow)?;
+
+        msg!("Raffle ticket {} bought", ticket.index);
+        Ok(())
+    }
+
+    /**
+     * Fix the slot whose hash will draw the launch raffle
+     *
+     * Permissionless crank once ticket sales close. The draw slot lies
+     * RAFFLE_DRAW_DELAY_SLOTS ahead, so its hash is unknown to everyone
+     * while tickets can still be bought. A new slot can only be committed if
+     * the committed one was skipped or has aged out of SlotHashes unused.
+     */
+    pub fn commit_raffle_draw(ctx: Context<DrawRaffle>) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        require!(
+            bonding_curve.status == CurveStatus::Raffle,
+            BondingCurveError::RaffleNotOpen
+        );
+        require!(
+            Clock::get()?.unix_timestamp >= bonding_curve.raffle.end_ts,
+            BondingCurveError::LaunchNotDue
+        );
+        let slot = Clock::get()?.slot;
+        let committed = bonding_curve.raffle_draw_slot;
+        require!(
+            committed == 0
+                || (slot > committed
+                    && slot_hash_seed(&ctx.accounts.slot_hashes, committed)?.is_none()),
+            BondingCurveError::RaffleDrawAlreadyCommitted
+        );
+
+        let draw_slot = slot
+            .checked_add(RAFFLE_DRAW_DELAY_SLOTS)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        bonding_curve.raffle_draw_slot = draw_slot;
+
+        emit!(RaffleDrawCommitted {
+            bonding_curve: bonding_curve.key(),
+            draw_slot,
+        });
+
+        msg!("Raffle will be drawn from the hash of slot {}", draw_slot);
+        Ok(())
+    }
+
+    /**
+     * Draw the launch raffle and open the curve for trading
+     *
+     * Permissionless crank once the slot fixed by commit_raffle_draw has
+     * passed. The winning window's start comes from that slot's hash, which
+     * nobody knew when ticket sales closed; only its leader has any say in
+     * it. All winning SOL buys from the curve as one order, split evenly
+     * across winners. A raffle with no tickets sold has nothing to draw.
+     */
+    pub fn draw_raffle(ctx: Context<DrawRaffle>) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        let clock = Clock::get()?;
+        require!(
+            bonding_curve.status == CurveStatus::Raffle,
+            BondingCurveError::RaffleNotOpen
+        );
+        require!(
+            bonding_curve.raffle.ticket_count > 0,
+            BondingCurveError::RaffleNoTickets
+        );
+        let draw_slot = bonding_curve.raffle_draw_slot;
+        require!(
+            draw_slot != 0 && clock.slot > draw_slot,
+            BondingCurveError::RaffleDrawNotReady
+        );
+        let seed = slot_hash_seed(&ctx.accounts.slot_hashes, draw_slot)?
+            .ok_or(BondingCurveError::RaffleDrawSlotUnavailable)?;
+
+        let ticket_count = bonding_curve.raffle.ticket_count;
+        let winner_count = ticket_count.min(bonding_curve.raffle.max_winners);
+        let offset = (seed % ticket_count as u64) as u32;
+
+        // Winning SOL buys as one order; every winner gets the same share
+        let winning_sol = bonding_curve
+            .raffle
+            .ticket_price
+            .checked_mul(winner_count as u64)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        let tokens = calculate_tokens_for_curve(&bonding_curve.params(), 0, winning_sol)?;
+        let tokens_per_winner = tokens / winner_count as u64;
+
+        let raffle = &mut bonding_curve.raffle;
+        raffle.winner_count = winner_count;
+        raffle.offset = offset;
+        raffle.tokens_per_winner = tokens_per_winner;
+        raffle.drawn = true;
+
+        bonding_curve.current_supply = bonding_curve
+            .current_supply
+            .checked_add(tokens_per_winner * winner_count as u64)
+            .ok_or(BondingCurveError::SupplyOverflow)?;
+        bonding_curve.sol_reserves = bonding_curve
+            .sol_reserves
+            .checked_add(winning_sol)
+            .ok_or(BondingCurveError::ReservesOverflow)?;
+        bonding_curve.status = CurveStatus::Active;
+
+        if bonding_curve.refresh_sold_out() {
+            emit!(CurveSoldOut {
+                bonding_curve: bonding_curve.key(),
+                token_mint: bonding_curve.token_mint,
+                max_supply: bonding_curve.max_supply,
+                sol_reserves: bonding_curve.sol_reserves,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+        latch_raise_target(bonding_curve, clock.unix_timestamp);
+
+        emit!(RaffleDrawn {
+            bonding_curve: bonding_curve.key(),
+            ticket_count,
+            winner_count,
+            offset,
+            tokens_per_winner,
+        });
+
+        msg!(
+            "Raffle drawn: {} of {} tickets won {} tokens each",
+            winner_count,
+            ticket_count,
+            tokens_per_winner
+        );
+        Ok(())
+    }
+
+    /**
+     * Settle a raffle ticket after the draw
+     *
+     * Winning tickets mint their allocation, losing tickets are refunded
+     * the ticket price. Either way the ticket account is closed.
+     */
+    pub fn settle_raffle_ticket(ctx: Context<SettleRaffleTicket>) -> Result<()> {
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        let raffle = bonding_curve.raffle;
+        require!(raffle.drawn, BondingCurveError::RaffleNotOpen);
+
+        let token_mint_key = ctx.accounts.token_mint.key();
+        if raffle.is_winner(ctx.accounts.raffle_ticket.index) {
+            let salt = bonding_curve.salt_seed();
+            let seeds = &[
+                BONDING_CURVE_SEED,
+                token_mint_key.as_ref(),
+                &salt,
+                &[bonding_curve.bump],
+            ];
+            token_interface::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token_interface::MintTo {
+                        mint: ctx.accounts.token_mint.to_account_info(),
+                        to: ctx.accounts.owner_token_account.to_account_info(),
+                        authority: bonding_curve.to_account_info(),
+                    },
+                    &[&seeds[..]],
+                ),
+                raffle.tokens_per_winner,
+            )?;
+            msg!("Winning ticket settled for {} tokens", raffle.tokens_per_winner);
+        } else {
+            let seeds = &[
+                SOL_VAULT_SEED,
+                token_mint_key.as_ref(),
+                &[ctx.bumps.sol_vault],
+            ];
+            system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.sol_vault.to_account_info(),
+                        to: ctx.accounts.owner.to_account_info(),
+                    },
+                    &[&seeds[..]],
+                ),
+                raffle.ticket_price,
+            )?;
+            msg!("Losing ticket refunded {} lamports", raffle.ticket_price);
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Choose between compact and verbose trade events
+     *
+     * Compact curves emit a single CompactTrade event with only the key
+     * fields and skip the trade logs, for integrators watching compute and
+     * log budgets. Verbose curves (the default) emit the full fee breakdown.
+     */
+    pub fn set_event_verbosity(
+        ctx: Context<UpdateCurveSettings>,
+        compact_events: bool,  // Emit CompactTrade instead of the full events
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        bonding_curve.compact_events = compact_events;
+
+        msg!("Compact events: {}", compact_events);
+        Ok(())
+    }
+
+    /**
+     * Set the size of the reserve buffer
+     *
+     * Trading fees top the buffer up to reserve_buffer_bps of the reserves
+     * before accruing. Sells never pay out of it, except to cover the few
+     * lamports of rounding drift that would otherwise leave the last seller
+     * facing InsufficientReserves. Capped at MAX_RESERVE_BUFFER_BPS.
+     */
+    pub fn set_reserve_buffer(
+        ctx: Context<UpdateCurveSettings>,
+        reserve_buffer_bps: u16,  // Buffer target in basis points of reserves
+    ) -> Result<()> {
+        require!(
+            reserve_buffer_bps <= MAX_RESERVE_BUFFER_BPS,
+            BondingCurveError::InvalidReserveBuffer
+        );
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        bonding_curve.reserve_buffer_bps = reserve_buffer_bps;
+
+        msg!("Reserve buffer set to {} bps", reserve_buffer_bps);
+        Ok(())
+    }
+
+    /**
+     * Sweep the part of the reserve buffer above its target to the creator
+     *
+     * The buffer overshoots once reserves shrink after sells; anything above
+     * reserve_buffer_bps of the current reserves is no longer needed.
+     */
+    pub fn withdraw_excess_sol(ctx: Context<WithdrawExcessSol>) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        require!(!bonding_curve.has_quote_mint(), BondingCurveError::QuoteCurveUnsupported);
+        let excess = bonding_curve
+            .reserve_buffer
+            .saturating_sub(bonding_curve.reserve_buffer_target()?);
+        require!(excess > 0, BondingCurveError::NoExcessSol);
+
+        let token_mint_key = bonding_curve.token_mint;
+        let seeds = &[
+            SOL_VAULT_SEED,
+            token_mint_key.as_ref(),
+            &[ctx.bumps.sol_vault],
+        ];
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.sol_vault.to_account_info(),
+                    to: ctx.accounts.creator.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            excess,
+        )?;
+
+        bonding_curve.reserve_buffer -= excess;
+
+        msg!("Swept {} lamports of excess reserve buffer", excess);
+        Ok(())
+    }
+
+    /**
+     * Pre-fund the curve's SOL vault as exit liquidity
+     *
+     * Creator only, SOL curves only. The lamports sit in the vault as
+     * seeded_reserves, outside the trade-derived sol_reserves, and cover
+     * whatever a sale or exit claim needs beyond the reserves and the
+     * reserve buffer, e.g. when airdropped tokens are sold before anyone
+     * has bought. Seeding never moves the price or the graduation metric.
+     */
+    pub fn seed_reserves(
+        ctx: Context<CreatorReserves>,
+        lamports: u64, // SOL to deposit, in lamports
+    ) -> Result<()> {
+        require!(lamports > 0, BondingCurveError::InvalidAmount);
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        require!(!bonding_curve.has_quote_mint(), BondingCurveError::QuoteCurveUnsupported);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.creator.to_account_info(),
+                    to: ctx.accounts.sol_vault.to_account_info(),
+                },
+            ),
+            lamports,
+        )?;
+
+        bonding_curve.seeded_reserves = bonding_curve
+            .seeded_reserves
+            .checked_add(lamports)
+            .ok_or(BondingCurveError::ReservesOverflow)?;
+
+        emit!(ReservesSeeded {
+            bonding_curve: bonding_curve.key(),
+            creator: ctx.accounts.creator.key(),
+            amount: lamports,
+            seeded_reserves: bonding_curve.seeded_reserves,
+        });
+
+        msg!("Seeded {} lamports of exit liquidity", lamports);
+        Ok(())
+    }
+
+    /**
+     * Take back unspent seeded reserves
+     *
+     * Creator only, and never more than what is left of seeded_reserves, so
+     * trade-derived reserves can't be withdrawn this way. Until the curve
+     * graduates, the reserves, buffer and remaining seed together must
+     * still cover the curve's full buy-back value at the current supply.
+     */
+    pub fn withdraw_seeded_reserves(
+        ctx: Context<CreatorReserves>,
+        lamports: u64, // SOL to withdraw, in lamports
+    ) -> Result<()> {
+        require!(lamports > 0, BondingCurveError::InvalidAmount);
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        require!(
+            lamports <= bonding_curve.seeded_reserves,
+            BondingCurveError::InsufficientSeededReserves
+        );
+        let seed_left = bonding_curve.seeded_reserves - lamports;
+        if bonding_curve.status != CurveStatus::Graduated {
+            let liability =
+                calculate_sol_for_curve(&bonding_curve.params(), bonding_curve.current_supply, 0)?;
+            let backing = bonding_curve.sol_reserves as u128
+                + bonding_curve.reserve_buffer as u128
+                + seed_left as u128;
+            require!(backing >= liability as u128, BondingCurveError::SeedStillNeeded);
+        }
+
+        let token_mint_key = bonding_curve.token_mint;
+        let seeds = &[
+            SOL_VAULT_SEED,
+            token_mint_key.as_ref(),
+            &[ctx.bumps.sol_vault],
+        ];
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.sol_vault.to_account_info(),
+                    to: ctx.accounts.creator.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            lamports,
+        )?;
+
+        bonding_curve.seeded_reserves = seed_left;
+
+        emit!(SeededReservesWithdrawn {
+            bonding_curve: bonding_curve.key(),
+            creator: ctx.accounts.creator.key(),
+            amount: lamports,
+            seeded_reserves: seed_left,
+        });
+
+        msg!("Withdrew {} lamports of seeded reserves", lamports);
+        Ok(())
+    }
+
+    /**
+     * Creates the protocol-wide statistics account
+     */
+    pub fn initialize_protocol_stats(ctx: Context<InitializeProtocolStats>) -> Result<()> {
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        protocol_stats.totals = ProtocolTotals::default();
+        protocol_stats.last_summary = ProtocolTotals::default();
+        protocol_stats.last_summary_epoch = Clock::get()?.epoch;
+        protocol_stats.bump = ctx.bumps.protocol_stats;
+
+        msg!("Protocol stats initialized");
+        Ok(())
+    }
+
+    /**
+     * Emit the protocol's growth since the last summary
+     *
+     * Permissionless crank, callable once per epoch. The ProtocolEpochSummary
+     * event carries the deltas since the previous summary, so dashboards can
+     * chart protocol growth purely from the event stream.
+     */
+    pub fn emit_protocol_epoch_summary(ctx: Context<EmitProtocolEpochSummary>) -> Result<()> {
+        let epoch = Clock::get()?.epoch;
+        let protocol_stats = &mut ctx.accounts.protocol_stats;
+        require!(
+            epoch > protocol_stats.last_summary_epoch,
+            BondingCurveError::EpochSummaryNotDue
+        );
+
+        let totals = protocol_stats.totals;
+        let last = protocol_stats.last_summary;
+        emit!(ProtocolEpochSummary {
+            epoch,
+            curves_created: totals.curves_created - last.curves_created,
+            curves_graduated: totals.curves_graduated - last.curves_graduated,
+            volume_lamports: totals.volume_lamports - last.volume_lamports,
+            fees_lamports: totals.fees_lamports - last.fees_lamports,
+        });
+
+        protocol_stats.last_summary = totals;
+        protocol_stats.last_summary_epoch = epoch;
+
+        msg!("Protocol summary emitted for epoch {}", epoch);
+        Ok(())
+    }
+
+    /**
+     * Archive a graduated curve as terminal, read-only state
+     *
+     * Callable by the creator or the insurance fund's governance authority
+     * once the curve has graduated. Archived curves reject every mutating
+     * instruction with CurveArchived. With shrink set, the account is cut
+     * down to the fixed prefix described in layout::bonding_curve (creator,
+     * mint, supply, reserves, pricing, name and symbol) and the freed rent
+     * is returned to the creator; a shrunk curve can only be read raw.
+     * Since the rest of its state is gone, a curve only shrinks once no fees,
+     * exits or stakes are outstanding, and it gives up its mint authority
+     * first (pass the token mint and token program if it still holds it).
+     */
+    pub fn archive_curve(
+        ctx: Context<ArchiveCurve>,
+        shrink: bool,  // Shrink the account to its fixed prefix and refund rent
+    ) -> Result<()> {
+        let info = ctx.accounts.bonding_curve.to_account_info();
+        let mut bonding_curve = BondingCurve::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+
+        let expected = Pubkey::create_program_address(
+            &[
+                BONDING_CURVE_SEED,
+                bonding_curve.token_mint.as_ref(),
+                &bonding_curve.salt_seed(),
+                &[bonding_curve.bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| BondingCurveError::Unauthorized)?;
+        require_keys_eq!(info.key(), expected, BondingCurveError::Unauthorized);
+        require_keys_eq!(
+            ctx.accounts.creator.key(),
+            bonding_curve.creator,
+            BondingCurveError::Unauthorized
+        );
+
+        let authority = ctx.accounts.authority.key();
+        let is_governance = ctx
+            .accounts
+            .insurance_fund
+            .as_ref()
+            .is_some_and(|fund| fund.authority == authority);
+        require!(
+            authority == bonding_curve.creator || is_governance,
+            BondingCurveError::Unauthorized
+        );
+        require!(
+            bonding_curve.status == CurveStatus::Graduated,
+            BondingCurveError::CurveNotArchivable
+        );
+
+        if shrink {
+            require!(bonding_curve.exit_queue_len() == 0, BondingCurveError::ExitQueuePending);
+            require!(
+                bonding_curve.creator_fees_unclaimed == 0
+                    && bonding_curve.protocol_fees_unclaimed == 0
+                    && bonding_curve.holder_rewards_pending == 0,
+                BondingCurveError::FeesUnclaimed
+            );
+            require!(bonding_curve.rewards_staked == 0, BondingCurveError::RewardsStaked);
+            if !bonding_curve.mint_authority_handed_off {
+                let (Some(token_mint), Some(token_program)) =
+                    (&ctx.accounts.token_mint, &ctx.accounts.token_program)
+                else {
+                    return err!(BondingCurveError::MissingMintAccount);
+                };
+                require_keys_eq!(
+                    token_mint.key(),
+                    bonding_curve.token_mint,
+                    BondingCurveError::MissingMintAccount
+                );
+                require_keys_eq!(
+                    token_program.key(),
+                    bonding_curve.token_program,
+                    BondingCurveError::TokenProgramMismatch
+                );
+                revoke_curve_mint_authority(
+                    &mut bonding_curve,
+                    &info,
+                    &token_mint.to_account_info(),
+                    &token_program.to_account_info(),
+                )?;
+            }
+        }
+
+        bonding_curve.status = CurveStatus::Archived;
+        bonding_curve.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        if shrink {
+            let new_len = layout::bonding_curve::PREFIX_LEN;
+            info.resize(new_len)?;
+
+            let refund = info
+                .lamports()
+                .saturating_sub(Rent::get()?.minimum_balance(new_len));
+            **info.try_borrow_mut_lamports()? -= refund;
+            **ctx.accounts.creator.try_borrow_mut_lamports()? += refund;
+        }
+
+        emit!(CurveArchived {
+            bonding_curve: info.key(),
+            shrunk: shrink,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Curve {} archived", info.key());
+        Ok(())
+    }
+
+    /**
+     * Cap the number of trades the curve accepts per slot
+     *
+     * An incident control for the protocol admin (the insurance fund's
+     * governance authority): while set, buys and sells beyond the cap in a
+     * single slot fail with RateLimited. Pass 0 to lift the limit.
+     */
+    pub fn set_trade_rate_limit(
+        ctx: Context<AdminCurveSettings>,
+        max_trades_per_slot: u16,  // Trades allowed per slot (0 = unlimited)
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.max_trades_per_slot = max_trades_per_slot;
+
+        msg!("Trade rate limit set to {} per slot", max_trades_per_slot);
+        Ok(())
+    }
+
+    /**
+     * Set the creator's share of the trading fee
+     *
+     * Charged on top of the protocol fee on every buy and sell and
+     * collected in the curve's fee vault. Capped at MAX_CREATOR_FEE_BPS.
+     */
+    pub fn set_creator_fee(
+        ctx: Context<UpdateCurveSettings>,
+        creator_fee_bps: u16,  // Creator fee in basis points
+    ) -> Result<()> {
+        require!(creator_fee_bps <= MAX_CREATOR_FEE_BPS, BondingCurveError::InvalidFee);
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        bonding_curve.creator_fee_bps = creator_fee_bps;
+        bonding_curve.check_sell_fee_cap()?;
+
+        msg!("Creator fee set to {} bps", creator_fee_bps);
+        Ok(())
+    }
+
+    /**
+     * Set the protocol's share of the trading fee on a curve
+     *
+     * Admin only (the insurance fund's governance authority). Capped at
+     * MAX_PROTOCOL_FEE_BPS.
+     */
+    pub fn set_protocol_fee(
+        ctx: Context<AdminCurveSettings>,
+        protocol_fee_bps: u16,  // Protocol fee in basis points
+    ) -> Result<()> {
+        require!(protocol_fee_bps <= MAX_PROTOCOL_FEE_BPS, BondingCurveError::InvalidFee);
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.protocol_fee_bps = protocol_fee_bps;
+        bonding_curve.check_sell_fee_cap()?;
+
+        msg!("Protocol fee set to {} bps", protocol_fee_bps);
+        Ok(())
+    }
+
+    /**
+     * Withdraw the creator's unclaimed fees from the fee vault
+     */
+    pub fn claim_creator_fees(ctx: Context<ClaimCreatorFees>) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        require!(!bonding_curve.has_quote_mint(), BondingCurveError::QuoteCurveUnsupported);
+        let amount = bonding_curve.creator_fees_unclaimed;
+        require!(amount > 0, BondingCurveError::NoFeesToClaim);
+
+        let token_mint_key = bonding_curve.token_mint;
+        let seeds = &[
+            FEE_VAULT_SEED,
+            token_mint_key.as_ref(),
+            &[ctx.bumps.fee_vault],
+        ];
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.fee_vault.to_account_info(),
+                    to: ctx.accounts.creator.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            amount,
+        )?;
+
+        bonding_curve.creator_fees_unclaimed = 0;
+
+        emit!(FeesClaimed {
+            bonding_curve: bonding_curve.key(),
+            recipient: ctx.accounts.creator.key(),
+            is_protocol: false,
+            amount,
+        });
+
+        msg!("Creator claimed {} lamports of fees", amount);
+        Ok(())
+    }
+
+    /**
+     * Withdraw the protocol's unclaimed fees from a curve's fee vault
+     *
+     * Admin only; the fees go to the insurance fund's governance authority.
+     */
+    pub fn claim_protocol_fees(ctx: Context<ClaimProtocolFees>) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        require!(!bonding_curve.has_quote_mint(), BondingCurveError::QuoteCurveUnsupported);
+        let amount = bonding_curve.protocol_fees_unclaimed;
+        require!(amount > 0, BondingCurveError::NoFeesToClaim);
+
+        let token_mint_key = bonding_curve.token_mint;
+        let seeds = &[
+            FEE_VAULT_SEED,
+            token_mint_key.as_ref(),
+            &[ctx.bumps.fee_vault],
+        ];
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.fee_vault.to_account_info(),
+                    to: ctx.accounts.authority.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            amount,
+        )?;
+
+        bonding_curve.protocol_fees_unclaimed = 0;
+
+        emit!(FeesClaimed {
+            bonding_curve: bonding_curve.key(),
+            recipient: ctx.accounts.authority.key(),
+            is_protocol: true,
+            amount,
+        });
+
+        msg!("Protocol claimed {} lamports of fees", amount);
+        Ok(())
+    }
+
+    /**
+     * Set the creator inactivity timeout (dead-man switch)
+     *
+     * If the creator performs no authority action for timeout_days, every
+     * creator-only setting, the launch schedule and the mint authority
+     * handoff are locked for good with CreatorPowersLapsed, leaving the
+     * curve immutable instead of hostage to a lost or abandoned key.
+     * Any creator action, including creator_heartbeat, restarts the clock.
+     * Pass 0 to disable.
+     */
+    pub fn set_creator_timeout(
+        ctx: Context<UpdateCurveSettings>,
+        timeout_days: u16,  // Days of inactivity before creator powers lapse (0 = never)
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        bonding_curve.creator_timeout_days = timeout_days;
+
+        msg!("Creator inactivity timeout set to {} days", timeout_days);
+        Ok(())
+    }
+
+    /**
+     * Prove the creator is still around, restarting the inactivity clock
+     */
+    pub fn creator_heartbeat(ctx: Context<UpdateCurveSettings>) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+
+        msg!("Creator heartbeat recorded");
+        Ok(())
+    }
+
+    /**
+     * Halt buys on the curve
+     *
+     * Either the creator or the insurance fund authority (the protocol
+     * admin) can pause; the admin passes the insurance fund to prove it.
+     * Buys fail with CurvePaused until unpause_curve, while sells stay open
+     * so holders can always exit. A creator pause lapses on its own after
+     * CREATOR_PAUSE_MAX_SECONDS and can't be renewed while it runs; an
+     * admin pause lasts until the admin lifts it.
+     */
+    pub fn pause_curve(ctx: Context<PauseCurve>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let is_admin = ctx.accounts.check_pauser(now)?;
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        if is_admin {
+            bonding_curve.pause_expires_ts = 0;
+        } else {
+            require!(!bonding_curve.buys_paused(now), BondingCurveError::CurvePaused);
+            bonding_curve.pause_expires_ts = now
+                .checked_add(CREATOR_PAUSE_MAX_SECONDS)
+                .ok_or(BondingCurveError::MathOverflow)?;
+        }
+        bonding_curve.paused = true;
+
+        emit!(CurvePauseChanged {
+            bonding_curve: bonding_curve.key(),
+            authority: ctx.accounts.authority.key(),
+            paused: true,
+            timestamp: now,
+        });
+
+        msg!("Trading paused");
+        Ok(())
+    }
+
+    /**
+     * Resume buys on a paused curve
+     *
+     * The creator can only lift their own pause; an admin pause, and with
+     * it any emergency withdrawal still waiting out its timelock, can only
+     * be lifted by the admin. A curve whose reserves went to emergency
+     * redemptions stays paused for good.
+     */
+    pub fn unpause_curve(ctx: Context<PauseCurve>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let is_admin = ctx.accounts.check_pauser(now)?;
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        require!(bonding_curve.paused, BondingCurveError::CurveNotPaused);
+        require!(
+            is_admin || bonding_curve.pause_expires_ts != 0,
+            BondingCurveError::Unauthorized
+        );
+        require!(
+            bonding_curve.redemption_supply == 0,
+            BondingCurveError::EmergencyRedemptionOpen
+        );
+        bonding_curve.paused = false;
+        bonding_curve.pause_expires_ts = 0;
+        bonding_curve.emergency_withdraw_ts = 0;
+
+        emit!(CurvePauseChanged {
+            bonding_curve: bonding_curve.key(),
+            authority: ctx.accounts.authority.key(),
+            paused: false,
+            timestamp: now,
+        });
+
+        msg!("Trading resumed");
+        Ok(())
+    }
+
+    /**
+     * Start the timelock on an emergency withdrawal from an admin-paused curve
+     *
+     * Only the protocol admin can schedule, and only on a curve it paused
+     * itself. emergency_withdraw becomes callable EMERGENCY_WITHDRAW_DELAY
+     * later, giving holders a window to see it coming and sell; unpausing
+     * the curve in the meantime cancels it.
+     */
+    pub fn schedule_emergency_withdraw(ctx: Context<AdminCurveSettings>) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        require!(!bonding_curve.has_quote_mint(), BondingCurveError::QuoteCurveUnsupported);
+        require!(
+            bonding_curve.paused && bonding_curve.pause_expires_ts == 0,
+            BondingCurveError::CurveNotPaused
+        );
+
+        let executable_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(EMERGENCY_WITHDRAW_DELAY)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        bonding_curve.emergency_withdraw_ts = executable_at;
+
+        emit!(EmergencyWithdrawScheduled {
+            bonding_curve: bonding_curve.key(),
+            executable_at,
+        });
+
+        msg!("Emergency withdrawal scheduled for {}", executable_at);
+        Ok(())
+    }
+
+    /**
+     * Move a paused curve's SOL vault into its redemption vault
+     *
+     * Requires a schedule_emergency_withdraw whose timelock has run out.
+     * Everything above the vault's rent floor and what queued exits are owed
+     * goes to the redemption vault, whose own rent floor the admin pays; the
+     * queue's lamports stay as the curve's only reserves, so its claims
+     * still settle. Holders then redeem_emergency their tokens pro rata
+     * against the supply outstanding at this point; the curve stays paused
+     * for good.
+     */
+    pub fn emergency_withdraw(ctx: Context<EmergencyWithdraw>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        require!(
+            bonding_curve.paused && bonding_curve.pause_expires_ts == 0,
+            BondingCurveError::CurveNotPaused
+        );
+        require!(
+            bonding_curve.emergency_withdraw_ts > 0 && now >= bonding_curve.emergency_withdraw_ts,
+            BondingCurveError::EmergencyWithdrawNotReady
+        );
+        require!(bonding_curve.circulating_supply() > 0, BondingCurveError::InsufficientSupply);
+        require!(
+            bonding_curve.redemption_supply == 0,
+            BondingCurveError::EmergencyRedemptionOpen
+        );
+
+        // The admin tops the redemption vault up to its rent floor, so every
+        // lamport moved in below is redeemable
+        let rent_shortfall = vault_rent_floor()?
+            .saturating_sub(ctx.accounts.redemption_vault.lamports());
+        if rent_shortfall > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.authority.to_account_info(),
+                        to: ctx.accounts.redemption_vault.to_account_info(),
+                    },
+                ),
+                rent_shortfall,
+            )?;
+        }
+
+        let available = ctx
+            .accounts
+            .sol_vault
+            .lamports()
+            .saturating_sub(vault_rent_floor()?);
+        // Queued exits burned their tokens already, so they have no share of
+        // the redemption; what they are owed stays behind for them to settle
+        let queue_reserves = bonding_curve.exit_queue_lamports.min(available);
+        let amount = available - queue_reserves;
+
+        let token_mint_key = bonding_curve.token_mint;
+        let seeds = &[
+            SOL_VAULT_SEED,
+            token_mint_key.as_ref(),
+            &[ctx.bumps.sol_vault],
+        ];
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.sol_vault.to_account_info(),
+                    to: ctx.accounts.redemption_vault.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            amount,
+        )?;
+
+        bonding_curve.sol_reserves = queue_reserves;
+        bonding_curve.reserve_buffer = 0;
+        bonding_curve.seeded_reserves = 0;
+        bonding_curve.emergency_withdraw_ts = 0;
+        bonding_curve.redemption_lamports = amount;
+        bonding_curve.redemption_supply = bonding_curve.circulating_supply();
+
+        emit!(EmergencyWithdrawal {
+            bonding_curve: bonding_curve.key(),
+            recipient: ctx.accounts.redemption_vault.key(),
+            amount,
+            timestamp: now,
+        });
+
+        msg!("Emergency withdrawal of {} lamports", amount);
+        Ok(())
+    }
+
+    /**
+     * Redeem tokens for their share of an emergency withdrawal
+     *
+     * Burns the tokens and pays token_amount * redemption_lamports /
+     * redemption_supply from the redemption vault, rounded down, so every
+     * holder gets the same price however late they redeem.
+     */
+    pub fn redeem_emergency(
+        ctx: Context<RedeemEmergency>,
+        token_amount: u64,  // Tokens to burn, in base units
+    ) -> Result<()> {
+        require!(token_amount > 0, BondingCurveError::InvalidAmount);
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        require!(!bonding_curve.is_simulation(), BondingCurveError::SimulationCurve);
+        require!(
+            bonding_curve.redemption_supply > 0,
+            BondingCurveError::NoEmergencyRedemption
+        );
+        require!(
+            token_amount <= bonding_curve.current_supply,
+            BondingCurveError::InsufficientSupply
+        );
+        let lamports = mul_div_u128(
+            token_amount as u128,
+            bonding_curve.redemption_lamports as u128,
+            bonding_curve.redemption_supply as u128,
+        )? as u64;
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    from: ctx.accounts.holder_token_account.to_account_info(),
+                    authority: ctx.accounts.holder.to_account_info(),
+                },
+            ),
+            token_amount,
+        )?;
+
+        let token_mint_key = ctx.accounts.token_mint.key();
+        let seeds = &[
+            REDEMPTION_VAULT_SEED,
+            token_mint_key.as_ref(),
+            &[ctx.bumps.redemption_vault],
+        ];
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.redemption_vault.to_account_info(),
+                    to: ctx.accounts.holder.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            lamports,
+        )?;
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.current_supply -= token_amount;
+
+        emit!(EmergencyRedeemed {
+            bonding_curve: bonding_curve.key(),
+            holder: ctx.accounts.holder.key(),
+            tokens_burned: token_amount,
+            lamports,
+        });
+
+        msg!("Redeemed {} tokens for {} lamports", token_amount, lamports);
+        Ok(())
+    }
+
+    /**
+     * Create an empty cross-curve price index
+     *
+     * Protocol admin only. An index is a basket of up to
+     * CURVE_INDEX_CAPACITY curves whose volume-weighted TWAP is sampled by
+     * update_curve_index, e.g. a "sector index" over related launches.
+     */
+    pub fn initialize_curve_index(
+        ctx: Context<InitializeCurveIndex>,
+        index_id: u32,  // Distinguishes the protocol's indexes
+    ) -> Result<()> {
+        let curve_index = &mut ctx.accounts.curve_index;
+        curve_index.index_id = index_id;
+        curve_index.len = 0;
+        curve_index.curves = [Pubkey::default(); CURVE_INDEX_CAPACITY];
+        curve_index.last_price = 0;
+        curve_index.cumulative_price = 0;
+        curve_index.last_update_ts = 0;
+        curve_index.bump = ctx.bumps.curve_index;
+
+        msg!("Curve index {} initialized", index_id);
+        Ok(())
+    }
+
+    /**
+     * Add a curve to an index's basket
+     *
+     * The index samples each curve's TWAP, so only curves recording price
+     * observations can join.
+     */
+    pub fn add_index_curve(ctx: Context<AddIndexCurve>) -> Result<()> {
+        require!(
+            !ctx.accounts.bonding_curve.is_simulation(),
+            BondingCurveError::SimulationCurve
+        );
+        require!(
+            ctx.accounts.bonding_curve.price_observations_required,
+            BondingCurveError::PriceObservationsRequired
+        );
+        let curve_key = ctx.accounts.bonding_curve.key();
+        let curve_index = &mut ctx.accounts.curve_index;
+        let len = curve_index.len as usize;
+        require!(len < CURVE_INDEX_CAPACITY, BondingCurveError::IndexFull);
+        require!(
+            !curve_index.curves[..len].contains(&curve_key),
+            BondingCurveError::CurveAlreadyIndexed
+        );
+
+        curve_index.curves[len] = curve_key;
+        curve_index.len += 1;
+
+        msg!("Curve {} added to index {}", curve_key, curve_index.index_id);
+        Ok(())
+    }
+
+    /**
+     * Take a curve out of an index's basket
+     *
+     * Protocol admin only. The curve is named by key, since it may already
+     * be closed.
+     */
+    pub fn remove_index_curve(
+        ctx: Context<RemoveIndexCurve>,
+        bonding_curve: Pubkey,  // Curve to take out of the basket
+    ) -> Result<()> {
+        let curve_index = &mut ctx.accounts.curve_index;
+        curve_index.remove(&bonding_curve)?;
+
+        msg!("Curve {} removed from index {}", bonding_curve, curve_index.index_id);
+        Ok(())
+    }
+
+    /**
+     * Sample the basket's volume-weighted TWAP into the index
+     *
+     * Permissionless crank. Pass each basket curve followed by its price
+     * observations as remaining accounts, in basket order. Each curve's TWAP
+     * over the last CURVE_INDEX_TWAP_SLOTS slots is weighted by its SOL
+     * volume for the current day; with no volume yet the plain average is
+     * used. A price pushed around within the crank's own transaction barely
+     * moves a TWAP, where it would move the spot price all the way.
+     *
+     * Closed and shrunk curves are left out of the sample; pass any account
+     * in place of their observations.
+     *
+     * The previous sample is accumulated into cumulative_price for the time
+     * it was in force, so the index's TWAP between two updates is the change
+     * in cumulative_price divided by the change in last_update_ts.
+     */
+    pub fn update_curve_index(ctx: Context<UpdateCurveIndex>) -> Result<()> {
+        let clock = Clock::get()?;
+        let now = clock.unix_timestamp;
+        let today = now.div_euclid(SECONDS_PER_DAY);
+        let curve_index = &mut ctx.accounts.curve_index;
+        let basket = &curve_index.curves[..curve_index.len as usize];
+        require!(
+            !basket.is_empty() && ctx.remaining_accounts.len() == 2 * basket.len(),
+            BondingCurveError::IndexBasketMismatch
+        );
+
+        let (mut weighted_sum, mut total_volume, mut price_sum, mut sampled) =
+            (0u128, 0u128, 0u128, 0u128);
+        for (pair, expected) in ctx.remaining_accounts.chunks_exact(2).zip(basket) {
+            let (info, observations_info) = (&pair[0], &pair[1]);
+            require_keys_eq!(info.key(), *expected, BondingCurveError::IndexBasketMismatch);
+            // The basket holds curve PDAs, so a foreign owner means the curve was closed
+            if info.owner != &crate::ID || info.data_len() < BondingCurve::LEN {
+                continue;
+            }
+            let curve = BondingCurve::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+
+            require_keys_eq!(
+                *observations_info.owner,
+                crate::ID,
+                BondingCurveError::PriceObservationsRequired
+            );
+            let observations =
+                PriceObservations::try_deserialize(&mut &observations_info.try_borrow_data()?[..])?;
+            require_keys_eq!(
+                observations.bonding_curve,
+                info.key(),
+                BondingCurveError::PriceObservationsRequired
+            );
+
+            let price = observations.twap(clock.slot, CURVE_INDEX_TWAP_SLOTS)? as u128;
+            let volume = if curve.daily_stats.day == today {
+                curve.daily_stats.volume_lamports as u128
+            } else {
+                0
+            };
+            weighted_sum += price * volume;
+            total_volume += volume;
+            price_sum += price;
+            sampled += 1;
+        }
+        require!(sampled > 0, BondingCurveError::NoLiveIndexCurves);
+        let index_price = weighted_sum
+            .checked_div(total_volume)
+            .unwrap_or(price_sum / sampled) as u64;
+
+        if curve_index.last_update_ts > 0 {
+            let elapsed = now.saturating_sub(curve_index.last_update_ts).max(0) as u128;
+            curve_index.cumulative_price = curve_index
+                .cumulative_price
+                .checked_add(curve_index.last_price as u128 * elapsed)
+                .ok_or(BondingCurveError::MathOverflow)?;
+        }
+        curve_index.last_price = index_price;
+        curve_index.last_update_ts = now;
+
+        emit!(CurveIndexUpdated {
+            curve_index: curve_index.key(),
+            price: index_price,
+            cumulative_price: curve_index.cumulative_price,
+            timestamp: now,
+        });
+
+        msg!("Index {} price: {}", curve_index.index_id, index_price);
+        Ok(())
+    }
+
+    /**
+     * Pay out the referral rewards accrued on a referral code
+     *
+     * Rewards follow the NFT: whoever holds it when claiming receives
+     * everything accrued so far, including rewards earned under previous
+     * holders.
+     */
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        let referral = &mut ctx.accounts.referral;
+        let amount = referral.rewards_unclaimed;
+        require!(amount > 0, BondingCurveError::NoFeesToClaim);
+
+        referral.rewards_unclaimed = 0;
+        **referral.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.holder.try_borrow_mut_lamports()? += amount;
+
+        emit!(ReferralRewardsClaimed {
+            referral: referral.key(),
+            recipient: ctx.accounts.holder.key(),
+            amount,
+        });
+
+        msg!("Claimed {} lamports of referral rewards", amount);
+        Ok(())
+    }
+
+    /**
+     * Sell tokens into the exit queue when reserves can't pay right away
+     *
+     * For when a direct sell would fail with InsufficientReserves, or other
+     * exits are already waiting. The tokens burn now at the current curve
+     * price and an ExitClaim PDA records the payout at the back of a FIFO
+     * queue; settle_exit_claim pays it once buys have replenished the
+     * reserves. The trading fee is withheld at settlement; queued exits
+     * skip the charity share.
+     */
+    pub fn queue_sell(
+        ctx: Context<QueueSell>,
+        token_amount: u64,           // Amount of tokens to sell
+        min_sol_out: Option<u64>,    // Fewest lamports the seller accepts, after fees
+    ) -> Result<()> {
+        require!(token_amount > 0, BondingCurveError::InvalidAmount);
+
+        ctx.accounts.bonding_curve.record_trade_in_slot(Clock::get()?.slot)?;
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        require!(!bonding_curve.has_quote_mint(), BondingCurveError::QuoteCurveUnsupported);
+        require!(bonding_curve.status == CurveStatus::Active, BondingCurveError::CurveNotActive);
+        require!(
+            bonding_curve.selling_open(Clock::get()?.unix_timestamp),
+            BondingCurveError::TradingClosed
+        );
+        check_strict_mode(bonding_curve, &ctx.accounts.instructions_sysvar)?;
+        check_eligibility(
+            bonding_curve,
+            ctx.accounts.seller.key(),
+            &ctx.accounts.eligibility_record,
+        )?;
+        check_not_blacklisted(
+            bonding_curve,
+            ctx.accounts.seller.key(),
+            &ctx.accounts.blacklist_entry,
+        )?;
+        check_hold_period(
+            &ctx.accounts.user_trade_state,
+            bonding_curve,
+            ctx.accounts.seller.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        // Price the sale exactly like sell_tokens
+        let new_supply_after_sale = bonding_curve.current_supply
+            .checked_sub(token_amount)
+            .ok_or(BondingCurveError::InsufficientSupply)?;
+        let tax_tokens = calculate_fee(token_amount, bonding_curve.sell_burn_tax_bps)?;
+        let total_burned = token_amount
+            .checked_add(tax_tokens)
+            .ok_or(BondingCurveError::SupplyOverflow)?;
+        require!(
+            total_burned <= bonding_curve.current_supply,
+            BondingCurveError::InsufficientSupply
+        );
+        let curve_sol = calculate_sol_for_curve(
+            &bonding_curve.params(),
+            token_amount,
+            new_supply_after_sale,
+        )?;
+        let now = Clock::get()?.unix_timestamp;
+        let active_phase = bonding_curve.active_phase(now)?;
+        let sol_amount = match active_phase {
+            Some(phase) => {
+                phase.check_sell()?;
+                phase.apply_price_multiplier(curve_sol)?
+            }
+            None => curve_sol,
+        };
+        require!(
+            sol_amount <= bonding_curve.effective_max_sol_per_trade(),
+            BondingCurveError::TradeSizeExceeded
+        );
+
+        // Only queue when a direct sell can't pay, or to keep FIFO order behind earlier exits
+        let reserve_shortfall = sol_amount.saturating_sub(bonding_curve.sellable_reserves());
+        require!(
+            reserve_shortfall > bonding_curve.shortfall_cover() || bonding_curve.exit_queue_len() > 0,
+            BondingCurveError::ExitQueueNotNeeded
+        );
+
+        let sell_fee_bps = bonding_curve.sell_fee_bps(Clock::get()?.slot, active_phase);
+        let fee_lamports = calculate_fee(sol_amount, sell_fee_bps)?;
+        let sol_owed = sol_amount
+            .checked_sub(fee_lamports)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        let (sol_owed, fee_lamports) = apply_dust_policy(bonding_curve, sol_owed, fee_lamports)?;
+        require!(
+            sol_owed >= min_sol_out.unwrap_or(0),
+            BondingCurveError::SlippageExceeded
+        );
+
+        // Burn tokens from seller
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    from: ctx.accounts.seller_token_account.to_account_info(),
+                    authority: ctx.accounts.seller.to_account_info(),
+                },
+            ),
+            total_burned,
+        )?;
+
+        // Supply drops now; the reserves only drop once the claim is paid
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        let price_before = bonding_curve.current_price()?;
+        bonding_curve.current_supply -= total_burned;
+        bonding_curve.refresh_sold_out();
+        let position = bonding_curve.exit_queue_tail;
+        bonding_curve.exit_queue_tail = position
+            .checked_add(1)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        bonding_curve.exit_queue_lamports = bonding_curve
+            .exit_queue_lamports
+            .checked_add(sol_amount)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        let new_price = bonding_curve.current_price()?;
+        bonding_curve.record_trade(now, sol_amount, fee_lamports, price_before, new_price)?;
+        record_price_observation(&mut ctx.accounts.price_observations, bonding_curve)?;
+        record_curve_stats(&mut ctx.accounts.curve_stats, bonding_curve, sol_amount, false, false)?;
+
+        let exit_claim = &mut ctx.accounts.exit_claim;
+        exit_claim.bonding_curve = bonding_curve.key();
+        exit_claim.seller = ctx.accounts.seller.key();
+        exit_claim.position = position;
+        exit_claim.sol_amount = sol_amount;
+        exit_claim.fee_lamports = fee_lamports;
+        exit_claim.queued_ts = now;
+        exit_claim.bump = ctx.bumps.exit_claim;
+
+        emit!(ExitQueued {
+            seller: exit_claim.seller,
+            bonding_curve: exit_claim.bonding_curve,
+            position,
+            ahead_in_queue: position - bonding_curve.exit_queue_head,
+            tokens_burned: total_burned,
+            sol_owed,
+            fee_lamports,
+            queue_lamports: bonding_curve.exit_queue_lamports,
+        });
+
+        msg!(
+            "Queued exit {} for {} lamports ({} ahead)",
+            position,
+            sol_owed,
+            position - bonding_curve.exit_queue_head
+        );
+        Ok(())
+    }
+
+    /**
+     * Pay out the exit at the front of the queue
+     *
+     * Permissionless crank. Succeeds once buys have refilled the reserves
+     * enough to cover the claim, with the same rounding allowance from the
+     * reserve buffer as direct sells. The claim's rent goes back to the seller.
+     */
+    pub fn settle_exit_claim(ctx: Context<SettleExitClaim>) -> Result<()> {
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        let exit_claim = &ctx.accounts.exit_claim;
+        require!(
+            exit_claim.position == bonding_curve.exit_queue_head,
+            BondingCurveError::ExitClaimNotAtHead
+        );
+
+        let sol_amount = exit_claim.sol_amount;
+        let fee_lamports = exit_claim.fee_lamports;
+        let reserve_shortfall = sol_amount.saturating_sub(bonding_curve.sol_reserves);
+        require!(
+            reserve_shortfall <= bonding_curve.shortfall_cover(),
+            BondingCurveError::InsufficientReserves
+        );
+        let sol_to_seller = sol_amount - fee_lamports;
+
+        let token_mint_key = bonding_curve.token_mint;
+        let seeds = &[
+            SOL_VAULT_SEED,
+            token_mint_key.as_ref(),
+            &[ctx.bumps.sol_vault],
+        ];
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.sol_vault.to_account_info(),
+                    to: ctx.accounts.seller.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            sol_to_seller,
+        )?;
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.draw_shortfall(reserve_shortfall);
+        bonding_curve.sol_reserves = (bonding_curve.sol_reserves + reserve_shortfall)
+            .checked_sub(sol_amount)
+            .unwrap();
+        bonding_curve.exit_queue_lamports -= sol_amount;
+        bonding_curve.exit_queue_head += 1;
+        let vault_fees = bonding_curve.accrue_fees(fee_lamports)?;
+        route_fees_to_vault(
+            &ctx.accounts.sol_vault,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.system_program,
+            token_mint_key,
+            ctx.bumps.sol_vault,
+            vault_fees,
+        )?;
+
+        emit!(ExitClaimSettled {
+            seller: ctx.accounts.seller.key(),
+            bonding_curve: bonding_curve.key(),
+            position: ctx.accounts.exit_claim.position,
+            sol_received: sol_to_seller,
+            fee_lamports,
+            remaining_in_queue: bonding_curve.exit_queue_len(),
+        });
+
+        msg!(
+            "Settled exit {} for {} lamports",
+            ctx.accounts.exit_claim.position,
+            sol_to_seller
+        );
+        Ok(())
+    }
+
+    /**
+     * Configure the anti-sniping launch window on a fresh curve
+     *
+     * Buys are refused before launch_start_slot. For launch_window_slots
+     * after it, each wallet may buy at most max_buy_per_wallet_during_launch
+     * tokens in total, tracked on its UserTradeState PDA, so bots can't sweep
+     * the bottom of the curve in the first blocks. Pass all zeros to clear.
+     */
+    pub fn set_launch_window(
+        ctx: Context<UpdateCurveSettings>,
+        launch_start_slot: u64,                 // Slot trading opens (0 = immediately)
+        launch_window_slots: u64,               // Slots the per-wallet limit lasts
+        max_buy_per_wallet_during_launch: u64,  // Token cap per wallet in the window (0 = none)
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        require!(bonding_curve.current_supply == 0, BondingCurveError::CurveAlreadyLaunched);
+        require!(
+            (launch_window_slots == 0) == (max_buy_per_wallet_during_launch == 0),
+            BondingCurveError::InvalidLaunchWindow
+        );
+
+        bonding_curve.launch_start_slot = launch_start_slot;
+        bonding_curve.launch_window_slots = launch_window_slots;
+        bonding_curve.max_buy_per_wallet_during_launch = max_buy_per_wallet_during_launch;
+
+        msg!(
+            "Launch window: slot {} for {} slots, {} tokens per wallet",
+            launch_start_slot,
+            launch_window_slots,
+            max_buy_per_wallet_during_launch
+        );
+        Ok(())
+    }
+
+    /**
+     * Create a curve's market-maker vault
+     *
+     * A creator-funded, program-managed vault holding SOL and tokens that
+     * quotes the curve's own price for each fill, half_spread_bps either side.
+     * It only trades while that quote sits inside the curve's own fee
+     * spread, so users get a better price from it and the curve stays the
+     * backstop once the vault's inventory runs out. Starts disabled.
+     */
+    pub fn initialize_mm_vault(
+        ctx: Context<InitializeMmVault>,
+        half_spread_bps: u16,  // Distance of the vault's bid and ask from spot, in basis points
+    ) -> Result<()> {
+        require!(half_spread_bps <= MAX_FEE_BPS, BondingCurveError::InvalidFee);
+        require!(
+            !ctx.accounts.bonding_curve.has_quote_mint(),
+            BondingCurveError::QuoteCurveUnsupported
+        );
+        ctx.accounts
+            .bonding_curve
+            .record_creator_action(Clock::get()?.unix_timestamp)?;
+
+        let mm_vault = &mut ctx.accounts.mm_vault;
+        mm_vault.bonding_curve = ctx.accounts.bonding_curve.key();
+        mm_vault.half_spread_bps = half_spread_bps;
+        mm_vault.enabled = false;
+        mm_vault.sol_balance = 0;
+        mm_vault.volume_lamports = 0;
+        mm_vault.bump = ctx.bumps.mm_vault;
+
+        msg!("Market-maker vault created at {} bps half spread", half_spread_bps);
+        Ok(())
+    }
+
+    /**
+     * Turn the market-maker vault's quotes on or off and set its spread
+     */
+    pub fn configure_mm_vault(
+        ctx: Context<ConfigureMmVault>,
+        half_spread_bps: u16,  // Distance of the vault's bid and ask from spot, in basis points
+        enabled: bool,         // Whether the vault quotes
+    ) -> Result<()> {
+        require!(half_spread_bps <= MAX_FEE_BPS, BondingCurveError::InvalidFee);
+        ctx.accounts
+            .bonding_curve
+            .record_creator_action(Clock::get()?.unix_timestamp)?;
+
+        let mm_vault = &mut ctx.accounts.mm_vault;
+        mm_vault.half_spread_bps = half_spread_bps;
+        mm_vault.enabled = enabled;
+
+        msg!("Market-maker vault {} at {} bps", if enabled { "enabled" } else { "disabled" }, half_spread_bps);
+        Ok(())
+    }
+
+    /**
+     * Deposit SOL and tokens into the market-maker vault
+     */
+    pub fn fund_mm_vault(
+        ctx: Context<ManageMmVault>,
+        sol_amount: u64,    // Lamports to deposit
+        token_amount: u64,  // Tokens to deposit
+    ) -> Result<()> {
+        require!(sol_amount > 0 || token_amount > 0, BondingCurveError::InvalidAmount);
+        ctx.accounts
+            .bonding_curve
+            .record_creator_action(Clock::get()?.unix_timestamp)?;
+        let inventory_before = ctx.accounts.mm_token_account.amount;
+
+        if sol_amount > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.creator.to_account_info(),
+                        to: ctx.accounts.mm_vault.to_account_info(),
+                    },
+                ),
+                sol_amount,
+            )?;
+        }
+        if token_amount > 0 {
+            token_interface::transfer_checked(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token_interface::TransferChecked {
+                        from: ctx.accounts.creator_token_account.to_account_info(),
+                        mint: ctx.accounts.token_mint.to_account_info(),
+                        to: ctx.accounts.mm_token_account.to_account_info(),
+                        authority: ctx.accounts.creator.to_account_info(),
+                    },
+                ),
+                token_amount,
+                ctx.accounts.token_mint.decimals,
+            )?;
+        }
+
+        // A transfer-fee mint delivers less than was sent; count what arrived
+        ctx.accounts.mm_token_account.reload()?;
+        let tokens_received = ctx.accounts.mm_token_account.amount - inventory_before;
+
+        let mm_vault = &mut ctx.accounts.mm_vault;
+        mm_vault.sol_balance = mm_vault
+            .sol_balance
+            .checked_add(sol_amount)
+            .ok_or(BondingCurveError::MathOverflow)?;
+
+        emit!(MmVaultUpdated {
+            bonding_curve: mm_vault.bonding_curve,
+            sol_balance: mm_vault.sol_balance,
+            token_inventory: ctx.accounts.mm_token_account.amount,
+        });
+
+        msg!("Funded market-maker vault with {} lamports and {} tokens", sol_amount, tokens_received);
+        Ok(())
+    }
+
+    /**
+     * Withdraw SOL and tokens from the market-maker vault back to the creator
+     */
+    pub fn withdraw_mm_vault(
+        ctx: Context<ManageMmVault>,
+        sol_amount: u64,    // Lamports to withdraw
+        token_amount: u64,  // Tokens to withdraw
+    ) -> Result<()> {
+        require!(sol_amount > 0 || token_amount > 0, BondingCurveError::InvalidAmount);
+        ctx.accounts
+            .bonding_curve
+            .record_creator_action(Clock::get()?.unix_timestamp)?;
+        require!(
+            sol_amount <= ctx.accounts.mm_vault.sol_balance
+                && token_amount <= ctx.accounts.mm_token_account.amount,
+            BondingCurveError::MmInventoryExhausted
+        );
+
+        if token_amount > 0 {
+            let bonding_curve_key = ctx.accounts.bonding_curve.key();
+            let seeds = &[
+                MM_VAULT_SEED,
+                bonding_curve_key.as_ref(),
+                &[ctx.accounts.mm_vault.bump],
+            ];
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token_interface::TransferChecked {
+                        from: ctx.accounts.mm_token_account.to_account_info(),
+                        mint: ctx.accounts.token_mint.to_account_info(),
+                        to: ctx.accounts.creator_token_account.to_account_info(),
+                        authority: ctx.accounts.mm_vault.to_account_info(),
+                    },
+                    &[&seeds[..]],
+                ),
+                token_amount,
+                ctx.accounts.token_mint.decimals,
+            )?;
+        }
+
+        ctx.accounts.mm_token_account.reload()?;
+        let mm_vault = &mut ctx.accounts.mm_vault;
+        mm_vault.sol_balance -= sol_amount;
+        **mm_vault.to_account_info().try_borrow_mut_lamports()? -= sol_amount;
+        **ctx.accounts.creator.try_borrow_mut_lamports()? += sol_amount;
+
+        emit!(MmVaultUpdated {
+            bonding_curve: mm_vault.bonding_curve,
+            sol_balance: mm_vault.sol_balance,
+            token_inventory: ctx.accounts.mm_token_account.amount,
+        });
+
+        msg!("Withdrew {} lamports and {} tokens from market-maker vault", sol_amount, token_amount);
+        Ok(())
+    }
+
+    /**
+     * Buy tokens from the market-maker vault's inventory at its ask
+     *
+     * The ask is what the curve would charge to mint the same tokens
+     * (phase-adjusted) plus half_spread_bps, which must be tighter than the
+     * curve's trading fee; one fill takes at most MAX_MM_FILL_BPS of supply.
+     * The curve's supply and reserves are untouched, so the spot price
+     * doesn't move.
+     */
+    pub fn mm_buy(
+        ctx: Context<MmTrade>,
+        token_amount: u64,  // Tokens to buy
+        max_sol_in: u64,    // Most lamports the buyer pays
+    ) -> Result<()> {
+        require!(token_amount > 0, BondingCurveError::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        require!(!ctx.accounts.bonding_curve.buys_paused(now), BondingCurveError::CurvePaused);
+        require!(
+            ctx.accounts.bonding_curve.trading_open(now),
+            BondingCurveError::TradingClosed
+        );
+        check_strict_mode(&ctx.accounts.bonding_curve, &ctx.accounts.instructions_sysvar)?;
+        require!(
+            token_amount <= ctx.accounts.mm_token_account.amount,
+            BondingCurveError::MmInventoryExhausted
+        );
+        let (sol_amount, price) = mm_quote(
+            &ctx.accounts.bonding_curve,
+            &ctx.accounts.mm_vault,
+            token_amount,
+            true,
+        )?;
+        require!(sol_amount <= max_sol_in, BondingCurveError::SlippageExceeded);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.trader.to_account_info(),
+                    to: ctx.accounts.mm_vault.to_account_info(),
+                },
+            ),
+            sol_amount,
+        )?;
+
+        let bonding_curve_key = ctx.accounts.bonding_curve.key();
+        let seeds = &[
+            MM_VAULT_SEED,
+            bonding_curve_key.as_ref(),
+            &[ctx.accounts.mm_vault.bump],
+        ];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::TransferChecked {
+                    from: ctx.accounts.mm_token_account.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.trader_token_account.to_account_info(),
+                    authority: ctx.accounts.mm_vault.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            token_amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        let mm_vault = &mut ctx.accounts.mm_vault;
+        mm_vault.sol_balance = mm_vault
+            .sol_balance
+            .checked_add(sol_amount)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        mm_vault.record_volume(sol_amount)?;
+        record_price_observation(&mut ctx.accounts.price_observations, &ctx.accounts.bonding_curve)?;
+        let first_buy = mark_buyer(
+            &ctx.accounts.bonding_curve,
+            &ctx.accounts.curve_stats,
+            ctx.accounts.trader.key(),
+            &ctx.accounts.buyer_mark,
+            ctx.accounts.trader.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        )?;
+        record_curve_stats(
+            &mut ctx.accounts.curve_stats,
+            &ctx.accounts.bonding_curve,
+            sol_amount,
+            true,
+            first_buy,
+        )?;
+
+        emit!(MmTradeExecuted {
+            bonding_curve: bonding_curve_key,
+            trader: ctx.accounts.trader.key(),
+            is_buy: true,
+            token_amount,
+            sol_amount,
+            spot_price: price,
+        });
+
+        msg!("Bought {} tokens from the market-maker vault for {} lamports", token_amount, sol_amount);
+        Ok(())
+    }
+
+    /**
+     * Sell tokens to the market-maker vault at its bid
+     *
+     * The bid is what the curve would pay to burn the same tokens
+     * (phase-adjusted) minus half_spread_bps and the curve's current sell
+     * tax, paid from the vault's SOL;
+     * one fill takes at most MAX_MM_FILL_BPS of supply. The tokens join the
+     * vault's inventory instead of being burned, so the curve's price
+     * doesn't move.
+     */
+    pub fn mm_sell(
+        ctx: Context<MmTrade>,
+        token_amount: u64,  // Tokens to sell
+        min_sol_out: u64,   // Fewest lamports the seller accepts
+    ) -> Result<()> {
+        require!(token_amount > 0, BondingCurveError::InvalidAmount);
+        require!(
+            ctx.accounts.bonding_curve.selling_open(Clock::get()?.unix_timestamp),
+            BondingCurveError::TradingClosed
+        );
+        check_strict_mode(&ctx.accounts.bonding_curve, &ctx.accounts.instructions_sysvar)?;
+        check_not_blacklisted(
+            &ctx.accounts.bonding_curve,
+            ctx.accounts.trader.key(),
+            &ctx.accounts.blacklist_entry,
+        )?;
+        let (sol_amount, price) = mm_quote(
+            &ctx.accounts.bonding_curve,
+            &ctx.accounts.mm_vault,
+            token_amount,
+            false,
+        )?;
+        require!(sol_amount >= min_sol_out, BondingCurveError::SlippageExceeded);
+        // The vault has no fees to sweep dust into, so dust is refused whatever the curve's policy
+        require!(
+            sol_amount >= ctx.accounts.bonding_curve.dust_threshold,
+            BondingCurveError::DustTrade
+        );
+        require!(
+            sol_amount <= ctx.accounts.mm_vault.sol_balance,
+            BondingCurveError::MmInventoryExhausted
+        );
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::TransferChecked {
+                    from: ctx.accounts.trader_token_account.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.mm_token_account.to_account_info(),
+                    authority: ctx.accounts.trader.to_account_info(),
+                },
+            ),
+            token_amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        let mm_vault = &mut ctx.accounts.mm_vault;
+        mm_vault.sol_balance -= sol_amount;
+        mm_vault.record_volume(sol_amount)?;
+        **mm_vault.to_account_info().try_borrow_mut_lamports()? -= sol_amount;
+        **ctx.accounts.trader.try_borrow_mut_lamports()? += sol_amount;
+        record_price_observation(&mut ctx.accounts.price_observations, &ctx.accounts.bonding_curve)?;
+        record_curve_stats(
+            &mut ctx.accounts.curve_stats,
+            &ctx.accounts.bonding_curve,
+            sol_amount,
+            false,
+            false,
+        )?;
+
+        emit!(MmTradeExecuted {
+            bonding_curve: ctx.accounts.bonding_curve.key(),
+            trader: ctx.accounts.trader.key(),
+            is_buy: false,
+            token_amount,
+            sol_amount,
+            spot_price: price,
+        });
+
+        msg!("Sold {} tokens to the market-maker vault for {} lamports", token_amount, sol_amount);
+        Ok(())
+    }
+
+    /**
+     * Create a throwaway curve for classroom demos and load tests
+     *
+     * The curve trades like a real one, on a fresh mint and with real
+     * lamports, but charges no protocol fee and skips the creation fee,
+     * metadata, protocol stats, launch queue and indexes. It can never
+     * graduate or hand off its mint authority. The creator pre-funds the
+     * SOL vault's rent floor, as on a real curve. After duration_secs anyone
+     * may close it with close_simulation, which refunds the holders.
+     */
+    pub fn simulate_launch(
+        ctx: Context<SimulateLaunch>,
+        initial_price: u64,      // Price in lamports per token
+        slope: u64,              // Price increase per token minted
+        name: String,            // Token name
+        symbol: String,          // Token symbol
+        curve_type: CurveType,   // Curve shape; LinearThenFlat and ConstantProduct are not supported here
+        duration_secs: i64,      // Seconds until anyone may close the simulation
+    ) -> Result<()> {
+        require!(initial_price > 0, BondingCurveError::InvalidPrice);
+        require!(slope > 0, BondingCurveError::InvalidSlope);
+        require!(name.len() <= MAX_NAME_LEN, BondingCurveError::NameTooLong);
+        require!(symbol.len() <= MAX_SYMBOL_LEN, BondingCurveError::SymbolTooLong);
+        require!(
+            !matches!(curve_type, CurveType::LinearThenFlat | CurveType::ConstantProduct),
+            BondingCurveError::InvalidThreshold
+        );
+        require!(
+            duration_secs > 0 && duration_secs <= MAX_SIMULATION_SECONDS,
+            BondingCurveError::InvalidSimulationDuration
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut name_bytes = [0u8; 32];
+        let mut symbol_bytes = [0u8; 8];
+        name_bytes[..name.len().min(32)].copy_from_slice(&name.as_bytes()[..name.len().min(32)]);
+        symbol_bytes[..symbol.len().min(8)].copy_from_slice(&symbol.as_bytes()[..symbol.len().min(8)]);
+
+        // Everything not set here stays zeroed: no fees, phases, limits or extras
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.creator = ctx.accounts.creator.key();
+        bonding_curve.token_mint = ctx.accounts.token_mint.key();
+        bonding_curve.initial_price = initial_price;
+        bonding_curve.slope = slope;
+        bonding_curve.curve_type = curve_type;
+        bonding_curve.bump = ctx.bumps.bonding_curve;
+        bonding_curve.name = name_bytes;
+        bonding_curve.symbol = symbol_bytes;
+        bonding_curve.status = CurveStatus::Active;
+        bonding_curve.graduation_metric = GraduationMetric::SolRaised;
+        bonding_curve.graduation_threshold = u64::MAX;
+        bonding_curve.creator_last_action_ts = now;
+        bonding_curve.token_program = ctx.accounts.token_program.key();
+        bonding_curve.simulation_expiry_ts = now
+            .checked_add(duration_secs)
+            .ok_or(BondingCurveError::MathOverflow)?;
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.creator.to_account_info(),
+                    to: ctx.accounts.sol_vault.to_account_info(),
+                },
+            ),
+            vault_rent_floor()?,
+        )?;
+
+        emit!(SimulationLaunched {
+            bonding_curve: bonding_curve.key(),
+            token_mint: bonding_curve.token_mint,
+            creator: bonding_curve.creator,
+            expiry_ts: bonding_curve.simulation_expiry_ts,
+        });
+
+        msg!(
+            "Simulation curve for {} open until {}",
+            bonding_curve.token_mint,
+            bonding_curve.simulation_expiry_ts
+        );
+        Ok(())
+    }
+
+    /**
+     * Close an expired simulation curve
+     *
+     * Permissionless once the simulation has expired, and done in two
+     * calls. While holders still hold tokens, the first call stops trading
+     * and records the refund per token: the vault above its rent floor over
+     * the supply outstanding. Holders then claim_simulation_refund in as
+     * many transactions as they like. Once every token has been refunded,
+     * the next call sends the vault's rent floor and rounding dust back to
+     * the creator, who funded it, and closes the curve account to the
+     * creator too. Nothing can mint more afterwards, since the mint
+     * authority is the closed curve PDA.
+     */
+    pub fn close_simulation(ctx: Context<CloseSimulation>) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        require!(bonding_curve.is_simulation(), BondingCurveError::SimulationCurve);
+        require!(
+            Clock::get()?.unix_timestamp >= bonding_curve.simulation_expiry_ts,
+            BondingCurveError::SimulationNotExpired
+        );
+
+        if bonding_curve.redemption_supply == 0 && bonding_curve.circulating_supply() > 0 {
+            let available = ctx
+                .accounts
+                .sol_vault
+                .lamports()
+                .saturating_sub(vault_rent_floor()?);
+            // Queued exits burned their tokens already; what they are owed
+            // stays behind for them to settle
+            let queue_reserves = bonding_curve.exit_queue_lamports.min(available);
+            let refund_lamports = available - queue_reserves;
+
+            bonding_curve.sol_reserves = queue_reserves;
+            bonding_curve.reserve_buffer = 0;
+            bonding_curve.seeded_reserves = 0;
+            bonding_curve.paused = true;
+            bonding_curve.pause_expires_ts = 0;
+            bonding_curve.redemption_lamports = refund_lamports;
+            bonding_curve.redemption_supply = bonding_curve.circulating_supply();
+
+            emit!(SimulationRefundsOpened {
+                bonding_curve: bonding_curve.key(),
+                opened_by: ctx.accounts.closer.key(),
+                refund_lamports,
+                supply: bonding_curve.redemption_supply,
+            });
+
+            msg!(
+                "Simulation refunds open: {} lamports over {} tokens",
+                refund_lamports,
+                bonding_curve.redemption_supply
+            );
+            return Ok(());
+        }
+
+        require!(
+            bonding_curve.circulating_supply() == 0 && bonding_curve.exit_queue_lamports == 0,
+            BondingCurveError::SimulationRefundsOutstanding
+        );
+
+        let lamports = ctx.accounts.sol_vault.lamports();
+        if lamports > 0 {
+            let token_mint_key = bonding_curve.token_mint;
+            let seeds = &[
+                SOL_VAULT_SEED,
+                token_mint_key.as_ref(),
+                &[ctx.bumps.sol_vault],
+            ];
+            system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.sol_vault.to_account_info(),
+                        to: ctx.accounts.creator.to_account_info(),
+                    },
+                    &[&seeds[..]],
+                ),
+                lamports,
+            )?;
+        }
+
+        emit!(SimulationClosed {
+            bonding_curve: bonding_curve.key(),
+            closed_by: ctx.accounts.closer.key(),
+            vault_lamports: lamports,
+            refunded_lamports: bonding_curve.redemption_lamports,
+        });
+
+        msg!("Simulation {} closed", bonding_curve.key());
+        bonding_curve.close(ctx.accounts.creator.to_account_info())
+    }
+
+    /**
+     * Refund a token account's share of a closed simulation
+     *
+     * Burns the account's whole balance and pays balance * redemption_lamports
+     * / redemption_supply, rounded down, to the account's owner. The holder
+     * signs, or anyone the holder approved as delegate for the balance, so a
+     * crank can refund holders in batches on their behalf.
+     */
+    pub fn claim_simulation_refund(ctx: Context<ClaimSimulationRefund>) -> Result<()> {
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        require!(bonding_curve.is_simulation(), BondingCurveError::SimulationCurve);
+        require!(
+            bonding_curve.redemption_supply > 0,
+            BondingCurveError::SimulationRefundsNotOpen
+        );
+        let token_amount = ctx.accounts.holder_token_account.amount;
+        require!(token_amount > 0, BondingCurveError::InvalidAmount);
+        let lamports = mul_div_u128(
+            token_amount as u128,
+            bonding_curve.redemption_lamports as u128,
+            bonding_curve.redemption_supply as u128,
+        )? as u64;
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::Burn {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    from: ctx.accounts.holder_token_account.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            ),
+            token_amount,
+        )?;
+
+        let token_mint_key = ctx.accounts.token_mint.key();
+        let seeds = &[
+            SOL_VAULT_SEED,
+            token_mint_key.as_ref(),
+            &[ctx.bumps.sol_vault],
+        ];
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.sol_vault.to_account_info(),
+                    to: ctx.accounts.holder.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            lamports,
+        )?;
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.current_supply -= token_amount;
+
+        emit!(SimulationRefundClaimed {
+            bonding_curve: bonding_curve.key(),
+            holder: ctx.accounts.holder.key(),
+            tokens_burned: token_amount,
+            lamports,
+        });
+
+        msg!("Refunded {} tokens for {} lamports", token_amount, lamports);
+        Ok(())
+    }
+
+    /**
+     * Run a presale for an allowlist of wallets on the curve itself
+     *
+     * Until end_slot only buy_tokens_whitelisted can buy, with a Merkle proof
+     * that the buyer is in the list committed to by merkle_root; no off-chain
+     * escrow is needed. An all-zero root clears the allowlist.
+     */
+    pub fn set_whitelist_root(
+        ctx: Context<UpdateCurveSettings>,
+        merkle_root: [u8; 32],  // Root of the allowlist Merkle tree
+        end_slot: u64,          // Slot the allowlist period ends
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        require!(
+            merkle_root == [0; 32] || end_slot > Clock::get()?.slot,
+            BondingCurveError::InvalidWhitelistEnd
+        );
+
+        bonding_curve.whitelist_root = merkle_root;
+        bonding_curve.whitelist_end_slot = end_slot;
+
+        msg!("Allowlist set until slot {}", end_slot);
+        Ok(())
+    }
+
+    /**
+     * Close a user's empty auxiliary accounts on a curve and reclaim their rent
+     *
+     * Pass any of the user's per-curve PDAs; the ones that no longer hold
+     * anything are closed to the user and the rest are left alone, so a
+     * wallet can tidy up after a curve in one call. A UserTradeState is
+     * empty once its hold period has elapsed and the launch window is not
+     * running; a BootstrapDeposit once its allocation has been claimed; a
+     * HolderRewards checkpoint once nothing is staked, as unstaking pays out
+     * every reward owed; an AirdropReceipt once the curve is past PreLaunch
+     * and claims are over; a buyer mark once the curve stops trading, so it
+     * can't be counted as a new buyer again. A buy limit order is cancelled,
+     * its escrowed lamports returned with the rent (sell orders escrow tokens
+     * and go through cancel_limit_order). A trading session closes once
+     * expired, returning any deposit left; a referral code the user holds
+     * the NFT of, once its rewards have been claimed.
+     */
+    pub fn close_user_accounts(ctx: Context<CloseUserAccounts>) -> Result<()> {
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        let user = ctx.accounts.user.to_account_info();
+        let clock = Clock::get()?;
+
+        let mut accounts_closed = 0u8;
+        let mut lamports_reclaimed = 0u64;
+
+        if let Some(state) = &ctx.accounts.user_trade_state {
+            let hold_elapsed = clock.unix_timestamp
+                >= state.last_buy_ts.saturating_add(bonding_curve.min_hold_seconds);
+            if hold_elapsed && !bonding_curve.in_launch_window(clock.slot) {
+                lamports_reclaimed += state.to_account_info().lamports();
+                state.close(user.clone())?;
+                accounts_closed += 1;
+            }
+        }
+
+        if let Some(deposit) = &ctx.accounts.bootstrap_deposit {
+            if deposit.claimed {
+                lamports_reclaimed += deposit.to_account_info().lamports();
+                deposit.close(user.clone())?;
+                accounts_closed += 1;
+            }
+        }
+
+        if let Some(holder_rewards) = &ctx.accounts.holder_rewards {
+            if holder_rewards.staked == 0 {
+                lamports_reclaimed += holder_rewards.to_account_info().lamports();
+                holder_rewards.close(user.clone())?;
+                accounts_closed += 1;
+            }
+        }
+
+        if let Some(receipt) = &ctx.accounts.airdrop_receipt {
+            // Claims end by launch_ts, and a curve only leaves PreLaunch then
+            if bonding_curve.status != CurveStatus::PreLaunch {
+                lamports_reclaimed += receipt.to_account_info().lamports();
+                receipt.close(user.clone())?;
+                accounts_closed += 1;
+            }
+        }
+
+        if let Some(buyer_mark) = &ctx.accounts.buyer_mark {
+            let buyer_mark = buyer_mark.to_account_info();
+            let trading_over = matches!(
+                bonding_curve.status,
+                CurveStatus::Graduated | CurveStatus::Archived
+            );
+            if trading_over && is_created(&buyer_mark) {
+                lamports_reclaimed += buyer_mark.lamports();
+                close_account(&buyer_mark, &user)?;
+                accounts_closed += 1;
+            }
+        }
+
+        if let Some(limit_order) = &ctx.accounts.limit_order {
+            if limit_order.side == OrderSide::Buy {
+                lamports_reclaimed += limit_order.to_account_info().lamports();
+                limit_order.close(user.clone())?;
+                accounts_closed += 1;
+                emit!(LimitOrderCancelled {
+                    limit_order: limit_order.key(),
+                    bonding_curve: limit_order.bonding_curve,
+                    owner: limit_order.owner,
+                });
+            }
+        }
+
+        if let Some(session) = &ctx.accounts.session {
+            if clock.slot > session.expiry_slot {
+                lamports_reclaimed += session.to_account_info().lamports();
+                session.close(user.clone())?;
+                accounts_closed += 1;
+            }
+        }
+
+        if let Some(referral) = &ctx.accounts.referral {
+            let holds_nft = ctx.accounts.referral_nft_account.as_ref().is_some_and(|nft| {
+                nft.mint == referral.nft_mint && nft.owner == user.key() && nft.amount == 1
+            });
+            require!(holds_nft, BondingCurveError::InvalidReferral);
+            if referral.rewards_unclaimed == 0 {
+                lamports_reclaimed += referral.to_account_info().lamports();
+                referral.close(user.clone())?;
+                accounts_closed += 1;
+            }
+        }
+
+        require!(accounts_closed > 0, BondingCurveError::NoEmptyAccounts);
+
+        emit!(UserAccountsClosed {
+            bonding_curve: bonding_curve.key(),
+            user: user.key(),
+            accounts_closed,
+            lamports_reclaimed,
+        });
+
+        msg!(
+            "Closed {} accounts, reclaimed {} lamports",
+            accounts_closed,
+            lamports_reclaimed
+        );
+        Ok(())
+    }
+
+    /**
+     * Retire a dead or graduated curve and return its rent to the creator
+     *
+     * Allowed once every token has been sold back (zero supply and reserves
+     * on an active curve) or after graduation, and only when no fees are
+     * left unclaimed and no exits are queued. The curve's mint authority is
+     * revoked if it hasn't been handed off, so the supply is fixed for good;
+     * whatever is left in the SOL, fee and rewards vaults, and the curve
+     * account's own rent, goes to the creator. Holder reward stakes must have
+     * been withdrawn first, which also pays out every reward owed.
+     *
+     * The curve's MmVault is closed with it if it exists, and must hold no
+     * SOL. A registered curve's registry entry must be passed and is closed,
+     * leaving its index in the creator's registry empty; the curve is taken
+     * out of the launch queue, if passed, and of every CurveIndex passed as a
+     * remaining account. Archived curves shrunk to their prefix close too,
+     * as archive_curve only shrinks them once nothing is owed on them.
+     * Simulations use close_simulation.
+     */
+    pub fn close_bonding_curve(ctx: Context<CloseBondingCurve>) -> Result<()> {
+        let info = ctx.accounts.bonding_curve.to_account_info();
+        let curve_key = info.key();
+        let token_mint_key = ctx.accounts.token_mint.key();
+
+        let mut full_curve = None;
+        let (creator, token_mint, registered) = if info.data_len() == layout::bonding_curve::PREFIX_LEN {
+            let data = info.try_borrow_data()?;
+            require!(
+                data.starts_with(BondingCurve::DISCRIMINATOR),
+                BondingCurveError::NotABondingCurve
+            );
+            let pubkey_at = |offset: usize| Pubkey::try_from(&data[offset..offset + 32]).unwrap();
+            let creator = pubkey_at(layout::bonding_curve::CREATOR);
+            let token_mint = pubkey_at(layout::bonding_curve::TOKEN_MINT);
+            // Whether it was registered went with the rest of the layout
+            (creator, token_mint, false)
+        } else {
+            let bonding_curve = BondingCurve::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+            require!(!bonding_curve.is_simulation(), BondingCurveError::SimulationCurve);
+
+            let sold_out = bonding_curve.status == CurveStatus::Active
+                && bonding_curve.current_supply == 0
+                && bonding_curve.sol_reserves == 0;
+            let graduated = matches!(
+                bonding_curve.status,
+                CurveStatus::Graduated | CurveStatus::Archived
+            );
+            require!(sold_out || graduated, BondingCurveError::CurveNotClosable);
+            require!(bonding_curve.exit_queue_len() == 0, BondingCurveError::ExitQueuePending);
+            require!(
+                bonding_curve.creator_fees_unclaimed == 0
+                    && bonding_curve.protocol_fees_unclaimed == 0
+                    && bonding_curve.holder_rewards_pending == 0,
+                BondingCurveError::FeesUnclaimed
+            );
+            require!(bonding_curve.rewards_staked == 0, BondingCurveError::RewardsStaked);
+
+            let fields = (bonding_curve.creator, bonding_curve.token_mint, bonding_curve.registered);
+            full_curve = Some(bonding_curve);
+            fields
+        };
+        require_keys_eq!(creator, ctx.accounts.creator.key(), BondingCurveError::Unauthorized);
+        require_keys_eq!(token_mint, token_mint_key, BondingCurveError::Unauthorized);
+        require!(
+            !registered || ctx.accounts.registry_entry.is_some(),
+            BondingCurveError::RegistryEntryRequired
+        );
+
+        // A shrunk curve gave up its mint authority before shrinking
+        if let Some(bonding_curve) = full_curve.as_mut() {
+            if !bonding_curve.mint_authority_handed_off {
+                revoke_curve_mint_authority(
+                    bonding_curve,
+                    &info,
+                    &ctx.accounts.token_mint.to_account_info(),
+                    &ctx.accounts.token_program.to_account_info(),
+                )?;
+            }
+        }
+
+        let creator_info = ctx.accounts.creator.to_account_info();
+        let mm_vault = ctx.accounts.mm_vault.to_account_info();
+        if is_created(&mm_vault) {
+            let vault = MmVault::try_deserialize(&mut &mm_vault.try_borrow_data()?[..])?;
+            require!(vault.sol_balance == 0, BondingCurveError::CurveNotClosable);
+            close_account(&mm_vault, &creator_info)?;
+        }
+        if let Some(launch_queue) = ctx.accounts.launch_queue.as_mut() {
+            launch_queue.remove(&curve_key);
+        }
+        for index_info in ctx.remaining_accounts {
+            require_keys_eq!(*index_info.owner, crate::ID, BondingCurveError::IndexBasketMismatch);
+            let mut curve_index = CurveIndex::try_deserialize(&mut &index_info.try_borrow_data()?[..])?;
+            curve_index.remove(&curve_key)?;
+            curve_index.try_serialize(&mut &mut index_info.try_borrow_mut_data()?[..])?;
+        }
+
+        // Sweep the vaults; with no PDA data they are gone once empty. With
+        // no stakes left, the rewards vault holds only rent and rounding dust
+        let mut vault_lamports = 0u64;
+        for (vault, seed, bump) in [
+            (&ctx.accounts.sol_vault, SOL_VAULT_SEED, ctx.bumps.sol_vault),
+            (&ctx.accounts.fee_vault, FEE_VAULT_SEED, ctx.bumps.fee_vault),
+            (&ctx.accounts.rewards_vault, REWARDS_VAULT_SEED, ctx.bumps.rewards_vault),
+        ] {
+            let lamports = vault.lamports();
+            if lamports == 0 {
+                continue;
+            }
+            let seeds = &[seed, token_mint_key.as_ref(), &[bump]];
+            system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: vault.to_account_info(),
+                        to: creator_info.clone(),
+                    },
+                    &[&seeds[..]],
+                ),
+                lamports,
+            )?;
+            vault_lamports += lamports;
+        }
+        close_account(&info, &creator_info)?;
+
+        emit!(CurveClosed {
+            bonding_curve: curve_key,
+            token_mint: token_mint_key,
+            creator,
+            vault_lamports,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Curve {} closed, {} vault lamports returned", curve_key, vault_lamports);
+        Ok(())
+    }
+
+    /**
+     * Turn the curve into a fixed raise that stops selling at a SOL target
+     *
+     * The buy that takes the reserves to raise_target_lamports or beyond
+     * still fills; after it, buys are refused for good and TargetReached is
+     * emitted. Sells stay open so holders can always exit. The target is
+     * set once, before the first token is sold, so buyers always know the
+     * raise they are joining; it can't be moved or removed afterwards.
+     */
+    pub fn set_raise_target(
+        ctx: Context<UpdateCurveSettings>,
+        raise_target_lamports: u64,  // Reserves that end the sale
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        require!(bonding_curve.current_supply == 0, BondingCurveError::CurveAlreadyLaunched);
+        require!(
+            bonding_curve.raise_target_lamports == 0,
+            BondingCurveError::RaiseTargetAlreadySet
+        );
+        require!(
+            raise_target_lamports > bonding_curve.sol_reserves,
+            BondingCurveError::InvalidRaiseTarget
+        );
+
+        bonding_curve.raise_target_lamports = raise_target_lamports;
+
+        msg!("Raise target set to {} lamports", raise_target_lamports);
+        Ok(())
+    }
+
+    /**
+     * Record a fingerprint of critical state before a program upgrade
+     *
+     * Config admin only. Hashes the global config and, for each bonding
+     * curve passed as a remaining account (up to MAX_UPGRADE_SAMPLE_CURVES),
+     * the fixed prefix described in layout::bonding_curve. Run
+     * verify_after_upgrade with the same curves once the new program is
+     * deployed; curves that trade in between will show up as changed.
+     */
+    pub fn snapshot_for_upgrade(ctx: Context<SnapshotForUpgrade>) -> Result<()> {
+        let curves = ctx.remaining_accounts;
+        require!(
+            curves.len() <= MAX_UPGRADE_SAMPLE_CURVES,
+            BondingCurveError::UpgradeSampleMismatch
+        );
+
+        let snapshot = &mut ctx.accounts.upgrade_snapshot;
+        snapshot.admin = ctx.accounts.admin.key();
+        snapshot.taken_slot = Clock::get()?.slot;
+        snapshot.config_hash = upgrade_state_hash(&ctx.accounts.global_config.to_account_info(), usize::MAX)?;
+        snapshot.curve_count = curves.len() as u8;
+        snapshot.curves = [Pubkey::default(); MAX_UPGRADE_SAMPLE_CURVES];
+        snapshot.curve_hashes = [[0; 32]; MAX_UPGRADE_SAMPLE_CURVES];
+        for (i, info) in curves.iter().enumerate() {
+            snapshot.curves[i] = info.key();
+            snapshot.curve_hashes[i] = upgrade_curve_hash(info)?;
+        }
+        snapshot.verified_slot = 0;
+        snapshot.bump = ctx.bumps.upgrade_snapshot;
+
+        emit!(UpgradeSnapshotTaken {
+            admin: snapshot.admin,
+            slot: snapshot.taken_slot,
+            config_hash: snapshot.config_hash,
+            curve_count: snapshot.curve_count,
+        });
+
+        msg!("Upgrade snapshot of config and {} curves taken", curves.len());
+        Ok(())
+    }
+
+    /**
+     * Compare critical state against the pre-upgrade snapshot
+     *
+     * Pass the same curves, in the same order, as snapshot_for_upgrade.
+     * Every account whose fingerprint changed gets an UpgradeStateMismatch
+     * event, followed by one UpgradeVerified summary; nothing is reverted,
+     * so the outcome is readable from the transaction's events.
+     */
+    pub fn verify_after_upgrade(ctx: Context<VerifyAfterUpgrade>) -> Result<()> {
+        let snapshot = &mut ctx.accounts.upgrade_snapshot;
+        let curves = ctx.remaining_accounts;
+        require!(
+            curves.len() == snapshot.curve_count as usize,
+            BondingCurveError::UpgradeSampleMismatch
+        );
+
+        let config = ctx.accounts.global_config.to_account_info();
+        let config_hash = upgrade_state_hash(&config, usize::MAX)?;
+        let config_matches = config_hash == snapshot.config_hash;
+        if !config_matches {
+            emit!(UpgradeStateMismatch {
+                account: config.key(),
+                expected_hash: snapshot.config_hash,
+                actual_hash: config_hash,
+            });
+        }
+
+        let mut curves_changed = 0u8;
+        for (i, info) in curves.iter().enumerate() {
+            require_keys_eq!(info.key(), snapshot.curves[i], BondingCurveError::UpgradeSampleMismatch);
+            let curve_hash = upgrade_curve_hash(info)?;
+            if curve_hash != snapshot.curve_hashes[i] {
+                curves_changed += 1;
+                emit!(UpgradeStateMismatch {
+                    account: info.key(),
+                    expected_hash: snapshot.curve_hashes[i],
+                    actual_hash: curve_hash,
+                });
+            }
+        }
+        snapshot.verified_slot = Clock::get()?.slot;
+
+        emit!(UpgradeVerified {
+            snapshot_slot: snapshot.taken_slot,
+            verified_slot: snapshot.verified_slot,
+            config_matches,
+            curves_checked: snapshot.curve_count,
+            curves_changed,
+        });
+
+        msg!(
+            "Upgrade check: config {}, {} of {} curves changed",
+            if config_matches { "unchanged" } else { "changed" },
+            curves_changed,
+            snapshot.curve_count
+        );
+        Ok(())
+    }
+
+    /**
+     * Buy the creator's allocation into a vesting lockup
+     *
+     * The creator pays for `amount` tokens at the curve's exact-buy price,
+     * fees included, so the allocation is backed like any other supply.
+     * The tokens are minted into an account owned by the curve's
+     * CreatorVesting PDA instead of the creator's wallet, and claim_vested
+     * releases them: nothing until cliff_slots have passed, then linearly
+     * over vesting_slots. One allocation per curve.
+     *
+     * The allocation is a buy like any other, held to the same guards as
+     * buy_exact_tokens: trading hours, strict mode, eligibility, the
+     * whitelist and launch window, max_sol_per_trade, the sale phase, the
+     * price impact cap and the per-slot rate limit.
+     */
+    pub fn create_creator_allocation(
+        ctx: Context<CreateCreatorAllocation>,
+        amount: u64,         // Tokens to lock up
+        cliff_slots: u64,    // Slots before anything unlocks
+        vesting_slots: u64,  // Slots over which the tokens unlock after the cliff
+        max_sol_in: u64,     // Most the creator will pay, fees included
+    ) -> Result<()> {
+        require!(
+            cliff_slots > 0 || vesting_slots > 0,
+            BondingCurveError::InvalidVestingSchedule
+        );
+
+        let creator = ctx.accounts.creator.key();
+        let accounts = &mut *ctx.accounts;
+        let fill = execute_exact_buy(
+            ExactBuyAccounts {
+                payer: accounts.creator.to_account_info(),
+                bonding_curve: &mut accounts.bonding_curve,
+                token_mint: accounts.token_mint.to_account_info(),
+                destination: accounts.vesting_token_account.to_account_info(),
+                sol_vault: &accounts.sol_vault,
+                sol_vault_bump: ctx.bumps.sol_vault,
+                fee_vault: &accounts.fee_vault,
+                token_program: accounts.token_program.to_account_info(),
+                system_program: &accounts.system_program,
+                global_config: None,
+                eligibility_record: &accounts.eligibility_record,
+                referral: &mut None,
+                referrer_nft_account: &None,
+                charity: &accounts.charity,
+                user_trade_state: &mut accounts.user_trade_state,
+                protocol_stats: &mut accounts.protocol_stats,
+                price_observations: &mut accounts.price_observations,
+                curve_stats: &mut accounts.curve_stats,
+                instructions_sysvar: &accounts.instructions_sysvar,
+                buyer_mark: &accounts.buyer_mark,
+            },
+            creator,
+            amount,
+            max_sol_in,
+            0,
+        )?;
+
+        let vesting = &mut ctx.accounts.creator_vesting;
+        vesting.bonding_curve = ctx.accounts.bonding_curve.key();
+        vesting.beneficiary = creator;
+        vesting.total_amount = amount;
+        vesting.claimed_amount = 0;
+        vesting.start_slot = Clock::get()?.slot;
+        vesting.cliff_slots = cliff_slots;
+        vesting.vesting_slots = vesting_slots;
+        vesting.bump = ctx.bumps.creator_vesting;
+
+        emit!(CreatorAllocationCreated {
+            bonding_curve: vesting.bonding_curve,
+            beneficiary: vesting.beneficiary,
+            amount,
+            sol_paid: fill.gross_sol,
+            start_slot: vesting.start_slot,
+            cliff_slots,
+            vesting_slots,
+        });
+
+        msg!(
+            "Creator allocation of {} tokens locked: {} slot cliff, {} slot vesting",
+            amount,
+            cliff_slots,
+            vesting_slots
+        );
+        Ok(())
+    }
+
+    /**
+     * Release the creator allocation's unlocked tokens to the beneficiary
+     */
+    pub fn claim_vested(ctx: Context<ClaimVested>) -> Result<()> {
+        let vesting = &ctx.accounts.creator_vesting;
+        let claimable = vesting
+            .vested_amount(Clock::get()?.slot)
+            .saturating_sub(vesting.claimed_amount);
+        require!(claimable > 0, BondingCurveError::NothingVested);
+
+        let bonding_curve_key = ctx.accounts.bonding_curve.key();
+        let seeds = &[
+            CREATOR_VESTING_SEED,
+            bonding_curve_key.as_ref(),
+            &[vesting.bump],
+        ];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::TransferChecked {
+                    from: ctx.accounts.vesting_token_account.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.beneficiary_token_account.to_account_info(),
+                    authority: vesting.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            claimable,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        let vesting = &mut ctx.accounts.creator_vesting;
+        vesting.claimed_amount += claimable;
+
+        emit!(VestedClaimed {
+            bonding_curve: bonding_curve_key,
+            beneficiary: vesting.beneficiary,
+            amount: claimable,
+            claimed_total: vesting.claimed_amount,
+            remaining: vesting.total_amount - vesting.claimed_amount,
+        });
+
+        msg!("Claimed {} vested tokens", claimable);
+        Ok(())
+    }
+
+    /**
+     * Set up a Merkle airdrop of free tokens
+     *
+     * Creator only, once per curve, while its launch is scheduled
+     * (PreLaunch). Each leaf of merkle_root is the SHA-256 hash of a
+     * claimant's public key followed by their amount as little-endian u64,
+     * with pairs hashed sorted as for the allowlist. Claims mint new tokens
+     * to holders without any SOL, so they raise current_supply and the spot
+     * price but not sol_reserves; creators who want those holders to be able
+     * to sell early should seed_reserves. Claims close at claim_deadline_ts,
+     * no later than launch_ts, so the whole airdrop is minted before anyone
+     * trades against the curve and no buyer is diluted after the fact.
+     */
+    pub fn create_airdrop(
+        ctx: Context<CreateAirdrop>,
+        merkle_root: [u8; 32],  // Root over (claimant, amount) leaves
+        total_amount: u64,      // Tokens the airdrop may mint in total
+        claim_deadline_ts: i64, // Claims close at this time, at or before launch_ts
+    ) -> Result<()> {
+        require!(total_amount > 0, BondingCurveError::InvalidAmount);
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        require!(
+            bonding_curve.status == CurveStatus::PreLaunch,
+            BondingCurveError::AirdropClosed
+        );
+        require!(
+            claim_deadline_ts > Clock::get()?.unix_timestamp
+                && claim_deadline_ts <= bonding_curve.launch_ts,
+            BondingCurveError::InvalidAirdropDeadline
+        );
+        require!(
+            total_amount <= bonding_curve.remaining_supply(),
+            BondingCurveError::MaxSupplyExceeded
+        );
+
+        let airdrop = &mut ctx.accounts.airdrop;
+        airdrop.bonding_curve = bonding_curve.key();
+        airdrop.merkle_root = merkle_root;
+        airdrop.total_amount = total_amount;
+        airdrop.claimed_amount = 0;
+        airdrop.claim_count = 0;
+        airdrop.claim_deadline_ts = claim_deadline_ts;
+        airdrop.bump = ctx.bumps.airdrop;
+
+        emit!(AirdropCreated {
+            bonding_curve: airdrop.bonding_curve,
+            merkle_root,
+            total_amount,
+            claim_deadline_ts,
+        });
+
+        msg!("Airdrop of up to {} tokens created", total_amount);
+        Ok(())
+    }
+
+    /**
+     * Claim tokens from a curve's airdrop
+     *
+     * The claimant proves their (wallet, amount) leaf against the airdrop's
+     * root and has the tokens minted to their associated token account. A
+     * claim receipt PDA per claimant makes a second claim fail. Claims stop
+     * at the airdrop's deadline, once the curve leaves PreLaunch, or when the
+     * airdrop total runs out.
+     */
+    pub fn claim_airdrop(
+        ctx: Context<ClaimAirdrop>,
+        proof: Vec<[u8; 32]>, // Sibling hashes from the claimant's leaf up to the root
+        amount: u64,          // Tokens allotted to the claimant
+    ) -> Result<()> {
+        require!(amount > 0, BondingCurveError::InvalidAmount);
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        require!(
+            bonding_curve.status == CurveStatus::PreLaunch
+                && Clock::get()?.unix_timestamp < ctx.accounts.airdrop.claim_deadline_ts,
+            BondingCurveError::AirdropClosed
+        );
+        require!(amount <= bonding_curve.remaining_supply(), BondingCurveError::MaxSupplyExceeded);
+
+        let claimant = ctx.accounts.claimant.key();
+        let leaf = hashv(&[claimant.as_ref(), &amount.to_le_bytes()]).to_bytes();
+        require!(
+            verify_merkle_proof(&ctx.accounts.airdrop.merkle_root, leaf, &proof),
+            BondingCurveError::InvalidAirdropProof
+        );
+        let airdrop = &mut ctx.accounts.airdrop;
+        airdrop.claimed_amount = airdrop
+            .claimed_amount
+            .checked_add(amount)
+            .filter(|claimed| *claimed <= airdrop.total_amount)
+            .ok_or(BondingCurveError::AirdropExhausted)?;
+        airdrop.claim_count += 1;
+
+        let token_mint_key = ctx.accounts.token_mint.key();
+        let salt = bonding_curve.salt_seed();
+        let seeds = &[
+            BONDING_CURVE_SEED,
+            token_mint_key.as_ref(),
+            &salt,
+            &[bonding_curve.bump],
+        ];
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::MintTo {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.claimant_token_account.to_account_info(),
+                    authority: bonding_curve.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            amount,
+        )?;
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.current_supply = bonding_curve
+            .current_supply
+            .checked_add(amount)
+            .ok_or(BondingCurveError::SupplyOverflow)?;
+        bonding_curve.refresh_sold_out();
+
+        let receipt = &mut ctx.accounts.airdrop_receipt;
+        receipt.airdrop = airdrop.key();
+        receipt.claimant = claimant;
+        receipt.amount = amount;
+        receipt.bump = ctx.bumps.airdrop_receipt;
+
+        emit!(AirdropClaimed {
+            bonding_curve: bonding_curve.key(),
+            claimant,
+            amount,
+            claimed_total: airdrop.claimed_amount,
+            new_supply: bonding_curve.current_supply,
+        });
+
+        msg!("Claimed {} airdropped tokens", amount);
+        Ok(())
+    }
+
+    /**
+     * Withdraw unclaimed fees of a quote-token curve from its quote vault
+     *
+     * Quote-token curves keep their fees in the quote vault rather than the
+     * SOL fee vault. The creator collects the creator share and the
+     * insurance fund's governance authority the protocol share; a signer
+     * that is both collects both.
+     */
+    pub fn claim_quote_fees(ctx: Context<ClaimQuoteFees>) -> Result<()> {
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        require!(bonding_curve.has_quote_mint(), BondingCurveError::NotQuoteCurve);
+        let recipient = ctx.accounts.recipient.key();
+        let is_creator = recipient == bonding_curve.creator;
+        let is_protocol = recipient == ctx.accounts.insurance_fund.authority;
+        require!(is_creator || is_protocol, BondingCurveError::Unauthorized);
+
+        let mut amount = 0;
+        if is_creator {
+            amount += bonding_curve.creator_fees_unclaimed;
+        }
+        if is_protocol {
+            amount += bonding_curve.protocol_fees_unclaimed;
+        }
+        require!(amount > 0, BondingCurveError::NoFeesToClaim);
+
+        transfer_quote_out(
+            &ctx.accounts.quote_vault,
+            &ctx.accounts.recipient_quote_account,
+            &ctx.accounts.quote_token_mint,
+            &ctx.accounts.quote_token_program,
+            bonding_curve,
+            amount,
+        )?;
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        if is_creator {
+            bonding_curve.creator_fees_unclaimed = 0;
+        }
+        if is_protocol {
+            bonding_curve.protocol_fees_unclaimed = 0;
+        }
+
+        emit!(FeesClaimed {
+            bonding_curve: bonding_curve.key(),
+            recipient,
+            is_protocol,
+            amount,
+        });
+
+        msg!("Claimed {} quote-token units of fees", amount);
+        Ok(())
+    }
+
+    /**
+     * Cap how far a single trade may move the spot price
+     *
+     * Buys whose spot-price rise exceeds max_price_impact_bps of the price
+     * before the trade are rejected, a coarse anti-whale knob enforced
+     * on-chain. Sells are never capped, so holders can always exit. The cap
+     * is at least MIN_PRICE_IMPACT_BPS, and once tokens are out it can only
+     * be loosened. Pass 0 to remove the cap.
+     */
+    pub fn set_max_price_impact(
+        ctx: Context<UpdateCurveSettings>,
+        max_price_impact_bps: u16,  // Largest price move per trade (0 = uncapped)
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        bonding_curve.check_price_impact_update(max_price_impact_bps)?;
+        bonding_curve.max_price_impact_bps = max_price_impact_bps;
+
+        msg!("Max price impact set to {} bps", max_price_impact_bps);
+        Ok(())
+    }
+
+    /**
+     * Create a curve's price history ring buffer
+     *
+     * Anyone may pay for it. Once it exists, every trade on the curve must
+     * pass it and records the curve's spot price and supply, one observation
+     * per slot, so get_twap can average them over a window.
+     */
+    pub fn initialize_price_observations(ctx: Context<InitializePriceObservations>) -> Result<()> {
+        let price_observations = &mut ctx.accounts.price_observations;
+        price_observations.bonding_curve = ctx.accounts.bonding_curve.key();
+        price_observations.head = 0;
+        price_observations.count = 0;
+        price_observations.bump = ctx.bumps.price_observations;
+
+        // From here on no trade may skip the history
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.price_observations_required = true;
+
+        // Seed the history with the current price so the first window has a starting point
+        price_observations.record(
+            Clock::get()?.slot,
+            bonding_curve.current_price()?,
+            bonding_curve.current_supply,
+        );
+
+        msg!("Price observations initialized for curve {}", bonding_curve.key());
+        Ok(())
+    }
+
+    /**
+     * Time-weighted average spot price over the last window_slots slots
+     * This is a view function that doesn't modify state
+     *
+     * Each observation's price holds until the next one, so a price that was
+     * only briefly pushed around counts for just the slots it lasted. Fails
+     * if the recorded history doesn't reach back to the window's start.
+     */
+    pub fn get_twap(ctx: Context<GetTwap>, window_slots: u64) -> Result<u64> {
+        let twap = ctx
+            .accounts
+            .price_observations
+            .twap(Clock::get()?.slot, window_slots)?;
+
+        msg!("TWAP over {} slots: {} lamports per token", window_slots, twap);
+        Ok(twap)
+    }
+
+    /**
+     * Start handing the curve over to a new creator
+     *
+     * Two-step: nothing changes until new_creator signs
+     * accept_curve_authority, so a mistyped key can't strand the curve.
+     * Calling it again replaces the pending creator; Pubkey::default()
+     * cancels the handover. Creator fees must be claimed before a handover
+     * starts, so they can't pass to the new creator with the curve.
+     */
+    pub fn transfer_curve_authority(
+        ctx: Context<UpdateCurveSettings>,
+        new_creator: Pubkey,  // Wallet that may accept the curve
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        require_keys_neq!(new_creator, bonding_curve.creator, BondingCurveError::InvalidNewCreator);
+        require!(
+            new_creator == Pubkey::default() || bonding_curve.creator_fees_unclaimed == 0,
+            BondingCurveError::FeesUnclaimed
+        );
+        bonding_curve.pending_creator = new_creator;
+
+        emit!(CurveAuthorityTransferStarted {
+            bonding_curve: bonding_curve.key(),
+            creator: bonding_curve.creator,
+            pending_creator: new_creator,
+        });
+
+        msg!("Curve authority transfer to {} pending", new_creator);
+        Ok(())
+    }
+
+    /**
+     * Accept a pending curve handover, becoming the curve's creator
+     *
+     * Creator fees accrued while the handover was pending go to whoever
+     * claims them, so the outgoing creator should claim again before it
+     * completes. The inactivity clock carries over: handing the curve to
+     * another wallet doesn't count as the creator being active.
+     */
+    pub fn accept_curve_authority(ctx: Context<AcceptCurveAuthority>) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        let previous_creator = bonding_curve.creator;
+        bonding_curve.creator = ctx.accounts.new_creator.key();
+        bonding_curve.pending_creator = Pubkey::default();
+
+        emit!(CurveAuthorityTransferred {
+            bonding_curve: bonding_curve.key(),
+            previous_creator,
+            new_creator: bonding_curve.creator,
+        });
+
+        msg!("Curve authority transferred to {}", bonding_curve.creator);
+        Ok(())
+    }
+
+    /**
+     * Adjust several of the curve's fee and limit settings at once
+     *
+     * Each field left as None keeps its value, and each is validated like
+     * its single-field setter. Pricing parameters (initial price, slope,
+     * curve shape, decimals) are deliberately not updatable: holders bought
+     * on them, so changing them would reprice past trades.
+     */
+    pub fn update_curve_params(
+        ctx: Context<UpdateCurveSettings>,
+        update: CurveParamsUpdate,  // Settings to change
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+
+        if let Some(creator_fee_bps) = update.creator_fee_bps {
+            require!(creator_fee_bps <= MAX_CREATOR_FEE_BPS, BondingCurveError::InvalidFee);
+            bonding_curve.creator_fee_bps = creator_fee_bps;
+            bonding_curve.check_sell_fee_cap()?;
+        }
+        if let Some(sell_burn_tax_bps) = update.sell_burn_tax_bps {
+            require!(
+                sell_burn_tax_bps <= MAX_SELL_BURN_TAX_BPS,
+                BondingCurveError::InvalidBurnTax
+            );
+            bonding_curve.sell_burn_tax_bps = sell_burn_tax_bps;
+        }
+        if let Some(max_sol_per_trade) = update.max_sol_per_trade {
+            require!(
+                max_sol_per_trade <= PROTOCOL_MAX_SOL_PER_TRADE,
+                BondingCurveError::InvalidTradeLimit
+            );
+            bonding_curve.max_sol_per_trade = max_sol_per_trade;
+        }
+        if let Some(max_price_impact_bps) = update.max_price_impact_bps {
+            bonding_curve.check_price_impact_update(max_price_impact_bps)?;
+            bonding_curve.max_price_impact_bps = max_price_impact_bps;
+        }
+        if let Some(large_trade_bps) = update.large_trade_bps {
+            require!(large_trade_bps <= 10_000, BondingCurveError::InvalidLargeTradeThreshold);
+            bonding_curve.large_trade_bps = large_trade_bps;
+        }
+        if let Some(min_hold_seconds) = update.min_hold_seconds {
+            require!(
+                (0..=MAX_HOLD_SECONDS).contains(&min_hold_seconds),
+                BondingCurveError::InvalidHoldPeriod
+            );
+            bonding_curve.min_hold_seconds = min_hold_seconds;
+        }
+
+        emit!(CurveParamsUpdated {
+            bonding_curve: bonding_curve.key(),
+            creator_fee_bps: bonding_curve.creator_fee_bps,
+            sell_burn_tax_bps: bonding_curve.sell_burn_tax_bps,
+            max_sol_per_trade: bonding_curve.max_sol_per_trade,
+            max_price_impact_bps: bonding_curve.max_price_impact_bps,
+            large_trade_bps: bonding_curve.large_trade_bps,
+            min_hold_seconds: bonding_curve.min_hold_seconds,
+        });
+
+        msg!("Curve parameters updated");
+        Ok(())
+    }
+
+    /**
+     * Create a curve's cumulative trade statistics account
+     *
+     * Anyone may pay for it. From then on every trade must pass it and adds
+     * to its volume and buy/sell counts and keeps its all-time-high price,
+     * so frontends can show them without an indexer. unique_buyers counts
+     * wallets on their first buy, which also pays the rent of their buyer
+     * mark (pda::buyer_mark_address), so every buy passes that too.
+     */
+    pub fn initialize_curve_stats(ctx: Context<InitializeCurveStats>) -> Result<()> {
+        ctx.accounts.bonding_curve.stats_required = true;
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        let curve_stats = &mut ctx.accounts.curve_stats;
+        curve_stats.bonding_curve = bonding_curve.key();
+        curve_stats.volume_lamports = 0;
+        curve_stats.buy_count = 0;
+        curve_stats.sell_count = 0;
+        curve_stats.unique_buyers = 0;
+        curve_stats.ath_price = bonding_curve.current_price()?;
+        curve_stats.ath_slot = Clock::get()?.slot;
+        curve_stats.last_trade_slot = 0;
+        curve_stats.bump = ctx.bumps.curve_stats;
+
+        msg!("Trade stats initialized for curve {}", bonding_curve.key());
+        Ok(())
+    }
+
+    /**
+     * Get a curve's cumulative trade statistics
+     * This is a view function that doesn't modify state
+     */
+    pub fn get_curve_stats(ctx: Context<GetCurveStats>) -> Result<CurveStats> {
+        let curve_stats: &CurveStats = &ctx.accounts.curve_stats;
+
+        msg!(
+            "Volume: {} lamports over {} buys and {} sells, ATH price {}",
+            curve_stats.volume_lamports,
+            curve_stats.buy_count,
+            curve_stats.sell_count,
+            curve_stats.ath_price
+        );
+        Ok(curve_stats.clone())
+    }
+
+    /**
+     * Place a limit order on a curve
+     *
+     * Buy orders escrow amount lamports and fill once the curve's spot
+     * price (phase-adjusted) is at or below trigger_price; sell orders
+     * escrow amount tokens and fill once it is at or above. Either way the
+     * whole fill must average no worse than trigger_price before fees,
+     * so a large order waits until the curve has room for it. The order PDA
+     * also holds LIMIT_ORDER_TIP_LAMPORTS for whoever executes it. SOL
+     * curves only; order_id lets a wallet keep several orders per curve.
+     */
+    pub fn place_limit_order(
+        ctx: Context<PlaceLimitOrder>,
+        order_id: u64,      // Caller-chosen id, part of the order's address
+        side: OrderSide,    // Buy with SOL or sell tokens
+        trigger_price: u64, // Limit price in lamports per whole token
+        amount: u64,        // Lamports to spend (Buy) or tokens to sell (Sell)
+    ) -> Result<()> {
+        require!(amount > 0, BondingCurveError::InvalidAmount);
+        require!(trigger_price > 0, BondingCurveError::InvalidPrice);
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        require!(!bonding_curve.has_quote_mint(), BondingCurveError::QuoteCurveUnsupported);
+        require!(bonding_curve.status == CurveStatus::Active, BondingCurveError::CurveNotActive);
+
+        // Buy orders escrow their SOL along with the tip, sell orders their tokens
+        let escrow_lamports = match side {
+            OrderSide::Buy => amount
+                .checked_add(LIMIT_ORDER_TIP_LAMPORTS)
+                .ok_or(BondingCurveError::MathOverflow)?,
+            OrderSide::Sell => {
+                let escrow = ctx
+                    .accounts
+                    .escrow_token_account
+                    .as_ref()
+                    .ok_or(BondingCurveError::MissingOrderEscrow)?;
+                token_interface::transfer_checked(
+                    CpiContext::new(
+                        ctx.accounts.token_program.to_account_info(),
+                        token_interface::TransferChecked {
+                            from: ctx.accounts.owner_token_account.to_account_info(),
+                            mint: ctx.accounts.token_mint.to_account_info(),
+                            to: escrow.to_account_info(),
+                            authority: ctx.accounts.owner.to_account_info(),
+                        },
+                    ),
+                    amount,
+                    ctx.accounts.token_mint.decimals,
+                )?;
+                LIMIT_ORDER_TIP_LAMPORTS
+            }
+        };
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.limit_order.to_account_info(),
+                },
+            ),
+            escrow_lamports,
+        )?;
+
+        let limit_order = &mut ctx.accounts.limit_order;
+        limit_order.bonding_curve = bonding_curve.key();
+        limit_order.owner = ctx.accounts.owner.key();
+        limit_order.order_id = order_id;
+        limit_order.side = side;
+        limit_order.trigger_price = trigger_price;
+        limit_order.amount = amount;
+        limit_order.tip_lamports = LIMIT_ORDER_TIP_LAMPORTS;
+        limit_order.created_ts = Clock::get()?.unix_timestamp;
+        limit_order.bump = ctx.bumps.limit_order;
+
+        emit!(LimitOrderPlaced {
+            limit_order: limit_order.key(),
+            bonding_curve: limit_order.bonding_curve,
+            owner: limit_order.owner,
+            side,
+            trigger_price,
+            amount,
+        });
+
+        msg!("Limit order {} placed: {:?} {} at {}", order_id, side, amount, trigger_price);
+        Ok(())
+    }
+
+    /**
+     * Fill a limit order whose trigger price has been crossed
+     *
+     * Permissionless crank that earns the order's tip. The whole order
+     * trades at the curve's current price, under the same fees, limits and
+     * sale phase rules as a direct trade: a buy spends what its escrowed SOL
+     * buys outright and refunds the rounding remainder, and a sell pays the
+     * sell burn tax out of its escrowed tokens. Fills that would average
+     * worse than the trigger price fail with SlippageExceeded. Buys restart
+     * the owner's hold period in their trade state, which must be passed
+     * when the curve has a hold period or a launch window. The order closes
+     * to its owner afterwards.
+     */
+    pub fn execute_limit_order(mut ctx: Context<ExecuteLimitOrder>) -> Result<()> {
+        ctx.accounts.bonding_curve.record_trade_in_slot(Clock::get()?.slot)?;
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        require!(bonding_curve.status == CurveStatus::Active, BondingCurveError::CurveNotActive);
+        let now = Clock::get()?.unix_timestamp;
+        let schedule_open = match ctx.accounts.limit_order.side {
+            OrderSide::Buy => bonding_curve.trading_open(now),
+            OrderSide::Sell => bonding_curve.selling_open(now),
+        };
+        require!(schedule_open, BondingCurveError::TradingClosed);
+        check_strict_mode(bonding_curve, &ctx.accounts.instructions_sysvar)?;
+        check_eligibility(bonding_curve, ctx.accounts.owner.key(), &None)?;
+        require!(
+            ctx.accounts.limit_order.side == OrderSide::Sell || !bonding_curve.buys_paused(now),
+            BondingCurveError::CurvePaused
+        );
+        let slot = Clock::get()?.slot;
+        let active_phase = bonding_curve.active_phase(now)?;
+
+        let limit_order = &ctx.accounts.limit_order;
+        let spot_price = phase_price(bonding_curve.current_price()?, active_phase)?;
+        let triggered = match limit_order.side {
+            OrderSide::Buy => spot_price <= limit_order.trigger_price,
+            OrderSide::Sell => spot_price >= limit_order.trigger_price,
+        };
+        require!(triggered, BondingCurveError::LimitPriceNotReached);
+
+        let (tokens, sol_amount, fee_lamports) = match limit_order.side {
+            OrderSide::Buy => fill_limit_buy(&mut ctx, now, slot, active_phase)?,
+            OrderSide::Sell => fill_limit_sell(&mut ctx, now, slot, active_phase)?,
+        };
+
+        // The tip goes to the cranker; everything else left in the order closes to the owner
+        let limit_order = &ctx.accounts.limit_order;
+        **limit_order.to_account_info().try_borrow_mut_lamports()? -= limit_order.tip_lamports;
+        **ctx.accounts.cranker.try_borrow_mut_lamports()? += limit_order.tip_lamports;
+
+        emit!(LimitOrderExecuted {
+            limit_order: limit_order.key(),
+            bonding_curve: limit_order.bonding_curve,
+            owner: limit_order.owner,
+            side: limit_order.side,
+            tokens,
+            sol_amount,
+            fee_lamports,
+            cranker: ctx.accounts.cranker.key(),
+            tip_lamports: limit_order.tip_lamports,
+        });
+
+        msg!(
+            "Limit order {} filled: {} tokens for {} lamports",
+            limit_order.order_id,
+            tokens,
+            sol_amount
+        );
+        Ok(())
+    }
+
+    /**
+     * Cancel a limit order and take back its escrow
+     *
+     * Owner only. Escrowed tokens go back to the owner's token account, and
+     * the escrowed SOL, tip and rent come back as the order closes.
+     */
+    pub fn cancel_limit_order(ctx: Context<CancelLimitOrder>) -> Result<()> {
+        let limit_order = &ctx.accounts.limit_order;
+        if limit_order.side == OrderSide::Sell {
+            let escrow = ctx
+                .accounts
+                .escrow_token_account
+                .as_ref()
+                .ok_or(BondingCurveError::MissingOrderEscrow)?;
+            let bonding_curve_key = limit_order.bonding_curve;
+            let order_id = limit_order.order_id.to_le_bytes();
+            let seeds = &[
+                LIMIT_ORDER_SEED,
+                bonding_curve_key.as_ref(),
+                limit_order.owner.as_ref(),
+                &order_id,
+                &[limit_order.bump],
+            ];
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token_interface::TransferChecked {
+                        from: escrow.to_account_info(),
+                        mint: ctx.accounts.token_mint.to_account_info(),
+                        to: ctx.accounts.owner_token_account.to_account_info(),
+                        authority: limit_order.to_account_info(),
+                    },
+                    &[&seeds[..]],
+                ),
+                limit_order.amount,
+                ctx.accounts.token_mint.decimals,
+            )?;
+            token_interface::close_account(CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::CloseAccount {
+                    account: escrow.to_account_info(),
+                    destination: ctx.accounts.owner.to_account_info(),
+                    authority: limit_order.to_account_info(),
+                },
+                &[&seeds[..]],
+            ))?;
+        }
+
+        emit!(LimitOrderCancelled {
+            limit_order: limit_order.key(),
+            bonding_curve: limit_order.bonding_curve,
+            owner: limit_order.owner,
+        });
+
+        msg!("Limit order {} cancelled", limit_order.order_id);
+        Ok(())
+    }
+
+    /**
+     * Freeze a holder's token account
+     *
+     * Creator only. The curve is the mint's freeze authority; a frozen
+     * account can neither send, receive nor burn tokens until thawed, which
+     * also keeps it from selling to the curve.
+     */
+    pub fn freeze_holder(ctx: Context<FreezeHolder>) -> Result<()> {
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        let token_mint_key = ctx.accounts.token_mint.key();
+        let salt = bonding_curve.salt_seed();
+        let seeds = &[
+            BONDING_CURVE_SEED,
+            token_mint_key.as_ref(),
+            &salt,
+            &[bonding_curve.bump],
+        ];
+        token_interface::freeze_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token_interface::FreezeAccount {
+                account: ctx.accounts.holder_token_account.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+                authority: bonding_curve.to_account_info(),
+            },
+            &[&seeds[..]],
+        ))?;
+
+        emit!(HolderFrozen {
+            bonding_curve: bonding_curve.key(),
+            token_account: ctx.accounts.holder_token_account.key(),
+            owner: ctx.accounts.holder_token_account.owner,
+            frozen: true,
+        });
+
+        msg!("Froze token account {}", ctx.accounts.holder_token_account.key());
+        Ok(())
+    }
+
+    /**
+     * Thaw a previously frozen token account
+     *
+     * Creator only.
+     */
+    pub fn thaw_holder(ctx: Context<FreezeHolder>) -> Result<()> {
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        let token_mint_key = ctx.accounts.token_mint.key();
+        let salt = bonding_curve.salt_seed();
+        let seeds = &[
+            BONDING_CURVE_SEED,
+            token_mint_key.as_ref(),
+            &salt,
+            &[bonding_curve.bump],
+        ];
+        token_interface::thaw_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token_interface::ThawAccount {
+                account: ctx.accounts.holder_token_account.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+                authority: bonding_curve.to_account_info(),
+            },
+            &[&seeds[..]],
+        ))?;
+
+        emit!(HolderFrozen {
+            bonding_curve: bonding_curve.key(),
+            token_account: ctx.accounts.holder_token_account.key(),
+            owner: ctx.accounts.holder_token_account.owner,
+            frozen: false,
+        });
+
+        msg!("Thawed token account {}", ctx.accounts.holder_token_account.key());
+        Ok(())
+    }
+
+    /**
+     * Put a wallet on the curve's blacklist
+     *
+     * Creator only. Creates the wallet's BlacklistEntry; while any entry
+     * exists, every sell path (sell_tokens, queue_sell, mm_sell and sell
+     * limit order fills) requires the seller's blacklist address and
+     * refuses the sale if an entry lives there.
+     */
+    pub fn blacklist_holder(
+        ctx: Context<BlacklistHolder>,
+        holder: Pubkey, // Wallet to blacklist
+    ) -> Result<()> {
+        let blacklist_entry = &mut ctx.accounts.blacklist_entry;
+        blacklist_entry.bonding_curve = ctx.accounts.bonding_curve.key();
+        blacklist_entry.holder = holder;
+        blacklist_entry.added_ts = Clock::get()?.unix_timestamp;
+        blacklist_entry.bump = ctx.bumps.blacklist_entry;
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.blacklisted_holders = bonding_curve
+            .blacklisted_holders
+            .checked_add(1)
+            .ok_or(BondingCurveError::MathOverflow)?;
+
+        emit!(HolderBlacklisted {
+            bonding_curve: bonding_curve.key(),
+            holder,
+            blacklisted: true,
+        });
+
+        msg!("Blacklisted {}", holder);
+        Ok(())
+    }
+
+    /**
+     * Take a wallet off the curve's blacklist
+     *
+     * Creator only; the entry's rent goes back to the creator.
+     */
+    pub fn unblacklist_holder(ctx: Context<UnblacklistHolder>) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.blacklisted_holders = bonding_curve.blacklisted_holders.saturating_sub(1);
+
+        let holder = ctx.accounts.blacklist_entry.holder;
+        emit!(HolderBlacklisted {
+            bonding_curve: bonding_curve.key(),
+            holder,
+            blacklisted: false,
+        });
+
+        msg!("Removed {} from the blacklist", holder);
+        Ok(())
+    }
+
+    /**
+     * Configure buybacks funded by the curve's protocol fees
+     *
+     * Admin only, since the fees spent are the protocol's. Each
+     * buyback_and_burn spends at most max_lamports of unclaimed protocol
+     * fees and at least window_slots slots must pass between two of them.
+     * Pass max_lamports = 0 to turn buybacks off.
+     */
+    pub fn set_buyback(
+        ctx: Context<AdminCurveSettings>,
+        max_lamports: u64,  // Cap per buyback (0 = off)
+        window_slots: u64,  // Minimum slots between buybacks
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        require!(
+            max_lamports == 0 || !bonding_curve.has_quote_mint(),
+            BondingCurveError::QuoteCurveUnsupported
+        );
+        bonding_curve.buyback_max_lamports = max_lamports;
+        bonding_curve.buyback_window_slots = window_slots;
+
+        msg!("Buybacks of up to {} lamports every {} slots", max_lamports, window_slots);
+        Ok(())
+    }
+
+    /**
+     * Buy tokens back with accrued protocol fees and burn them
+     *
+     * Permissionless crank, at most once per buyback window. Up to
+     * buyback_max_lamports of unclaimed protocol fees move from the fee
+     * vault into the curve's reserves, buying the tokens that SOL gets at
+     * the current supply, and those tokens are burned in the same step.
+     * Since the buy and the burn cancel out, nothing is actually minted:
+     * supply and price stay put, the reserves grow, and every holder's
+     * floor_price rises. Buybacks pay no trading fee and count towards
+     * virtual_burned.
+     */
+    pub fn buyback_and_burn(ctx: Context<BuybackAndBurn>) -> Result<()> {
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        require!(bonding_curve.status == CurveStatus::Active, BondingCurveError::CurveNotActive);
+        require!(
+            !bonding_curve.buys_paused(Clock::get()?.unix_timestamp),
+            BondingCurveError::CurvePaused
+        );
+        require!(bonding_curve.buyback_max_lamports > 0, BondingCurveError::BuybacksDisabled);
+        let slot = Clock::get()?.slot;
+        require!(
+            bonding_curve.last_buyback_slot == 0
+                || slot >= bonding_curve.last_buyback_slot.saturating_add(bonding_curve.buyback_window_slots),
+            BondingCurveError::BuybackTooSoon
+        );
+        let sol_spent = bonding_curve.protocol_fees_unclaimed.min(bonding_curve.buyback_max_lamports);
+        require!(sol_spent > 0, BondingCurveError::NoFeesToClaim);
+        let tokens_burned = calculate_tokens_for_curve(
+            &bonding_curve.params(),
+            bonding_curve.current_supply,
+            sol_spent,
+        )?;
+        require!(tokens_burned > 0, BondingCurveError::InvalidAmount);
+
+        let token_mint_key = bonding_curve.token_mint;
+        let seeds = &[
+            FEE_VAULT_SEED,
+            token_mint_key.as_ref(),
+            &[ctx.bumps.fee_vault],
+        ];
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.fee_vault.to_account_info(),
+                    to: ctx.accounts.sol_vault.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            sol_spent,
+        )?;
+
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.protocol_fees_unclaimed -= sol_spent;
+        bonding_curve.sol_reserves = bonding_curve
+            .sol_reserves
+            .checked_add(sol_spent)
+            .ok_or(BondingCurveError::ReservesOverflow)?;
+        bonding_curve.virtual_burned = bonding_curve
+            .virtual_burned
+            .checked_add(tokens_burned)
+            .ok_or(BondingCurveError::SupplyOverflow)?;
+        bonding_curve.last_buyback_slot = slot;
+        latch_raise_target(bonding_curve, Clock::get()?.unix_timestamp);
+
+        emit!(BuybackExecuted {
+            bonding_curve: bonding_curve.key(),
+            cranker: ctx.accounts.cranker.key(),
+            sol_spent,
+            tokens_burned,
+            virtual_burned: bonding_curve.virtual_burned,
+            sol_reserves: bonding_curve.sol_reserves,
+            floor_price: bonding_curve.floor_price(),
+            slot,
+        });
+
+        msg!("Bought back and burned {} tokens for {} lamports", tokens_burned, sol_spent);
+        Ok(())
+    }
+
+    /**
+     * Restrict trading on the curve to a schedule
+     *
+     * Creator only. Buys and sells (limit order fills, market-maker fills
+     * and queued sells included) fail with TradingClosed outside the
+     * schedule: before start_ts, outside the daily UTC window and, with
+     * weekend_halt, on weekends. From end_ts on buys stop for good, while
+     * sells still follow the daily window and exit claims keep settling, so
+     * an end date never traps holders. Once tokens are out, start_ts can't
+     * be moved past the current time. Pass the default schedule to trade
+     * around the clock again.
+     */
+    pub fn set_trading_schedule(
+        ctx: Context<UpdateCurveSettings>,
+        schedule: TradingSchedule, // When the curve may trade
+    ) -> Result<()> {
+        require!(
+            schedule.end_ts == 0 || schedule.end_ts > schedule.start_ts,
+            BondingCurveError::InvalidTradingSchedule
+        );
+        require!(
+            (schedule.daily_open_secs as i64) < SECONDS_PER_DAY
+                && (schedule.daily_close_secs as i64) < SECONDS_PER_DAY,
+            BondingCurveError::InvalidTradingSchedule
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(now)?;
+        // Holders can't be locked out by moving the start back into the future
+        require!(
+            bonding_curve.current_supply == 0 || schedule.start_ts <= now,
+            BondingCurveError::InvalidTradingSchedule
+        );
+        bonding_curve.trading_start_ts = schedule.start_ts;
+        bonding_curve.trading_end_ts = schedule.end_ts;
+        bonding_curve.trading_open_secs = schedule.daily_open_secs;
+        bonding_curve.trading_close_secs = schedule.daily_close_secs;
+        bonding_curve.weekend_halt = schedule.weekend_halt;
+
+        msg!("Trading schedule set: {:?}", schedule);
+        Ok(())
+    }
+
+    /**
+     * Get the next time the curve's schedule allows trading
+     * This is a view function that doesn't modify state; it returns the
+     * current time while trading is open and None once it has closed for good
+     */
+    pub fn get_next_trading_open(ctx: Context<GetPrice>) -> Result<Option<i64>> {
+        let now = Clock::get()?.unix_timestamp;
+        let next_open = ctx.accounts.bonding_curve.next_trading_open(now);
+
+        match next_open {
+            Some(ts) => msg!("Trading open from {}", ts),
+            None => msg!("Trading has closed for good"),
+        }
+        Ok(next_open)
+    }
+
+    /**
+     * Turn strict mode on or off
+     *
+     * Creator only. In strict mode buys and sells read the Instructions
+     * sysvar and fail with BundleRejected when the transaction holds more
+     * than one instruction for this program (or the trusted program) on the
+     * same curve, which keeps a sandwich from wrapping a victim's trade in
+     * one bundle-style transaction. They also fail when invoked by CPI from
+     * any program other than trusted_program; pass None to allow no CPI.
+     */
+    pub fn set_strict_mode(
+        ctx: Context<UpdateCurveSettings>,
+        enabled: bool,                   // Whether trades are inspected
+        trusted_program: Option<Pubkey>, // Router allowed to trade by CPI
+    ) -> Result<()> {
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+        bonding_curve.strict_mode = enabled;
+        bonding_curve.strict_trusted_program = trusted_program.unwrap_or_default();
+
+        msg!("Strict mode {}", if enabled { "on" } else { "off" });
+        Ok(())
+    }
+
+    /**
+     * Upgrade a curve written by an older program version to the current layout
+     *
+     * Permissionless; the payer covers any extra rent. A curve created before
+     * later fields were added is too short to load as a BondingCurve, which
+     * leaves every instruction on it failing. This grows the account to
+     * BondingCurve::LEN, with the new fields zeroed, and stamps it with
+     * CURVE_VERSION. Archived curves shrunk to their prefix stay as they are.
+     *
+     * Fields carved from `reserved` read as zero on older curves, so those
+     * whose zero would be wrong are set here: a curve below version 4 that
+     * already has price observations or stats now requires them on every trade.
+     */
+    pub fn migrate_curve(ctx: Context<MigrateCurve>) -> Result<()> {
+        let info = ctx.accounts.bonding_curve.to_account_info();
+        require!(
+            info.try_borrow_data()?.starts_with(BondingCurve::DISCRIMINATOR),
+            BondingCurveError::NotABondingCurve
+        );
+        let old_len = info.data_len();
+        require!(old_len > layout::bonding_curve::PREFIX_LEN, BondingCurveError::CurveArchived);
+
+        grow_account(
+            &info,
+            BondingCurve::LEN,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        let mut bonding_curve = BondingCurve::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        let from_version = bonding_curve.version;
+        require!(from_version < CURVE_VERSION, BondingCurveError::CurveUpToDate);
+        if from_version < 4 {
+            // Both were optional before version 4, and created once they existed
+            bonding_curve.price_observations_required |=
+                is_created(&ctx.accounts.price_observations.to_account_info());
+            bonding_curve.stats_required |= is_created(&ctx.accounts.curve_stats.to_account_info());
+        }
+        bonding_curve.version = CURVE_VERSION;
+        bonding_curve.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        emit!(CurveMigrated {
+            bonding_curve: info.key(),
+            from_version,
+            to_version: CURVE_VERSION,
+            old_len: old_len as u64,
+            new_len: info.data_len() as u64,
+        });
+
+        msg!("Curve {} migrated from version {} to {}", info.key(), from_version, CURVE_VERSION);
+        Ok(())
+    }
+
+    /**
+     * Grow an eligibility record issued before rejection codes to the current layout
+     *
+     * Permissionless; the payer covers the extra rent. Such records are too
+     * short to load, so trades presenting them fail until they are extended;
+     * the new rejection_code reads as 0 (none given).
+     */
+    pub fn migrate_eligibility_record(ctx: Context<MigrateEligibilityRecord>) -> Result<()> {
+        let info = ctx.accounts.eligibility_record.to_account_info();
+        require!(
+            info.try_borrow_data()?.starts_with(EligibilityRecord::DISCRIMINATOR),
+            BondingCurveError::NotAnEligibilityRecord
+        );
+        let old_len = info.data_len();
+        require!(old_len < EligibilityRecord::LEN, BondingCurveError::EligibilityRecordUpToDate);
+        grow_account(
+            &info,
+            EligibilityRecord::LEN,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        msg!("Eligibility record {} grown from {} to {} bytes", info.key(), old_len, info.data_len());
+        Ok(())
+    }
+
+    /**
+     * Set the share of the creator's fees paid out to holders
+     *
+     * Creator only, SOL curves only. From then on every trade's creator fee
+     * share sends holder_reward_bps to holders who staked tokens with
+     * stake_for_rewards, pro rata to their stake at the time, claimed with
+     * claim_holder_rewards. With nothing staked the share stays with the
+     * creator. Turning rewards on for the first time funds the rewards
+     * vault's rent exemption.
+     */
+    pub fn set_holder_rewards(
+        ctx: Context<HolderRewardsSettings>,
+        holder_reward_bps: u16, // Share of the creator's fees, in basis points
+    ) -> Result<()> {
+        require!(holder_reward_bps <= 10_000, BondingCurveError::InvalidFee);
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        require!(!bonding_curve.has_quote_mint(), BondingCurveError::QuoteCurveUnsupported);
+        bonding_curve.record_creator_action(Clock::get()?.unix_timestamp)?;
+
+        let rent_lamports = Rent::get()?.minimum_balance(0);
+        let vault_lamports = ctx.accounts.rewards_vault.lamports();
+        if holder_reward_bps > 0 && vault_lamports < rent_lamports {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.creator.to_account_info(),
+                        to: ctx.accounts.rewards_vault.to_account_info(),
+                    },
+                ),
+                rent_lamports - vault_lamports,
+            )?;
+        }
+        bonding_curve.holder_reward_bps = holder_reward_bps;
+
+        msg!("Holder rewards set to {} bps of creator fees", holder_reward_bps);
+        Ok(())
+    }
+
+    /**
+     * Claim a holder's share of the curve's holder rewards
+     *
+     * Rewards accrue to tokens staked with stake_for_rewards, through a
+     * reward-per-token index over the curve's total stake bumped on every
+     * trade. The holder's HolderRewards checkpoint records their stake and
+     * the index at their last settlement; a claim pays the index growth
+     * since then on that stake. Staked tokens sit in the program's stake
+     * vault, so the same tokens can't be moved to another wallet and claimed
+     * again.
+     */
+    pub fn claim_holder_rewards(ctx: Context<HolderRewardsStake>) -> Result<()> {
+        let amount = settle_holder_rewards(ctx.accounts, &ctx.bumps)?;
+        let holder_rewards = &ctx.accounts.holder_rewards;
+
+        emit!(HolderRewardsClaimed {
+            bonding_curve: holder_rewards.bonding_curve,
+            holder: holder_rewards.holder,
+            amount,
+            staked: holder_rewards.staked,
+            reward_per_token: ctx.accounts.bonding_curve.reward_per_token,
+        });
+
+        msg!("Holder {} claimed {} lamports of rewards", holder_rewards.holder, amount);
+        Ok(())
+    }
+
+    /**
+     * Stake tokens to earn holder rewards
+     *
+     * The tokens move from the holder's token account into the curve's
+     * stake vault and earn from the next trade on. Rewards earned by the
+     * earlier stake are paid out first. Simulations and archived curves
+     * take no stakes.
+     */
+    pub fn stake_for_rewards(
+        ctx: Context<HolderRewardsStake>,
+        amount: u64, // Tokens to stake
+    ) -> Result<()> {
+        require!(amount > 0, BondingCurveError::InvalidAmount);
+        require!(
+            !ctx.accounts.bonding_curve.is_simulation(),
+            BondingCurveError::SimulationCurve
+        );
+        require!(
+            ctx.accounts.bonding_curve.status != CurveStatus::Archived,
+            BondingCurveError::CurveArchived
+        );
+        let rewards_paid = settle_holder_rewards(ctx.accounts, &ctx.bumps)?;
+
+        let vault_before = ctx.accounts.stake_vault.amount;
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::TransferChecked {
+                    from: ctx.accounts.holder_token_account.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.stake_vault.to_account_info(),
+                    authority: ctx.accounts.holder.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+        // A transfer-fee mint delivers less than was sent; stake what arrived
+        ctx.accounts.stake_vault.reload()?;
+        let staked_amount = ctx.accounts.stake_vault.amount - vault_before;
+
+        let holder_rewards = &mut ctx.accounts.holder_rewards;
+        holder_rewards.staked = holder_rewards
+            .staked
+            .checked_add(staked_amount)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.rewards_staked = bonding_curve
+            .rewards_staked
+            .checked_add(staked_amount)
+            .ok_or(BondingCurveError::MathOverflow)?;
+
+        emit!(RewardsStakeChanged {
+            bonding_curve: bonding_curve.key(),
+            holder: holder_rewards.holder,
+            staked: holder_rewards.staked,
+            total_staked: bonding_curve.rewards_staked,
+            rewards_paid,
+        });
+
+        msg!("Holder {} staked {} tokens", holder_rewards.holder, staked_amount);
+        Ok(())
+    }
+
+    /**
+     * Take staked tokens back out of the stake vault
+     *
+     * Rewards earned so far are paid out first; the tokens then stop
+     * earning. Allowed in any curve state, so stakes can always be
+     * recovered.
+     */
+    pub fn unstake_from_rewards(
+        ctx: Context<HolderRewardsStake>,
+        amount: u64, // Tokens to unstake
+    ) -> Result<()> {
+        require!(amount > 0, BondingCurveError::InvalidAmount);
+        require!(
+            amount <= ctx.accounts.holder_rewards.staked,
+            BondingCurveError::InsufficientStake
+        );
+        let rewards_paid = settle_holder_rewards(ctx.accounts, &ctx.bumps)?;
+
+        let bonding_curve = &ctx.accounts.bonding_curve;
+        let token_mint_key = bonding_curve.token_mint;
+        let salt = bonding_curve.salt_seed();
+        let seeds = &[
+            BONDING_CURVE_SEED,
+            token_mint_key.as_ref(),
+            &salt,
+            &[bonding_curve.bump],
+        ];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::TransferChecked {
+                    from: ctx.accounts.stake_vault.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.holder_token_account.to_account_info(),
+                    authority: bonding_curve.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        let holder_rewards = &mut ctx.accounts.holder_rewards;
+        holder_rewards.staked -= amount;
+        let bonding_curve = &mut ctx.accounts.bonding_curve;
+        bonding_curve.rewards_staked -= amount;
+
+        emit!(RewardsStakeChanged {
+            bonding_curve: bonding_curve.key(),
+            holder: holder_rewards.holder,
+            staked: holder_rewards.staked,
+            total_staked: bonding_curve.rewards_staked,
+            rewards_paid,
+        });
+
+        msg!("Holder {} unstaked {} tokens", holder_rewards.holder, amount);
+        Ok(())
+    }
+
+    /**
+     * Register a curve created before the curve registry existed
+     *
+     * Permissionless backfill: the payer covers the entry's rent, and the
+     * curve gets the next index in its creator's registry, just as
+     * initialize_bonding_curve numbers new curves. Each curve registers
+     * once; simulations never do.
+     */
+    pub fn register_curve(ctx: Context<RegisterCurve>) -> Result<()> {
+        require!(
+            !ctx.accounts.bonding_curve.is_simulation(),
+            BondingCurveError::SimulationCurve
+        );
+        require!(
+            !ctx.accounts.bonding_curve.registered,
+            BondingCurveError::CurveAlreadyRegistered
+        );
+
+        let index = register_in_curve_registry(
+            &mut ctx.accounts.curve_registry,
+            ctx.bumps.curve_registry,
+            &mut ctx.accounts.registry_entry,
+            ctx.bumps.registry_entry,
+            &mut ctx.accounts.bonding_curve,
+        )?;
+
+        msg!("Curve {} registered at index {}", ctx.accounts.bonding_curve.key(), index);
+        Ok(())
+    }
+
+    /**
+     * Get the bonding curve a creator registered at an index
+     * This is a view function that doesn't modify state
+     */
+    pub fn get_curve_by_index(
+        ctx: Context<GetCurveByIndex>,
+        creator: Pubkey, // Creator whose registry to read
+        index: u64,      // Index in the creator's registry
+    ) -> Result<Pubkey> {
+        let registry_entry = &ctx.accounts.registry_entry;
+
+        msg!("Curve {} of {}: {}", index, creator, registry_entry.bonding_curve);
+        Ok(registry_entry.bonding_curve)
+    }
+
+    /**
+     * Open a trading session for a session key
+     *
+     * Lets a client trade without prompting the owner's wallet each time:
+     * buy_tokens and sell_tokens signed by `authority` and passed the
+     * session spend from and pay into the session's deposit, which the
+     * owner funds here. Trades are capped at max_sol_per_trade and stop
+     * after expiry_slot. Tokens bought in a session are minted to the
+     * owner's token account, so the session key never holds them; to let it
+     * sell, the owner approves it as delegate on that account.
+     */
+    pub fn create_session(
+        ctx: Context<CreateSession>,
+        authority: Pubkey,        // Session key allowed to trade
+        expiry_slot: u64,         // Last slot the session can trade in
+        max_sol_per_trade: u64,   // Largest trade the session may make, in lamports
+        deposit_lamports: u64,    // SOL the session's buys can spend
+    ) -> Result<()> {
+        require!(expiry_slot > Clock::get()?.slot, BondingCurveError::InvalidSessionExpiry);
+        require!(max_sol_per_trade > 0, BondingCurveError::InvalidAmount);
+
+        if deposit_lamports > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.owner.to_account_info(),
+                        to: ctx.accounts.session.to_account_info(),
+                    },
+                ),
+                deposit_lamports,
+            )?;
+        }
+
+        let session = &mut ctx.accounts.session;
+        session.owner = ctx.accounts.owner.key();
+        session.authority = authority;
+        session.expiry_slot = expiry_slot;
+        session.max_sol_per_trade = max_sol_per_trade;
+        session.bump = ctx.bumps.session;
+
+        emit!(SessionCreated {
+            session: session.key(),
+            owner: session.owner,
+            authority,
+            expiry_slot,
+            max_sol_per_trade,
+            deposit_lamports,
+        });
+
+        msg!("Session opened for {} until slot {}", authority, expiry_slot);
+        Ok(())
+    }
+
+    /**
+     * End a trading session
+     *
+     * Callable by the owner or the session key, expired or not. The session
+     * closes and its remaining deposit and rent go back to the owner.
+     */
+    pub fn revoke_session(ctx: Context<RevokeSession>) -> Result<()> {
+        let session = &ctx.accounts.session;
+        let refunded_lamports = session_deposit(session)?;
+        let rent_lamports = session.to_account_info().lamports() - refunded_lamports;
+
+        emit!(SessionRevoked {
+            session: session.key(),
+            owner: session.owner,
+            authority: session.authority,
+            refunded_lamports,
+            rent_lamports,
+        });
+
+        msg!("Session of {} revoked", session.authority);
+        Ok(())
+    }
+}
+
+/**
+ * ACCOUNT CONTEXTS
+ * These define the required accounts for each instruction
+ */
+
+#[derive(Accounts)]
+#[instruction(initial_price: u64, slope: u64, name: String, symbol: String, uri: String, curve_type: CurveType, args: CurveInitArgs)]
+pub struct InitializeBondingCurve<'info> {
+    /// The creator of the bonding curve
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// The token mint
+    #[account(
+        init,
+        payer = creator,
+        mint::decimals = args.decimals.unwrap_or(0),
+        mint::authority = bonding_curve,
+        mint::freeze_authority = bonding_curve,
+        mint::token_program = token_program,
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// The bonding curve state
+    #[account(
+        init,
+        payer = creator,
+        space = BondingCurve::LEN,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &salt_seed(args.salt.unwrap_or(0))],
+        bump
+    )]
+    pub bonding_curve: Account<'info, BondingCurve>,
+
+    /// SOL vault to receive payment
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    /// Fee vault collecting the protocol and creator fees
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: AccountInfo<'info>,
+
+    /// Metaplex metadata account of the token mint
+    /// CHECK: Created and validated by the token metadata program
+    #[account(
+        mut,
+        seeds = [METADATA_SEED, token_metadata_program.key().as_ref(), token_mint.key().as_ref()],
+        seeds::program = token_metadata_program.key(),
+        bump
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// Protocol settings the new curve is validated against
+    #[account(
+        seeds = [GLOBAL_CONFIG_SEED],
+        bump = global_config.bump
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    /// Receives the curve creation fee
+    /// CHECK: Only receives lamports; must match the config
+    #[account(
+        mut,
+        address = global_config.fee_recipient @ BondingCurveError::Unauthorized
+    )]
+    pub fee_recipient: AccountInfo<'info>,
+
+    // Required programs
+    pub token_program: Interface<'info, TokenInterface>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+
+    /// Registry numbering the creator's curves, created with their first one
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = CurveRegistry::LEN,
+        seeds = [CURVE_REGISTRY_SEED, creator.key().as_ref()],
+        bump
+    )]
+    pub curve_registry: Box<Account<'info, CurveRegistry>>,
+
+    /// The new curve's registry entry, at the registry's next index
+    #[account(
+        init,
+        payer = creator,
+        space = CurveRegistryEntry::LEN,
+        seeds = [
+            CURVE_REGISTRY_ENTRY_SEED,
+            creator.key().as_ref(),
+            &curve_registry.curve_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub registry_entry: Box<Account<'info, CurveRegistryEntry>>,
+
+    /// Protocol-wide counters; pass it so the action is counted
+    #[account(
+        mut,
+        seeds = [PROTOCOL_STATS_SEED],
+        bump = protocol_stats.bump
+    )]
+    pub protocol_stats: Option<Account<'info, ProtocolStats>>,
+
+    /// Creator's token account for the dev buy (required with initial_buy_lamports)
+    #[account(
+        init_if_needed,
+        payer = creator,
+        associated_token::mint = token_mint,
+        associated_token::authority = creator,
+        associated_token::token_program = token_program
+    )]
+    pub creator_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+    /// Quote mint the curve trades against (required with quote_mint)
+    pub quote_token_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    /// Token account holding the curve's quote reserves (required with quote_mint)
+    #[account(
+        init,
+        payer = creator,
+        seeds = [QUOTE_VAULT_SEED, token_mint.key().as_ref()],
+        bump,
+        token::mint = quote_token_mint,
+        token::authority = bonding_curve,
+        token::token_program = quote_token_program
+    )]
+    pub quote_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Token program of the quote mint (required with quote_mint)
+    pub quote_token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+impl<'info> InitializeBondingCurve<'info> {
+    pub fn validate(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+#[instruction()]
+pub struct BuyTokens<'info> {
+
+    /// The buyer of tokens
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// The bonding curve state
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Account<'info, BondingCurve>,
+
+    /// The token mint
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Buyer's associated token account (created if needed)
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = token_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program
+    )]
+    pub buyer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// SOL vault to receive payment
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    /// Fee vault collecting the protocol and creator fees
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: AccountInfo<'info>,
+
+    /// Protocol settings (referral fee)
+    #[account(
+        seeds = [GLOBAL_CONFIG_SEED],
+        bump = global_config.bump
+    )]
+    pub global_config: Box<Account<'info, GlobalConfig>>,
+
+    // Required programs
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+
+    /// Buyer's eligibility record (required only when the curve has an attestor)
+    pub eligibility_record: Option<Account<'info, EligibilityRecord>>,
+
+    /// Referral code credited with this purchase (optional)
+    #[account(mut)]
+    pub referral: Option<Account<'info, ReferralCode>>,
+
+    /// Token account holding the referral NFT; its owner is the referrer
+    pub referrer_nft_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Charity receiving the curve's donation share (required when charity_bps > 0)
+    /// CHECK: Must match bonding_curve.charity; only receives lamports
+    #[account(mut)]
+    pub charity: Option<UncheckedAccount<'info>>,
+
+    /// Trader's trade state on this curve (required when min_hold_seconds > 0 or during the launch window)
+    #[account(mut)]
+    pub user_trade_state: Option<Account<'info, UserTradeState>>,
+
+    /// Protocol-wide counters; pass it so the action is counted
+    #[account(
+        mut,
+        seeds = [PROTOCOL_STATS_SEED],
+        bump = protocol_stats.bump
+    )]
+    pub protocol_stats: Option<Account<'info, ProtocolStats>>,
+
+    /// Quote-token vault of the curve (required on quote-token curves)
+    #[account(
+        mut,
+        seeds = [QUOTE_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub quote_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Trader's quote-token account (required on quote-token curves)
+    #[account(mut)]
+    pub buyer_quote_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The curve's quote mint (required on quote-token curves)
+    #[account(address = bonding_curve.quote_mint @ BondingCurveError::QuoteMintMismatch)]
+    pub quote_token_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(address = bonding_curve.quote_token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub quote_token_program: Option<Interface<'info, TokenInterface>>,
+
+    /// Curve's price history; pass it so the trade is recorded for the TWAP
+    /// (required once the curve has one)
+    #[account(
+        mut,
+        seeds = [PRICE_OBSERVATIONS_SEED, bonding_curve.key().as_ref()],
+        bump = price_observations.bump
+    )]
+    pub price_observations: Option<Box<Account<'info, PriceObservations>>>,
+
+    /// Curve's cumulative trade stats; pass it so the trade is counted
+    /// (required once the curve has them)
+    #[account(
+        mut,
+        seeds = [CURVE_STATS_SEED, bonding_curve.key().as_ref()],
+        bump = curve_stats.bump
+    )]
+    pub curve_stats: Option<Box<Account<'info, CurveStats>>>,
+
+    /// Instructions sysvar, inspected in strict mode (required while strict_mode is on)
+    /// CHECK: Address-checked; read only through the sysvar helpers
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+
+    /// Trading session the buyer signs for as its session key; the buy is paid from its deposit
+    #[account(
+        mut,
+        constraint = session.authority == buyer.key() @ BondingCurveError::SessionKeyMismatch
+    )]
+    pub session: Option<Account<'info, TradingSession>>,
+
+    /// Session owner's token account receiving a session buy's tokens (required with session)
+    #[account(mut)]
+    pub owner_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Buyer's mark on this curve, created on their first buy (required once the curve keeps stats)
+    /// CHECK: Address-checked against pda::buyer_mark_address; only ever assigned, never holds data
+    #[account(mut)]
+    pub buyer_mark: Option<UncheckedAccount<'info>>,
+
+    /// Trader's nonce window (required with a nonce, so for every session trade)
+    #[account(
+        mut,
+        seeds = [TRADE_NONCES_SEED, trade_nonces.user.as_ref()],
+        bump = trade_nonces.bump
+    )]
+    pub trade_nonces: Option<Account<'info, TradeNonces>>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+#[instruction()]
+pub struct SellTokens<'info> {
+    /// The seller of tokens
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    /// The bonding curve state
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Account<'info, BondingCurve>,
+
+    /// The token mint
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Seller's token account
+    #[account(mut)]
+    pub seller_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// SOL vault to send payment from
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    /// Fee vault collecting the protocol and creator fees
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: AccountInfo<'info>,
+
+    // Required programs
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+
+    /// Seller's eligibility record (required only when the curve has an attestor)
+    pub eligibility_record: Option<Account<'info, EligibilityRecord>>,
+
+    /// Charity receiving the curve's donation share (required when charity_bps > 0)
+    /// CHECK: Must match bonding_curve.charity; only receives lamports
+    #[account(mut)]
+    pub charity: Option<UncheckedAccount<'info>>,
+
+    /// Trader's hold-period state on this curve (required when min_hold_seconds > 0)
+    #[account(mut)]
+    pub user_trade_state: Option<Account<'info, UserTradeState>>,
+
+    /// Protocol-wide counters; pass it so the action is counted
+    #[account(
+        mut,
+        seeds = [PROTOCOL_STATS_SEED],
+        bump = protocol_stats.bump
+    )]
+    pub protocol_stats: Option<Account<'info, ProtocolStats>>,
+
+    /// Quote-token vault of the curve (required on quote-token curves)
+    #[account(
+        mut,
+        seeds = [QUOTE_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub quote_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Trader's quote-token account (required on quote-token curves)
+    #[account(mut)]
+    pub seller_quote_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The curve's quote mint (required on quote-token curves)
+    #[account(address = bonding_curve.quote_mint @ BondingCurveError::QuoteMintMismatch)]
+    pub quote_token_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(address = bonding_curve.quote_token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub quote_token_program: Option<Interface<'info, TokenInterface>>,
+
+    /// Curve's price history; pass it so the trade is recorded for the TWAP
+    /// (required once the curve has one)
+    #[account(
+        mut,
+        seeds = [PRICE_OBSERVATIONS_SEED, bonding_curve.key().as_ref()],
+        bump = price_observations.bump
+    )]
+    pub price_observations: Option<Box<Account<'info, PriceObservations>>>,
+
+    /// Curve's cumulative trade stats; pass it so the trade is counted
+    /// (required once the curve has them)
+    #[account(
+        mut,
+        seeds = [CURVE_STATS_SEED, bonding_curve.key().as_ref()],
+        bump = curve_stats.bump
+    )]
+    pub curve_stats: Option<Box<Account<'info, CurveStats>>>,
+
+    /// Instructions sysvar, inspected in strict mode (required while strict_mode is on)
+    /// CHECK: Address-checked; read only through the sysvar helpers
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+
+    /// Seller's blacklist address, which must be empty (required while the curve blacklists anyone)
+    /// CHECK: Address and emptiness are checked in check_not_blacklisted
+    pub blacklist_entry: Option<UncheckedAccount<'info>>,
+
+    /// The native mint (required with settle_as_wsol)
+    #[account(address = native_mint::ID)]
+    pub wsol_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    /// Seller's wSOL account receiving the proceeds (required with settle_as_wsol)
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = seller,
+        associated_token::token_program = wsol_token_program
+    )]
+    pub seller_wsol_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Token program of the native mint (required with settle_as_wsol)
+    pub wsol_token_program: Option<Program<'info, Token>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+    /// Trading session the seller signs for as its session key; the proceeds go to its
+    /// deposit, and seller_token_account must be the session owner's
+    #[account(
+        mut,
+        constraint = session.authority == seller.key() @ BondingCurveError::SessionKeyMismatch
+    )]
+    pub session: Option<Account<'info, TradingSession>>,
+
+    /// Trader's nonce window (required with a nonce, so for every session trade)
+    #[account(
+        mut,
+        seeds = [TRADE_NONCES_SEED, trade_nonces.user.as_ref()],
+        bump = trade_nonces.bump
+    )]
+    pub trade_nonces: Option<Account<'info, TradeNonces>>,
+}
+
+/// Outer accounts of buy_tokens_multi; each leg's accounts follow as remaining accounts
+#[derive(Accounts)]
+pub struct BuyTokensMulti<'info> {
+    /// The buyer on every leg
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BurnTokens<'info> {
+    /// Holder burning their tokens
+    pub holder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = token_mint,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Account<'info, BondingCurve>,
+
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Holder's token account to burn from
+    #[account(mut)]
+    pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct GetPrice<'info> {
+    /// The bonding curve to check price for
+    pub bonding_curve: Account<'info, BondingCurve>,
+}
+
+/// Pure math view; the system program is only there because the generated
+/// CPI bindings need at least one account
+#[derive(Accounts)]
+pub struct QuoteWithParams<'info> {
+    pub system_program: Program<'info, System>,
+}
+
+/// Pure math view; see QuoteWithParams
+#[derive(Accounts)]
+pub struct SuggestParams<'info> {
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetHealth<'info> {
+    /// The bonding curve to check
+    pub bonding_curve: Account<'info, BondingCurve>,
+
+    /// SOL vault backing the curve
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        seeds = [SOL_VAULT_SEED, bonding_curve.token_mint.as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeInsuranceFund<'info> {
+    /// The program's upgrade authority, paying for the fund account and vault rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// This program, pointing at its program data
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ BondingCurveError::Unauthorized)]
+    pub program: Program<'info, crate::program::BondingCurveProgram>,
+
+    /// The program's data account, recording its upgrade authority
+    #[account(constraint = program_data.upgrade_authority_address == Some(payer.key()) @ BondingCurveError::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+
+    /// The insurance fund state
+    #[account(
+        init,
+        payer = payer,
+        space = InsuranceFund::LEN,
+        seeds = [INSURANCE_FUND_SEED],
+        bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    /// SOL vault holding the insurance balance
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [INSURANCE_VAULT_SEED],
+        bump
+    )]
+    pub insurance_vault: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositInsurance<'info> {
+    /// Anyone can fund the insurance vault
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    /// The insurance fund state
+    #[account(
+        mut,
+        seeds = [INSURANCE_FUND_SEED],
+        bump = insurance_fund.bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    /// SOL vault holding the insurance balance
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [INSURANCE_VAULT_SEED],
+        bump
+    )]
+    pub insurance_vault: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemediateInsolvency<'info> {
+    /// Governance authority of the insurance fund
+    pub authority: Signer<'info>,
+
+    /// The insurance fund state
+    #[account(
+        mut,
+        seeds = [INSURANCE_FUND_SEED],
+        bump = insurance_fund.bump,
+        has_one = authority @ BondingCurveError::Unauthorized
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    /// SOL vault holding the insurance balance
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [INSURANCE_VAULT_SEED],
+        bump
+    )]
+    pub insurance_vault: AccountInfo<'info>,
+
+    /// The curve being repaired
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Account<'info, BondingCurve>,
+
+    /// SOL vault of the curve being repaired
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, bonding_curve.token_mint.as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Shared by creator-only instructions that change a curve's settings
+#[derive(Accounts)]
+pub struct UpdateCurveSettings<'info> {
+    /// The curve creator
+    pub creator: Signer<'info>,
+
+    /// The bonding curve to configure
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = creator @ BondingCurveError::Unauthorized,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Account<'info, BondingCurve>,
+}
+
+/// Shared by protocol-admin instructions that change a curve's settings
+#[derive(Accounts)]
+pub struct AdminCurveSettings<'info> {
+    /// Governance authority of the insurance fund
+    pub authority: Signer<'info>,
+
+    /// The insurance fund state
+    #[account(
+        seeds = [INSURANCE_FUND_SEED],
+        bump = insurance_fund.bump,
+        has_one = authority @ BondingCurveError::Unauthorized
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    /// The bonding curve to configure
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Account<'info, BondingCurve>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct IssueEligibility<'info> {
+    /// The attestor issuing the record (pays for the account)
+    #[account(mut)]
+    pub attestor: Signer<'info>,
+
+    /// The eligibility record for the wallet
+    #[account(
+        init,
+        payer = attestor,
+        space = EligibilityRecord::LEN,
+        seeds = [ELIGIBILITY_SEED, attestor.key().as_ref(), wallet.as_ref()],
+        bump
+    )]
+    pub eligibility_record: Account<'info, EligibilityRecord>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeEligibility<'info> {
+    /// The attestor that issued the record
+    pub attestor: Signer<'info>,
+
+    /// The eligibility record to revoke
+    #[account(
+        mut,
+        seeds = [ELIGIBILITY_SEED, attestor.key().as_ref(), eligibility_record.wallet.as_ref()],
+        bump = eligibility_record.bump,
+        has_one = attestor @ BondingCurveError::Unauthorized
+    )]
+    pub eligibility_record: Account<'info, EligibilityRecord>,
+}
+
+#[derive(Accounts)]
+pub struct EmitDailyStats<'info> {
+    /// The bonding curve whose stats are checkpointed
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Account<'info, BondingCurve>,
+}
+
+#[derive(Accounts)]
+pub struct CreateReferralNft<'info> {
+    /// Initial holder of the referral NFT
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Mint of the referral NFT
+    #[account(
+        init,
+        payer = owner,
+        mint::decimals = 0,
+        mint::authority = referral,
+        mint::token_program = token_program,
+    )]
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+
+    /// Referral code state, keyed by the NFT mint
+    #[account(
+        init,
+        payer = owner,
+        space = ReferralCode::LEN,
+        seeds = [REFERRAL_SEED, nft_mint.key().as_ref()],
+        bump
+    )]
+    pub referral: Account<'info, ReferralCode>,
+
+    /// Owner's token account for the NFT
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = nft_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_nft_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeLaunchQueue<'info> {
+    /// Pays for the queue account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The launch queue
+    #[account(
+        init,
+        payer = payer,
+        space = LaunchQueue::LEN,
+        seeds = [LAUNCH_QUEUE_SEED],
+        bump
+    )]
+    pub launch_queue: Box<Account<'info, LaunchQueue>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ScheduleLaunch<'info> {
+    /// The curve creator, posting the launch bond
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// The bonding curve being scheduled
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = creator @ BondingCurveError::Unauthorized,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Account<'info, BondingCurve>,
+
+    /// The launch queue
+    #[account(
+        mut,
+        seeds = [LAUNCH_QUEUE_SEED],
+        bump = launch_queue.bump
+    )]
+    pub launch_queue: Box<Account<'info, LaunchQueue>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ActivateNextLaunch<'info> {
+    /// The curve at the head of the queue
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Account<'info, BondingCurve>,
+
+    /// The launch queue
+    #[account(
+        mut,
+        seeds = [LAUNCH_QUEUE_SEED],
+        bump = launch_queue.bump
+    )]
+    pub launch_queue: Box<Account<'info, LaunchQueue>>,
+
+    /// CHECK: Address-checked against the curve's creator; receives the launch bond
+    #[account(mut, address = bonding_curve.creator @ BondingCurveError::Unauthorized)]
+    pub creator: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireLaunch<'info> {
+    /// Whoever cranks the expiry
+    pub cranker: Signer<'info>,
+
+    /// The curve whose launch expired
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump
+    )]
+    pub bonding_curve: Account<'info, BondingCurve>,
+
+    /// The launch queue
+    #[account(
+        mut,
+        seeds = [LAUNCH_QUEUE_SEED],
+        bump = launch_queue.bump
+    )]
+    pub launch_queue: Box<Account<'info, LaunchQueue>>,
+
+    /// CHECK: Address-checked against the curve's creator; receives the launch bond
+    #[account(mut, address = bonding_curve.creator @ BondingCurveError::Unauthorized)]
+    pub creator: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTradeNonces<'info> {
+    /// The user whose relayed trades are protected
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// The user's nonce window
+    #[account(
+        init,
+        payer = user,
+        space = TradeNonces::LEN,
+        seeds = [TRADE_NONCES_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub trade_nonces: Account<'info, TradeNonces>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConsumeTradeNonce<'info> {
+    /// The user who signed the relayed request
+    pub user: Signer<'info>,
+
+    /// The user's nonce window
+    #[account(
+        mut,
+        seeds = [TRADE_NONCES_SEED, user.key().as_ref()],
+        bump = trade_nonces.bump
+    )]
+    pub trade_nonces: Account<'info, TradeNonces>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeGlobalConfig<'info> {
+    /// The program's upgrade authority, paying for the config
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// This program, pointing at its program data
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ BondingCurveError::Unauthorized)]
+    pub program: Program<'info, crate::program::BondingCurveProgram>,
+
+    /// The program's data account, recording its upgrade authority
+    #[account(constraint = program_data.upgrade_authority_address == Some(payer.key()) @ BondingCurveError::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+
+    /// The global config
+    #[account(
+        init,
+        payer = payer,
+        space = GlobalConfig::LEN,
+        seeds = [GLOBAL_CONFIG_SEED],
+        bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateGlobalConfig<'info> {
+    /// The config admin
+    pub admin: Signer<'info>,
+
+    /// The global config
+    #[account(
+        mut,
+        seeds = [GLOBAL_CONFIG_SEED],
+        bump = global_config.bump,
+        has_one = admin @ BondingCurveError::Unauthorized
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptGlobalConfigAdmin<'info> {
+    /// The wallet named by transfer_global_config_admin
+    pub new_admin: Signer<'info>,
+
+    /// The global config being handed over
+    #[account(
+        mut,
+        seeds = [GLOBAL_CONFIG_SEED],
+        bump = global_config.bump,
+        constraint = global_config.pending_admin == new_admin.key() @ BondingCurveError::Unauthorized
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+}
+
+#[derive(Accounts)]
+pub struct StartMigration<'info> {
+    /// Pays for the migration state and token account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The bonding curve to graduate
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = token_mint
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The curve's token mint
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Progress of the curve's migration
+    #[account(
+        init,
+        payer = payer,
+        space = MigrationState::LEN,
+        seeds = [MIGRATION_STATE_SEED, bonding_curve.key().as_ref()],
+        bump
+    )]
+    pub migration_state: Account<'info, MigrationState>,
+
+    /// Migration authority receiving the liquidity for the AMM pool
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        seeds = [MIGRATION_AUTHORITY_SEED],
+        bump
+    )]
+    pub migration_authority: AccountInfo<'info>,
+
+    /// Migration authority's token account receiving the pool-side tokens
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = token_mint,
+        associated_token::authority = migration_authority,
+        associated_token::token_program = token_program
+    )]
+    pub migration_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateTranche<'info> {
+    /// The migrating bonding curve
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = token_mint,
+        constraint = bonding_curve.status == CurveStatus::Migrating @ BondingCurveError::CurveNotActive
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The curve's token mint
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Progress of the curve's migration
+    #[account(
+        mut,
+        seeds = [MIGRATION_STATE_SEED, bonding_curve.key().as_ref()],
+        bump = migration_state.bump
+    )]
+    pub migration_state: Account<'info, MigrationState>,
+
+    /// SOL vault holding the reserves being migrated
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    /// Migration authority receiving the liquidity for the AMM pool
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [MIGRATION_AUTHORITY_SEED],
+        bump
+    )]
+    pub migration_authority: AccountInfo<'info>,
+
+    /// Migration authority's token account receiving the pool-side tokens
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = migration_authority,
+        associated_token::token_program = token_program
+    )]
+    pub migration_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeMigration<'info> {
+    /// The migrating bonding curve
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.status == CurveStatus::Migrating @ BondingCurveError::CurveNotActive
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// Progress of the curve's migration
+    #[account(
+        seeds = [MIGRATION_STATE_SEED, bonding_curve.key().as_ref()],
+        bump = migration_state.bump
+    )]
+    pub migration_state: Account<'info, MigrationState>,
+
+    /// Protocol-wide counters; pass it so the action is counted
+    #[account(
+        mut,
+        seeds = [PROTOCOL_STATS_SEED],
+        bump = protocol_stats.bump
+    )]
+    pub protocol_stats: Option<Account<'info, ProtocolStats>>,
+}
+
+#[derive(Accounts)]
+pub struct HandoffMintAuthority<'info> {
+    /// The curve creator
+    pub creator: Signer<'info>,
+
+    /// The graduated bonding curve, current mint authority
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = creator @ BondingCurveError::Unauthorized,
+        has_one = token_mint,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The curve's token mint
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct DepositBootstrap<'info> {
+    /// The depositor
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    /// The bonding curve in its bootstrap phase
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The depositor's allocation record
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        space = BootstrapDeposit::LEN,
+        seeds = [BOOTSTRAP_DEPOSIT_SEED, bonding_curve.key().as_ref(), depositor.key().as_ref()],
+        bump
+    )]
+    pub bootstrap_deposit: Account<'info, BootstrapDeposit>,
+
+    /// SOL vault receiving the deposit
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, bonding_curve.token_mint.as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ActivateBootstrap<'info> {
+    /// The bonding curve closing its bootstrap phase
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// Protocol-wide counters; pass it so the bootstrap volume is counted
+    #[account(
+        mut,
+        seeds = [PROTOCOL_STATS_SEED],
+        bump = protocol_stats.bump
+    )]
+    pub protocol_stats: Option<Account<'info, ProtocolStats>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimBootstrapAllocation<'info> {
+    /// The depositor claiming tokens
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    /// The bonding curve, mint authority for its token
+    #[account(
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = token_mint,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The depositor's allocation record
+    #[account(
+        mut,
+        seeds = [BOOTSTRAP_DEPOSIT_SEED, bonding_curve.key().as_ref(), depositor.key().as_ref()],
+        bump = bootstrap_deposit.bump
+    )]
+    pub bootstrap_deposit: Account<'info, BootstrapDeposit>,
+
+    /// SOL vault paying out the unspent share of the deposit
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, bonding_curve.token_mint.as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    /// The curve's token mint
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Depositor's associated token account (created if needed)
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        associated_token::mint = token_mint,
+        associated_token::authority = depositor,
+        associated_token::token_program = token_program
+    )]
+    pub depositor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeUserTradeState<'info> {
+    /// The trader
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// The curve the state applies to
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The user's trade state on this curve
+    #[account(
+        init,
+        payer = user,
+        space = UserTradeState::LEN,
+        seeds = [USER_TRADE_STATE_SEED, bonding_curve.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_trade_state: Account<'info, UserTradeState>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuyRaffleTicket<'info> {
+    /// The ticket buyer
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// The bonding curve running the raffle
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The new ticket, indexed by sale order
+    #[account(
+        init,
+        payer = buyer,
+        space = RaffleTicket::LEN,
+        seeds = [
+            RAFFLE_TICKET_SEED,
+            bonding_curve.key().as_ref(),
+            &bonding_curve.raffle.ticket_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub raffle_ticket: Account<'info, RaffleTicket>,
+
+    /// SOL vault holding ticket payments
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, bonding_curve.token_mint.as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DrawRaffle<'info> {
+    /// The bonding curve running the raffle
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// CHECK: The SlotHashes sysvar, read raw to avoid deserializing every entry
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleRaffleTicket<'info> {
+    /// The ticket owner
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The bonding curve, mint authority for its token
+    #[account(
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = token_mint,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The ticket being settled; its rent goes back to the owner
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        has_one = bonding_curve
+    )]
+    pub raffle_ticket: Account<'info, RaffleTicket>,
+
+    /// The curve's token mint
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Owner's associated token account (created if needed)
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = token_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// SOL vault refunding losing tickets
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawExcessSol<'info> {
+    /// The curve creator, receiving the excess
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// The bonding curve
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = creator @ BondingCurveError::Unauthorized,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// SOL vault holding the buffer
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, bonding_curve.token_mint.as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreatorReserves<'info> {
+    /// The curve creator, funding or taking back the seed
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// The bonding curve
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = creator @ BondingCurveError::Unauthorized,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// SOL vault holding the seed
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, bonding_curve.token_mint.as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeProtocolStats<'info> {
+    /// Pays for the stats account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The protocol stats
+    #[account(
+        init,
+        payer = payer,
+        space = ProtocolStats::LEN,
+        seeds = [PROTOCOL_STATS_SEED],
+        bump
+    )]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EmitProtocolEpochSummary<'info> {
+    /// The protocol stats
+    #[account(
+        mut,
+        seeds = [PROTOCOL_STATS_SEED],
+        bump = protocol_stats.bump
+    )]
+    pub protocol_stats: Account<'info, ProtocolStats>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey)]
+pub struct BuyExactTokensFor<'info> {
+    /// Pays for the tokens
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The wallet receiving the tokens
+    /// CHECK: Any wallet can receive tokens; only used as the token account authority
+    #[account(address = recipient)]
+    pub recipient_wallet: UncheckedAccount<'info>,
+
+    /// The bonding curve state
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The token mint
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Recipient's associated token account (created if needed)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = token_mint,
+        associated_token::authority = recipient_wallet,
+        associated_token::token_program = token_program
+    )]
+    pub recipient_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// SOL vault to receive payment
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    /// Fee vault collecting the protocol and creator fees
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: AccountInfo<'info>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    /// Recipient's eligibility record (required only when the curve has an attestor)
+    pub eligibility_record: Option<Account<'info, EligibilityRecord>>,
+
+    /// Charity receiving the curve's donation share (required when charity_bps > 0)
+    /// CHECK: Must match bonding_curve.charity; only receives lamports
+    #[account(mut)]
+    pub charity: Option<UncheckedAccount<'info>>,
+
+    /// Recipient's trade state on this curve (required when min_hold_seconds > 0 or during the launch window)
+    #[account(mut)]
+    pub user_trade_state: Option<Account<'info, UserTradeState>>,
+
+    /// Protocol-wide counters; pass it so the action is counted
+    #[account(
+        mut,
+        seeds = [PROTOCOL_STATS_SEED],
+        bump = protocol_stats.bump
+    )]
+    pub protocol_stats: Option<Account<'info, ProtocolStats>>,
+
+    /// Curve's price history; pass it so the trade is recorded for the TWAP
+    /// (required once the curve has one)
+    #[account(
+        mut,
+        seeds = [PRICE_OBSERVATIONS_SEED, bonding_curve.key().as_ref()],
+        bump = price_observations.bump
+    )]
+    pub price_observations: Option<Box<Account<'info, PriceObservations>>>,
+
+    /// Curve's cumulative trade stats; pass it so the trade is counted
+    /// (required once the curve has them)
+    #[account(
+        mut,
+        seeds = [CURVE_STATS_SEED, bonding_curve.key().as_ref()],
+        bump = curve_stats.bump
+    )]
+    pub curve_stats: Option<Box<Account<'info, CurveStats>>>,
+
+    /// Instructions sysvar, inspected in strict mode (required while strict_mode is on)
+    /// CHECK: Address-checked; read only through the sysvar helpers
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+
+    /// Recipient's mark on this curve, created on their first buy (required once the curve keeps stats)
+    /// CHECK: Address-checked against pda::buyer_mark_address; only ever assigned, never holds data
+    #[account(mut)]
+    pub buyer_mark: Option<UncheckedAccount<'info>>,
+
+    /// Referral code credited with this purchase (optional)
+    #[account(mut)]
+    pub referral: Option<Account<'info, ReferralCode>>,
+
+    /// Token account holding the referral NFT; its owner is the referrer
+    pub referrer_nft_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Protocol settings (referral fee; required with a referral)
+    #[account(
+        seeds = [GLOBAL_CONFIG_SEED],
+        bump = global_config.bump
+    )]
+    pub global_config: Option<Box<Account<'info, GlobalConfig>>>,
+}
+
+#[derive(Accounts)]
+pub struct ArchiveCurve<'info> {
+    /// The creator or the governance authority
+    pub authority: Signer<'info>,
+
+    /// The curve creator, receiving any refunded rent
+    /// CHECK: Checked against bonding_curve.creator in the instruction
+    #[account(mut)]
+    pub creator: UncheckedAccount<'info>,
+
+    /// The graduated bonding curve
+    /// CHECK: Deserialized and validated in the instruction, which may shrink
+    /// it below the size Account<BondingCurve> would write back on exit
+    #[account(mut, owner = crate::ID)]
+    pub bonding_curve: UncheckedAccount<'info>,
+
+    /// Insurance fund naming the governance authority (optional)
+    #[account(seeds = [INSURANCE_FUND_SEED], bump = insurance_fund.bump)]
+    pub insurance_fund: Option<Account<'info, InsuranceFund>>,
+
+    /// The curve's token mint, to revoke the curve's mint authority before
+    /// shrinking it (optional)
+    #[account(mut)]
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimCreatorFees<'info> {
+    /// The curve creator, receiving the fees
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// The bonding curve
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = creator @ BondingCurveError::Unauthorized
+    )]
+    pub bonding_curve: Account<'info, BondingCurve>,
+
+    /// Fee vault holding the fees
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, bonding_curve.token_mint.as_ref()],
+        bump
+    )]
+    pub fee_vault: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimProtocolFees<'info> {
+    /// Governance authority of the insurance fund, receiving the fees
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The insurance fund state
+    #[account(
+        seeds = [INSURANCE_FUND_SEED],
+        bump = insurance_fund.bump,
+        has_one = authority @ BondingCurveError::Unauthorized
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    /// The bonding curve
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump
+    )]
+    pub bonding_curve: Account<'info, BondingCurve>,
+
+    /// Fee vault holding the fees
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, bonding_curve.token_mint.as_ref()],
+        bump
+    )]
+    pub fee_vault: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PauseCurve<'info> {
+    /// The curve creator or the insurance fund authority
+    pub authority: Signer<'info>,
+
+    /// The insurance fund state; pass it when the admin signs
+    #[account(
+        seeds = [INSURANCE_FUND_SEED],
+        bump = insurance_fund.bump
+    )]
+    pub insurance_fund: Option<Account<'info, InsuranceFund>>,
+
+    /// The bonding curve to pause or resume
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Account<'info, BondingCurve>,
+}
+
+impl<'info> PauseCurve<'info> {
+    /// Accept the protocol admin, or the creator while their powers last;
+    /// returns whether the signer is the admin
+    fn check_pauser(&mut self, now: i64) -> Result<bool> {
+        let authority = self.authority.key();
+        if self
+            .insurance_fund
+            .as_ref()
+            .is_some_and(|fund| fund.authority == authority)
+        {
+            return Ok(true);
+        }
+        require!(authority == self.bonding_curve.creator, BondingCurveError::Unauthorized);
+        self.bonding_curve.record_creator_action(now)?;
+        Ok(false)
+    }
+}
+
+#[derive(Accounts)]
+pub struct EmergencyWithdraw<'info> {
+    /// Governance authority of the insurance fund, paying the redemption vault's rent
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The insurance fund state
+    #[account(
+        seeds = [INSURANCE_FUND_SEED],
+        bump = insurance_fund.bump,
+        has_one = authority @ BondingCurveError::Unauthorized
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    /// The paused bonding curve
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// SOL vault being drained
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, bonding_curve.token_mint.as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    /// Vault holders redeem the SOL from
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [REDEMPTION_VAULT_SEED, bonding_curve.token_mint.as_ref()],
+        bump
+    )]
+    pub redemption_vault: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemEmergency<'info> {
+    /// Holder redeeming their tokens, receiving the SOL
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = token_mint
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Holder's token account to burn from
+    #[account(mut)]
+    pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Vault paying the redemptions
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [REDEMPTION_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub redemption_vault: AccountInfo<'info>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(index_id: u32)]
+pub struct InitializeCurveIndex<'info> {
+    /// Governance authority of the insurance fund, paying for the index
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The insurance fund state
+    #[account(
+        seeds = [INSURANCE_FUND_SEED],
+        bump = insurance_fund.bump,
+        has_one = authority @ BondingCurveError::Unauthorized
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    /// The new index
+    #[account(
+        init,
+        payer = authority,
+        space = CurveIndex::LEN,
+        seeds = [CURVE_INDEX_SEED, &index_id.to_le_bytes()],
+        bump
+    )]
+    pub curve_index: Box<Account<'info, CurveIndex>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddIndexCurve<'info> {
+    /// Governance authority of the insurance fund
+    pub authority: Signer<'info>,
+
+    /// The insurance fund state
+    #[account(
+        seeds = [INSURANCE_FUND_SEED],
+        bump = insurance_fund.bump,
+        has_one = authority @ BondingCurveError::Unauthorized
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    /// The index gaining a curve
+    #[account(
+        mut,
+        seeds = [CURVE_INDEX_SEED, &curve_index.index_id.to_le_bytes()],
+        bump = curve_index.bump
+    )]
+    pub curve_index: Box<Account<'info, CurveIndex>>,
+
+    /// The curve to add
+    #[account(
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveIndexCurve<'info> {
+    /// Governance authority of the insurance fund
+    pub authority: Signer<'info>,
+
+    /// The insurance fund state
+    #[account(
+        seeds = [INSURANCE_FUND_SEED],
+        bump = insurance_fund.bump,
+        has_one = authority @ BondingCurveError::Unauthorized
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    /// The index losing a curve
+    #[account(
+        mut,
+        seeds = [CURVE_INDEX_SEED, &curve_index.index_id.to_le_bytes()],
+        bump = curve_index.bump
+    )]
+    pub curve_index: Box<Account<'info, CurveIndex>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCurveIndex<'info> {
+    /// The index being sampled; its basket curves and their price
+    /// observations follow as remaining accounts
+    #[account(
+        mut,
+        seeds = [CURVE_INDEX_SEED, &curve_index.index_id.to_le_bytes()],
+        bump = curve_index.bump
+    )]
+    pub curve_index: Box<Account<'info, CurveIndex>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    /// Current holder of the referral NFT, receiving the rewards
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    /// The referral code holding the rewards
+    #[account(
+        mut,
+        seeds = [REFERRAL_SEED, referral.nft_mint.as_ref()],
+        bump = referral.bump
+    )]
+    pub referral: Account<'info, ReferralCode>,
+
+    /// Holder's token account with the referral NFT
+    #[account(
+        constraint = holder_nft_account.mint == referral.nft_mint
+            && holder_nft_account.owner == holder.key()
+            && holder_nft_account.amount == 1 @ BondingCurveError::InvalidReferral
+    )]
+    pub holder_nft_account: InterfaceAccount<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct QueueSell<'info> {
+    /// The seller of tokens; pays the claim's rent
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    /// The bonding curve state
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The new claim, at the back of the curve's exit queue
+    #[account(
+        init,
+        payer = seller,
+        space = ExitClaim::LEN,
+        seeds = [
+            EXIT_CLAIM_SEED,
+            bonding_curve.key().as_ref(),
+            &bonding_curve.exit_queue_tail.to_le_bytes()
+        ],
+        bump
+    )]
+    pub exit_claim: Account<'info, ExitClaim>,
+
+    /// The token mint
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Seller's token account
+    #[account(mut)]
+    pub seller_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    // Required programs
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+
+    /// Seller's eligibility record (required only when the curve has an attestor)
+    pub eligibility_record: Option<Account<'info, EligibilityRecord>>,
+
+    /// Trader's hold-period state on this curve (required when min_hold_seconds > 0)
+    #[account(mut)]
+    pub user_trade_state: Option<Account<'info, UserTradeState>>,
+
+    /// Seller's blacklist address, which must be empty (required while the curve blacklists anyone)
+    /// CHECK: Address and emptiness are checked in check_not_blacklisted
+    pub blacklist_entry: Option<UncheckedAccount<'info>>,
+
+    /// Curve's price history; pass it so the trade is recorded for the TWAP
+    /// (required once the curve has one)
+    #[account(
+        mut,
+        seeds = [PRICE_OBSERVATIONS_SEED, bonding_curve.key().as_ref()],
+        bump = price_observations.bump
+    )]
+    pub price_observations: Option<Box<Account<'info, PriceObservations>>>,
+
+    /// Curve's cumulative trade stats; pass it so the trade is counted
+    /// (required once the curve has them)
+    #[account(
+        mut,
+        seeds = [CURVE_STATS_SEED, bonding_curve.key().as_ref()],
+        bump = curve_stats.bump
+    )]
+    pub curve_stats: Option<Box<Account<'info, CurveStats>>>,
+
+    /// Instructions sysvar, inspected in strict mode (required while strict_mode is on)
+    /// CHECK: Address-checked; read only through the sysvar helpers
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct SettleExitClaim<'info> {
+    /// The bonding curve owing the exit
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The claim being paid; its rent goes back to the seller
+    #[account(
+        mut,
+        close = seller,
+        has_one = seller,
+        has_one = bonding_curve
+    )]
+    pub exit_claim: Account<'info, ExitClaim>,
+
+    /// Seller receiving the payout
+    /// CHECK: Matches exit_claim.seller; only receives lamports
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+
+    /// SOL vault paying the claim
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, bonding_curve.token_mint.as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    /// Fee vault collecting the protocol and creator fees
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, bonding_curve.token_mint.as_ref()],
+        bump
+    )]
+    pub fee_vault: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeMmVault<'info> {
+    /// The curve creator, funding the vault
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// The bonding curve the vault quotes around
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = creator @ BondingCurveError::Unauthorized,
+        has_one = token_mint,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The new market-maker vault; also holds its SOL
+    #[account(
+        init,
+        payer = creator,
+        space = MmVault::LEN,
+        seeds = [MM_VAULT_SEED, bonding_curve.key().as_ref()],
+        bump
+    )]
+    pub mm_vault: Account<'info, MmVault>,
+
+    /// The curve's token mint
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// The vault's token inventory
+    #[account(
+        init,
+        payer = creator,
+        associated_token::mint = token_mint,
+        associated_token::authority = mm_vault,
+        associated_token::token_program = token_program
+    )]
+    pub mm_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureMmVault<'info> {
+    /// The curve creator
+    pub creator: Signer<'info>,
+
+    /// The bonding curve the vault quotes around
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = creator @ BondingCurveError::Unauthorized
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The market-maker vault
+    #[account(
+        mut,
+        seeds = [MM_VAULT_SEED, bonding_curve.key().as_ref()],
+        bump = mm_vault.bump
+    )]
+    pub mm_vault: Account<'info, MmVault>,
+}
+
+#[derive(Accounts)]
+pub struct ManageMmVault<'info> {
+    /// The curve creator
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// The bonding curve the vault quotes around
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = creator @ BondingCurveError::Unauthorized,
+        has_one = token_mint
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The market-maker vault
+    #[account(
+        mut,
+        seeds = [MM_VAULT_SEED, bonding_curve.key().as_ref()],
+        bump = mm_vault.bump
+    )]
+    pub mm_vault: Account<'info, MmVault>,
+
+    /// The curve's token mint
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Creator's token account
+    #[account(mut)]
+    pub creator_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The vault's token inventory
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = mm_vault,
+        associated_token::token_program = token_program
+    )]
+    pub mm_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MmTrade<'info> {
+    /// The trader
+    #[account(mut)]
+    pub trader: Signer<'info>,
+
+    /// The bonding curve setting the spot price
+    #[account(
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = token_mint
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The market-maker vault
+    #[account(
+        mut,
+        seeds = [MM_VAULT_SEED, bonding_curve.key().as_ref()],
+        bump = mm_vault.bump
+    )]
+    pub mm_vault: Account<'info, MmVault>,
+
+    /// The curve's token mint
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Trader's token account
+    #[account(mut)]
+    pub trader_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The vault's token inventory
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = mm_vault,
+        associated_token::token_program = token_program
+    )]
+    pub mm_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+
+    /// Seller's blacklist address, which must be empty (required by mm_sell while the curve blacklists anyone)
+    /// CHECK: Address and emptiness are checked in check_not_blacklisted
+    pub blacklist_entry: Option<UncheckedAccount<'info>>,
+
+    /// Curve's price history; pass it so the trade is recorded for the TWAP
+    /// (required once the curve has one)
+    #[account(
+        mut,
+        seeds = [PRICE_OBSERVATIONS_SEED, bonding_curve.key().as_ref()],
+        bump = price_observations.bump
+    )]
+    pub price_observations: Option<Box<Account<'info, PriceObservations>>>,
+
+    /// Curve's cumulative trade stats; pass it so the trade is counted
+    /// (required once the curve has them)
+    #[account(
+        mut,
+        seeds = [CURVE_STATS_SEED, bonding_curve.key().as_ref()],
+        bump = curve_stats.bump
+    )]
+    pub curve_stats: Option<Box<Account<'info, CurveStats>>>,
+
+    /// Buyer's mark on this curve, created on their first buy (required once the curve keeps stats)
+    /// CHECK: Address-checked against pda::buyer_mark_address; only ever assigned, never holds data
+    #[account(mut)]
+    pub buyer_mark: Option<UncheckedAccount<'info>>,
+
+    /// Instructions sysvar, inspected in strict mode (required while strict_mode is on)
+    /// CHECK: Address-checked; read only through the sysvar helpers
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct SimulateLaunch<'info> {
+    /// The educator running the simulation
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// A fresh mint for the simulation
+    #[account(
+        init,
+        payer = creator,
+        mint::decimals = 0,
+        mint::authority = bonding_curve,
+        mint::freeze_authority = bonding_curve,
+        mint::token_program = token_program,
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// The simulation curve, at the plain mint-derived address
+    #[account(
+        init,
+        payer = creator,
+        space = BondingCurve::LEN,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// SOL vault, whose rent floor the creator funds
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSimulation<'info> {
+    /// Anyone, once the simulation has expired
+    pub closer: Signer<'info>,
+
+    /// The simulation curve; its rent goes back to the creator
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = creator
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The simulation's creator
+    /// CHECK: Matches bonding_curve.creator; only receives lamports
+    #[account(mut)]
+    pub creator: UncheckedAccount<'info>,
+
+    /// SOL vault whose rent floor and dust go back to the creator
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, bonding_curve.token_mint.as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSimulationRefund<'info> {
+    /// The holder, or a delegate approved for the whole balance
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = token_mint
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Token account whose balance is burned and refunded
+    #[account(mut)]
+    pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Owner of the token account, receiving the refund
+    /// CHECK: Matches holder_token_account.owner; only receives lamports
+    #[account(
+        mut,
+        address = holder_token_account.owner @ BondingCurveError::Unauthorized
+    )]
+    pub holder: UncheckedAccount<'info>,
+
+    /// SOL vault paying the refunds
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseUserAccounts<'info> {
+    /// The user reclaiming rent
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// The curve the accounts belong to
+    #[account(
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// User's trade state on this curve
+    #[account(
+        mut,
+        seeds = [USER_TRADE_STATE_SEED, bonding_curve.key().as_ref(), user.key().as_ref()],
+        bump = user_trade_state.bump
+    )]
+    pub user_trade_state: Option<Account<'info, UserTradeState>>,
+
+    /// User's bootstrap deposit on this curve
+    #[account(
+        mut,
+        seeds = [BOOTSTRAP_DEPOSIT_SEED, bonding_curve.key().as_ref(), user.key().as_ref()],
+        bump = bootstrap_deposit.bump
+    )]
+    pub bootstrap_deposit: Option<Account<'info, BootstrapDeposit>>,
+
+    /// User's holder rewards checkpoint on this curve
+    #[account(
+        mut,
+        seeds = [HOLDER_REWARDS_SEED, bonding_curve.key().as_ref(), user.key().as_ref()],
+        bump = holder_rewards.bump
+    )]
+    pub holder_rewards: Option<Account<'info, HolderRewards>>,
+
+    /// User's receipt for the curve's airdrop
+    #[account(
+        mut,
+        seeds = [
+            AIRDROP_RECEIPT_SEED,
+            airdrop_address(&bonding_curve.key()).0.as_ref(),
+            user.key().as_ref()
+        ],
+        bump = airdrop_receipt.bump
+    )]
+    pub airdrop_receipt: Option<Account<'info, AirdropReceipt>>,
+
+    /// User's buyer mark in the curve's stats
+    /// CHECK: Address-checked; closed only if the program created it
+    #[account(
+        mut,
+        seeds = [BUYER_MARK_SEED, bonding_curve.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub buyer_mark: Option<UncheckedAccount<'info>>,
+
+    /// One of the user's limit orders on this curve
+    #[account(
+        mut,
+        seeds = [
+            LIMIT_ORDER_SEED,
+            bonding_curve.key().as_ref(),
+            user.key().as_ref(),
+            &limit_order.order_id.to_le_bytes()
+        ],
+        bump = limit_order.bump
+    )]
+    pub limit_order: Option<Account<'info, LimitOrder>>,
+
+    /// One of the user's trading sessions
+    #[account(
+        mut,
+        seeds = [SESSION_SEED, user.key().as_ref(), session.authority.as_ref()],
+        bump = session.bump
+    )]
+    pub session: Option<Account<'info, TradingSession>>,
+
+    /// A referral code whose NFT the user holds
+    #[account(
+        mut,
+        seeds = [REFERRAL_SEED, referral.nft_mint.as_ref()],
+        bump = referral.bump
+    )]
+    pub referral: Option<Account<'info, ReferralCode>>,
+
+    /// User's token account with the referral NFT; required with referral
+    pub referral_nft_account: Option<InterfaceAccount<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseBondingCurve<'info> {
+    /// The curve creator, receiving the rent and vault leftovers
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// The curve being retired
+    /// CHECK: Deserialized in the instruction, or read through
+    /// layout::bonding_curve if archive_curve shrank it; the program's
+    /// ownership and the discriminator make it a genuine curve
+    #[account(mut, owner = crate::ID)]
+    pub bonding_curve: UncheckedAccount<'info>,
+
+    /// The curve's token mint
+    #[account(mut, mint::token_program = token_program)]
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// SOL vault swept to the creator
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    /// Fee vault swept to the creator
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: AccountInfo<'info>,
+
+    /// Holder rewards vault swept to the creator
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [REWARDS_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub rewards_vault: AccountInfo<'info>,
+
+    /// The curve's market-maker vault, closed alongside it if created
+    /// CHECK: Address-checked; deserialized in the instruction if it exists
+    #[account(
+        mut,
+        seeds = [MM_VAULT_SEED, bonding_curve.key().as_ref()],
+        bump
+    )]
+    pub mm_vault: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+
+    /// The curve's entry in its creator's registry, closed alongside it;
+    /// required if the curve is registered
+    #[account(
+        mut,
+        close = creator,
+        constraint = registry_entry.bonding_curve == bonding_curve.key() @ BondingCurveError::RegistryEntryRequired
+    )]
+    pub registry_entry: Option<Account<'info, CurveRegistryEntry>>,
+
+    /// The launch queue, cleared of any entry for the curve (optional)
+    #[account(
+        mut,
+        seeds = [LAUNCH_QUEUE_SEED],
+        bump = launch_queue.bump
+    )]
+    pub launch_queue: Option<Box<Account<'info, LaunchQueue>>>,
+}
+
+#[derive(Accounts)]
+pub struct SnapshotForUpgrade<'info> {
+    /// The config admin, paying for the snapshot the first time
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The global config being fingerprinted
+    #[account(
+        seeds = [GLOBAL_CONFIG_SEED],
+        bump = global_config.bump,
+        has_one = admin @ BondingCurveError::Unauthorized
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+
+    /// The snapshot, overwritten before each upgrade
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = UpgradeSnapshot::LEN,
+        seeds = [UPGRADE_SNAPSHOT_SEED],
+        bump
+    )]
+    pub upgrade_snapshot: Account<'info, UpgradeSnapshot>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyAfterUpgrade<'info> {
+    /// The config admin
+    pub admin: Signer<'info>,
+
+    /// The global config, read raw so layout changes show up as a mismatch
+    /// CHECK: Address-checked; only its bytes are hashed
+    #[account(address = global_config_address().0)]
+    pub global_config: UncheckedAccount<'info>,
+
+    /// The snapshot taken before the upgrade
+    #[account(
+        mut,
+        seeds = [UPGRADE_SNAPSHOT_SEED],
+        bump = upgrade_snapshot.bump,
+        has_one = admin @ BondingCurveError::Unauthorized
+    )]
+    pub upgrade_snapshot: Account<'info, UpgradeSnapshot>,
+}
+
+#[derive(Accounts)]
+pub struct CreateCreatorAllocation<'info> {
+    /// The curve creator, paying for the allocation
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// The bonding curve minting the allocation
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = creator @ BondingCurveError::Unauthorized,
+        has_one = token_mint,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The lockup schedule
+    #[account(
+        init,
+        payer = creator,
+        space = CreatorVesting::LEN,
+        seeds = [CREATOR_VESTING_SEED, bonding_curve.key().as_ref()],
+        bump
+    )]
+    pub creator_vesting: Account<'info, CreatorVesting>,
+
+    /// The curve's token mint
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Locked tokens, owned by the vesting PDA
+    #[account(
+        init,
+        payer = creator,
+        associated_token::mint = token_mint,
+        associated_token::authority = creator_vesting,
+        associated_token::token_program = token_program
+    )]
+    pub vesting_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// SOL vault receiving the payment
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    /// Fee vault collecting the protocol and creator fees
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: AccountInfo<'info>,
+
+    /// Charity receiving the curve's donation share (required when charity_bps > 0)
+    /// CHECK: Must match bonding_curve.charity; only receives lamports
+    #[account(mut)]
+    pub charity: Option<UncheckedAccount<'info>>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    /// Creator's eligibility record (required only when the curve has an attestor)
+    pub eligibility_record: Option<Account<'info, EligibilityRecord>>,
+
+    /// Creator's trade state on this curve (required during the launch window)
+    #[account(mut)]
+    pub user_trade_state: Option<Account<'info, UserTradeState>>,
+
+    /// Instructions sysvar, inspected in strict mode (required while strict_mode is on)
+    /// CHECK: Address-checked; read only through the sysvar helpers
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+
+    /// Protocol-wide counters; pass it so the action is counted
+    #[account(
+        mut,
+        seeds = [PROTOCOL_STATS_SEED],
+        bump = protocol_stats.bump
+    )]
+    pub protocol_stats: Option<Account<'info, ProtocolStats>>,
+
+    /// Curve's price history; pass it so the trade is recorded for the TWAP
+    /// (required once the curve has one)
+    #[account(
+        mut,
+        seeds = [PRICE_OBSERVATIONS_SEED, bonding_curve.key().as_ref()],
+        bump = price_observations.bump
+    )]
+    pub price_observations: Option<Box<Account<'info, PriceObservations>>>,
+
+    /// Curve's cumulative trade stats; pass it so the trade is counted
+    /// (required once the curve has them)
+    #[account(
+        mut,
+        seeds = [CURVE_STATS_SEED, bonding_curve.key().as_ref()],
+        bump = curve_stats.bump
+    )]
+    pub curve_stats: Option<Box<Account<'info, CurveStats>>>,
+
+    /// Creator's mark on this curve, created on their first buy (required once the curve keeps stats)
+    /// CHECK: Address-checked against pda::buyer_mark_address; only ever assigned, never holds data
+    #[account(mut)]
+    pub buyer_mark: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    /// The allocation's beneficiary
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+
+    /// The bonding curve the allocation belongs to
+    #[account(has_one = token_mint)]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The lockup schedule
+    #[account(
+        mut,
+        seeds = [CREATOR_VESTING_SEED, bonding_curve.key().as_ref()],
+        bump = creator_vesting.bump,
+        has_one = beneficiary @ BondingCurveError::Unauthorized,
+        has_one = bonding_curve
+    )]
+    pub creator_vesting: Account<'info, CreatorVesting>,
+
+    /// The curve's token mint
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Locked tokens, owned by the vesting PDA
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = creator_vesting,
+        associated_token::token_program = token_program
+    )]
+    pub vesting_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Beneficiary's token account (created if needed)
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        associated_token::mint = token_mint,
+        associated_token::authority = beneficiary,
+        associated_token::token_program = token_program
+    )]
+    pub beneficiary_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateAirdrop<'info> {
+    /// The curve creator, paying for the airdrop account
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        has_one = creator @ BondingCurveError::Unauthorized,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The airdrop's root and claim totals
+    #[account(
+        init,
+        payer = creator,
+        space = Airdrop::LEN,
+        seeds = [AIRDROP_SEED, bonding_curve.key().as_ref()],
+        bump
+    )]
+    pub airdrop: Account<'info, Airdrop>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimAirdrop<'info> {
+    /// The claimant, paying for the receipt and token account
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+
+    /// The bonding curve minting the tokens
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = token_mint
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    #[account(
+        mut,
+        seeds = [AIRDROP_SEED, bonding_curve.key().as_ref()],
+        bump = airdrop.bump,
+        has_one = bonding_curve
+    )]
+    pub airdrop: Account<'info, Airdrop>,
+
+    /// Proof the claimant has claimed; its existence blocks a second claim
+    #[account(
+        init,
+        payer = claimant,
+        space = AirdropReceipt::LEN,
+        seeds = [AIRDROP_RECEIPT_SEED, airdrop.key().as_ref(), claimant.key().as_ref()],
+        bump
+    )]
+    pub airdrop_receipt: Account<'info, AirdropReceipt>,
+
+    #[account(mut)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Claimant's token account (created if needed)
+    #[account(
+        init_if_needed,
+        payer = claimant,
+        associated_token::mint = token_mint,
+        associated_token::authority = claimant,
+        associated_token::token_program = token_program
+    )]
+    pub claimant_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimQuoteFees<'info> {
+    /// The curve creator or the insurance fund's governance authority
+    pub recipient: Signer<'info>,
+
+    /// The quote-token curve whose fees are claimed
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = token_mint
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The curve's token mint
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Protocol insurance fund, naming the protocol's fee authority
+    #[account(
+        seeds = [INSURANCE_FUND_SEED],
+        bump = insurance_fund.bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    /// Quote-token vault holding the fees
+    #[account(
+        mut,
+        seeds = [QUOTE_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub quote_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Recipient's quote-token account
+    #[account(mut)]
+    pub recipient_quote_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The curve's quote mint
+    #[account(address = bonding_curve.quote_mint @ BondingCurveError::QuoteMintMismatch)]
+    pub quote_token_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(address = bonding_curve.quote_token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub quote_token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePriceObservations<'info> {
+    /// Pays for the observations account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The curve to track; its trades must record observations from now on
+    #[account(mut)]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The curve's price history
+    #[account(
+        init,
+        payer = payer,
+        space = PriceObservations::LEN,
+        seeds = [PRICE_OBSERVATIONS_SEED, bonding_curve.key().as_ref()],
+        bump
+    )]
+    pub price_observations: Box<Account<'info, PriceObservations>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetTwap<'info> {
+    /// The curve's price history
+    pub price_observations: Box<Account<'info, PriceObservations>>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptCurveAuthority<'info> {
+    /// The wallet named by transfer_curve_authority
+    pub new_creator: Signer<'info>,
+
+    /// The bonding curve being handed over
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        constraint = bonding_curve.pending_creator == new_creator.key() @ BondingCurveError::Unauthorized,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Account<'info, BondingCurve>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeCurveStats<'info> {
+    /// Pays for the stats account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The curve to track
+    #[account(mut)]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The curve's trade stats
+    #[account(
+        init,
+        payer = payer,
+        space = CurveStats::LEN,
+        seeds = [CURVE_STATS_SEED, bonding_curve.key().as_ref()],
+        bump
+    )]
+    pub curve_stats: Box<Account<'info, CurveStats>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetCurveStats<'info> {
+    /// The curve's trade stats
+    pub curve_stats: Box<Account<'info, CurveStats>>,
+}
+
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct PlaceLimitOrder<'info> {
+    /// The order's owner, funding the escrow
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = token_mint
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// The order, also holding a buy order's SOL and the tip
+    #[account(
+        init,
+        payer = owner,
+        space = LimitOrder::LEN,
+        seeds = [LIMIT_ORDER_SEED, bonding_curve.key().as_ref(), owner.key().as_ref(), &order_id.to_le_bytes()],
+        bump
+    )]
+    pub limit_order: Account<'info, LimitOrder>,
+
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Owner's token account: the source of a sell, where a buy's tokens land (created if needed)
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = token_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token escrow of a sell order, owned by the order (required for Sell)
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = token_mint,
+        associated_token::authority = limit_order,
+        associated_token::token_program = token_program
+    )]
+    pub escrow_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg_attr(feature = "cpi-events", event_cpi)]
+#[derive(Accounts)]
+pub struct ExecuteLimitOrder<'info> {
+    /// Whoever fills the order, earning its tip
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    /// The order's owner, receiving the fill and the closed order's lamports
+    /// CHECK: Must match limit_order.owner; only receives lamports
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = token_mint,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    #[account(
+        mut,
+        has_one = owner @ BondingCurveError::Unauthorized,
+        has_one = bonding_curve,
+        close = owner
+    )]
+    pub limit_order: Account<'info, LimitOrder>,
+
+    #[account(mut)]
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Owner's token account
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token escrow of a sell order (required for Sell)
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = limit_order,
+        associated_token::token_program = token_program
+    )]
+    pub escrow_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// SOL vault of the curve
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    /// Fee vault collecting the protocol and creator fees
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: AccountInfo<'info>,
+
+    /// Charity receiving the curve's donation share (required when charity_bps > 0)
+    /// CHECK: Must match bonding_curve.charity; only receives lamports
+    #[account(mut)]
+    pub charity: Option<UncheckedAccount<'info>>,
+
+    /// Owner's trade state on this curve (required when min_hold_seconds > 0 or during the launch window)
+    #[account(mut)]
+    pub user_trade_state: Option<Account<'info, UserTradeState>>,
+
+    /// Owner's blacklist address, which must be empty (required by sell orders while the curve blacklists anyone)
+    /// CHECK: Address and emptiness are checked in check_not_blacklisted
+    pub blacklist_entry: Option<UncheckedAccount<'info>>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+
+    /// Curve's price history; pass it so the trade is recorded for the TWAP
+    /// (required once the curve has one)
+    #[account(
+        mut,
+        seeds = [PRICE_OBSERVATIONS_SEED, bonding_curve.key().as_ref()],
+        bump = price_observations.bump
+    )]
+    pub price_observations: Option<Box<Account<'info, PriceObservations>>>,
+
+    /// Curve's cumulative trade stats; pass it so the trade is counted
+    /// (required once the curve has them)
+    #[account(
+        mut,
+        seeds = [CURVE_STATS_SEED, bonding_curve.key().as_ref()],
+        bump = curve_stats.bump
+    )]
+    pub curve_stats: Option<Box<Account<'info, CurveStats>>>,
+
+    /// Buyer's mark on this curve, created on their first buy (required once the curve keeps stats)
+    /// CHECK: Address-checked against pda::buyer_mark_address; only ever assigned, never holds data
+    #[account(mut)]
+    pub buyer_mark: Option<UncheckedAccount<'info>>,
+
+    /// Instructions sysvar, inspected in strict mode (required while strict_mode is on)
+    /// CHECK: Address-checked; read only through the sysvar helpers
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelLimitOrder<'info> {
+    /// The order's owner
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = owner @ BondingCurveError::Unauthorized,
+        close = owner
+    )]
+    pub limit_order: Account<'info, LimitOrder>,
+
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Owner's token account, receiving a sell order's tokens back
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token escrow of a sell order (required for Sell)
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = limit_order,
+        associated_token::token_program = token_program
+    )]
+    pub escrow_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct FreezeHolder<'info> {
+    /// The curve creator
+    pub creator: Signer<'info>,
+
+    /// The bonding curve, the mint's freeze authority
+    #[account(
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = creator @ BondingCurveError::Unauthorized,
+        has_one = token_mint
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Token account to freeze or thaw
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::token_program = token_program
+    )]
+    pub holder_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(holder: Pubkey)]
+pub struct BlacklistHolder<'info> {
+    /// The curve creator, paying for the entry
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = creator @ BondingCurveError::Unauthorized
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = BlacklistEntry::LEN,
+        seeds = [BLACKLIST_SEED, bonding_curve.key().as_ref(), holder.as_ref()],
+        bump
+    )]
+    pub blacklist_entry: Account<'info, BlacklistEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnblacklistHolder<'info> {
+    /// The curve creator, receiving the entry's rent
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = creator @ BondingCurveError::Unauthorized
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    #[account(
+        mut,
+        seeds = [BLACKLIST_SEED, bonding_curve.key().as_ref(), blacklist_entry.holder.as_ref()],
+        bump = blacklist_entry.bump,
+        close = creator
+    )]
+    pub blacklist_entry: Account<'info, BlacklistEntry>,
+}
+
+#[derive(Accounts)]
+pub struct BuybackAndBurn<'info> {
+    /// Whoever cranks the buyback
+    pub cranker: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// Fee vault paying for the buyback
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, bonding_curve.token_mint.as_ref()],
+        bump
+    )]
+    pub fee_vault: AccountInfo<'info>,
+
+    /// SOL vault receiving the buyback as reserves
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, bonding_curve.token_mint.as_ref()],
+        bump
+    )]
+    pub sol_vault: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateCurve<'info> {
+    /// Pays any rent the larger layout needs
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The curve to upgrade
+    /// CHECK: Checked for the BondingCurve discriminator and deserialized in
+    /// the instruction, after it has grown enough to load
+    #[account(mut, owner = crate::ID)]
+    pub bonding_curve: UncheckedAccount<'info>,
+
+    /// The curve's price observations, created or not
+    /// CHECK: Only checked for whether the program has created it
+    #[account(seeds = [PRICE_OBSERVATIONS_SEED, bonding_curve.key().as_ref()], bump)]
+    pub price_observations: UncheckedAccount<'info>,
+
+    /// The curve's stats, created or not
+    /// CHECK: Only checked for whether the program has created it
+    #[account(seeds = [CURVE_STATS_SEED, bonding_curve.key().as_ref()], bump)]
+    pub curve_stats: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateEligibilityRecord<'info> {
+    /// Pays any rent the larger layout needs
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The record to upgrade
+    /// CHECK: Checked for the EligibilityRecord discriminator in the instruction
+    #[account(mut, owner = crate::ID)]
+    pub eligibility_record: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct HolderRewardsSettings<'info> {
+    /// The curve creator, funding the rewards vault's rent
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = creator @ BondingCurveError::Unauthorized,
+        constraint = bonding_curve.status != CurveStatus::Archived @ BondingCurveError::CurveArchived
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// Rewards vault paying holders
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [REWARDS_VAULT_SEED, bonding_curve.token_mint.as_ref()],
+        bump
+    )]
+    pub rewards_vault: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct HolderRewardsStake<'info> {
+    /// The holder, receiving the rewards
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, token_mint.key().as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump,
+        has_one = token_mint
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    pub token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Holder's token account, staking from and unstaking to
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program
+    )]
+    pub holder_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The curve's stake vault, holding every staked token (created on first use)
+    #[account(
+        init_if_needed,
+        payer = holder,
+        seeds = [REWARDS_STAKE_SEED, token_mint.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = bonding_curve,
+        token::token_program = token_program
+    )]
+    pub stake_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Holder's stake and reward checkpoint, created on first use
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = HolderRewards::LEN,
+        seeds = [HOLDER_REWARDS_SEED, bonding_curve.key().as_ref(), holder.key().as_ref()],
+        bump
+    )]
+    pub holder_rewards: Account<'info, HolderRewards>,
+
+    /// Fee vault holding rewards not yet moved to the rewards vault
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: AccountInfo<'info>,
+
+    /// Rewards vault paying holders
+    /// CHECK: This is a PDA that holds SOL
+    #[account(
+        mut,
+        seeds = [REWARDS_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub rewards_vault: AccountInfo<'info>,
+
+    #[account(address = bonding_curve.token_program @ BondingCurveError::TokenProgramMismatch)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterCurve<'info> {
+    /// Anyone; pays for the registry entry
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The curve to register
+    #[account(
+        mut,
+        seeds = [BONDING_CURVE_SEED, bonding_curve.token_mint.as_ref(), &bonding_curve.salt_seed()],
+        bump = bonding_curve.bump
+    )]
+    pub bonding_curve: Box<Account<'info, BondingCurve>>,
+
+    /// Registry of the curve's creator, created with its first entry
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = CurveRegistry::LEN,
+        seeds = [CURVE_REGISTRY_SEED, bonding_curve.creator.as_ref()],
+        bump
+    )]
+    pub curve_registry: Box<Account<'info, CurveRegistry>>,
+
+    /// The curve's registry entry, at the registry's next index
+    #[account(
+        init,
+        payer = payer,
+        space = CurveRegistryEntry::LEN,
+        seeds = [
+            CURVE_REGISTRY_ENTRY_SEED,
+            bonding_curve.creator.as_ref(),
+            &curve_registry.curve_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub registry_entry: Box<Account<'info, CurveRegistryEntry>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(creator: Pubkey, index: u64)]
+pub struct GetCurveByIndex<'info> {
+    /// Registry entry at the index
+    #[account(
+        seeds = [CURVE_REGISTRY_ENTRY_SEED, creator.as_ref(), &index.to_le_bytes()],
+        bump = registry_entry.bump
+    )]
+    pub registry_entry: Account<'info, CurveRegistryEntry>,
+}
+
+#[derive(Accounts)]
+#[instruction(authority: Pubkey)]
+pub struct CreateSession<'info> {
+    /// Wallet opening the session and funding its deposit
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The new session
+    #[account(
+        init,
+        payer = owner,
+        space = TradingSession::LEN,
+        seeds = [SESSION_SEED, owner.key().as_ref(), authority.as_ref()],
+        bump
+    )]
+    pub session: Account<'info, TradingSession>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeSession<'info> {
+    /// The session's owner or its session key
+    #[account(
+        constraint = signer.key() == session.owner
+            || signer.key() == session.authority @ BondingCurveError::Unauthorized
+    )]
+    pub signer: Signer<'info>,
+
+    /// Receives the remaining deposit and rent
+    /// CHECK: Must be the session's owner; only receives lamports
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// The session being ended
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        seeds = [SESSION_SEED, owner.key().as_ref(), session.authority.as_ref()],
+        bump = session.bump
+    )]
+    pub session: Account<'info, TradingSession>,
+}
+
+/**
+ * ACCOUNT DATA STRUCTURES
+ */
+
+#[account]
+#[derive(InitSpace)]
+pub struct BondingCurve {
+    /// The creator/authority of the bonding curve
+    pub creator: Pubkey,
+    /// The token mint that this bonding curve manages
+    pub token_mint: Pubkey,
+    /// Current total supply of tokens
+    pub current_supply: u64,
+    /// Current SOL reserves
+    pub sol_reserves: u64,
+    /// Initial price in lamports
+    pub initial_price: u64,
+    /// Price slope in lamports
+    pub slope: u64,
+    /// Shape of the price curve
+    pub curve_type: CurveType,
+    /// Supply after which a LinearThenFlat curve stays at a constant price
+    pub flat_threshold: u64,
+    /// PDA bump seed
+    pub bump: u8,
+    /// Token name
+    pub name: [u8; 32],
+    /// Token symbol
+    pub symbol: [u8; 8],
+    /// Number of configured entries in sale_phases (0 = no schedule)
+    pub phase_count: u8,
+    /// Sale schedule, ordered by start time
+    pub sale_phases: [SalePhase; MAX_SALE_PHASES],
+    /// Attestor whose eligibility records gate trading (default = no gate)
+    pub eligibility_attestor: Pubkey,
+    /// Running stats for the current day
+    pub daily_stats: DailyStats,
+    /// Stats for the most recent completed day with trades
+    pub previous_day_stats: DailyStats,
+    /// Last day index whose stats were emitted by the crank
+    pub last_stats_day: i64,
+    /// Largest SOL amount a single trade may move (0 = protocol ceiling)
+    pub max_sol_per_trade: u64,
+    /// Lifecycle status; only Active curves trade
+    pub status: CurveStatus,
+    /// Scheduled launch time (0 = not scheduled through the launch queue)
+    pub launch_ts: i64,
+    /// Lifetime trading fees routed to the fee vault
+    pub fees_accrued: u64,
+    /// Start of the zero-fee buy window
+    pub fee_bootstrap_start_ts: i64,
+    /// End (exclusive) of the zero-fee buy window
+    pub fee_bootstrap_end_ts: i64,
+    /// Extra share of each sale burned without compensation, in basis points
+    pub sell_burn_tax_bps: u16,
+    /// Recipient of the charity share of each trade
+    pub charity: Pubkey,
+    /// Share of each trade donated to charity, in basis points
+    pub charity_bps: u16,
+    /// Whether the charity split can no longer change
+    pub charity_locked: bool,
+    /// Which metric graduation_threshold is measured against
+    pub graduation_metric: GraduationMetric,
+    /// Value of graduation_metric at which the curve graduates, in lamports
+    pub graduation_threshold: u64,
+    /// Whether the curve has given up the mint authority
+    pub mint_authority_handed_off: bool,
+    /// Mint authority after the handoff (None = revoked for good)
+    pub mint_authority_target: Option<Pubkey>,
+    /// Share of reserves or supply above which a trade emits LargeTrade (0 = off)
+    pub large_trade_bps: u16,
+    /// Every lamport of buy SOL stays in reserves: no fees, no charity, no creator payout
+    pub fully_backed: bool,
+    /// Seconds a wallet must wait after buying before it can sell (0 = none)
+    pub min_hold_seconds: i64,
+    /// End of the deposit-only bootstrap phase (0 = no bootstrap)
+    pub bootstrap_end_ts: i64,
+    /// SOL deposited during the bootstrap phase
+    pub bootstrap_deposits: u64,
+    /// Tokens allocated to bootstrap depositors at activation
+    pub bootstrap_tokens: u64,
+    /// Launch raffle settings and draw result
+    pub raffle: LaunchRaffle,
+    /// Emit CompactTrade instead of the full trade events and logs
+    pub compact_events: bool,
+    /// Target size of the reserve buffer, in basis points of sol_reserves
+    pub reserve_buffer_bps: u16,
+    /// Lamports held in the vault outside sol_reserves to absorb rounding drift
+    pub reserve_buffer: u64,
+    /// Admin-set cap on trades per slot (0 = unlimited)
+    pub max_trades_per_slot: u16,
+    /// Slot the trade counter belongs to
+    pub rate_limit_slot: u64,
+    /// Trades executed in rate_limit_slot
+    pub trades_in_slot: u16,
+    /// Protocol's share of the trading fee, in basis points
+    pub protocol_fee_bps: u16,
+    /// Creator's share of the trading fee, in basis points
+    pub creator_fee_bps: u16,
+    /// Protocol fees in the fee vault awaiting claim_protocol_fees
+    pub protocol_fees_unclaimed: u64,
+    /// Creator fees in the fee vault awaiting claim_creator_fees
+    pub creator_fees_unclaimed: u64,
+    /// Days without a creator action after which creator powers lapse (0 = never)
+    pub creator_timeout_days: u16,
+    /// Time of the creator's most recent authority action
+    pub creator_last_action_ts: i64,
+    /// Extra PDA seed distinguishing curves on the same mint (0 = none)
+    pub salt: u64,
+    /// Token program that owns the mint, pinned at init for every later CPI
+    pub token_program: Pubkey,
+    /// Hard cap on minted supply for fixed-supply launches (0 = uncapped)
+    pub max_supply: u64,
+    /// Whether supply has reached max_supply; buys are refused while set
+    pub sold_out: bool,
+    /// Trading halt set by pause_curve
+    pub paused: bool,
+    /// When a scheduled emergency_withdraw may run (0 = none scheduled)
+    pub emergency_withdraw_ts: i64,
+    /// Position of the oldest unsettled queued exit
+    pub exit_queue_head: u64,
+    /// Position the next queued exit takes
+    pub exit_queue_tail: u64,
+    /// Lamports of reserves owed to queued exits
+    pub exit_queue_lamports: u64,
+    /// Slot trading opens; buys before it are refused (0 = no launch window)
+    pub launch_start_slot: u64,
+    /// Length of the anti-sniping window after launch_start_slot, in slots
+    pub launch_window_slots: u64,
+    /// Most tokens one wallet may buy during the launch window (0 = uncapped)
+    pub max_buy_per_wallet_during_launch: u64,
+    /// When a simulate_launch curve may be closed by anyone (0 = real launch)
+    pub simulation_expiry_ts: i64,
+    /// Merkle root of the presale allowlist (all zeros = no allowlist)
+    pub whitelist_root: [u8; 32],
+    /// Slot the allowlist period ends; until then only buy_tokens_whitelisted may buy
+    pub whitelist_end_slot: u64,
+    /// Sells paying out fewer lamports than this are dust (0 = no floor)
+    pub dust_threshold: u64,
+    /// What happens to a dust sell
+    pub dust_policy: DustPolicy,
+    /// Mint decimals; prices are per whole token of 10^decimals base units
+    pub decimals: u8,
+    /// Reserves at which buys stop for good, for fixed-raise sales (0 = none)
+    pub raise_target_lamports: u64,
+    /// Whether the reserves have reached raise_target_lamports; buys are refused once set
+    pub raise_target_reached: bool,
+    /// SPL mint the curve trades against (default pubkey = SOL); reserves,
+    /// prices and fees are then in its base units, held in the quote vault
+    pub quote_mint: Pubkey,
+    /// Token program of quote_mint (default pubkey on SOL curves)
+    pub quote_token_program: Pubkey,
+    /// Largest spot-price move one trade may cause, in basis points (0 = uncapped)
+    pub max_price_impact_bps: u16,
+    /// Extra sell fee at sell_tax_start_slot, in basis points
+    pub sell_tax_initial_bps: u16,
+    /// Extra sell fee once the ramp has decayed, in basis points
+    pub sell_tax_final_bps: u16,
+    /// Slots over which the extra sell fee decays from initial to final
+    pub sell_tax_decay_slots: u64,
+    /// Slot the sell tax ramp started (the curve's initialization)
+    pub sell_tax_start_slot: u64,
+    /// Wallet a pending transfer_curve_authority hands the curve to (default pubkey = none)
+    pub pending_creator: Pubkey,
+    /// Lifetime tokens removed by burn_tokens; their SOL stayed in the reserves
+    pub virtual_burned: u64,
+    /// Creator-deposited exit liquidity left in the vault, outside sol_reserves
+    pub seeded_reserves: u64,
+    /// Holders currently on the curve's blacklist; while nonzero, sell_tokens needs the seller's blacklist address
+    pub blacklisted_holders: u32,
+    /// Most protocol-fee lamports one buyback_and_burn may spend (0 = buybacks off)
+    pub buyback_max_lamports: u64,
+    /// Slots that must pass between two buybacks
+    pub buyback_window_slots: u64,
+    /// Slot of the last buyback
+    pub last_buyback_slot: u64,
+    /// Trading opens at this timestamp (0 = no start)
+    pub trading_start_ts: i64,
+    /// Trading closes for good at this timestamp (0 = no end)
+    pub trading_end_ts: i64,
+    /// Daily window opening, in seconds after UTC midnight
+    pub trading_open_secs: u32,
+    /// Daily window closing, in seconds after UTC midnight (equal to open = all day)
+    pub trading_close_secs: u32,
+    /// Whether trading halts on Saturdays and Sundays (UTC)
+    pub weekend_halt: bool,
+    /// Whether trades check the transaction for bundles and unknown CPI callers
+    pub strict_mode: bool,
+    /// Program allowed to trade by CPI in strict mode (default = none)
+    pub strict_trusted_program: Pubkey,
+    /// Layout version the account was written with (0 = before versioning);
+    /// migrate_curve brings older curves up to CURVE_VERSION
+    pub version: u8,
+    /// Share of the creator's fees paid out to holders, in basis points
+    pub holder_reward_bps: u16,
+    /// Lifetime holder rewards per staked base unit, scaled by REWARD_INDEX_SCALE
+    pub reward_per_token: u128,
+    /// Holder rewards still in the fee vault, moved to the rewards vault on the next claim
+    pub holder_rewards_pending: u64,
+    /// Trades recorded on the curve; numbers its trade events
+    pub trade_index: u64,
+    /// When a creator pause lapses (0 = admin pause, lasting until lifted)
+    pub pause_expires_ts: i64,
+    /// Lamports emergency_withdraw set aside for redemptions
+    pub redemption_lamports: u64,
+    /// Supply outstanding at emergency_withdraw (0 = no redemption open)
+    pub redemption_supply: u64,
+    /// Tokens staked for holder rewards, held in the stake vault
+    pub rewards_staked: u64,
+    /// Whether every trade must pass and record to the price observations;
+    /// set for good when they are created
+    pub price_observations_required: bool,
+    /// Tokens burn_tokens took out of circulation; still part of current_supply,
+    /// which the curve prices at, but no longer held by anyone
+    pub burned_supply: u64,
+    /// Whether the curve has an entry in its creator's curve registry
+    pub registered: bool,
+    /// Whether every trade must pass and count in the curve stats;
+    /// set for good when they are created
+    pub stats_required: bool,
+    /// Slot whose hash draws the launch raffle (0 = not committed yet)
+    pub raffle_draw_slot: u64,
+    /// Bootstrap deposits left unspent by a max_supply cap, refunded with the claims
+    pub bootstrap_refund_lamports: u64,
+    /// Zeroed space kept free for future fields
+    pub reserved: [u8; 5],
+}
+
+impl BondingCurve {
+    /// Account size, derived from the struct so it can't drift as fields are added
+    pub const LEN: usize = 8 + // Discriminator
+        BondingCurve::INIT_SPACE;
+
+    /// The curve's pricing parameters
+    pub fn params(&self) -> CurveParams {
+        CurveParams {
+            curve_type: self.curve_type,
+            initial_price: self.initial_price,
+            slope: self.slope,
+            flat_threshold: self.flat_threshold,
+            decimals: self.decimals,
+        }
+    }
+
+    /// Spot price in lamports per whole token at the given supply
+    pub fn price_at_supply(&self, supply: u64) -> Result<u64> {
+        self.params().price_at_supply(supply)
+    }
+
+    /// Spot price in lamports per whole token at the current supply
+    pub fn current_price(&self) -> Result<u64> {
+        self.price_at_supply(self.current_supply)
+    }
+
+    /// Tokens actually held: current_supply less what burn_tokens removed
+    pub fn circulating_supply(&self) -> u64 {
+        self.current_supply.saturating_sub(self.burned_supply)
+    }
+
+    /// Reserves backing each whole token in circulation, in lamports (0 with
+    /// no supply); burn_tokens raises it by shrinking circulation alone
+    pub fn floor_price(&self) -> u64 {
+        let circulating = self.circulating_supply();
+        if circulating == 0 {
+            return 0;
+        }
+        let floor = self.sol_reserves as u128 * self.params().unit() / circulating as u128;
+        u64::try_from(floor).unwrap_or(u64::MAX)
+    }
+
+    /// Whether the curve's trading schedule allows buys at `now`
+    pub fn trading_open(&self, now: i64) -> bool {
+        let ended = self.trading_end_ts != 0 && now >= self.trading_end_ts;
+        !ended && self.selling_open(now)
+    }
+
+    /// Whether the curve's trading schedule allows sells at `now`: as for
+    /// buys, except that end_ts never stops holders from exiting
+    pub fn selling_open(&self, now: i64) -> bool {
+        if self.trading_start_ts != 0 && now < self.trading_start_ts {
+            return false;
+        }
+        // Day 0 of the unix epoch was a Thursday; weekday 0 is Monday
+        let weekday = (now.div_euclid(SECONDS_PER_DAY) + 3).rem_euclid(7);
+        if self.weekend_halt && weekday >= 5 {
+            return false;
+        }
+        let secs = now.rem_euclid(SECONDS_PER_DAY) as u32;
+        let (open, close) = (self.trading_open_secs, self.trading_close_secs);
+        match open.cmp(&close) {
+            std::cmp::Ordering::Equal => true,
+            std::cmp::Ordering::Less => secs >= open && secs < close,
+            // The window runs past midnight
+            std::cmp::Ordering::Greater => secs >= open || secs < close,
+        }
+    }
+
+    /// Earliest time at or after `now` the schedule allows trading, or None
+    /// if it never will again
+    pub fn next_trading_open(&self, now: i64) -> Option<i64> {
+        let from = now.max(self.trading_start_ts);
+        if self.trading_open(from) {
+            return Some(from);
+        }
+        // Otherwise trading resumes within the next week, either at a day's
+        // window opening or at midnight once a weekend halt ends
+        let first_day = from.div_euclid(SECONDS_PER_DAY);
+        (first_day..=first_day + 7)
+            .flat_map(|day| {
+                let midnight = day * SECONDS_PER_DAY;
+                [midnight, midnight + self.trading_open_secs as i64]
+            })
+            .find(|&ts| ts > from && self.trading_open(ts))
+    }
+
+    /// Whether a trade moving `sol` and `tokens` crosses the large trade threshold,
+    /// measured against the reserves and supply before the trade
+    pub fn is_large_trade(&self, sol: u64, tokens: u64) -> bool {
+        if self.large_trade_bps == 0 {
+            return false;
+        }
+        let bps = self.large_trade_bps as u128;
+        sol as u128 * 10_000 > self.sol_reserves as u128 * bps
+            || tokens as u128 * 10_000 > self.current_supply as u128 * bps
+    }
+
+    /// The sale phase in effect at `now`, or None if the curve has no schedule
+    /// Errors if a schedule exists but its first phase hasn't started yet
+    pub fn active_phase(&self, now: i64) -> Result<Option<SalePhase>> {
+        if self.phase_count == 0 {
+            return Ok(None);
+        }
+
+        let phase = self.sale_phases[..self.phase_count as usize]
+            .iter()
+            .rev()
+            .find(|phase| phase.start_ts <= now)
+            .ok_or(BondingCurveError::TradingNotStarted)?;
+
+        Ok(Some(*phase))
+    }
+
+    /// Spot price × circulating supply, in lamports
+    pub fn market_cap(&self) -> Result<u64> {
+        Ok(value_at_price(self.current_price()?, self.circulating_supply(), self.decimals)
+            .unwrap_or(u64::MAX))
+    }
+
+    /// Current value of the metric graduation is measured against
+    pub fn graduation_metric_value(&self) -> Result<u64> {
+        match self.graduation_metric {
+            GraduationMetric::SolRaised => Ok(self.sol_reserves),
+            GraduationMetric::MarketCap => self.market_cap(),
+        }
+    }
+
+    /// Per-trade SOL cap in force, falling back to the protocol ceiling
+    pub fn effective_max_sol_per_trade(&self) -> u64 {
+        if self.max_sol_per_trade == 0 {
+            PROTOCOL_MAX_SOL_PER_TRADE
+        } else {
+            self.max_sol_per_trade
+        }
+    }
+
+    /// Trading fee in basis points, honouring the active phase's override;
+    /// fully backed curves charge nothing
+    pub fn trade_fee_bps(&self, phase: Option<SalePhase>) -> u16 {
+        if self.fully_backed {
+            return 0;
+        }
+        phase
+            .and_then(|phase| phase.fee_bps_override)
+            .unwrap_or(self.protocol_fee_bps + self.creator_fee_bps)
+    }
+
+    /// Extra sell fee the ramp charges at `slot`, decaying linearly to its final rate
+    pub fn sell_tax_bps(&self, slot: u64) -> u16 {
+        let elapsed = slot.saturating_sub(self.sell_tax_start_slot);
+        if elapsed >= self.sell_tax_decay_slots {
+            return self.sell_tax_final_bps;
+        }
+        let span = (self.sell_tax_initial_bps - self.sell_tax_final_bps) as u64;
+        let decayed = span * elapsed / self.sell_tax_decay_slots;
+        self.sell_tax_initial_bps - decayed as u16
+    }
+
+    /// Fee for a sell at `slot`: the trading fee plus the sell tax ramp
+    pub fn sell_fee_bps(&self, slot: u64, phase: Option<SalePhase>) -> u16 {
+        self.trade_fee_bps(phase) + self.sell_tax_bps(slot)
+    }
+
+    /// Fee for a buy at `now`: zero inside the bootstrap window, standard otherwise
+    pub fn buy_fee_bps(&self, now: i64, phase: Option<SalePhase>) -> u16 {
+        if now >= self.fee_bootstrap_start_ts && now < self.fee_bootstrap_end_ts {
+            0
+        } else {
+            self.trade_fee_bps(phase)
+        }
+    }
+
+    /// Reserve buffer size the curve aims for at its current reserves
+    pub fn reserve_buffer_target(&self) -> Result<u64> {
+        calculate_fee(self.sol_reserves, self.reserve_buffer_bps)
+    }
+
+    /// Whether the creator went quiet for longer than their inactivity timeout
+    pub fn creator_powers_lapsed(&self, now: i64) -> bool {
+        self.creator_timeout_days > 0
+            && now.saturating_sub(self.creator_last_action_ts)
+                >= self.creator_timeout_days as i64 * SECONDS_PER_DAY
+    }
+
+    /// Gate a creator-only action on the inactivity timeout and restart its clock
+    pub fn record_creator_action(&mut self, now: i64) -> Result<()> {
+        require!(!self.creator_powers_lapsed(now), BondingCurveError::CreatorPowersLapsed);
+        self.creator_last_action_ts = now;
+        Ok(())
+    }
+
+    /// Tokens that can still be minted under max_supply
+    pub fn remaining_supply(&self) -> u64 {
+        if self.max_supply == 0 {
+            return u64::MAX;
+        }
+        self.max_supply.saturating_sub(self.current_supply)
+    }
+
+    /// Whether only allowlisted wallets may buy at `slot`
+    pub fn whitelist_active(&self, slot: u64) -> bool {
+        self.whitelist_root != [0; 32] && slot < self.whitelist_end_slot
+    }
+
+    /// Whether this is a throwaway curve created by simulate_launch
+    pub fn is_simulation(&self) -> bool {
+        self.simulation_expiry_ts != 0
+    }
+
+    /// Whether `slot` falls in the launch window's per-wallet buy limits
+    pub fn in_launch_window(&self, slot: u64) -> bool {
+        self.max_buy_per_wallet_during_launch > 0
+            && slot >= self.launch_start_slot
+            && slot < self.launch_start_slot.saturating_add(self.launch_window_slots)
+    }
+
+    /// Reserves not already owed to queued exits
+    pub fn sellable_reserves(&self) -> u64 {
+        self.sol_reserves.saturating_sub(self.exit_queue_lamports)
+    }
+
+    /// Lamports that can make up a payout the reserves fall short of
+    pub fn shortfall_cover(&self) -> u64 {
+        self.reserve_buffer.saturating_add(self.seeded_reserves)
+    }
+
+    /// Move `shortfall` (at most shortfall_cover) out of the reserve buffer
+    /// and then the seeded reserves, for the caller to add to sol_reserves
+    pub fn draw_shortfall(&mut self, shortfall: u64) {
+        let from_buffer = shortfall.min(self.reserve_buffer);
+        self.reserve_buffer -= from_buffer;
+        self.seeded_reserves -= shortfall - from_buffer;
+    }
+
+    /// Number of queued exits still waiting to be paid
+    pub fn exit_queue_len(&self) -> u64 {
+        self.exit_queue_tail - self.exit_queue_head
+    }
+
+    /// Require that every deduction from a sell fits within its proceeds: the
+    /// highest trading fee any phase charges, the full sell tax and the
+    /// charity share can't add up to more than 100%
+    pub fn check_sell_fee_cap(&self) -> Result<()> {
+        let trade_fee_bps = self.sale_phases[..self.phase_count as usize]
+            .iter()
+            .filter_map(|phase| phase.fee_bps_override)
+            .fold(self.protocol_fee_bps + self.creator_fee_bps, u16::max);
+        let total_bps = trade_fee_bps as u32 + self.sell_tax_initial_bps as u32 + self.charity_bps as u32;
+        require!(total_bps <= MAX_FEE_BPS as u32, BondingCurveError::InvalidFee);
+        Ok(())
+    }
+
+    /// Require that sells never pay out more than buys put into reserves.
+    /// Sells pay at their phase's price, so a selling phase must keep the
+    /// multiplier of every buying phase before it, and may not price above
+    /// the curve that tokens minted outside the schedule were paid at
+    pub fn check_phase_multipliers(&self) -> Result<()> {
+        let phases = &self.sale_phases[..self.phase_count as usize];
+        for (index, phase) in phases.iter().enumerate() {
+            if phase.kind != SalePhaseKind::Public {
+                continue;
+            }
+            require!(
+                phase.price_multiplier_bps <= 10_000
+                    && phases[..index]
+                        .iter()
+                        .filter(|earlier| earlier.kind != SalePhaseKind::Graduation)
+                        .all(|earlier| earlier.price_multiplier_bps == phase.price_multiplier_bps),
+                BondingCurveError::InvalidPriceMultiplier
+            );
+        }
+        Ok(())
+    }
+
+    /// Validate a new max_price_impact_bps: 0 or between MIN_PRICE_IMPACT_BPS
+    /// and 100%, and once tokens are out no tighter than the current cap
+    pub fn check_price_impact_update(&self, max_price_impact_bps: u16) -> Result<()> {
+        require!(
+            max_price_impact_bps == 0
+                || (MIN_PRICE_IMPACT_BPS..=10_000).contains(&max_price_impact_bps),
+            BondingCurveError::InvalidPriceImpact
+        );
+        let loosens = max_price_impact_bps == 0
+            || (self.max_price_impact_bps != 0 && max_price_impact_bps >= self.max_price_impact_bps);
+        require!(
+            self.current_supply == 0 || loosens,
+            BondingCurveError::InvalidPriceImpact
+        );
+        Ok(())
+    }
+
+    /// Reject a buy that raises the spot price by more than max_price_impact_bps
+    pub fn check_price_impact(&self, price_before: u64, price_after: u64) -> Result<()> {
+        if self.max_price_impact_bps == 0 || price_before == 0 {
+            return Ok(());
+        }
+        let impact_bps = price_before.abs_diff(price_after) as u128 * 10_000 / price_before as u128;
+        require!(
+            impact_bps <= self.max_price_impact_bps as u128,
+            BondingCurveError::PriceImpactExceeded
+        );
+        Ok(())
+    }
+
+    /// Whether buys are halted: always under an admin pause, and under a
+    /// creator pause until it lapses
+    pub fn buys_paused(&self, now: i64) -> bool {
+        self.paused && (self.pause_expires_ts == 0 || now < self.pause_expires_ts)
+    }
+
+    /// Whether the curve trades against an SPL quote mint instead of SOL
+    pub fn has_quote_mint(&self) -> bool {
+        self.quote_mint != Pubkey::default()
+    }
+
+    /// Latch raise_target_reached once the reserves reach the raise target;
+    /// returns true when this call reached it
+    pub fn refresh_raise_target(&mut self) -> bool {
+        if self.raise_target_reached
+            || self.raise_target_lamports == 0
+            || self.sol_reserves < self.raise_target_lamports
+        {
+            return false;
+        }
+        self.raise_target_reached = true;
+        true
+    }
+
+    /// Sync sold_out with the supply; returns true when this call sold the curve out
+    pub fn refresh_sold_out(&mut self) -> bool {
+        let was_sold_out = self.sold_out;
+        self.sold_out = self.max_supply > 0 && self.current_supply >= self.max_supply;
+        self.sold_out && !was_sold_out
+    }
+
+    /// The curve's salt as a PDA seed (empty when unsalted)
+    pub fn salt_seed(&self) -> Vec<u8> {
+        salt_seed(self.salt)
+    }
+
+    /// Count a trade against the per-slot limit, failing once the slot is full
+    pub fn record_trade_in_slot(&mut self, slot: u64) -> Result<()> {
+        if self.rate_limit_slot != slot {
+            self.rate_limit_slot = slot;
+            self.trades_in_slot = 0;
+        }
+        self.trades_in_slot = self.trades_in_slot.saturating_add(1);
+        if self.max_trades_per_slot > 0 {
+            require!(
+                self.trades_in_slot <= self.max_trades_per_slot,
+                BondingCurveError::RateLimited
+            );
+        }
+        Ok(())
+    }
+
+    /// Book trading fees, topping up the reserve buffer to its target first
+    /// The rest is split between protocol and creator in proportion to their
+    /// fee shares, with holder_reward_bps of the creator's part going to
+    /// staked holders; returns the lamports to move into the fee vault
+    pub fn accrue_fees(&mut self, fee_lamports: u64) -> Result<u64> {
+        let to_buffer = self
+            .reserve_buffer_target()?
+            .saturating_sub(self.reserve_buffer)
+            .min(fee_lamports);
+        self.reserve_buffer += to_buffer;
+
+        let to_vault = fee_lamports - to_buffer;
+        let total_bps = self.protocol_fee_bps as u128 + self.creator_fee_bps as u128;
+        let mut creator_share = match total_bps {
+            0 => 0,
+            _ => (to_vault as u128 * self.creator_fee_bps as u128 / total_bps) as u64,
+        };
+        if self.holder_reward_bps > 0 && self.rewards_staked > 0 {
+            let holder_share = calculate_fee(creator_share, self.holder_reward_bps)?;
+            creator_share -= holder_share;
+            self.reward_per_token = self
+                .reward_per_token
+                .checked_add(holder_share as u128 * REWARD_INDEX_SCALE / self.rewards_staked as u128)
+                .ok_or(BondingCurveError::MathOverflow)?;
+            self.holder_rewards_pending = self
+                .holder_rewards_pending
+                .checked_add(holder_share)
+                .ok_or(BondingCurveError::MathOverflow)?;
+        }
+        self.creator_fees_unclaimed = self
+            .creator_fees_unclaimed
+            .checked_add(creator_share)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        self.protocol_fees_unclaimed = self
+            .protocol_fees_unclaimed
+            .checked_add(to_vault - creator_share)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        self.fees_accrued = self
+            .fees_accrued
+            .checked_add(to_vault)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        Ok(to_vault)
+    }
+
+    /// Move the running day into previous_day_stats once `day` has moved past it
+    pub fn roll_daily_stats(&mut self, day: i64) {
+        if self.daily_stats.day < day {
+            if self.daily_stats.trade_count > 0 {
+                self.previous_day_stats = self.daily_stats;
+            }
+            self.daily_stats = DailyStats {
+                day,
+                ..DailyStats::default()
+            };
+        }
+    }
+
+    /// Add a trade to the running day's volume, fee and OHLC stats
+    pub fn record_trade(
+        &mut self,
+        now: i64,
+        volume_lamports: u64,
+        fees_lamports: u64,
+        price_before: u64,
+        price_after: u64,
+    ) -> Result<()> {
+        self.roll_daily_stats(now.div_euclid(SECONDS_PER_DAY));
+
+        let stats = &mut self.daily_stats;
+        if stats.trade_count == 0 {
+            stats.open_price = price_before;
+            stats.high_price = price_before;
+            stats.low_price = price_before;
+        }
+        stats.volume_lamports = stats
+            .volume_lamports
+            .checked_add(volume_lamports)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        stats.fees_lamports = stats
+            .fees_lamports
+            .checked_add(fees_lamports)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        stats.trade_count = stats
+            .trade_count
+            .checked_add(1)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        stats.high_price = stats.high_price.max(price_after);
+        stats.low_price = stats.low_price.min(price_after);
+        stats.close_price = price_after;
+
+        self.trade_index = self
+            .trade_index
+            .checked_add(1)
+            .ok_or(BondingCurveError::MathOverflow)?;
+
+        Ok(())
+    }
+}
+
+/// Lifecycle status of a curve
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum CurveStatus {
+    /// Open for trading
+    #[default]
+    Active,
+    /// Waiting in the launch queue for its launch time
+    PreLaunch,
+    /// Hit its graduation target and migrated its liquidity; trading is closed
+    Graduated,
+    /// Collecting deposits that are filled at one clearing price on activation
+    Bootstrap,
+    /// Selling raffle tickets whose winners are filled at one price on the draw
+    Raffle,
+    /// Terminal, read-only state after graduation; every mutating instruction is rejected
+    Archived,
+    /// Frozen while its liquidity moves to the migration authority in tranches
+    Migrating,
+}
+
+/// Longest token name the program accepts, in bytes; GlobalConfig can only tighten it
+pub const MAX_NAME_LEN: usize = 32;
+
+/// Longest token symbol the program accepts, in bytes; GlobalConfig can only tighten it
+pub const MAX_SYMBOL_LEN: usize = 10;
+
+/// Longest metadata URI accepted at initialization, in bytes
+pub const MAX_URI_LEN: usize = 200;
+
+/// Longest a simulate_launch curve can run before anyone may close it
+pub const MAX_SIMULATION_SECONDS: i64 = 7 * 24 * 60 * 60; // 7 days
+
+/// Observations a curve's price history ring buffer keeps
+pub const MAX_PRICE_OBSERVATIONS: usize = 64;
+
+/// Most curves snapshot_for_upgrade can sample
+pub const MAX_UPGRADE_SAMPLE_CURVES: usize = 8;
+
+/// Most legs a buy_tokens_multi can carry
+pub const MAX_MULTI_BUY_LEGS: usize = 8;
+
+/// Current BondingCurve layout version. Since version 3 new fields are carved
+/// from the front of `reserved`, keeping the account size fixed, so older
+/// curves read them as zero; earlier fields grew the account, which
+/// migrate_curve zero-extends. Bump this whenever a field is added, and have
+/// migrate_curve set any field whose zero is wrong for an existing curve.
+pub const CURVE_VERSION: u8 = 5;
+
+/// Schema version of TokensPurchased and TokensSold; bump it whenever their
+/// fields change, only ever appending new fields
+pub const TRADE_EVENT_VERSION: u8 = 1;
+
+/// Fixed-point scale of BondingCurve::reward_per_token
+pub const REWARD_INDEX_SCALE: u128 = 1_000_000_000_000;
+
+/// Tip a limit order escrows for whoever executes it
+pub const LIMIT_ORDER_TIP_LAMPORTS: u64 = 50_000; // 0.00005 SOL
+
+/// Largest share of supply one market-maker vault fill can take, in basis points
+pub const MAX_MM_FILL_BPS: u16 = 100; // 1%
+
+/// Custom error numbers from GATE_ERROR_BASE up are gate rejection codes,
+/// well clear of the program's own 6000-range errors
+pub const GATE_ERROR_BASE: u32 = 0x1_0000;
+
+/// Most decimals a curve's mint can have
+pub const MAX_TOKEN_DECIMALS: u8 = 9;
+
+/// Highest fee a sale phase can override the trading fee with
+pub const MAX_FEE_BPS: u16 = 10_000;
+
+/// Suggested GlobalConfig floor for explicit graduation thresholds
+pub const MIN_GRADUATION_THRESHOLD: u64 = 1_000_000_000; // 1 SOL
+
+/// Lamports seeded into each SOL vault on top of its rent-exempt minimum,
+/// so rounding dust can never pull the vault below rent exemption
+pub const VAULT_RENT_BUFFER: u64 = 5_000;
+
+/// Protocol-wide ceiling on the SOL a single trade may move
+pub const PROTOCOL_MAX_SOL_PER_TRADE: u64 = 1_000 * 1_000_000_000; // 1,000 SOL
+
+/// Ratio between the graduation and initial price of curves from suggest_params
+pub const SUGGESTED_PRICE_RANGE: u64 = 10;
+
+/// Suggested GlobalConfig protocol fee in basis points, also used by quote_with_params
+pub const PROTOCOL_FEE_BPS: u16 = 100; // 1%
+
+/// Longest zero-fee bootstrap window a curve can configure
+pub const MAX_FEE_BOOTSTRAP_SECONDS: i64 = SECONDS_PER_DAY;
+
+/// Longest hold period a curve can require between a buy and a sell
+pub const MAX_HOLD_SECONDS: i64 = SECONDS_PER_DAY;
+
+/// Largest reserve buffer a curve can target
+pub const MAX_RESERVE_BUFFER_BPS: u16 = 100; // 1%
+
+/// Tightest per-trade price impact cap a curve can configure
+pub const MIN_PRICE_IMPACT_BPS: u16 = 500; // 5%
+
+/// Highest sell burn tax a curve can configure
+pub const MAX_SELL_BURN_TAX_BPS: u16 = 1_000; // 10%
+
+/// Highest dust threshold a curve can set, so it can't lock holders out of small sells
+pub const MAX_DUST_THRESHOLD_LAMPORTS: u64 = 1_000_000; // 0.001 SOL
+
+/// What a curve does with sells paying out less than its dust threshold
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DustPolicy {
+    /// Fail the sell with DustTrade
+    #[default]
+    Reject,
+    /// Burn the tokens and add the payout to the fee vault
+    SweepToFees,
+}
+
+/// Dust handling chosen at initialization
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct DustSettings {
+    /// Smallest sell payout, in lamports after fees
+    pub threshold_lamports: u64,
+    /// What happens to smaller sells
+    pub policy: DustPolicy,
+}
+
+/// Optional initialize_bonding_curve settings; each is described on the instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct CurveInitArgs {
+    pub flat_threshold: Option<u64>,
+    pub sale_phases: Vec<SalePhase>,
+    pub graduation_metric: GraduationMetric,
+    pub graduation_threshold: Option<u64>,
+    pub salt: Option<u64>,
+    pub max_supply: Option<u64>,
+    pub dust: Option<DustSettings>,
+    pub decimals: Option<u8>,
+    pub initial_buy_lamports: Option<u64>,
+    pub quote_mint: Option<Pubkey>,
+    pub sell_tax_ramp: Option<SellTaxRamp>,
+}
+
+/// Fee and limit settings update_curve_params can change; None keeps the current value
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct CurveParamsUpdate {
+    pub creator_fee_bps: Option<u16>,
+    pub sell_burn_tax_bps: Option<u16>,
+    pub max_sol_per_trade: Option<u64>,
+    pub max_price_impact_bps: Option<u16>,
+    pub large_trade_bps: Option<u16>,
+    pub min_hold_seconds: Option<i64>,
+}
+
+/// Decaying sell fee chosen at initialization
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct SellTaxRamp {
+    /// Extra sell fee right after launch, in basis points
+    pub initial_sell_tax_bps: u16,
+    /// Extra sell fee once decayed, in basis points
+    pub final_sell_tax_bps: u16,
+    /// Slots the fee takes to decay from initial to final
+    pub decay_slots: u64,
+}
+
+/// Trading hours of a curve; every part is optional and all of them apply
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct TradingSchedule {
+    /// Trading opens at this timestamp (0 = no start)
+    pub start_ts: i64,
+    /// Trading closes for good at this timestamp (0 = no end)
+    pub end_ts: i64,
+    /// Daily window opening, in seconds after UTC midnight
+    pub daily_open_secs: u32,
+    /// Daily window closing, in seconds after UTC midnight; before the
+    /// opening for overnight windows, equal to it to trade all day
+    pub daily_close_secs: u32,
+    /// Halt trading on Saturdays and Sundays (UTC)
+    pub weekend_halt: bool,
+}
+
+/// Completion metric a curve's graduation is measured against
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GraduationMetric {
+    /// SOL held in reserves
+    #[default]
+    SolRaised,
+    /// Spot price × current supply
+    MarketCap,
+}
+
+/// Suggested GlobalConfig default for the SOL reserves a curve must raise to graduate
+pub const DEFAULT_GRADUATION_THRESHOLD: u64 = 85 * 1_000_000_000; // 85 SOL
+
+/// Most reserves a single migrate_tranche call moves
+pub const MIGRATION_TRANCHE_LAMPORTS: u64 = 25 * 1_000_000_000; // 25 SOL
+
+/// Highest extra sell fee a sell tax ramp can charge
+pub const MAX_SELL_TAX_BPS: u16 = 2_500; // 25%
+
+/// Highest charity share a curve can configure
+pub const MAX_CHARITY_BPS: u16 = 500; // 5%
+
+/// Highest protocol fee the admin can set on a curve
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 500; // 5%
+
+/// Highest referral share the global config can set
+pub const MAX_REFERRAL_FEE_BPS: u16 = 50; // 0.5%
+
+/// Highest creator fee a curve can configure
+pub const MAX_CREATOR_FEE_BPS: u16 = 500; // 5%
+
+/// Delay between scheduling an emergency withdrawal and running it
+pub const EMERGENCY_WITHDRAW_DELAY: i64 = 2 * SECONDS_PER_DAY;
+
+/// Longest a creator's pause_curve halts buys for
+pub const CREATOR_PAUSE_MAX_SECONDS: i64 = 3 * SECONDS_PER_DAY;
+
+/// buy_tokens flag: fill what the limits allow instead of failing
+pub const BUY_FLAG_ALLOW_PARTIAL: u8 = 1 << 0;
+
+/// Every flag bit buy_tokens understands
+pub const BUY_FLAGS_ALL: u8 = BUY_FLAG_ALLOW_PARTIAL;
+
+/// Length of a stats day in seconds
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Aggregate trading stats for one UTC day
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct DailyStats {
+    /// Day index (unix timestamp / SECONDS_PER_DAY)
+    pub day: i64,
+    /// SOL traded in both directions
+    pub volume_lamports: u64,
+    /// Number of buys and sells
+    pub trade_count: u64,
+    /// Fees charged on trades
+    pub fees_lamports: u64,
+    /// Spot price before the day's first trade
+    pub open_price: u64,
+    /// Highest spot price of the day
+    pub high_price: u64,
+    /// Lowest spot price of the day
+    pub low_price: u64,
+    /// Spot price after the day's last trade
+    pub close_price: u64,
+}
+
+/// A curve's launch raffle: tickets sold during a window, winners drawn at the end
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct LaunchRaffle {
+    /// When ticket sales close (0 = no raffle)
+    pub end_ts: i64,
+    /// Lamports paid per ticket
+    pub ticket_price: u64,
+    /// Most tickets that can win
+    pub max_winners: u32,
+    /// Tickets sold
+    pub ticket_count: u32,
+    /// Tickets that won, set by the draw
+    pub winner_count: u32,
+    /// Index of the first winning ticket, set by the draw
+    pub offset: u32,
+    /// Tokens minted to each winning ticket
+    pub tokens_per_winner: u64,
+    /// Whether the draw has happened
+    pub drawn: bool,
+}
+
+impl LaunchRaffle {
+    /// Winners are the winner_count consecutive tickets starting at offset, wrapping around
+    pub fn is_winner(&self, index: u32) -> bool {
+        let count = self.ticket_count as u64;
+        count > 0
+            && (index as u64 + count - self.offset as u64) % count < self.winner_count as u64
+    }
+}
+
+/// Slots between committing a raffle draw and the slot whose hash draws it
+pub const RAFFLE_DRAW_DELAY_SLOTS: u64 = 8;
+
+/// Maximum number of sale phases a curve can schedule
+pub const MAX_SALE_PHASES: usize = 4;
+
+/// Kinds of sale phase a schedule can contain
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SalePhaseKind {
+    /// Early buyers only: buying is allowed, selling is not
+    Presale,
+    /// Open trading in both directions
+    #[default]
+    Public,
+    /// Curve has graduated: trading is closed
+    Graduation,
+}
+
+/// One entry of a curve's sale schedule
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct SalePhase {
+    /// What trading the phase allows
+    pub kind: SalePhaseKind,
+    /// Unix timestamp the phase begins (it ends when the next phase begins)
+    pub start_ts: i64,
+    /// Maximum lamports per buy during the phase (0 = unlimited)
+    pub max_buy_lamports: u64,
+    /// Supply the curve may not exceed during the phase (0 = unlimited)
+    pub supply_cap: u64,
+    /// Trading fee in basis points for this phase (None = curve default)
+    pub fee_bps_override: Option<u16>,
+    /// Price multiplier in basis points (10_000 = curve price, 9_000 = 10% discount)
+    pub price_multiplier_bps: u16,
+}
+
+impl SalePhase {
+    /// Check a buy of sol_amount that would leave the curve at supply_after
+    pub fn check_buy(&self, sol_amount: u64, supply_after: u64) -> Result<()> {
+        require!(
+            self.kind != SalePhaseKind::Graduation,
+            BondingCurveError::PhaseTradingDisabled
+        );
+        require!(
+            self.max_buy_lamports == 0 || sol_amount <= self.max_buy_lamports,
+            BondingCurveError::PhaseBuyLimitExceeded
+        );
+        require!(
+            self.supply_cap == 0 || supply_after <= self.supply_cap,
+            BondingCurveError::PhaseSupplyCapReached
+        );
+        Ok(())
+    }
+
+    /// Convert a curve-priced lamport amount into this phase's price
+    pub fn apply_price_multiplier(&self, lamports: u64) -> Result<u64> {
+        let scaled = (lamports as u128)
+            .checked_mul(self.price_multiplier_bps as u128)
+            .ok_or(BondingCurveError::MathOverflow)?
+            / 10_000;
+        u64::try_from(scaled).map_err(|_| BondingCurveError::MathOverflow.into())
+    }
+
+    /// Like apply_price_multiplier, but rounds up so the curve is never underpaid
+    pub fn apply_price_multiplier_ceil(&self, lamports: u64) -> Result<u64> {
+        let scaled = (lamports as u128)
+            .checked_mul(self.price_multiplier_bps as u128)
+            .ok_or(BondingCurveError::MathOverflow)?
+            .div_ceil(10_000);
+        u64::try_from(scaled).map_err(|_| BondingCurveError::MathOverflow.into())
+    }
+
+    /// Convert lamports paid at this phase's price back into curve-priced lamports
+    pub fn remove_price_multiplier(&self, lamports: u64) -> Result<u64> {
+        let scaled = (lamports as u128)
+            .checked_mul(10_000)
+            .ok_or(BondingCurveError::MathOverflow)?
+            .checked_div(self.price_multiplier_bps as u128)
+            .ok_or(BondingCurveError::InvalidPriceMultiplier)?;
+        u64::try_from(scaled).map_err(|_| BondingCurveError::MathOverflow.into())
+    }
+
+    /// Check that the phase allows selling
+    pub fn check_sell(&self) -> Result<()> {
+        require!(
+            self.kind == SalePhaseKind::Public,
+            BondingCurveError::PhaseTradingDisabled
+        );
+        Ok(())
+    }
+}
+
+/// Supported price curve shapes
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CurveType {
+    /// price = initial_price + supply * slope
+    Linear,
+    /// Linear until flat_threshold, then constant at the threshold price
+    LinearThenFlat,
+    /// price = initial_price * 2^(supply / slope): doubles every `slope` tokens
+    Exponential,
+    /// price = initial_price / (1 + e^(-(supply - flat_threshold) / slope)):
+    /// S-shaped, rising towards initial_price with its midpoint at flat_threshold
+    Sigmoid,
+    /// Constant-product (x * y = k) pricing over virtual reserves, as pump.fun
+    /// does: flat_threshold is the virtual token reserve and initial_price the
+    /// opening price, which fixes the virtual SOL reserve. The price is
+    /// initial_price * (flat_threshold / (flat_threshold - supply))^2, so
+    /// supply can approach but never reach flat_threshold; slope is unused
+    ConstantProduct,
+}
+
+/// Pricing parameters of a curve, independent of any curve account
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct CurveParams {
+    pub curve_type: CurveType,
+    pub initial_price: u64,
+    pub slope: u64,
+    pub flat_threshold: u64,
+    pub decimals: u8,
+}
+
+impl CurveParams {
+    /// Base units in one whole token
+    pub fn unit(&self) -> u128 {
+        token_unit(self.decimals)
+    }
+
+    /// Virtual token reserve of a ConstantProduct curve left at `supply`
+    fn tokens_left(&self, supply: u64) -> Result<u128> {
+        match self.flat_threshold.checked_sub(supply) {
+            Some(left) if left > 0 => Ok(left as u128),
+            _ => err!(BondingCurveError::SupplyOverflow),
+        }
+    }
+
+    /// Spot price in lamports per whole token at the given supply (in base units)
+    pub fn price_at_supply(&self, supply: u64) -> Result<u64> {
+        let priced_supply = match self.curve_type {
+            CurveType::Linear => supply,
+            CurveType::LinearThenFlat => supply.min(self.flat_threshold),
+            CurveType::Exponential => {
+                let growth = exp2_wad(supply as u128, self.slope as u128 * self.unit())?;
+                return mul_div_wad(self.initial_price as u128, growth);
+            }
+            CurveType::Sigmoid => {
+                let x = sigmoid_arg_wad(supply, self.flat_threshold, self.slope as u128 * self.unit());
+                return mul_div_wad(self.initial_price as u128, sigmoid_wad(x));
+            }
+            CurveType::ConstantProduct => {
+                let virtual_tokens = self.flat_threshold as u128;
+                let tokens_left = self.tokens_left(supply)?;
+                let price = mul_div_u128(self.initial_price as u128, virtual_tokens, tokens_left)?;
+                let price = mul_div_u128(price, virtual_tokens, tokens_left)?;
+                return u64::try_from(price).map_err(|_| BondingCurveError::PriceOverflow.into());
+            }
+        };
+
+        let rise = priced_supply as u128 * self.slope as u128 / self.unit();
+        u64::try_from(self.initial_price as u128 + rise)
+            .map_err(|_| BondingCurveError::PriceOverflow.into())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct InsuranceFund {
+    /// Governance authority allowed to spend the fund
+    pub authority: Pubkey,
+    /// Lifetime SOL deposited
+    pub total_deposited: u64,
+    /// Lifetime SOL paid out to insolvent curves
+    pub total_paid_out: u64,
+    /// Number of remediations performed
+    pub remediation_count: u64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl InsuranceFund {
+    pub const LEN: usize = 8 + // Discriminator
+        InsuranceFund::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct EligibilityRecord {
+    /// Attestor that issued the record
+    pub attestor: Pubkey,
+    /// Wallet the record applies to
+    pub wallet: Pubkey,
+    /// When the record was issued
+    pub issued_at: i64,
+    /// Whether the attestor has revoked the record
+    pub revoked: bool,
+    /// PDA bump seed
+    pub bump: u8,
+    /// Attestor-defined reason for the revocation (0 = none given); added
+    /// after the first records, which migrate_eligibility_record extends
+    pub rejection_code: u16,
+}
+
+impl EligibilityRecord {
+    pub const LEN: usize = 8 + // Discriminator
+        EligibilityRecord::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ReferralCode {
+    /// Mint of the NFT that carries the referral
+    pub nft_mint: Pubkey,
+    /// Wallet that created the code
+    pub creator: Pubkey,
+    /// Human-readable code
+    pub code: [u8; 16],
+    /// Lifetime SOL spent by referred buyers
+    pub referred_volume: u64,
+    /// Number of referred purchases
+    pub referral_count: u64,
+    /// Referral rewards held in this account awaiting claim_referral_rewards
+    pub rewards_unclaimed: u64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl ReferralCode {
+    pub const LEN: usize = 8 + // Discriminator
+        ReferralCode::INIT_SPACE;
+}
+
+/// Maximum number of pending launches the queue can hold
+pub const LAUNCH_QUEUE_CAPACITY: usize = 32;
+
+/// Refundable bond a creator posts into the launch queue per scheduled launch
+pub const LAUNCH_BOND_LAMPORTS: u64 = 100_000_000; // 0.1 SOL
+
+/// How long after its launch time an unactivated launch can be expired
+pub const LAUNCH_EXPIRY_SECONDS: i64 = SECONDS_PER_DAY;
+
+/// A pending launch in the queue
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct LaunchEntry {
+    /// Curve waiting to launch
+    pub bonding_curve: Pubkey,
+    /// Unix timestamp trading opens
+    pub launch_ts: i64,
+}
+
+/// Per-user, per-curve trading state
+#[account]
+#[derive(InitSpace)]
+pub struct UserTradeState {
+    /// Curve the state belongs to
+    pub bonding_curve: Pubkey,
+    /// Trader the state tracks
+    pub user: Pubkey,
+    /// Time of the user's most recent buy
+    pub last_buy_ts: i64,
+    /// Tokens bought during the curve's launch window
+    pub launch_tokens_bought: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl UserTradeState {
+    pub const LEN: usize = 8 + // Discriminator
+        UserTradeState::INIT_SPACE;
+}
+
+/// Progress of a graduating curve's tranche-by-tranche migration
+#[account]
+#[derive(InitSpace)]
+pub struct MigrationState {
+    /// Curve being migrated
+    pub bonding_curve: Pubkey,
+    /// Curve price when the migration started
+    pub final_price: u64,
+    /// Reserves to move in total
+    pub sol_total: u64,
+    /// Pool-side tokens to mint in total
+    pub tokens_total: u64,
+    /// Reserves moved so far
+    pub sol_migrated: u64,
+    /// Pool-side tokens minted so far
+    pub tokens_migrated: u64,
+    /// Tranches executed so far
+    pub tranches: u16,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl MigrationState {
+    pub const LEN: usize = 8 + // Discriminator
+        MigrationState::INIT_SPACE;
+}
+
+/// Most curves an index basket can hold
+pub const CURVE_INDEX_CAPACITY: usize = 8;
+
+/// Slots each basket curve's TWAP spans in update_curve_index; one short of
+/// MAX_PRICE_OBSERVATIONS, so a full history always covers it
+pub const CURVE_INDEX_TWAP_SLOTS: u64 = MAX_PRICE_OBSERVATIONS as u64 - 1;
+
+/// A basket of curves tracked as one volume-weighted TWAP index
+#[account]
+#[derive(InitSpace)]
+pub struct CurveIndex {
+    /// Id the index PDA is derived from
+    pub index_id: u32,
+    /// Number of curves in the basket
+    pub len: u8,
+    /// Basket curves; update_curve_index expects them in this order
+    pub curves: [Pubkey; CURVE_INDEX_CAPACITY],
+    /// Volume-weighted TWAP at the last update
+    pub last_price: u64,
+    /// Sum of last_price × seconds it was in force, for TWAPs
+    pub cumulative_price: u128,
+    /// Time of the last update (0 = never sampled)
+    pub last_update_ts: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl CurveIndex {
+    pub const LEN: usize = 8 + // Discriminator
+        CurveIndex::INIT_SPACE;
+
+    /// Take `bonding_curve` out of the basket, keeping the others in order
+    pub fn remove(&mut self, bonding_curve: &Pubkey) -> Result<()> {
+        let len = self.len as usize;
+        let position = self.curves[..len]
+            .iter()
+            .position(|curve| curve == bonding_curve)
+            .ok_or(BondingCurveError::CurveNotIndexed)?;
+        self.curves.copy_within(position + 1..len, position);
+        self.curves[len - 1] = Pubkey::default();
+        self.len -= 1;
+        Ok(())
+    }
+}
+
+/// One launch raffle ticket
+#[account]
+#[derive(InitSpace)]
+pub struct RaffleTicket {
+    /// Curve the ticket was bought on
+    pub bonding_curve: Pubkey,
+    /// Buyer entitled to the allocation or refund
+    pub owner: Pubkey,
+    /// Position in the ticket sale
+    pub index: u32,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl RaffleTicket {
+    pub const LEN: usize = 8 + // Discriminator
+        RaffleTicket::INIT_SPACE;
+}
+
+/// A sell waiting in a curve's exit queue for reserves to pay it
+#[account]
+#[derive(InitSpace)]
+pub struct ExitClaim {
+    /// Curve owing the payout
+    pub bonding_curve: Pubkey,
+    /// Seller entitled to the payout
+    pub seller: Pubkey,
+    /// Position in the curve's exit queue
+    pub position: u64,
+    /// Lamports the sale released from the curve, before the fee
+    pub sol_amount: u64,
+    /// Trading fee withheld from the payout
+    pub fee_lamports: u64,
+    /// When the sell was queued
+    pub queued_ts: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl ExitClaim {
+    pub const LEN: usize = 8 + // Discriminator
+        ExitClaim::INIT_SPACE;
+}
+
+/// Creator-funded market maker quoting inside a curve's spread
+#[account]
+#[derive(InitSpace)]
+pub struct MmVault {
+    /// Curve the vault quotes around
+    pub bonding_curve: Pubkey,
+    /// Distance of the bid and ask from the spot price, in basis points
+    pub half_spread_bps: u16,
+    /// Whether the vault currently quotes
+    pub enabled: bool,
+    /// Lamports available to buy tokens, held on this account above its rent
+    pub sol_balance: u64,
+    /// Lamports traded against the vault so far
+    pub volume_lamports: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl MmVault {
+    pub const LEN: usize = 8 + // Discriminator
+        MmVault::INIT_SPACE;
+
+    /// Add a trade to the vault's running volume
+    pub fn record_volume(&mut self, sol_amount: u64) -> Result<()> {
+        self.volume_lamports = self
+            .volume_lamports
+            .checked_add(sol_amount)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+/// A depositor's SOL committed during a curve's bootstrap phase
+#[account]
+#[derive(InitSpace)]
+pub struct BootstrapDeposit {
+    /// Curve the deposit belongs to
+    pub bonding_curve: Pubkey,
+    /// Depositor entitled to the allocation
+    pub depositor: Pubkey,
+    /// Lamports deposited
+    pub amount: u64,
+    /// Whether the allocation has been minted
+    pub claimed: bool,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl BootstrapDeposit {
+    pub const LEN: usize = 8 + // Discriminator
+        BootstrapDeposit::INIT_SPACE;
+}
+
+/// Cumulative protocol-wide counters
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct ProtocolTotals {
+    /// Curves initialized
+    pub curves_created: u64,
+    /// Curves that graduated
+    pub curves_graduated: u64,
+    /// SOL traded in both directions
+    pub volume_lamports: u64,
+    /// Trading fees charged
+    pub fees_lamports: u64,
+}
+
+/// Protocol-wide statistics, summarized into events once per epoch
+#[account]
+#[derive(InitSpace)]
+pub struct ProtocolStats {
+    /// Running totals
+    pub totals: ProtocolTotals,
+    /// Totals as of the last epoch summary
+    pub last_summary: ProtocolTotals,
+    /// Epoch of the last summary
+    pub last_summary_epoch: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl ProtocolStats {
+    pub const LEN: usize = 8 + // Discriminator
+        ProtocolStats::INIT_SPACE;
+}
+
+/// Singleton protocol settings: the admin, curve creation economics and
+/// the parameter ranges initialize_bonding_curve enforces. Clients can
+/// validate inputs against exactly what the program checks.
+#[account]
+#[derive(InitSpace)]
+pub struct GlobalConfig {
+    /// Key allowed to update the config
+    pub admin: Pubkey,
+    /// Admin named by transfer_global_config_admin, until it accepts (default = none)
+    pub pending_admin: Pubkey,
+    /// Receives curve creation fees
+    pub fee_recipient: Pubkey,
+    /// Lamports charged to create a curve (0 = free)
+    pub creation_fee_lamports: u64,
+    /// Protocol fee new curves start with
+    pub protocol_fee_bps: u16,
+    /// Share of a referred buy's SOL paid to the referrer, out of the trading fee
+    pub referral_fee_bps: u16,
+    /// Longest token name accepted
+    pub max_name_len: u16,
+    /// Longest token symbol accepted
+    pub max_symbol_len: u16,
+    /// Lowest initial price accepted
+    pub min_initial_price: u64,
+    /// Highest initial price accepted
+    pub max_initial_price: u64,
+    /// Lowest slope accepted
+    pub min_slope: u64,
+    /// Highest slope accepted
+    pub max_slope: u64,
+    /// Lowest graduation threshold accepted
+    pub min_graduation_threshold: u64,
+    /// Graduation threshold for curves that don't set one
+    pub default_graduation_threshold: u64,
+    /// MAX_FEE_BPS
+    pub max_fee_bps: u16,
+    /// PROTOCOL_MAX_SOL_PER_TRADE
+    pub max_sol_per_trade: u64,
+    /// VAULT_RENT_BUFFER
+    pub rent_buffer: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl GlobalConfig {
+    pub const LEN: usize = 8 + // Discriminator
+        GlobalConfig::INIT_SPACE;
+
+    /// Store admin-chosen settings and refresh the published constants
+    pub fn apply(&mut self, params: &GlobalConfigParams) {
+        self.fee_recipient = params.fee_recipient;
+        self.creation_fee_lamports = params.creation_fee_lamports;
+        self.protocol_fee_bps = params.protocol_fee_bps;
+        self.referral_fee_bps = params.referral_fee_bps;
+        self.max_name_len = params.max_name_len;
+        self.max_symbol_len = params.max_symbol_len;
+        self.min_initial_price = params.min_initial_price;
+        self.max_initial_price = params.max_initial_price;
+        self.min_slope = params.min_slope;
+        self.max_slope = params.max_slope;
+        self.min_graduation_threshold = params.min_graduation_threshold;
+        self.default_graduation_threshold = params.default_graduation_threshold;
+        self.max_fee_bps = MAX_FEE_BPS;
+        self.max_sol_per_trade = PROTOCOL_MAX_SOL_PER_TRADE;
+        self.rent_buffer = VAULT_RENT_BUFFER;
+    }
+
+    /// Check a new curve's pricing and naming against the configured ranges
+    pub fn check_curve_params(
+        &self,
+        initial_price: u64,
+        slope: u64,
+        name: &str,
+        symbol: &str,
+    ) -> Result<()> {
+        require!(
+            (self.min_initial_price..=self.max_initial_price).contains(&initial_price),
+            BondingCurveError::InvalidPrice
+        );
+        require!(
+            (self.min_slope..=self.max_slope).contains(&slope),
+            BondingCurveError::InvalidSlope
+        );
+        require!(name.len() <= self.max_name_len as usize, BondingCurveError::NameTooLong);
+        require!(symbol.len() <= self.max_symbol_len as usize, BondingCurveError::SymbolTooLong);
+        Ok(())
+    }
+}
+
+/// Admin-chosen GlobalConfig settings
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct GlobalConfigParams {
+    pub fee_recipient: Pubkey,
+    pub creation_fee_lamports: u64,
+    pub protocol_fee_bps: u16,
+    pub referral_fee_bps: u16,
+    pub max_name_len: u16,
+    pub max_symbol_len: u16,
+    pub min_initial_price: u64,
+    pub max_initial_price: u64,
+    pub min_slope: u64,
+    pub max_slope: u64,
+    pub min_graduation_threshold: u64,
+    pub default_graduation_threshold: u64,
+}
+
+impl GlobalConfigParams {
+    /// Ranges must be non-empty, start above zero and fit the account layouts
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.protocol_fee_bps <= MAX_PROTOCOL_FEE_BPS
+                && self.referral_fee_bps <= MAX_REFERRAL_FEE_BPS,
+            BondingCurveError::InvalidFee
+        );
+        require!(
+            self.max_name_len as usize <= MAX_NAME_LEN
+                && self.max_symbol_len as usize <= MAX_SYMBOL_LEN,
+            BondingCurveError::InvalidConfig
+        );
+        require!(
+            self.min_initial_price > 0 && self.min_initial_price <= self.max_initial_price,
+            BondingCurveError::InvalidConfig
+        );
+        require!(
+            self.min_slope > 0 && self.min_slope <= self.max_slope,
+            BondingCurveError::InvalidConfig
+        );
+        require!(
+            self.min_graduation_threshold > 0
+                && self.min_graduation_threshold <= self.default_graduation_threshold,
+            BondingCurveError::InvalidConfig
+        );
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct LaunchQueue {
+    /// Number of pending launches in entries
+    pub len: u16,
+    /// Pending launches sorted by launch_ts (earliest first)
+    pub entries: [LaunchEntry; LAUNCH_QUEUE_CAPACITY],
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl LaunchQueue {
+    pub const LEN: usize = 8 + // Discriminator
+        LaunchQueue::INIT_SPACE;
+
+    /// Insert an entry keeping the queue sorted by launch_ts
+    /// Entries with equal timestamps keep their scheduling order
+    pub fn insert(&mut self, entry: LaunchEntry) -> Result<()> {
+        let len = self.len as usize;
+        require!(len < LAUNCH_QUEUE_CAPACITY, BondingCurveError::LaunchQueueFull);
+
+        let position = self.entries[..len]
+            .iter()
+            .position(|pending| pending.launch_ts > entry.launch_ts)
+            .unwrap_or(len);
+        self.entries.copy_within(position..len, position + 1);
+        self.entries[position] = entry;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Drop every entry for `bonding_curve`, keeping the rest in order
+    pub fn remove(&mut self, bonding_curve: &Pubkey) {
+        let len = self.len as usize;
+        let mut kept = 0;
+        for i in 0..len {
+            if self.entries[i].bonding_curve != *bonding_curve {
+                self.entries[kept] = self.entries[i];
+                kept += 1;
+            }
+        }
+        self.entries[kept..len].fill(LaunchEntry::default());
+        self.len = kept as u16;
+    }
+
+    /// The earliest pending launch
+    pub fn peek(&self) -> Option<LaunchEntry> {
+        (self.len > 0).then(|| self.entries[0])
+    }
+
+    /// Remove the earliest pending launch
+    pub fn pop(&mut self) {
+        let len = self.len as usize;
+        if len == 0 {
+            return;
+        }
+        self.entries.copy_within(1..len, 0);
+        self.entries[len - 1] = LaunchEntry::default();
+        self.len -= 1;
+    }
+}
+
+/// Number of u64 words in a nonce window bitmap
+pub const NONCE_WINDOW_WORDS: usize = 4;
+/// Number of nonces tracked at once
+pub const NONCE_WINDOW_SIZE: u64 = 64 * NONCE_WINDOW_WORDS as u64;
+
+#[account]
+#[derive(InitSpace)]
+pub struct TradeNonces {
+    /// Owner of the nonce window
+    pub user: Pubkey,
+    /// Nonce represented by bit 0 of the bitmap; anything lower is expired
+    pub base: u64,
+    /// Used-nonce bitmap: bit i marks nonce base + i as consumed
+    pub bitmap: [u64; NONCE_WINDOW_WORDS],
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl TradeNonces {
+    pub const LEN: usize = 8 + // Discriminator
+        TradeNonces::INIT_SPACE;
+
+    /// Mark a nonce as used, sliding the window forward if it lies beyond it
+    pub fn consume(&mut self, nonce: u64) -> Result<()> {
+        require!(nonce >= self.base, BondingCurveError::NonceExpired);
+
+        let window_end = self.base.saturating_add(NONCE_WINDOW_SIZE);
+        if nonce >= window_end {
+            self.slide(nonce - window_end + 1);
+        }
+
+        let offset = nonce - self.base;
+        let word = (offset / 64) as usize;
+        let bit = 1u64 << (offset % 64);
+        require!(self.bitmap[word] & bit == 0, BondingCurveError::NonceAlreadyUsed);
+        self.bitmap[word] |= bit;
+        Ok(())
+    }
+
+    /// Advance the window by `shift` nonces, forgetting the oldest ones
+    fn slide(&mut self, shift: u64) {
+        let mut shifted = [0u64; NONCE_WINDOW_WORDS];
+        if shift < NONCE_WINDOW_SIZE {
+            let word_shift = (shift / 64) as usize;
+            let bit_shift = (shift % 64) as u32;
+            for (i, word) in shifted.iter_mut().enumerate() {
+                let src = i + word_shift;
+                if src < NONCE_WINDOW_WORDS {
+                    *word = self.bitmap[src] >> bit_shift;
+                    if bit_shift > 0 && src + 1 < NONCE_WINDOW_WORDS {
+                        *word |= self.bitmap[src + 1] << (64 - bit_shift);
+                    }
+                }
+            }
+        }
+        self.bitmap = shifted;
+        self.base += shift;
+    }
+}
+
+/// Purchase breakdown returned by buy_tokens
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct BuyResult {
+    /// SOL sent by the buyer
+    pub gross_sol: u64,
+    /// Fees deducted from gross_sol
+    pub fee_lamports: u64,
+    /// Charity share deducted from gross_sol
+    pub charity_lamports: u64,
+    /// SOL credited to the curve's reserves
+    pub sol_to_reserves: u64,
+    /// Tokens minted to the buyer
+    pub tokens_minted: u64,
+    /// Requested SOL left unspent by a partial fill
+    pub sol_unfilled: u64,
+}
+
+/// One curve's share of a buy_tokens_multi; fields as in buy_tokens
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct BuyLeg {
+    pub sol_amount: u64,
+    pub flags: u8,
+    pub max_entry_price: Option<u64>,
+    pub min_tokens_out: Option<u64>,
+    pub tolerance_bps: Option<u16>,
+    pub nonce: Option<u64>,
+}
+
+/// Buy preview returned by quote_with_params
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ParamsQuote {
+    /// Tokens the input would mint
+    pub tokens_out: u64,
+    /// Standard trading fee deducted from the input
+    pub fee_lamports: u64,
+    /// Spot price before the buy
+    pub price_before: u64,
+    /// Spot price after the buy
+    pub price_after: u64,
+}
+
+/// Trade preview returned by quote_buy and quote_sell
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct TradeQuote {
+    /// Tokens a buy mints, or lamports a sale pays out after fees
+    pub amount_out: u64,
+    /// Trading fee charged
+    pub fee_lamports: u64,
+    /// Charity share charged
+    pub charity_lamports: u64,
+    /// Lamports per whole token the trader pays or receives, fees included
+    pub average_price: u64,
+    /// Spot price (phase-adjusted) after the trade
+    pub price_after: u64,
+}
+
+/// Linear curve parameters returned by suggest_params
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct SuggestedParams {
+    /// Initial price in lamports per whole token
+    pub initial_price: u64,
+    /// Price increase per whole token sold
+    pub slope: u64,
+    /// Spot price at the target supply
+    pub graduation_price: u64,
+    /// Market cap at the target supply, usable as a MarketCap graduation threshold
+    pub market_cap_at_target: u64,
+    /// SOL raised by selling the target supply, usable as a SolRaised graduation threshold
+    pub sol_to_graduate: u64,
+}
+
+/// Graduation progress returned by get_progress
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct CurveProgress {
+    /// Spot price × current supply, in lamports
+    pub market_cap: u64,
+    /// SOL held in reserves
+    pub sol_raised: u64,
+    /// Metric the threshold applies to
+    pub graduation_metric: GraduationMetric,
+    /// Metric value needed to graduate
+    pub graduation_threshold: u64,
+    /// Progress towards graduation in basis points (10_000 = complete)
+    pub progress_bps: u16,
+}
+
+/// Solvency snapshot returned by get_health
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct CurveHealth {
+    /// SOL owed if the entire supply were sold back to the curve
+    pub theoretical_liability: u64,
+    /// Vault lamports above the rent-exempt floor, excluding accrued fees
+    pub actual_reserves: u64,
+    /// actual_reserves - theoretical_liability (negative = deficit)
+    pub surplus: i64,
+    /// Rent-exempt minimum the vault must keep
+    pub rent_floor: u64,
+}
+
+/// Fingerprint of critical state taken by snapshot_for_upgrade
+#[account]
+#[derive(InitSpace)]
+pub struct UpgradeSnapshot {
+    /// Config admin that took the snapshot
+    pub admin: Pubkey,
+    /// Slot the snapshot was taken in
+    pub taken_slot: u64,
+    /// SHA-256 of the global config's account data
+    pub config_hash: [u8; 32],
+    /// Curves sampled
+    pub curve_count: u8,
+    /// Sampled curves, in the order they were passed
+    pub curves: [Pubkey; MAX_UPGRADE_SAMPLE_CURVES],
+    /// SHA-256 of each sampled curve's key and fixed prefix
+    pub curve_hashes: [[u8; 32]; MAX_UPGRADE_SAMPLE_CURVES],
+    /// Slot of the last verify_after_upgrade (0 = not yet verified)
+    pub verified_slot: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl UpgradeSnapshot {
+    pub const LEN: usize = 8 + // Discriminator
+        UpgradeSnapshot::INIT_SPACE;
+}
+
+/// The creator's allocation locked by create_creator_allocation
+#[account]
+#[derive(InitSpace)]
+pub struct CreatorVesting {
+    /// Curve the allocation was minted on
+    pub bonding_curve: Pubkey,
+    /// Wallet the tokens unlock to
+    pub beneficiary: Pubkey,
+    /// Tokens locked in total
+    pub total_amount: u64,
+    /// Tokens released so far
+    pub claimed_amount: u64,
+    /// Slot the lockup started
+    pub start_slot: u64,
+    /// Slots after start_slot before anything unlocks
+    pub cliff_slots: u64,
+    /// Slots after the cliff over which the tokens unlock linearly
+    pub vesting_slots: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl CreatorVesting {
+    pub const LEN: usize = 8 + // Discriminator
+        CreatorVesting::INIT_SPACE;
+
+    /// Tokens unlocked by `slot`, claimed or not
+    pub fn vested_amount(&self, slot: u64) -> u64 {
+        let unlock_start = self.start_slot.saturating_add(self.cliff_slots);
+        if slot < unlock_start {
+            return 0;
+        }
+        if self.vesting_slots == 0 {
+            return self.total_amount;
+        }
+        let elapsed = (slot - unlock_start).min(self.vesting_slots);
+        (self.total_amount as u128 * elapsed as u128 / self.vesting_slots as u128) as u64
+    }
+}
+
+/// One recorded (slot, price, supply) point of a curve's price history
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default)]
+pub struct PriceObservation {
+    /// Slot of the last trade recorded in this observation
+    pub slot: u64,
+    /// Spot price after that trade, in lamports per whole token
+    pub price: u64,
+    /// Supply after that trade
+    pub supply: u64,
+}
+
+/// Ring buffer of a curve's recent spot prices, the source for get_twap
+#[account]
+#[derive(InitSpace)]
+pub struct PriceObservations {
+    /// Curve the observations belong to
+    pub bonding_curve: Pubkey,
+    /// Index the next new observation is written to
+    pub head: u16,
+    /// Observations stored, up to MAX_PRICE_OBSERVATIONS
+    pub count: u16,
+    /// The ring buffer; the newest observation sits just before head
+    pub observations: [PriceObservation; MAX_PRICE_OBSERVATIONS],
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl PriceObservations {
+    pub const LEN: usize = 8 + // Discriminator
+        PriceObservations::INIT_SPACE;
+
+    /// Observation `age` steps back from the newest (0 = newest)
+    fn nth_newest(&self, age: usize) -> PriceObservation {
+        let index = (self.head as usize + MAX_PRICE_OBSERVATIONS - 1 - age) % MAX_PRICE_OBSERVATIONS;
+        self.observations[index]
+    }
+
+    /// Record the state after a trade; later trades in the same slot overwrite it
+    pub fn record(&mut self, slot: u64, price: u64, supply: u64) {
+        let observation = PriceObservation { slot, price, supply };
+        if self.count > 0 && self.nth_newest(0).slot == slot {
+            let newest = (self.head as usize + MAX_PRICE_OBSERVATIONS - 1) % MAX_PRICE_OBSERVATIONS;
+            self.observations[newest] = observation;
+            return;
+        }
+        self.observations[self.head as usize] = observation;
+        self.head = ((self.head as usize + 1) % MAX_PRICE_OBSERVATIONS) as u16;
+        self.count = (self.count + 1).min(MAX_PRICE_OBSERVATIONS as u16);
+    }
+
+    /// Time-weighted average price over the window_slots slots ending at now_slot
+    pub fn twap(&self, now_slot: u64, window_slots: u64) -> Result<u64> {
+        require!(window_slots > 0, BondingCurveError::InvalidTwapWindow);
+        let start = now_slot.saturating_sub(window_slots);
+
+        // Walk back from the newest observation, weighting each price by the slots it held
+        let mut weighted: u128 = 0;
+        let mut end = now_slot;
+        for age in 0..self.count as usize {
+            let observation = self.nth_newest(age);
+            let from = observation.slot.max(start);
+            if end > from {
+                weighted += observation.price as u128 * (end - from) as u128;
+            }
+            if observation.slot <= start {
+                return Ok((weighted / window_slots as u128) as u64);
+            }
+            end = observation.slot;
+        }
+        err!(BondingCurveError::InsufficientObservations)
+    }
+}
+
+/// A curve's Merkle airdrop, set up by create_airdrop
+#[account]
+#[derive(InitSpace)]
+pub struct Airdrop {
+    pub bonding_curve: Pubkey,
+    /// Root over SHA-256(claimant || amount as u64 LE) leaves
+    pub merkle_root: [u8; 32],
+    /// Most the airdrop may mint
+    pub total_amount: u64,
+    /// Tokens claimed so far
+    pub claimed_amount: u64,
+    /// Claims made so far
+    pub claim_count: u32,
+    /// Claims close at this time, which is at or before the curve's launch_ts
+    pub claim_deadline_ts: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl Airdrop {
+    pub const LEN: usize = 8 + // Discriminator
+        Airdrop::INIT_SPACE;
+}
+
+/// Marks a claimant's airdrop as claimed
+#[account]
+#[derive(InitSpace)]
+pub struct AirdropReceipt {
+    pub airdrop: Pubkey,
+    pub claimant: Pubkey,
+    /// Tokens received
+    pub amount: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl AirdropReceipt {
+    pub const LEN: usize = 8 + // Discriminator
+        AirdropReceipt::INIT_SPACE;
+}
+
+/// Cumulative trade statistics of a curve, kept on-chain for indexer-less frontends
+#[account]
+#[derive(InitSpace)]
+pub struct CurveStats {
+    /// Curve the stats belong to
+    pub bonding_curve: Pubkey,
+    /// Lifetime SOL traded, buys and sells, in lamports
+    pub volume_lamports: u64,
+    pub buy_count: u64,
+    pub sell_count: u64,
+    /// Wallets counted on their first buy, by their buyer mark
+    pub unique_buyers: u64,
+    /// Highest spot price after any counted trade, in lamports per whole token
+    pub ath_price: u64,
+    /// Slot ath_price was reached
+    pub ath_slot: u64,
+    /// Slot of the latest counted trade (0 = none yet)
+    pub last_trade_slot: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl CurveStats {
+    pub const LEN: usize = 8 + // Discriminator
+        CurveStats::INIT_SPACE;
+
+    /// Count a trade of `volume` lamports that left the curve at `price`
+    pub fn record(
+        &mut self,
+        slot: u64,
+        price: u64,
+        volume: u64,
+        is_buy: bool,
+        first_buy: bool,
+    ) -> Result<()> {
+        self.volume_lamports = self
+            .volume_lamports
+            .checked_add(volume)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        if is_buy {
+            self.buy_count += 1;
+        } else {
+            self.sell_count += 1;
+        }
+        if first_buy {
+            self.unique_buyers += 1;
+        }
+        if price > self.ath_price {
+            self.ath_price = price;
+            self.ath_slot = slot;
+        }
+        self.last_trade_slot = slot;
+        Ok(())
+    }
+}
+
+/// Side of a limit order
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OrderSide {
+    /// Spend SOL once the price falls to the trigger
+    Buy,
+    /// Sell tokens once the price rises to the trigger
+    Sell,
+}
+
+/// A resting limit order and, for buys, its escrowed SOL
+#[account]
+#[derive(InitSpace)]
+pub struct LimitOrder {
+    pub bonding_curve: Pubkey,
+    pub owner: Pubkey,
+    /// Owner-chosen id, part of the PDA seeds
+    pub order_id: u64,
+    pub side: OrderSide,
+    /// Limit price in lamports per whole token
+    pub trigger_price: u64,
+    /// Lamports to spend (Buy) or tokens to sell (Sell)
+    pub amount: u64,
+    /// Paid to whoever executes the order
+    pub tip_lamports: u64,
+    pub created_ts: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl LimitOrder {
+    pub const LEN: usize = 8 + // Discriminator
+        LimitOrder::INIT_SPACE;
+
+    /// Fewest tokens a buy fill may mint for `curve_sol` lamports paid to the
+    /// curve before fees, so that it averages no worse than trigger_price
+    pub fn min_tokens_out(&self, curve_sol: u64, unit: u128) -> Result<u64> {
+        let min_tokens = (curve_sol as u128 * unit).div_ceil(self.trigger_price as u128);
+        u64::try_from(min_tokens).map_err(|_| error!(BondingCurveError::MathOverflow))
+    }
+
+    /// Least a sell fill may pay before fees for `tokens`, so that it
+    /// averages no worse than trigger_price
+    pub fn min_sol_out(&self, tokens: u64, unit: u128) -> Result<u64> {
+        let min_sol = tokens as u128 * self.trigger_price as u128 / unit;
+        u64::try_from(min_sol).map_err(|_| error!(BondingCurveError::MathOverflow))
+    }
+}
+
+/// A wallet barred from selling to a curve; its existence is the flag
+#[account]
+#[derive(InitSpace)]
+pub struct BlacklistEntry {
+    pub bonding_curve: Pubkey,
+    pub holder: Pubkey,
+    pub added_ts: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl BlacklistEntry {
+    pub const LEN: usize = 8 + // Discriminator
+        BlacklistEntry::INIT_SPACE;
+}
+
+/// A holder's reward stake and checkpoint on a curve
+#[account]
+#[derive(InitSpace)]
+pub struct HolderRewards {
+    pub bonding_curve: Pubkey,
+    pub holder: Pubkey,
+    /// Tokens staked in the curve's stake vault
+    pub staked: u64,
+    /// Curve's reward_per_token at the last settlement
+    pub reward_index: u128,
+    /// Lifetime lamports claimed
+    pub total_claimed: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl HolderRewards {
+    pub const LEN: usize = 8 + // Discriminator
+        HolderRewards::INIT_SPACE;
+}
+
+/// Counter of a creator's curves; one per creator, so creating curves never
+/// contends on a program-wide account
+#[account]
+#[derive(InitSpace)]
+pub struct CurveRegistry {
+    /// Creator whose curves are numbered here
+    pub creator: Pubkey,
+    /// Number of curves registered; also the next curve's index
+    pub curve_count: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl CurveRegistry {
+    pub const LEN: usize = 8 + // Discriminator
+        CurveRegistry::INIT_SPACE;
+}
+
+/// A curve's place in its creator's registry, one account per index
+#[account]
+#[derive(InitSpace)]
+pub struct CurveRegistryEntry {
+    /// Position of the curve in the creator's registry, from 0
+    pub index: u64,
+    /// The registered bonding curve
+    pub bonding_curve: Pubkey,
+    /// Its token mint
+    pub token_mint: Pubkey,
+    /// Its creator
+    pub creator: Pubkey,
+    /// Creation time, or registration time for curves backfilled with
+    /// register_curve (Unix timestamp)
+    pub created_ts: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl CurveRegistryEntry {
+    pub const LEN: usize = 8 + // Discriminator
+        CurveRegistryEntry::INIT_SPACE;
+}
+
+/// A session key allowed to trade for its owner, spending from the lamports
+/// deposited in this account above its rent
+#[account]
+#[derive(InitSpace)]
+pub struct TradingSession {
+    /// Wallet that opened the session; gets the deposit back
+    pub owner: Pubkey,
+    /// Session key allowed to sign buys and sells
+    pub authority: Pubkey,
+    /// Last slot the session can trade in
+    pub expiry_slot: u64,
+    /// Largest trade the session may make, in lamports
+    pub max_sol_per_trade: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl TradingSession {
+    pub const LEN: usize = 8 + // Discriminator
+        TradingSession::INIT_SPACE;
+
+    /// Check the session is still live and a trade of `sol_amount` within its cap
+    pub fn check_trade(&self, slot: u64, sol_amount: u64) -> Result<()> {
+        require!(slot <= self.expiry_slot, BondingCurveError::SessionExpired);
+        require!(
+            sol_amount <= self.max_sol_per_trade,
+            BondingCurveError::SessionTradeLimitExceeded
+        );
+        Ok(())
+    }
+}
+
+/**
+ * EVENTS
+ * These events are emitted for tracking and analytics
+ */
+
+#[event]
+pub struct BondingCurveInitialized {
+    pub bonding_curve: Pubkey,
+    pub token_mint: Pubkey,
+    pub creator: Pubkey,
+    pub initial_price: u64,
+    pub slope: u64,
+    pub curve_type: CurveType,
+    pub flat_threshold: u64,
+    pub phase_count: u8,
+    pub graduation_metric: GraduationMetric,
+    pub graduation_threshold: u64,
+    /// Quote mint of a quote-token curve (default pubkey = SOL)
+    pub quote_mint: Pubkey,
+    /// Index of the curve in its creator's curve registry
+    pub registry_index: u64,
+}
+
+#[event]
+pub struct TokensPurchased {
+    pub buyer: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub tokens_minted: u64,
+    /// Gross SOL sent by the buyer
+    pub sol_spent: u64,
+    /// Fees deducted from sol_spent
+    pub fee_lamports: u64,
+    /// Charity share deducted from sol_spent
+    pub charity_lamports: u64,
+    /// SOL credited to the curve's reserves
+    pub sol_to_reserves: u64,
+    pub new_supply: u64,
+    pub new_price: u64,
+    /// Holder of the referral NFT credited with the buy, if any
+    pub referrer: Option<Pubkey>,
+    /// Part of fee_lamports set aside for the referrer
+    pub referral_lamports: u64,
+    /// SOL offered but left with the buyer because the buy filled only partially
+    pub sol_unfilled: u64,
+    /// Spot price before the buy; new_price is the price after it
+    pub price_before: u64,
+    /// The curve's trade_index after this trade, one higher for every trade
+    pub trade_index: u64,
+    pub timestamp: i64,
+    /// TRADE_EVENT_VERSION this event was written with
+    pub event_version: u8,
+}
+
+#[event]
+pub struct TokensSold {
+    pub seller: Pubkey,
+    pub bonding_curve: Pubkey,
+    /// Tokens sold for SOL
+    pub tokens_burned: u64,
+    /// Extra tokens burned as sell tax, with no SOL paid for them
+    pub tax_tokens_burned: u64,
+    /// SOL paid to the seller after fees
+    pub sol_received: u64,
+    /// Fees withheld from the payout
+    pub fee_lamports: u64,
+    /// Charity share withheld from the payout
+    pub charity_lamports: u64,
+    pub new_supply: u64,
+    pub new_price: u64,
+    /// Spot price before the sale; new_price is the price after it
+    pub price_before: u64,
+    /// The curve's trade_index after this trade, one higher for every trade
+    pub trade_index: u64,
+    pub timestamp: i64,
+    /// TRADE_EVENT_VERSION this event was written with
+    pub event_version: u8,
+}
+
+#[event]
+pub struct InsuranceDeposited {
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub total_deposited: u64,
+}
+
+#[event]
+pub struct InsolvencyRemediated {
+    pub bonding_curve: Pubkey,
+    pub authority: Pubkey,
+    pub deficit: u64,
+    pub amount_paid: u64,
+    pub remaining_deficit: u64,
+    pub remediation_count: u64,
+}
+
+#[event]
+pub struct EligibilityUpdated {
+    pub attestor: Pubkey,
+    pub wallet: Pubkey,
+    pub revoked: bool,
+    pub rejection_code: u16,
+}
+
+#[event]
+pub struct DailyStatsEmitted {
+    pub bonding_curve: Pubkey,
+    pub day: i64,
+    pub volume_lamports: u64,
+    pub trade_count: u64,
+    pub fees_lamports: u64,
+    pub open_price: u64,
+    pub high_price: u64,
+    pub low_price: u64,
+    pub close_price: u64,
+}
+
+#[event]
+pub struct ReferralCredited {
+    pub referral: Pubkey,
+    pub referrer: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub buyer: Pubkey,
+    pub sol_amount: u64,
+    pub reward_lamports: u64,
+}
+
+#[event]
+pub struct ReferralRewardsClaimed {
+    pub referral: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct LaunchScheduled {
+    pub bonding_curve: Pubkey,
+    pub launch_ts: i64,
+}
+
+#[event]
+pub struct CurveLaunched {
+    pub bonding_curve: Pubkey,
+    pub launch_ts: i64,
+    pub activated_at: i64,
+}
+
+#[event]
+pub struct LaunchExpired {
+    pub bonding_curve: Pubkey,
+    pub launch_ts: i64,
+    pub cranker: Pubkey,
+    /// Launch bond refunded to the creator
+    pub bond_lamports: u64,
+}
+
+#[event]
+pub struct CharityLocked {
+    pub bonding_curve: Pubkey,
+    pub charity: Pubkey,
+    pub charity_bps: u16,
+}
+
+#[event]
+pub struct MintAuthorityHandedOff {
+    pub bonding_curve: Pubkey,
+    pub token_mint: Pubkey,
+    pub new_authority: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CompactTrade {
+    pub trader: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub is_buy: bool,
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    pub new_price: u64,
+}
+
+#[event]
+pub struct LargeTrade {
+    pub trader: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub is_buy: bool,
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    pub reserves_before: u64,
+    pub supply_before: u64,
+}
+
+#[event]
+pub struct BootstrapDeposited {
+    pub bonding_curve: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub total_deposits: u64,
+}
+
+#[event]
+pub struct BootstrapActivated {
+    pub bonding_curve: Pubkey,
+    pub total_deposits: u64,
+    pub total_tokens: u64,
+    pub clearing_price: u64,
+    pub refund_lamports: u64,
+}
+
+#[event]
+pub struct FullBackingEnabled {
+    pub bonding_curve: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RaffleDrawn {
+    pub bonding_curve: Pubkey,
+    pub ticket_count: u32,
+    pub winner_count: u32,
+    pub offset: u32,
+    pub tokens_per_winner: u64,
+}
+
+#[event]
+pub struct RaffleDrawCommitted {
+    pub bonding_curve: Pubkey,
+    pub draw_slot: u64,
+}
+
+#[event]
+pub struct ProtocolEpochSummary {
+    pub epoch: u64,
+    pub curves_created: u64,
+    pub curves_graduated: u64,
+    pub volume_lamports: u64,
+    pub fees_lamports: u64,
+}
+
+#[event]
+pub struct CurveArchived {
+    pub bonding_curve: Pubkey,
+    pub shrunk: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CurveSoldOut {
+    pub bonding_curve: Pubkey,
+    pub token_mint: Pubkey,
+    pub max_supply: u64,
+    pub sol_reserves: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CurveGraduated {
+    pub bonding_curve: Pubkey,
+    pub token_mint: Pubkey,
+    pub migration_authority: Pubkey,
+    pub sol_migrated: u64,
+    pub tokens_migrated: u64,
+    pub final_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeesClaimed {
+    pub bonding_curve: Pubkey,
+    pub recipient: Pubkey,
+    pub is_protocol: bool,
+    pub amount: u64,
+}
+
+#[event]
+pub struct CurvePauseChanged {
+    pub bonding_curve: Pubkey,
+    pub authority: Pubkey,
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyWithdrawScheduled {
+    pub bonding_curve: Pubkey,
+    pub executable_at: i64,
+}
+
+#[event]
+pub struct EmergencyWithdrawal {
+    pub bonding_curve: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyRedeemed {
+    pub bonding_curve: Pubkey,
+    pub holder: Pubkey,
+    pub tokens_burned: u64,
+    pub lamports: u64,
+}
+
+#[event]
+pub struct CurveIndexUpdated {
+    pub curve_index: Pubkey,
+    pub price: u64,
+    pub cumulative_price: u128,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GlobalConfigUpdated {
+    pub admin: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub creation_fee_lamports: u64,
+    pub protocol_fee_bps: u16,
+}
+
+#[event]
+pub struct ExitQueued {
+    pub seller: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub position: u64,
+    pub ahead_in_queue: u64,
+    pub tokens_burned: u64,
+    pub sol_owed: u64,
+    pub fee_lamports: u64,
+    pub queue_lamports: u64,
+}
+
+#[event]
+pub struct ExitClaimSettled {
+    pub seller: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub position: u64,
+    pub sol_received: u64,
+    pub fee_lamports: u64,
+    pub remaining_in_queue: u64,
+}
+
+#[event]
+pub struct MmVaultUpdated {
+    pub bonding_curve: Pubkey,
+    pub sol_balance: u64,
+    pub token_inventory: u64,
+}
+
+#[event]
+pub struct MmTradeExecuted {
+    pub bonding_curve: Pubkey,
+    pub trader: Pubkey,
+    pub is_buy: bool,
+    pub token_amount: u64,
+    pub sol_amount: u64,
+    pub spot_price: u64,
+}
+
+#[event]
+pub struct SimulationLaunched {
+    pub bonding_curve: Pubkey,
+    pub token_mint: Pubkey,
+    pub creator: Pubkey,
+    pub expiry_ts: i64,
+}
+
+#[event]
+pub struct SimulationClosed {
+    pub bonding_curve: Pubkey,
+    pub closed_by: Pubkey,
+    pub vault_lamports: u64,
+    pub refunded_lamports: u64,
+}
+
+#[event]
+pub struct SimulationRefundsOpened {
+    pub bonding_curve: Pubkey,
+    pub opened_by: Pubkey,
+    pub refund_lamports: u64,
+    pub supply: u64,
+}
+
+#[event]
+pub struct SimulationRefundClaimed {
+    pub bonding_curve: Pubkey,
+    pub holder: Pubkey,
+    pub tokens_burned: u64,
+    pub lamports: u64,
+}
+
+#[event]
+pub struct UserAccountsClosed {
+    pub bonding_curve: Pubkey,
+    pub user: Pubkey,
+    pub accounts_closed: u8,
+    pub lamports_reclaimed: u64,
+}
+
+#[event]
+pub struct CurveClosed {
+    pub bonding_curve: Pubkey,
+    pub token_mint: Pubkey,
+    pub creator: Pubkey,
+    pub vault_lamports: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GateRejected {
+    pub attestor: Pubkey,
+    pub trader: Pubkey,
+    /// The attestor's own rejection code
+    pub rejection_code: u16,
+}
+
+#[event]
+pub struct TargetReached {
+    pub bonding_curve: Pubkey,
+    pub token_mint: Pubkey,
+    pub raise_target_lamports: u64,
+    pub sol_reserves: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UpgradeSnapshotTaken {
+    pub admin: Pubkey,
+    pub slot: u64,
+    pub config_hash: [u8; 32],
+    pub curve_count: u8,
+}
+
+#[event]
+pub struct UpgradeStateMismatch {
+    pub account: Pubkey,
+    pub expected_hash: [u8; 32],
+    pub actual_hash: [u8; 32],
+}
+
+#[event]
+pub struct UpgradeVerified {
+    pub snapshot_slot: u64,
+    pub verified_slot: u64,
+    pub config_matches: bool,
+    pub curves_checked: u8,
+    pub curves_changed: u8,
+}
+
+#[event]
+pub struct CreatorAllocationCreated {
+    pub bonding_curve: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    /// Lamports the creator paid, fees included
+    pub sol_paid: u64,
+    pub start_slot: u64,
+    pub cliff_slots: u64,
+    pub vesting_slots: u64,
+}
+
+#[event]
+pub struct VestedClaimed {
+    pub bonding_curve: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub claimed_total: u64,
+    pub remaining: u64,
+}
+
+#[event]
+pub struct GlobalConfigAdminTransferStarted {
+    pub admin: Pubkey,
+    /// Default pubkey when a pending handover was cancelled
+    pub pending_admin: Pubkey,
+}
+
+#[event]
+pub struct GlobalConfigAdminTransferred {
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
+#[event]
+pub struct CurveAuthorityTransferStarted {
+    pub bonding_curve: Pubkey,
+    pub creator: Pubkey,
+    /// Default pubkey when a pending handover was cancelled
+    pub pending_creator: Pubkey,
+}
+
+#[event]
+pub struct CurveAuthorityTransferred {
+    pub bonding_curve: Pubkey,
+    pub previous_creator: Pubkey,
+    pub new_creator: Pubkey,
+}
+
+#[event]
+pub struct CurveParamsUpdated {
+    pub bonding_curve: Pubkey,
+    pub creator_fee_bps: u16,
+    pub sell_burn_tax_bps: u16,
+    pub max_sol_per_trade: u64,
+    pub max_price_impact_bps: u16,
+    pub large_trade_bps: u16,
+    pub min_hold_seconds: i64,
+}
+
+#[event]
+pub struct TokensBurnedPermanently {
+    pub bonding_curve: Pubkey,
+    pub holder: Pubkey,
+    pub amount: u64,
+    /// Lifetime tokens burned this way, this burn included
+    pub virtual_burned: u64,
+    /// Circulating supply after the burn
+    pub new_supply: u64,
+    pub sol_reserves: u64,
+    /// Lamports of reserves per whole token after the burn
+    pub floor_price: u64,
+}
+
+#[event]
+pub struct ReservesSeeded {
+    pub bonding_curve: Pubkey,
+    pub creator: Pubkey,
+    pub amount: u64,
+    /// Unspent seed after the deposit
+    pub seeded_reserves: u64,
+}
+
+#[event]
+pub struct SeededReservesWithdrawn {
+    pub bonding_curve: Pubkey,
+    pub creator: Pubkey,
+    pub amount: u64,
+    /// Unspent seed left in the vault
+    pub seeded_reserves: u64,
+}
+
+#[event]
+pub struct AirdropCreated {
+    pub bonding_curve: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub total_amount: u64,
+    pub claim_deadline_ts: i64,
+}
+
+#[event]
+pub struct AirdropClaimed {
+    pub bonding_curve: Pubkey,
+    pub claimant: Pubkey,
+    pub amount: u64,
+    /// Tokens claimed from the airdrop so far, this claim included
+    pub claimed_total: u64,
+    pub new_supply: u64,
+}
+
+#[event]
+pub struct LimitOrderPlaced {
+    pub limit_order: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub owner: Pubkey,
+    pub side: OrderSide,
+    pub trigger_price: u64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct LimitOrderExecuted {
+    pub limit_order: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub owner: Pubkey,
+    pub side: OrderSide,
+    /// Tokens minted (Buy) or burned (Sell), sell tax included
+    pub tokens: u64,
+    /// SOL paid (Buy) or the gross payout (Sell), fees included
+    pub sol_amount: u64,
+    pub fee_lamports: u64,
+    pub cranker: Pubkey,
+    pub tip_lamports: u64,
+}
+
+#[event]
+pub struct LimitOrderCancelled {
+    pub limit_order: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct HolderFrozen {
+    pub bonding_curve: Pubkey,
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+    /// false when the account was thawed
+    pub frozen: bool,
+}
+
+#[event]
+pub struct HolderBlacklisted {
+    pub bonding_curve: Pubkey,
+    pub holder: Pubkey,
+    /// false when the holder was taken off the blacklist
+    pub blacklisted: bool,
+}
+
+#[event]
+pub struct BuybackExecuted {
+    pub bonding_curve: Pubkey,
+    pub cranker: Pubkey,
+    /// Protocol fees moved into the reserves
+    pub sol_spent: u64,
+    /// Tokens the SOL bought at the current supply, burned on the spot
+    pub tokens_burned: u64,
+    /// Lifetime burned tokens, this buyback included
+    pub virtual_burned: u64,
+    pub sol_reserves: u64,
+    /// Lamports of reserves per whole token after the buyback
+    pub floor_price: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct CurveMigrated {
+    pub bonding_curve: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub old_len: u64,
+    pub new_len: u64,
+}
+
+#[event]
+pub struct HolderRewardsClaimed {
+    pub bonding_curve: Pubkey,
+    pub holder: Pubkey,
+    pub amount: u64,
+    /// Tokens staked, earning until the next claim
+    pub staked: u64,
+    pub reward_per_token: u128,
+}
+
+#[event]
+pub struct RewardsStakeChanged {
+    pub bonding_curve: Pubkey,
+    pub holder: Pubkey,
+    /// Holder's stake after the change
+    pub staked: u64,
+    /// Curve's total stake after the change
+    pub total_staked: u64,
+    /// Rewards paid out before the stake changed
+    pub rewards_paid: u64,
+}
+
+#[event]
+pub struct SessionCreated {
+    pub session: Pubkey,
+    pub owner: Pubkey,
+    pub authority: Pubkey,
+    pub expiry_slot: u64,
+    pub max_sol_per_trade: u64,
+    pub deposit_lamports: u64,
+}
+
+#[event]
+pub struct SessionRevoked {
+    pub session: Pubkey,
+    pub owner: Pubkey,
+    pub authority: Pubkey,
+    /// Deposit returned to the owner
+    pub refunded_lamports: u64,
+    /// Session account rent returned to the owner
+    pub rent_lamports: u64,
+}
+
+/**
+ * ERROR CODES
+ * Custom error types for better error handling
+ */
+
+#[error_code]
+pub enum BondingCurveError {
+    #[msg("Invalid price parameter")]
+    InvalidPrice,
+    #[msg("Invalid slope parameter")]
+    InvalidSlope,
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    #[msg("Token name too long")]
+    NameTooLong,
+    #[msg("Token symbol too long")]
+    SymbolTooLong,
+    #[msg("Insufficient SOL for purchase")]
+    InsufficientSol,
+    #[msg("Insufficient token supply")]
+    InsufficientSupply,
+    #[msg("Insufficient SOL reserves")]
+    InsufficientReserves,
+    #[msg("Supply overflow")]
+    SupplyOverflow,
+    #[msg("Supply underflow")]
+    SupplyUnderflow,
+    #[msg("Reserves overflow")]
+    ReservesOverflow,
+    #[msg("Reserves underflow")]
+    ReservesUnderflow,
+    #[msg("Price calculation overflow")]
+    PriceOverflow,
+    #[msg("Math overflow in calculations")]
+    MathOverflow,
+    #[msg("Invalid curve threshold")]
+    InvalidThreshold,
+    #[msg("Unauthorized")]
+    Unauthorized,
+    #[msg("Curve has no deficit to remediate")]
+    NoDeficit,
+    #[msg("Insufficient insurance funds")]
+    InsufficientInsuranceFunds,
+    #[msg("Too many sale phases")]
+    TooManyPhases,
+    #[msg("Sale phases must start in increasing order")]
+    InvalidPhaseSchedule,
+    #[msg("Invalid fee parameter")]
+    InvalidFee,
+    #[msg("Trading has not started yet")]
+    TradingNotStarted,
+    #[msg("Trade not allowed in the current sale phase")]
+    PhaseTradingDisabled,
+    #[msg("Buy exceeds the current sale phase limit")]
+    PhaseBuyLimitExceeded,
+    #[msg("Supply cap for the current sale phase reached")]
+    PhaseSupplyCapReached,
+    #[msg("Invalid price multiplier")]
+    InvalidPriceMultiplier,
+    #[msg("Wallet is not eligible to trade this curve")]
+    NotEligible,
+    #[msg("No unreported daily stats available")]
+    DailyStatsUnavailable,
+    #[msg("Referral code too long")]
+    ReferralCodeTooLong,
+    #[msg("Invalid referral")]
+    InvalidReferral,
+    #[msg("Cannot refer yourself")]
+    SelfReferral,
+    #[msg("Trade exceeds the maximum SOL per transaction")]
+    TradeSizeExceeded,
+    #[msg("Invalid per-trade limit")]
+    InvalidTradeLimit,
+    #[msg("Curve is not active")]
+    CurveNotActive,
+    #[msg("Curve has already launched")]
+    CurveAlreadyLaunched,
+    #[msg("Launch time must be in the future")]
+    InvalidLaunchTime,
+    #[msg("Launch queue is full")]
+    LaunchQueueFull,
+    #[msg("Launch queue is empty")]
+    LaunchQueueEmpty,
+    #[msg("Curve is not next in the launch queue")]
+    NotNextLaunch,
+    #[msg("Launch time has not been reached")]
+    LaunchNotDue,
+    #[msg("Invalid fee bootstrap window")]
+    InvalidBootstrapWindow,
+    #[msg("Invalid sell burn tax")]
+    InvalidBurnTax,
+    #[msg("Invalid graduation threshold")]
+    InvalidGraduationThreshold,
+    #[msg("Charity split is locked")]
+    CharityLocked,
+    #[msg("Invalid charity share")]
+    InvalidCharityShare,
+    #[msg("Charity account does not match the curve")]
+    InvalidCharityAccount,
+    #[msg("Mint authority has already been handed off")]
+    MintAuthorityHandedOff,
+    #[msg("Shrinking a curve that still holds its mint authority needs the token mint and token program")]
+    MissingMintAccount,
+    #[msg("Curve has not reached its graduation target")]
+    NotGraduated,
+    #[msg("Mint authority can only be revoked or handed to the governance PDA")]
+    InvalidMintAuthorityTarget,
+    #[msg("Invalid large trade threshold")]
+    InvalidLargeTradeThreshold,
+    #[msg("Bootstrap phase is not open")]
+    BootstrapNotOpen,
+    #[msg("Bootstrap allocation already claimed")]
+    BootstrapAlreadyClaimed,
+    #[msg("Fully backed curves cannot divert buy SOL from reserves")]
+    FullyBacked,
+    #[msg("Invalid hold period")]
+    InvalidHoldPeriod,
+    #[msg("Trade state missing or does not match the trader")]
+    InvalidTradeState,
+    #[msg("Tokens are still inside the hold period")]
+    HoldPeriodActive,
+    #[msg("Spot price moved past the order's price bound")]
+    PriceBoundExceeded,
+    #[msg("Invalid raffle settings")]
+    InvalidRaffle,
+    #[msg("Raffle is not open")]
+    RaffleNotOpen,
+    #[msg("Invalid reserve buffer")]
+    InvalidReserveBuffer,
+    #[msg("No excess SOL to withdraw")]
+    NoExcessSol,
+    #[msg("Protocol summary already emitted this epoch")]
+    EpochSummaryNotDue,
+    #[msg("Slippage tolerance exceeded")]
+    SlippageExceeded,
+    #[msg("Curve is archived and read-only")]
+    CurveArchived,
+    #[msg("Only graduated curves can be archived")]
+    CurveNotArchivable,
+    #[msg("Too many trades on this curve in the current slot")]
+    RateLimited,
+    #[msg("No fees to claim")]
+    NoFeesToClaim,
+    #[msg("Targets can't be reached with valid curve parameters")]
+    UnreachableTargets,
+    #[msg("Every migration tranche has already moved")]
+    MigrationComplete,
+    #[msg("Migration still has tranches left to move")]
+    MigrationIncomplete,
+    #[msg("Creator powers lapsed after the inactivity timeout")]
+    CreatorPowersLapsed,
+    #[msg("Metadata URI too long")]
+    UriTooLong,
+    #[msg("Tolerance must be at most 10000 basis points")]
+    InvalidTolerance,
+    #[msg("Token program does not match the one recorded for this curve")]
+    TokenProgramMismatch,
+    #[msg("Purchase would mint past the curve's max supply")]
+    MaxSupplyExceeded,
+    #[msg("Curve has sold out its max supply")]
+    SoldOut,
+    #[msg("Trading on this curve is paused")]
+    CurvePaused,
+    #[msg("Curve is not paused")]
+    CurveNotPaused,
+    #[msg("No emergency withdrawal is scheduled, or its timelock hasn't run out")]
+    EmergencyWithdrawNotReady,
+    #[msg("Unknown flag bits set")]
+    InvalidFlags,
+    #[msg("Index basket is full")]
+    IndexFull,
+    #[msg("Curve is already in the index basket")]
+    CurveAlreadyIndexed,
+    #[msg("Accounts passed don't match the index basket")]
+    IndexBasketMismatch,
+    #[msg("Curve is not in the index basket")]
+    CurveNotIndexed,
+    #[msg("Global config ranges are empty, zero or exceed the program's limits")]
+    InvalidConfig,
+    #[msg("Reserves can pay this sell directly; use sell_tokens")]
+    ExitQueueNotNeeded,
+    #[msg("Only the exit at the front of the queue can be settled")]
+    ExitClaimNotAtHead,
+    #[msg("Queued exits must be settled first")]
+    ExitQueuePending,
+    #[msg("Trading has not opened yet")]
+    LaunchNotStarted,
+    #[msg("Buy exceeds the wallet's launch window allowance")]
+    LaunchBuyLimitExceeded,
+    #[msg("Launch window length and per-wallet cap must be set together")]
+    InvalidLaunchWindow,
+    #[msg("Market-maker vault is not quoting")]
+    MmVaultDisabled,
+    #[msg("Market-maker quote would not be inside the curve's spread")]
+    MmQuoteOutsideSpread,
+    #[msg("Market-maker vault lacks the inventory for this trade")]
+    MmInventoryExhausted,
+    #[msg("Not allowed on a simulation curve, or the curve is not a simulation")]
+    SimulationCurve,
+    #[msg("Simulation has not expired yet")]
+    SimulationNotExpired,
+    #[msg("Simulation duration must be positive and at most MAX_SIMULATION_SECONDS")]
+    InvalidSimulationDuration,
+    #[msg("Only allowlisted wallets can buy right now")]
+    WhitelistOnly,
+    #[msg("Invalid allowlist proof")]
+    NotWhitelisted,
+    #[msg("Allowlist must end at a future slot")]
+    InvalidWhitelistEnd,
+    #[msg("Sell payout is below the curve's dust threshold")]
+    DustTrade,
+    #[msg("Mint decimals exceed MAX_TOKEN_DECIMALS")]
+    InvalidDecimals,
+    #[msg("None of the passed accounts are empty yet")]
+    NoEmptyAccounts,
+    #[msg("Curve still has supply, reserves or market-maker funds and can't be closed")]
+    CurveNotClosable,
+    #[msg("Creator or protocol fees must be claimed first")]
+    FeesUnclaimed,
+    #[msg("An initial buy needs the creator's token account")]
+    MissingInitialBuyAccount,
+    #[msg("The curve's raise target has been reached; buys are closed")]
+    RaiseTargetReached,
+    #[msg("Raise target must be above the current reserves")]
+    InvalidRaiseTarget,
+    #[msg("Upgrade sample curves don't match the snapshot")]
+    UpgradeSampleMismatch,
+    #[msg("A creator allocation needs a cliff or a vesting period")]
+    InvalidVestingSchedule,
+    #[msg("No vested tokens to claim yet")]
+    NothingVested,
+    #[msg("Quote-token curves need their quote vault, mint and token accounts")]
+    MissingQuoteAccount,
+    #[msg("Quote mint does not match the curve")]
+    QuoteMintMismatch,
+    #[msg("This action is only available on SOL curves")]
+    QuoteCurveUnsupported,
+    #[msg("This curve trades against SOL, not a quote token")]
+    NotQuoteCurve,
+    #[msg("Trade moves the price by more than the curve allows")]
+    PriceImpactExceeded,
+    #[msg("Price impact cap must be 0 or 500 to 10000 bps, and can only loosen once tokens are out")]
+    InvalidPriceImpact,
+    #[msg("Sell tax ramp must decay from at most MAX_SELL_TAX_BPS down to its final rate")]
+    InvalidSellTaxRamp,
+    #[msg("TWAP window must be at least one slot")]
+    InvalidTwapWindow,
+    #[msg("Price history doesn't cover the requested window")]
+    InsufficientObservations,
+    #[msg("The new creator must differ from the current one")]
+    InvalidNewCreator,
+    #[msg("Buy legs don't match the remaining accounts")]
+    InvalidBuyLegs,
+    #[msg("Amount exceeds the unspent seeded reserves")]
+    InsufficientSeededReserves,
+    #[msg("Seeded reserves are still needed to back the curve")]
+    SeedStillNeeded,
+    #[msg("Airdrop proof doesn't match the root")]
+    InvalidAirdropProof,
+    #[msg("Claim exceeds what is left of the airdrop")]
+    AirdropExhausted,
+    #[msg("Sell limit orders need their token escrow account")]
+    MissingOrderEscrow,
+    #[msg("The curve price hasn't reached the order's trigger price")]
+    LimitPriceNotReached,
+    #[msg("Trader is blacklisted on this curve")]
+    Blacklisted,
+    #[msg("Buybacks are turned off for this curve")]
+    BuybacksDisabled,
+    #[msg("The buyback window hasn't passed since the last buyback")]
+    BuybackTooSoon,
+    #[msg("Trading is closed on this curve at this time")]
+    TradingClosed,
+    #[msg("Trading schedule must end after it starts and use daily times within a day")]
+    InvalidTradingSchedule,
+    #[msg("Strict mode rejects this transaction: several trades on the curve or an unknown CPI caller")]
+    BundleRejected,
+    #[msg("Account is not a bonding curve")]
+    NotABondingCurve,
+    #[msg("Curve is already on the current layout version")]
+    CurveUpToDate,
+    #[msg("Account is not an eligibility record")]
+    NotAnEligibilityRecord,
+    #[msg("Eligibility record is already on the current layout")]
+    EligibilityRecordUpToDate,
+    #[msg("Settling as wSOL needs the native mint, the seller's wSOL account and the token program")]
+    MissingWsolAccount,
+    #[msg("Session expiry must be in the future")]
+    InvalidSessionExpiry,
+    #[msg("Trading session has expired")]
+    SessionExpired,
+    #[msg("Trade exceeds the session's per-trade limit")]
+    SessionTradeLimitExceeded,
+    #[msg("Signer is not the session's key")]
+    SessionKeyMismatch,
+    #[msg("Session deposit can't cover this trade")]
+    InsufficientSessionDeposit,
+    #[msg("Session sales are paid into the session deposit, not as wSOL")]
+    SessionPaysDeposit,
+    #[msg("An emergency redemption is open on this curve")]
+    EmergencyRedemptionOpen,
+    #[msg("No emergency redemption is open on this curve")]
+    NoEmergencyRedemption,
+    #[msg("Simulation holders still have refunds or queued exits to settle")]
+    SimulationRefundsOutstanding,
+    #[msg("Fill exceeds the market-maker vault's per-trade cap")]
+    MmFillTooLarge,
+    #[msg("Session trades must use a token account of the session's owner")]
+    InvalidSessionTokenAccount,
+    #[msg("Airdrops only run before launch, until their claim deadline")]
+    AirdropClosed,
+    #[msg("Airdrop claim deadline must be in the future and no later than launch")]
+    InvalidAirdropDeadline,
+    #[msg("Unstaking more than is staked")]
+    InsufficientStake,
+    #[msg("Holder reward stakes must be withdrawn first")]
+    RewardsStaked,
+    #[msg("This curve records every trade; pass its price observations")]
+    PriceObservationsRequired,
+    #[msg("Curve is already in its creator's curve registry")]
+    CurveAlreadyRegistered,
+    #[msg("This curve keeps trade stats; pass its stats account")]
+    CurveStatsRequired,
+    #[msg("Pass the buyer's mark (pda::buyer_mark_address) on curves that keep trade stats")]
+    InvalidBuyerMark,
+    #[msg("A registered curve closes only together with its own registry entry")]
+    RegistryEntryRequired,
+    #[msg("Dust threshold exceeds MAX_DUST_THRESHOLD_LAMPORTS")]
+    InvalidDustThreshold,
+    #[msg("Curve has no launch in the launch queue")]
+    LaunchNotScheduled,
+    #[msg("Launch is not past LAUNCH_EXPIRY_SECONDS after its launch time")]
+    LaunchNotExpired,
+    #[msg("Raffle draw slot already committed")]
+    RaffleDrawAlreadyCommitted,
+    #[msg("Raffle draw slot not committed or not reached yet")]
+    RaffleDrawNotReady,
+    #[msg("Raffle draw slot's hash is unavailable; commit a new draw slot")]
+    RaffleDrawSlotUnavailable,
+    #[msg("Every curve in the index basket is closed")]
+    NoLiveIndexCurves,
+    #[msg("Quote mints with a transfer fee are not supported")]
+    QuoteMintTransferFee,
+    #[msg("Raise target is already set")]
+    RaiseTargetAlreadySet,
+    #[msg("Nonce is older than the replay window")]
+    NonceExpired,
+    #[msg("Nonce has already been used")]
+    NonceAlreadyUsed,
+    #[msg("Session trades must carry a nonce")]
+    TradeNonceRequired,
+    #[msg("Raffle sold no tickets; there is nothing to draw")]
+    RaffleNoTickets,
+    #[msg("Simulation refunds are not open yet; close the expired simulation first")]
+    SimulationRefundsNotOpen,
+    #[msg("The new admin must differ from the current one")]
+    InvalidNewAdmin,
+}
+
+/*
+ * HELPER FUNCTIONS
+ * Mathematical functions for bonding curve calculations
+ */
+
+/// Enforce the curve's eligibility gate, if it has an attestor configured
+fn check_eligibility(
+    curve: &BondingCurve,
+    trader: Pubkey,
+    record: &Option<Account<EligibilityRecord>>,
+) -> Result<()> {
+    if curve.eligibility_attestor == Pubkey::default() {
+        return Ok(());
+    }
+
+    let record = record.as_ref().ok_or(BondingCurveError::NotEligible)?;
+    require!(
+        record.attestor == curve.eligibility_attestor && record.wallet == trader,
+        BondingCurveError::NotEligible
+    );
+    if record.revoked {
+        if record.rejection_code == 0 {
+            return err!(BondingCurveError::NotEligible);
+        }
+        // The event and log survive in the failed transaction's logs
+        emit!(GateRejected {
+            attestor: record.attestor,
+            trader,
+            rejection_code: record.rejection_code,
+        });
+        msg!("Rejected by gate {}: code {}", record.attestor, record.rejection_code);
+        return Err(ProgramError::Custom(GATE_ERROR_BASE + record.rejection_code as u32).into());
+    }
+    Ok(())
+}
+
+/// The trader's state on `curve`, required whenever the curve enforces a hold
+/// period or a launch-window buy limit
+fn checked_trade_state<'a, 'info>(
+    state: &'a mut Option<Account<'info, UserTradeState>>,
+    curve: &Account<BondingCurve>,
+    trader: Pubkey,
+    slot: u64,
+) -> Result<Option<&'a mut Account<'info, UserTradeState>>> {
+    let Some(state) = state.as_mut() else {
+        require!(
+            curve.min_hold_seconds == 0 && !curve.in_launch_window(slot),
+            BondingCurveError::InvalidTradeState
+        );
+        return Ok(None);
+    };
+    require!(
+        state.bonding_curve == curve.key() && state.user == trader,
+        BondingCurveError::InvalidTradeState
+    );
+    Ok(Some(state))
+}
+
+/// Restart the trader's hold period after a buy, and count launch-window buys
+fn record_buy_in_trade_state(
+    state: &mut Option<Account<UserTradeState>>,
+    curve: &Account<BondingCurve>,
+    trader: Pubkey,
+    now: i64,
+    slot: u64,
+    tokens: u64,
+) -> Result<()> {
+    if let Some(state) = checked_trade_state(state, curve, trader, slot)? {
+        state.last_buy_ts = now;
+        if curve.in_launch_window(slot) {
+            state.launch_tokens_bought = state
+                .launch_tokens_bought
+                .checked_add(tokens)
+                .ok_or(BondingCurveError::MathOverflow)?;
+        }
+    }
+    Ok(())
+}
+
+/// Tokens the trader may still buy in the curve's launch window (unlimited
+/// outside it); buys before launch_start_slot are refused
+fn launch_allowance(
+    state: &Option<Account<UserTradeState>>,
+    curve: &Account<BondingCurve>,
+    trader: Pubkey,
+    slot: u64,
+) -> Result<u64> {
+    require!(slot >= curve.launch_start_slot, BondingCurveError::LaunchNotStarted);
+    if !curve.in_launch_window(slot) {
+        return Ok(u64::MAX);
+    }
+
+    let state = state.as_ref().ok_or(BondingCurveError::InvalidTradeState)?;
+    require!(
+        state.bonding_curve == curve.key() && state.user == trader,
+        BondingCurveError::InvalidTradeState
+    );
+    let allowance = curve
+        .max_buy_per_wallet_during_launch
+        .saturating_sub(state.launch_tokens_bought);
+    require!(allowance > 0, BondingCurveError::LaunchBuyLimitExceeded);
+    Ok(allowance)
+}
+
+/// Enforce the curve's blacklist on a sell: while the curve blacklists
+/// anyone, the trader's blacklist address must be passed and hold no entry
+fn check_not_blacklisted(
+    curve: &Account<BondingCurve>,
+    trader: Pubkey,
+    entry: &Option<UncheckedAccount>,
+) -> Result<()> {
+    if curve.blacklisted_holders == 0 {
+        return Ok(());
+    }
+
+    let entry = entry.as_ref().ok_or(BondingCurveError::Blacklisted)?;
+    require_keys_eq!(
+        entry.key(),
+        pda::blacklist_address(&curve.key(), &trader).0,
+        BondingCurveError::Blacklisted
+    );
+    require!(entry.data_is_empty(), BondingCurveError::Blacklisted);
+    Ok(())
+}
+
+/// Consume a trade's nonce from the trader's window. Session trades, which a
+/// relayer submits for the owner, must carry one; direct trades may
+fn check_trade_nonce(
+    trade_nonces: &mut Option<Account<TradeNonces>>,
+    trader: Pubkey,
+    nonce: Option<u64>,
+    relayed: bool,
+) -> Result<()> {
+    let Some(nonce) = nonce else {
+        require!(!relayed, BondingCurveError::TradeNonceRequired);
+        return Ok(());
+    };
+    let trade_nonces = trade_nonces.as_mut().ok_or(BondingCurveError::TradeNonceRequired)?;
+    require_keys_eq!(trade_nonces.user, trader, BondingCurveError::Unauthorized);
+    trade_nonces.consume(nonce)
+}
+
+/// Enforce the curve's strict mode on a trade: the top-level instruction must
+/// be this program's or the trusted program's, and no other instruction of
+/// either may touch the curve in the same transaction
+fn check_strict_mode(
+    curve: &Account<BondingCurve>,
+    instructions_sysvar: &Option<UncheckedAccount>,
+) -> Result<()> {
+    if !curve.strict_mode {
+        return Ok(());
+    }
+
+    let sysvar = instructions_sysvar.as_ref().ok_or(BondingCurveError::BundleRejected)?;
+    let trusted = |program_id: &Pubkey| {
+        *program_id == crate::ID
+            || (curve.strict_trusted_program != Pubkey::default()
+                && *program_id == curve.strict_trusted_program)
+    };
+
+    // Called by CPI: the outermost instruction names the calling program
+    let current_index = sysvar_instructions::load_current_index_checked(sysvar)? as usize;
+    if get_stack_height() > TRANSACTION_LEVEL_STACK_HEIGHT {
+        let outer = sysvar_instructions::load_instruction_at_checked(current_index, sysvar)?;
+        require!(
+            outer.program_id != crate::ID && trusted(&outer.program_id),
+            BondingCurveError::BundleRejected
+        );
+    }
+
+    let curve_key = curve.key();
+    let mut index = 0;
+    while let Ok(ix) = sysvar_instructions::load_instruction_at_checked(index, sysvar) {
+        let touches_curve = ix.accounts.iter().any(|meta| meta.pubkey == curve_key);
+        require!(
+            index == current_index || !(trusted(&ix.program_id) && touches_curve),
+            BondingCurveError::BundleRejected
+        );
+        index += 1;
+    }
+    Ok(())
+}
+
+/// Apply the curve's dust policy to a sale paying out `payout` lamports:
+/// dust is refused, or swept into the fees instead of paying out
+/// Returns (payout, fee_lamports)
+fn apply_dust_policy(curve: &BondingCurve, payout: u64, fee_lamports: u64) -> Result<(u64, u64)> {
+    if payout >= curve.dust_threshold {
+        return Ok((payout, fee_lamports));
+    }
+    require!(curve.dust_policy == DustPolicy::SweepToFees, BondingCurveError::DustTrade);
+    Ok((0, fee_lamports + payout))
+}
+
+/// Enforce the curve's hold period on a sell
+fn check_hold_period(
+    state: &Option<Account<UserTradeState>>,
+    curve: &Account<BondingCurve>,
+    trader: Pubkey,
+    now: i64,
+) -> Result<()> {
+    if curve.min_hold_seconds == 0 {
+        return Ok(());
+    }
+
+    let state = state.as_ref().ok_or(BondingCurveError::InvalidTradeState)?;
+    require!(
+        state.bonding_curve == curve.key() && state.user == trader,
+        BondingCurveError::InvalidTradeState
+    );
+    require!(
+        now >= state.last_buy_ts.saturating_add(curve.min_hold_seconds),
+        BondingCurveError::HoldPeriodActive
+    );
+    Ok(())
+}
+
+/// Add a trade to the protocol-wide totals, if the stats account was passed;
+/// simulation curves are never counted
+fn record_protocol_trade(
+    stats: &mut Option<Account<ProtocolStats>>,
+    curve: &BondingCurve,
+    volume: u64,
+    fees: u64,
+) -> Result<()> {
+    if curve.is_simulation() {
+        return Ok(());
+    }
+    if let Some(stats) = stats.as_mut() {
+        stats.totals.volume_lamports = stats
+            .totals
+            .volume_lamports
+            .checked_add(volume)
+            .ok_or(BondingCurveError::MathOverflow)?;
+        stats.totals.fees_lamports = stats
+            .totals
+            .fees_lamports
+            .checked_add(fees)
+            .ok_or(BondingCurveError::MathOverflow)?;
+    }
+    Ok(())
+}
+
+/// Credit a purchase to a referral code, validating the referrer by NFT ownership
+fn credit_referral(
+    referral: &mut Option<Account<ReferralCode>>,
+    referrer_nft_account: &Option<InterfaceAccount<TokenAccount>>,
+    bonding_curve: Pubkey,
+    buyer: Pubkey,
+    sol_amount: u64,
+    reward_lamports: u64,
+) -> Result<Option<Pubkey>> {
+    let (referral, nft_account) = match (referral.as_mut(), referrer_nft_account.as_ref()) {
+        (None, None) => return Ok(None),
+        (Some(referral), Some(nft_account)) => (referral, nft_account),
+        _ => return err!(BondingCurveError::InvalidReferral),
+    };
+
+    // Whoever holds the NFT right now is the referrer
+    require!(
+        nft_account.mint == referral.nft_mint && nft_account.amount == 1,
+        BondingCurveError::InvalidReferral
+    );
+    require!(nft_account.owner != buyer, BondingCurveError::SelfReferral);
+
+    referral.referred_volume = referral
+        .referred_volume
+        .checked_add(sol_amount)
+        .ok_or(BondingCurveError::MathOverflow)?;
+    referral.referral_count = referral
+        .referral_count
+        .checked_add(1)
+        .ok_or(BondingCurveError::MathOverflow)?;
+    referral.rewards_unclaimed = referral
+        .rewards_unclaimed
+        .checked_add(reward_lamports)
+        .ok_or(BondingCurveError::MathOverflow)?;
+
+    emit!(ReferralCredited {
+        referral: referral.key(),
+        referrer: nft_account.owner,
+        bonding_curve,
+        buyer,
+        sol_amount,
+        reward_lamports,
+    });
+    Ok(Some(nft_account.owner))
+}
+
+/// Referrer's share of a buy's trading fee (0 for buys without a referral)
+fn referral_reward(
+    referral: &Option<Account<ReferralCode>>,
+    config: &GlobalConfig,
+    gross_sol: u64,
+    fee_lamports: u64,
+) -> Result<u64> {
+    if referral.is_none() {
+        return Ok(0);
+    }
+    Ok(calculate_fee(gross_sol, config.referral_fee_bps)?.min(fee_lamports))
+}
+
+/// The charity account passed to a trade, checked against the curve's charity
+fn checked_charity_account<'info>(
+    charity: &Option<UncheckedAccount<'info>>,
+    curve: &BondingCurve,
+) -> Result<AccountInfo<'info>> {
+    let charity = charity.as_ref().ok_or(BondingCurveError::InvalidCharityAccount)?;
+    require_keys_eq!(charity.key(), curve.charity, BondingCurveError::InvalidCharityAccount);
+    Ok(charity.to_account_info())
+}
+
+/// Lamports a curve's SOL vault keeps outside its reserves: rent exemption plus the buffer
+fn vault_rent_floor() -> Result<u64> {
+    Ok(Rent::get()?.minimum_balance(0) + VAULT_RENT_BUFFER)
+}
+
+/// Spot price as quoted to traders: the curve price adjusted by the active phase
+fn phase_price(price: u64, phase: Option<SalePhase>) -> Result<u64> {
+    match phase {
+        Some(phase) => phase.apply_price_multiplier(price),
+        None => Ok(price),
+    }
+}
+
+/// Fee in lamports charged on `amount` at `fee_bps`
+pub fn calculate_fee(amount: u64, fee_bps: u16) -> Result<u64> {
+    let fee = (amount as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(BondingCurveError::MathOverflow)?
+        / 10_000;
+    u64::try_from(fee).map_err(|_| BondingCurveError::MathOverflow.into())
+}
+
+/// Smallest gross amount that leaves at least `net` after a fee of `fee_bps`
+fn gross_up_for_fee(net: u64, fee_bps: u16) -> Result<u64> {
+    require!(fee_bps < 10_000, BondingCurveError::InvalidFee);
+    let gross = (net as u128 * 10_000).div_ceil(10_000 - fee_bps as u128);
+    u64::try_from(gross).map_err(|_| BondingCurveError::MathOverflow.into())
+}
+
+/// Compare a curve's theoretical liability against the lamports held in its vault
+fn calculate_health(curve: &BondingCurve, vault_lamports: u64) -> Result<CurveHealth> {
+    let theoretical_liability = calculate_sol_for_curve(&curve.params(), curve.current_supply, 0)?;
+
+    // Fees are routed to the fee vault, so everything above the rent floor backs the curve
+    let rent_floor = vault_rent_floor()?;
+    let actual_reserves = vault_lamports.saturating_sub(rent_floor);
+
+    let surplus = (actual_reserves as i128 - theoretical_liability as i128)
+        .clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+
+    Ok(CurveHealth {
+        theoretical_liability,
+        actual_reserves,
+        surplus,
+        rent_floor,
+    })
+}
+
+/// Calculate how many tokens a curve sells for a given amount of SOL starting at `supply`
+/// Dispatches on the curve type and walks each segment of piecewise curves
+pub fn calculate_tokens_for_curve(curve: &CurveParams, supply: u64, sol_amount: u64) -> Result<u64> {
+    match curve.curve_type {
+        CurveType::Linear => calculate_tokens_for_sol(
+            sol_amount,
+            supply,
+            curve.initial_price,
+            curve.slope,
+            curve.decimals,
+        ),
+        CurveType::LinearThenFlat => {
+            let threshold = curve.flat_threshold;
+            let flat_price = curve.price_at_supply(threshold)?;
+
+            // Already past the threshold: every token costs the flat price
+            if supply >= threshold {
+                return tokens_at_price(sol_amount, flat_price, curve.decimals);
+            }
+
+            // Cost of buying out the rest of the linear segment
+            // (an overflow here means the SOL can't possibly reach the flat segment)
+            let linear_tokens = threshold - supply;
+            let linear_cost = calculate_sol_for_tokens(
+                linear_tokens,
+                supply,
+                curve.initial_price,
+                curve.slope,
+                curve.decimals,
+                true,
+            );
+
+            match linear_cost {
+                Ok(linear_cost) if sol_amount > linear_cost => {
+                    let flat_tokens =
+                        tokens_at_price(sol_amount - linear_cost, flat_price, curve.decimals)?;
+                    linear_tokens
+                        .checked_add(flat_tokens)
+                        .ok_or(BondingCurveError::MathOverflow.into())
+                }
+                _ => calculate_tokens_for_sol(
+                    sol_amount,
+                    supply,
+                    curve.initial_price,
+                    curve.slope,
+                    curve.decimals,
+                ),
+            }
+        }
+        CurveType::Exponential | CurveType::Sigmoid | CurveType::ConstantProduct => {
+            search_tokens_for_sol(curve, supply, sol_amount)
+        }
+    }
+}
+
+/// Calculate the SOL value of token_amount tokens sitting on the curve just above from_supply
+/// Rounded down, as sells are paid
+pub fn calculate_sol_for_curve(curve: &CurveParams, token_amount: u64, from_supply: u64) -> Result<u64> {
+    sol_for_curve(curve, token_amount, from_supply, false)
+}
+
+/// Calculate the SOL cost of minting token_amount tokens on the curve from from_supply
+/// Rounded up, so exact-output buys never pay less than the tokens are worth
+pub fn calculate_sol_for_curve_ceil(curve: &CurveParams, token_amount: u64, from_supply: u64) -> Result<u64> {
+    sol_for_curve(curve, token_amount, from_supply, true)
+}
+
+/// Value of token_amount tokens just above from_supply, rounded down or up
+/// Dispatches on the curve type and sums each segment of piecewise curves
+fn sol_for_curve(curve: &CurveParams, token_amount: u64, from_supply: u64, round_up: bool) -> Result<u64> {
+    match curve.curve_type {
+        CurveType::Linear => calculate_sol_for_tokens(
+            token_amount,
+            from_supply,
+            curve.initial_price,
+            curve.slope,
+            curve.decimals,
+            round_up,
+        ),
+        CurveType::LinearThenFlat => {
+            let threshold = curve.flat_threshold;
+            let to_supply = from_supply
+                .checked_add(token_amount)
+                .ok_or(BondingCurveError::SupplyOverflow)?;
+
+            // Split the range into the part below the threshold and the part above it
+            let linear_tokens = to_supply.min(threshold).saturating_sub(from_supply);
+            let flat_tokens = token_amount - linear_tokens;
+
+            let linear_cost = calculate_sol_for_tokens(
+                linear_tokens,
+                from_supply,
+                curve.initial_price,
+                curve.slope,
+                curve.decimals,
+                round_up,
+            )?;
+            let threshold_price = curve.price_at_supply(threshold)? as u128;
+            let flat_cost = if round_up {
+                mul_div_u128_ceil(threshold_price, flat_tokens as u128, curve.unit())?
+            } else {
+                mul_div_u128(threshold_price, flat_tokens as u128, curve.unit())?
+            };
+            let flat_cost = u64::try_from(flat_cost).map_err(|_| BondingCurveError::MathOverflow)?;
+
+            linear_cost
+                .checked_add(flat_cost)
+                .ok_or(BondingCurveError::MathOverflow.into())
+        }
+        CurveType::Exponential | CurveType::Sigmoid | CurveType::ConstantProduct => {
+            let to_supply = from_supply
+                .checked_add(token_amount)
+                .ok_or(BondingCurveError::SupplyOverflow)?;
+            let cost = curve_antiderivative(curve, to_supply)?
+                .saturating_sub(curve_antiderivative(curve, from_supply)?);
+            // Each area is rounded down, so their difference can be a
+            // lamport short of the exact one
+            let cost = if round_up && token_amount > 0 { cost + 1 } else { cost };
+            u64::try_from(cost).map_err(|_| BondingCurveError::MathOverflow.into())
+        }
+    }
+}
+
+/// Largest token amount whose cost from `supply` fits in sol_amount
+/// Used by curves whose cost integral has no closed-form inverse
+fn search_tokens_for_sol(curve: &CurveParams, supply: u64, sol_amount: u64) -> Result<u64> {
+    let base = curve_antiderivative(curve, supply)?;
+    let fits = |tokens: u64| -> bool {
+        supply
+            .checked_add(tokens)
+            .and_then(|to| curve_antiderivative(curve, to).ok())
+            .is_some_and(|area| area.saturating_sub(base) <= sol_amount as u128)
+    };
+
+    // Double until the bound no longer fits, then bisect
+    let mut high = 1u64;
+    while fits(high) {
+        high = match high.checked_mul(2) {
+            Some(next) => next,
+            None => return Err(BondingCurveError::SupplyOverflow.into()),
+        };
+    }
+    let mut low = high / 2;
+    while low + 1 < high {
+        let mid = low + (high - low) / 2;
+        if fits(mid) {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(low)
+}
+
+/// Area under an Exponential, Sigmoid or ConstantProduct curve from its origin to `supply`, in lamports
+/// (up to a constant, which cancels when two values are subtracted). With the
+/// width d in whole tokens and prices per whole token, the 10^decimals factors
+/// cancel out of the area and only scale the exponent
+fn curve_antiderivative(curve: &CurveParams, supply: u64) -> Result<u128> {
+    let price = curve.initial_price as u128;
+    let width = curve.slope as u128;
+    match curve.curve_type {
+        // ∫ p0 * 2^(s/d) ds = p0 * d / ln2 * 2^(s/d)
+        CurveType::Exponential => {
+            let growth = exp2_wad(supply as u128, width * curve.unit())?;
+            let scaled = growth
+                .checked_mul(width)
+                .ok_or(BondingCurveError::MathOverflow)?;
+            // Divide last for precision, first if the full product would overflow
+            scaled
+                .checked_mul(price)
+                .map(|area| area / LN2_WAD)
+                .or_else(|| (scaled / LN2_WAD).checked_mul(price))
+                .ok_or(BondingCurveError::MathOverflow.into())
+        }
+        // ∫ p / (1 + e^(-(s-m)/w)) ds = p * w * ln(1 + e^((s-m)/w))
+        CurveType::Sigmoid => {
+            let x = sigmoid_arg_wad(supply, curve.flat_threshold, width * curve.unit());
+            softplus_wad(x)
+                .checked_mul(width)
+                .and_then(|area| area.checked_mul(price))
+                .map(|area| area / WAD)
+                .ok_or(BondingCurveError::MathOverflow.into())
+        }
+        // The virtual SOL reserve k / (t - s), with k = p0 * t^2 / 10^decimals
+        // fixed by the opening price p0 and virtual token reserve t
+        CurveType::ConstantProduct => {
+            let virtual_tokens = curve.flat_threshold as u128;
+            let virtual_sol = mul_div_u128(price, virtual_tokens, curve.unit())?;
+            mul_div_u128(virtual_sol, virtual_tokens, curve.tokens_left(supply)?)
+        }
+        CurveType::Linear | CurveType::LinearThenFlat => {
+            err!(BondingCurveError::MathOverflow)
+        }
+    }
+}
+
+/// Fixed-point scale (18 decimals) for the exponential and sigmoid curve math
+const WAD: u128 = 1_000_000_000_000_000_000;
+/// ln(2) scaled by WAD
+const LN2_WAD: u128 = 693_147_180_559_945_309;
+
+/// value * factor / WAD, as lamports
+fn mul_div_wad(value: u128, factor_wad: u128) -> Result<u64> {
+    let product = value
+        .checked_mul(factor_wad)
+        .ok_or(BondingCurveError::PriceOverflow)?
+        / WAD;
+    u64::try_from(product).map_err(|_| BondingCurveError::PriceOverflow.into())
+}
+
+/// 2^f for a fraction f in [0, WAD), via the series for e^(f ln2)
+fn exp2_frac_wad(fraction: u128) -> u128 {
+    let y = fraction * LN2_WAD / WAD;
+    let mut term = WAD;
+    let mut sum = WAD;
+    let mut n = 1;
+    while term > 0 {
+        term = term * y / (n * WAD);
+        sum += term;
+        n += 1;
+    }
+    sum
+}
+
+/// 2^(num / den), scaled by WAD
+fn exp2_wad(num: u128, den: u128) -> Result<u128> {
+    let whole = num / den;
+    let fraction = (num % den) * WAD / den;
+    // WAD * 2^66 still leaves headroom in a u128
+    require!(whole <= 66, BondingCurveError::PriceOverflow);
+    Ok(exp2_frac_wad(fraction) << whole)
+}
+
+/// e^(-x) for x >= 0, scaled by WAD
+fn exp_neg_wad(x: u128) -> u128 {
+    // e^(-x) = 2^(-x / ln2)
+    let z = x.saturating_mul(WAD) / LN2_WAD;
+    let whole = z / WAD;
+    if whole >= 128 {
+        return 0;
+    }
+    (WAD * WAD / exp2_frac_wad(z % WAD)) >> whole
+}
+
+/// ln(z) for z >= WAD, scaled by WAD
+fn ln_wad(z: u128) -> u128 {
+    // Reduce to m in [1, 2): ln z = k ln2 + ln m
+    let mut k = 0;
+    while (z >> k) >= 2 * WAD {
+        k += 1;
+    }
+    let m = z >> k;
+
+    // ln m = 2 atanh(t) with t = (m - 1) / (m + 1) <= 1/3
+    let t = (m - WAD) * WAD / (m + WAD);
+    let t2 = t * t / WAD;
+    let mut power = t;
+    let mut sum = 0;
+    let mut n = 1;
+    while power > 0 {
+        sum += power / n;
+        power = power * t2 / WAD;
+        n += 2;
+    }
+    k as u128 * LN2_WAD + 2 * sum
+}
+
+/// (supply - midpoint) / width, scaled by WAD
+fn sigmoid_arg_wad(supply: u64, midpoint: u64, width: u128) -> i128 {
+    (supply as i128 - midpoint as i128) * WAD as i128 / width as i128
+}
+
+/// 1 / (1 + e^(-x)), scaled by WAD
+fn sigmoid_wad(x: i128) -> u128 {
+    let e = exp_neg_wad(x.unsigned_abs());
+    if x >= 0 {
+        WAD * WAD / (WAD + e)
+    } else {
+        e * WAD / (WAD + e)
+    }
+}
+
+/// ln(1 + e^x), scaled by WAD
+fn softplus_wad(x: i128) -> u128 {
+    let tail = ln_wad(WAD + exp_neg_wad(x.unsigned_abs()));
+    if x >= 0 {
+        x as u128 + tail
+    } else {
+        tail
+    }
+}
+
+/// Calculate how many tokens (base units) can be bought with a given amount of SOL
+/// Solves the quadratic equation that arises from the bonding curve integral
+fn calculate_tokens_for_sol(
+    sol_amount: u64,
+    current_supply: u64,
+    initial_price: u64,
+    slope: u64,
+    decimals: u8,
+) -> Result<u64> {
+    // For a linear bonding curve, with D = 10^decimals base units per token:
+    // price per token = initial_price + supply * slope / D
+    // The integral gives us: sol_amount = (initial_price * tokens + slope * (current_supply * tokens + tokens^2 / 2) / D) / D
+    // Rearranging: slope * tokens^2 + 2 * (initial_price * D + slope * current_supply) * tokens - 2 * D^2 * sol_amount = 0
+    // Everything runs in u128 so D up to 10^9 fits alongside realistic prices
+
+    if slope == 0 {
+        // If slope is 0, it's a flat curve: sol_amount = initial_price * tokens / D
+        return tokens_at_price(sol_amount, initial_price, decimals);
+    }
+
+    let unit = token_unit(decimals);
+    let slope = slope as u128;
+
+    // Calculate b = 2 * (initial_price * D + slope * current_supply)
+    let b = (initial_price as u128)
+        .checked_mul(unit)
+        .and_then(|base| base.checked_add(slope.checked_mul(current_supply as u128)?))
+        .and_then(|sum| sum.checked_mul(2))
+        .ok_or(BondingCurveError::MathOverflow)?;
+
+    // Calculate 4ac where a = slope and c = -2 * D^2 * sol_amount
+    let four_ac = slope
+        .checked_mul(unit * unit)
+        .and_then(|product| product.checked_mul(sol_amount as u128))
+        .and_then(|product| product.checked_mul(8)) // 4 * 2 = 8
+        .ok_or(BondingCurveError::MathOverflow)?;
+
+    // Calculate discriminant: b^2 + 4ac
+    let discriminant = b
+        .checked_mul(b)
+        .and_then(|b_squared| b_squared.checked_add(four_ac))
+        .ok_or(BondingCurveError::MathOverflow)?;
+
+    // Calculate sqrt(discriminant)
+    let sqrt_discriminant = integer_sqrt(discriminant);
+
+    // Calculate tokens = (-b + sqrt(discriminant)) / (2a)
+    // Since b > 0 and we want positive result, we need sqrt_discriminant > b
+    if sqrt_discriminant <= b {
+        return Ok(0); // Not enough SOL to buy any tokens
+    }
+
+    let tokens = (sqrt_discriminant - b) / (slope * 2);
+    u64::try_from(tokens).map_err(|_| BondingCurveError::MathOverflow.into())
+}
+
+/// Integer square root (rounded down) by Newton's method
+pub fn integer_sqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+
+    // Start from the power of two at or above the root: the iteration then
+    // falls monotonically onto the floor root in a handful of steps, and
+    // x + n / x stays within 65 bits
+    let mut x = 1u128 << (128 - n.leading_zeros()).div_ceil(2);
+    loop {
+        let next = (x + n / x) / 2;
+        if next >= x {
+            return x;
+        }
+        x = next;
+    }
+}
+
+/// Calculate how much SOL is needed to buy a specific number of tokens (base units)
+/// This uses the integral of the linear bonding curve to calculate the area under the curve
+fn calculate_sol_for_tokens(
+    token_amount: u64,
+    current_supply: u64,
+    initial_price: u64,
+    slope: u64,
+    decimals: u8,
+    round_up: bool,
+) -> Result<u64> {
+    // For a linear bonding curve: price per token = initial_price + supply * slope / D
+    // To calculate the total cost for token_amount tokens, we need to integrate
+    // the price per base unit from current_supply to current_supply + token_amount
+
+    // The integral of (initial_price + (current_supply + x) * slope / D) / D dx from 0 to token_amount is:
+    // initial_price * token_amount / D + slope * (2 * current_supply * token_amount + token_amount^2) / (2 * D^2)
+    let unit = token_unit(decimals);
+
+    // Calculate base_cost = initial_price * token_amount / D
+    let (base_cost, base_rem) = mul_div_rem_u128(initial_price as u128, token_amount as u128, unit)?;
+
+    // Calculate slope_cost = slope * token_amount * (2 * current_supply + token_amount) / (2 * D^2)
+    let spread = (current_supply as u128 * 2 + token_amount as u128)
+        .checked_mul(token_amount as u128)
+        .ok_or(BondingCurveError::MathOverflow)?;
+    let (slope_cost, slope_rem) = mul_div_rem_u128(slope as u128, spread, 2 * unit * unit)?;
+
+    // Rounding up adds back both truncated fractions, over the common denominator 2 * D^2
+    let rounding = if round_up {
+        (base_rem * 2 * unit + slope_rem).div_ceil(2 * unit * unit)
+    } else {
+        0
+    };
+
+    // Total cost = base_cost + slope_cost
+    base_cost
+        .checked_add(slope_cost)
+        .and_then(|total| total.checked_add(rounding))
+        .and_then(|total| u64::try_from(total).ok())
+        .ok_or(BondingCurveError::MathOverflow.into())
+}
+
+/// Base units in one whole token of a mint with `decimals`
+fn token_unit(decimals: u8) -> u128 {
+    10u128.pow(decimals as u32)
+}
+
+/// a * b / c, rounded down; errors when the quotient doesn't fit or c is 0
+fn mul_div_u128(a: u128, b: u128, c: u128) -> Result<u128> {
+    Ok(mul_div_rem_u128(a, b, c)?.0)
+}
+
+/// a * b / c, rounded up; errors when the quotient doesn't fit or c is 0
+fn mul_div_u128_ceil(a: u128, b: u128, c: u128) -> Result<u128> {
+    let (quotient, remainder) = mul_div_rem_u128(a, b, c)?;
+    quotient
+        .checked_add((remainder > 0) as u128)
+        .ok_or(BondingCurveError::MathOverflow.into())
+}
+
+/// a * b / c rounded down, and its remainder. A product wider than 128 bits
+/// is divided exactly in 256 bits rather than dividing first, which would
+/// drop up to b - 1 from the result
+fn mul_div_rem_u128(a: u128, b: u128, c: u128) -> Result<(u128, u128)> {
+    require!(c > 0, BondingCurveError::MathOverflow);
+    if let Some(product) = a.checked_mul(b) {
+        return Ok((product / c, product % c));
+    }
+
+    // 256-bit product as (high, low) halves, from 64-bit limbs
+    const LOW: u128 = u64::MAX as u128;
+    let (a_high, a_low) = (a >> 64, a & LOW);
+    let (b_high, b_low) = (b >> 64, b & LOW);
+    let low_low = a_low * b_low;
+    let cross = (low_low >> 64) + ((a_low * b_high) & LOW) + ((a_high * b_low) & LOW);
+    let low = (low_low & LOW) | (cross << 64);
+    let high = a_high * b_high + ((a_low * b_high) >> 64) + ((a_high * b_low) >> 64) + (cross >> 64);
+    require!(high < c, BondingCurveError::MathOverflow);
+
+    // Long division of the low half into the remainder the high half leaves
+    let mut remainder = high;
+    let mut quotient = 0u128;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= c {
+            remainder = remainder.wrapping_sub(c);
+            quotient |= 1;
+        }
+    }
+    Ok((quotient, remainder))
+}
+
+/// Lamports that `tokens` base units are worth at `price` lamports per whole token
+fn value_at_price(price: u64, tokens: u64, decimals: u8) -> Result<u64> {
+    let value = price as u128 * tokens as u128 / token_unit(decimals);
+    u64::try_from(value).map_err(|_| BondingCurveError::MathOverflow.into())
+}
+
+/// Base units that `sol` lamports buy at `price` lamports per whole token
+fn tokens_at_price(sol: u64, price: u64, decimals: u8) -> Result<u64> {
+    let tokens = (sol as u128 * token_unit(decimals))
+        .checked_div(price as u128)
+        .ok_or(BondingCurveError::MathOverflow)?;
+    u64::try_from(tokens).map_err(|_| BondingCurveError::MathOverflow.into())
+}
+
+/// Average lamports per whole token when `sol` lamports trade for `tokens` base units
+/// (0 when no tokens change hands)
+fn price_per_token(sol: u64, tokens: u64, decimals: u8) -> u64 {
+    (sol as u128 * token_unit(decimals))
+        .checked_div(tokens as u128)
+        .map_or(0, |price| u64::try_from(price).unwrap_or(u64::MAX))
+}
+
+/// Move the fee share not kept in the reserve buffer from the SOL vault to the fee vault
+fn route_fees_to_vault<'info>(
+    sol_vault: &AccountInfo<'info>,
+    fee_vault: &AccountInfo<'info>,
+    system: &Program<'info, System>,
+    token_mint: Pubkey,
+    sol_vault_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let seeds = &[SOL_VAULT_SEED, token_mint.as_ref(), &[sol_vault_bump]];
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            system.to_account_info(),
+            system_program::Transfer {
+                from: sol_vault.clone(),
+                to: fee_vault.clone(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+    )
+}
+
+/// Price an exact-output buy: the curve cost (phase-adjusted, rounded up) grossed
+/// up for fees and the charity share
+/// Returns (gross_sol, fee_lamports, charity_lamports, sol_to_reserves)
+fn price_exact_buy(
+    curve: &BondingCurve,
+    token_amount: u64,
+    now: i64,
+    phase: Option<SalePhase>,
+) -> Result<(u64, u64, u64, u64)> {
+    let curve_cost = calculate_sol_for_curve_ceil(&curve.params(), token_amount, curve.current_supply)?;
+    let sol_to_reserves = match phase {
+        Some(phase) => phase.apply_price_multiplier_ceil(curve_cost)?,
+        None => curve_cost,
+    };
+    let fee_bps = curve.buy_fee_bps(now, phase);
+    let gross_sol = gross_up_for_fee(sol_to_reserves, fee_bps + curve.charity_bps)?;
+    let charity_lamports = calculate_fee(gross_sol, curve.charity_bps)?;
+    let fee_lamports = gross_sol - sol_to_reserves - charity_lamports;
+    Ok((gross_sol, fee_lamports, charity_lamports, sol_to_reserves))
+}
+
+/// Lamports the market-maker vault trades `token_amount` base units for, once
+/// it's cleared to trade: what the curve would charge to mint them (buys,
+/// rounded up) or pay to burn them (sells, rounded down), phase-adjusted and
+/// shifted half_spread_bps against the trader; sells also pay the sell tax ramp
+/// Returns (sol_amount, spot_price)
+fn mm_quote(
+    curve: &BondingCurve,
+    mm_vault: &MmVault,
+    token_amount: u64,
+    is_buy: bool,
+) -> Result<(u64, u64)> {
+    require!(mm_vault.enabled, BondingCurveError::MmVaultDisabled);
+    require!(curve.status == CurveStatus::Active, BondingCurveError::CurveNotActive);
+
+    // The vault only improves on the curve: its half spread must beat the curve's fee
+    let active_phase = curve.active_phase(Clock::get()?.unix_timestamp)?;
+    require!(
+        mm_vault.half_spread_bps < curve.trade_fee_bps(active_phase),
+        BondingCurveError::MmQuoteOutsideSpread
+    );
+    require!(
+        token_amount as u128
+            <= curve.current_supply as u128 * MAX_MM_FILL_BPS as u128 / 10_000,
+        BondingCurveError::MmFillTooLarge
+    );
+
+    let params = curve.params();
+    let half_spread_bps = mm_vault.half_spread_bps as u128;
+    let quote = if is_buy {
+        let cost = calculate_sol_for_curve_ceil(&params, token_amount, curve.current_supply)?;
+        let cost = match active_phase {
+            Some(phase) => phase.apply_price_multiplier_ceil(cost)?,
+            None => cost,
+        };
+        (cost as u128 * (10_000 + half_spread_bps)).div_ceil(10_000)
+    } else {
+        let value = calculate_sol_for_curve(
+            &params,
+            token_amount,
+            curve.current_supply - token_amount,
+        )?;
+        let value = phase_price(value, active_phase)?;
+        // Sells to the vault pay the curve's sell tax ramp like any other sell
+        let discount_bps = half_spread_bps + curve.sell_tax_bps(Clock::get()?.slot) as u128;
+        value as u128 * 10_000u128.saturating_sub(discount_bps) / 10_000
+    };
+    let sol_amount = u64::try_from(quote).map_err(|_| BondingCurveError::MathOverflow)?;
+    Ok((sol_amount, phase_price(curve.current_price()?, active_phase)?))
+}
+
+/// The creator's dev buy made by initialize_bonding_curve, charged the same
+/// fees as a regular buy
+fn execute_initial_buy(
+    accounts: &mut InitializeBondingCurve,
+    sol_vault_bump: u8,
+    sol_amount: u64,
+) -> Result<()> {
+    require!(sol_amount > 0, BondingCurveError::InvalidAmount);
+    let creator_token_account = accounts
+        .creator_token_account
+        .as_ref()
+        .ok_or(BondingCurveError::MissingInitialBuyAccount)?;
+
+    let bonding_curve = &accounts.bonding_curve;
+    let now = Clock::get()?.unix_timestamp;
+    let active_phase = bonding_curve.active_phase(now)?;
+    require!(
+        sol_amount <= bonding_curve.effective_max_sol_per_trade(),
+        BondingCurveError::TradeSizeExceeded
+    );
+
+    // No charity or referral can be set up yet, so the fee is the only deduction
+    let fee_lamports = calculate_fee(sol_amount, bonding_curve.buy_fee_bps(now, active_phase))?;
+    let sol_to_reserves = sol_amount - fee_lamports;
+    let curve_sol = match active_phase {
+        Some(phase) => phase.remove_price_multiplier(sol_to_reserves)?,
+        None => sol_to_reserves,
+    };
+    let tokens_to_mint = calculate_tokens_for_curve(&bonding_curve.params(), 0, curve_sol)?;
+    require!(tokens_to_mint > 0, BondingCurveError::InsufficientSol);
+    require!(
+        tokens_to_mint <= bonding_curve.remaining_supply(),
+        BondingCurveError::MaxSupplyExceeded
+    );
+    if let Some(phase) = active_phase {
+        phase.check_buy(sol_amount, tokens_to_mint)?;
+    }
+
+    system_program::transfer(
+        CpiContext::new(
+            accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: accounts.creator.to_account_info(),
+                to: accounts.sol_vault.to_account_info(),
+            },
+        ),
+        sol_amount,
+    )?;
+
+    let token_mint_key = accounts.token_mint.key();
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            token_interface::MintTo {
+                mint: accounts.token_mint.to_account_info(),
+                to: creator_token_account.to_account_info(),
+                authority: bonding_curve.to_account_info(),
+            },
+            &[&[
+                BONDING_CURVE_SEED,
+                token_mint_key.as_ref(),
+                &bonding_curve.salt_seed(),
+                &[bonding_curve.bump],
+            ]],
+        ),
+        tokens_to_mint,
+    )?;
+
+    let bonding_curve = &mut accounts.bonding_curve;
+    let price_before = bonding_curve.current_price()?;
+    bonding_curve.current_supply = tokens_to_mint;
+    bonding_curve.sol_reserves = sol_to_reserves;
+    let vault_fees = bonding_curve.accrue_fees(fee_lamports)?;
+    route_fees_to_vault(
+        &accounts.sol_vault,
+        &accounts.fee_vault,
+        &accounts.system_program,
+        token_mint_key,
+        sol_vault_bump,
+        vault_fees,
+    )?;
+    bonding_curve.refresh_sold_out();
+
+    let new_price = bonding_curve.current_price()?;
+    bonding_curve.record_trade(now, sol_amount, fee_lamports, price_before, new_price)?;
+    record_protocol_trade(&mut accounts.protocol_stats, bonding_curve, sol_amount, fee_lamports)?;
+
+    emit!(TokensPurchased {
+        buyer: accounts.creator.key(),
+        bonding_curve: bonding_curve.key(),
+        tokens_minted: tokens_to_mint,
+        sol_spent: sol_amount,
+        fee_lamports,
+        charity_lamports: 0,
+        sol_to_reserves,
+        new_supply: bonding_curve.current_supply,
+        new_price,
+        referrer: None,
+        referral_lamports: 0,
+        sol_unfilled: 0,
+        price_before,
+        trade_index: bonding_curve.trade_index,
+        timestamp: now,
+        event_version: TRADE_EVENT_VERSION,
+    });
+
+    msg!("Dev buy: {} tokens for {} lamports", tokens_to_mint, sol_amount);
+    Ok(())
+}
+
+/// Accounts an exact-out buy works on, gathered from the instruction making
+/// it; instructions without referrals pass None for them
+struct ExactBuyAccounts<'a, 'info> {
+    payer: AccountInfo<'info>,
+    bonding_curve: &'a mut Account<'info, BondingCurve>,
+    token_mint: AccountInfo<'info>,
+    /// Token account the bought tokens are minted to
+    destination: AccountInfo<'info>,
+    sol_vault: &'a AccountInfo<'info>,
+    sol_vault_bump: u8,
+    fee_vault: &'a AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    system_program: &'a Program<'info, System>,
+    /// Protocol settings, needed to pay a referral (required with one)
+    global_config: Option<&'a GlobalConfig>,
+    eligibility_record: &'a Option<Account<'info, EligibilityRecord>>,
+    referral: &'a mut Option<Account<'info, ReferralCode>>,
+    referrer_nft_account: &'a Option<InterfaceAccount<'info, TokenAccount>>,
+    charity: &'a Option<UncheckedAccount<'info>>,
+    user_trade_state: &'a mut Option<Account<'info, UserTradeState>>,
+    protocol_stats: &'a mut Option<Account<'info, ProtocolStats>>,
+    price_observations: &'a mut Option<Box<Account<'info, PriceObservations>>>,
+    curve_stats: &'a mut Option<Box<Account<'info, CurveStats>>>,
+    instructions_sysvar: &'a Option<UncheckedAccount<'info>>,
+    buyer_mark: &'a Option<UncheckedAccount<'info>>,
+}
+
+/// What an exact-out buy did, for the calling instruction's events
+struct ExactBuyFill {
+    token_amount: u64,
+    gross_sol: u64,
+    fee_lamports: u64,
+    charity_lamports: u64,
+    sol_to_reserves: u64,
+    referrer: Option<Pubkey>,
+    referral_lamports: u64,
+    price_before: u64,
+    new_price: u64,
+    large_trade: bool,
+    reserves_before: u64,
+    supply_before: u64,
+    timestamp: i64,
+}
+
+/// Shared body of buy_exact_tokens, buy_exact_tokens_for and
+/// create_creator_allocation: every buy guard, the payment, the mint into
+/// `destination` and the curve, stats and referral bookkeeping. `trader` is
+/// who the buy counts for (eligibility, launch window, hold period, buyer
+/// mark); the payer only funds it
+fn execute_exact_buy(
+    accounts: ExactBuyAccounts,
+    trader: Pubkey,
+    token_amount: u64,
+    max_sol_in: u64,
+    flags: u8,
+) -> Result<ExactBuyFill> {
+    require!(token_amount > 0, BondingCurveError::InvalidAmount);
+    require!(flags & !BUY_FLAGS_ALL == 0, BondingCurveError::InvalidFlags);
+
+    let clock = Clock::get()?;
+    let (now, slot) = (clock.unix_timestamp, clock.slot);
+    let bonding_curve = accounts.bonding_curve;
+    bonding_curve.record_trade_in_slot(slot)?;
+    require!(!bonding_curve.has_quote_mint(), BondingCurveError::QuoteCurveUnsupported);
+    require!(bonding_curve.status == CurveStatus::Active, BondingCurveError::CurveNotActive);
+    require!(!bonding_curve.buys_paused(now), BondingCurveError::CurvePaused);
+    require!(bonding_curve.trading_open(now), BondingCurveError::TradingClosed);
+    check_strict_mode(bonding_curve, accounts.instructions_sysvar)?;
+    require!(!bonding_curve.sold_out, BondingCurveError::SoldOut);
+    require!(!bonding_curve.raise_target_reached, BondingCurveError::RaiseTargetReached);
+    check_eligibility(bonding_curve, trader, accounts.eligibility_record)?;
+    let active_phase = bonding_curve.active_phase(now)?;
+
+    let token_amount = exact_buy_amount(bonding_curve, token_amount, flags)?;
+    let (gross_sol, fee_lamports, charity_lamports, sol_to_reserves) =
+        price_exact_buy(bonding_curve, token_amount, now, active_phase)?;
+    require!(gross_sol <= max_sol_in, BondingCurveError::SlippageExceeded);
+    require!(!bonding_curve.whitelist_active(slot), BondingCurveError::WhitelistOnly);
+    let launch_allowance =
+        launch_allowance(accounts.user_trade_state, bonding_curve, trader, slot)?;
+    require!(token_amount <= launch_allowance, BondingCurveError::LaunchBuyLimitExceeded);
+    require!(
+        gross_sol <= bonding_curve.effective_max_sol_per_trade(),
+        BondingCurveError::TradeSizeExceeded
+    );
+    let supply_after = bonding_curve
+        .current_supply
+        .checked_add(token_amount)
+        .ok_or(BondingCurveError::SupplyOverflow)?;
+    if let Some(phase) = active_phase {
+        phase.check_buy(gross_sol, supply_after)?;
+    }
+    let referral_lamports = match accounts.global_config {
+        Some(config) => referral_reward(accounts.referral, config, gross_sol, fee_lamports)?,
+        None => {
+            require!(accounts.referral.is_none(), BondingCurveError::InvalidReferral);
+            0
+        }
+    };
+
+    system_program::transfer(
+        CpiContext::new(
+            accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: accounts.payer.clone(),
+                to: accounts.sol_vault.clone(),
+            },
+        ),
+        gross_sol - charity_lamports - referral_lamports,
+    )?;
+    if charity_lamports > 0 {
+        let charity = checked_charity_account(accounts.charity, bonding_curve)?;
+        system_program::transfer(
+            CpiContext::new(
+                accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: accounts.payer.clone(),
+                    to: charity,
+                },
+            ),
+            charity_lamports,
+        )?;
+    }
+
+    // The referrer's share waits in the referral PDA for claim_referral_rewards
+    if referral_lamports > 0 {
+        let referral = accounts.referral.as_ref().ok_or(BondingCurveError::InvalidReferral)?;
+        system_program::transfer(
+            CpiContext::new(
+                accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: accounts.payer.clone(),
+                    to: referral.to_account_info(),
+                },
+            ),
+            referral_lamports,
+        )?;
+    }
+
+    let token_mint_key = accounts.token_mint.key();
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            accounts.token_program,
+            token_interface::MintTo {
+                mint: accounts.token_mint,
+                to: accounts.destination,
+                authority: bonding_curve.to_account_info(),
+            },
+            &[&[
+                BONDING_CURVE_SEED,
+                token_mint_key.as_ref(),
+                &bonding_curve.salt_seed(),
+                &[bonding_curve.bump],
+            ]],
+        ),
+        token_amount,
+    )?;
+
+    let price_before = bonding_curve.current_price()?;
+    let large_trade = bonding_curve.is_large_trade(sol_to_reserves, token_amount);
+    let (reserves_before, supply_before) =
+        (bonding_curve.sol_reserves, bonding_curve.current_supply);
+    bonding_curve.current_supply = supply_after;
+    bonding_curve.sol_reserves = bonding_curve
+        .sol_reserves
+        .checked_add(sol_to_reserves)
+        .ok_or(BondingCurveError::ReservesOverflow)?;
+    let vault_fees = bonding_curve.accrue_fees(fee_lamports - referral_lamports)?;
+    route_fees_to_vault(
+        accounts.sol_vault,
+        accounts.fee_vault,
+        accounts.system_program,
+        token_mint_key,
+        accounts.sol_vault_bump,
+        vault_fees,
+    )?;
+
+    if bonding_curve.refresh_sold_out() {
+        emit!(CurveSoldOut {
+            bonding_curve: bonding_curve.key(),
+            token_mint: bonding_curve.token_mint,
+            max_supply: bonding_curve.max_supply,
+            sol_reserves: bonding_curve.sol_reserves,
+            timestamp: now,
+        });
+    }
+    latch_raise_target(bonding_curve, now);
+    let new_price = bonding_curve.current_price()?;
+    bonding_curve.check_price_impact(price_before, new_price)?;
+
+    bonding_curve.record_trade(now, gross_sol, fee_lamports, price_before, new_price)?;
+    record_protocol_trade(accounts.protocol_stats, bonding_curve, gross_sol, fee_lamports)?;
+    record_price_observation(accounts.price_observations, bonding_curve)?;
+    let first_buy = mark_buyer(
+        bonding_curve,
+        accounts.curve_stats,
+        trader,
+        accounts.buyer_mark,
+        accounts.payer,
+        accounts.system_program.to_account_info(),
+    )?;
+    record_curve_stats(accounts.curve_stats, bonding_curve, gross_sol, true, first_buy)?;
+    record_buy_in_trade_state(accounts.user_trade_state, bonding_curve, trader, now, slot, token_amount)?;
+    let referrer = credit_referral(
+        accounts.referral,
+        accounts.referrer_nft_account,
+        bonding_curve.key(),
+        trader,
+        gross_sol,
+        referral_lamports,
+    )?;
+
+    Ok(ExactBuyFill {
+        token_amount,
+        gross_sol,
+        fee_lamports,
+        charity_lamports,
+        sol_to_reserves,
+        referrer,
+        referral_lamports,
+        price_before,
+        new_price,
+        large_trade,
+        reserves_before,
+        supply_before,
+        timestamp: now,
+    })
+}
+
+/// Shared body of buy_tokens and buy_tokens_whitelisted; `whitelisted` is set
+/// once the buyer's allowlist proof has been verified
+#[allow(clippy::too_many_arguments)]
+fn execute_buy(
+    ctx: Context<BuyTokens>,
+    sol_amount: u64,
+    flags: u8,
+    max_entry_price: Option<u64>,
+    min_tokens_out: Option<u64>,
+    tolerance_bps: Option<u16>,
+    nonce: Option<u64>,
+    whitelisted: bool,
+) -> Result<BuyResult> {
+    // Validate input
+    require!(sol_amount > 0, BondingCurveError::InvalidAmount);
+    require!(flags & !BUY_FLAGS_ALL == 0, BondingCurveError::InvalidFlags);
+    let allow_partial = flags & BUY_FLAG_ALLOW_PARTIAL != 0;
+
+    // One clock read and one mutable borrow of the curve for the whole buy
+    let clock = Clock::get()?;
+    let (now, slot) = (clock.unix_timestamp, clock.slot);
+    // A session key buys for the session's owner
+    let trader = ctx
+        .accounts
+        .session
+        .as_ref()
+        .map_or(ctx.accounts.buyer.key(), |session| session.owner);
+    check_trade_nonce(
+        &mut ctx.accounts.trade_nonces,
+        trader,
+        nonce,
+        ctx.accounts.session.is_some(),
+    )?;
+    let bonding_curve = &mut ctx.accounts.bonding_curve;
+    bonding_curve.record_trade_in_slot(slot)?;
+    require!(bonding_curve.status == CurveStatus::Active, BondingCurveError::CurveNotActive);
+    require!(!bonding_curve.buys_paused(now), BondingCurveError::CurvePaused);
+    require!(bonding_curve.trading_open(now), BondingCurveError::TradingClosed);
+    check_strict_mode(bonding_curve, &ctx.accounts.instructions_sysvar)?;
+    require!(!bonding_curve.sold_out, BondingCurveError::SoldOut);
+    require!(!bonding_curve.raise_target_reached, BondingCurveError::RaiseTargetReached);
+    require!(
+        !bonding_curve.has_quote_mint() || ctx.accounts.referral.is_none(),
+        BondingCurveError::QuoteCurveUnsupported
+    );
+    require!(
+        !bonding_curve.has_quote_mint() || ctx.accounts.session.is_none(),
+        BondingCurveError::QuoteCurveUnsupported
+    );
+    check_eligibility(
+        bonding_curve,
+        trader,
+        &ctx.accounts.eligibility_record,
+    )?;
+    require!(
+        whitelisted || !bonding_curve.whitelist_active(slot),
+        BondingCurveError::WhitelistOnly
+    );
+    let active_phase = bonding_curve.active_phase(now)?;
+    let launch_allowance = launch_allowance(
+        &ctx.accounts.user_trade_state,
+        bonding_curve,
+        trader,
+        slot,
+    )?;
+
+    // Cap the size of a single trade
+    let mut gross_sol = sol_amount;
+    let max_sol_per_trade = bonding_curve.effective_max_sol_per_trade();
+    if gross_sol > max_sol_per_trade {
+        require!(allow_partial, BondingCurveError::TradeSizeExceeded);
+        gross_sol = max_sol_per_trade;
+    }
+    if let Some(phase) = active_phase {
+        if phase.max_buy_lamports > 0 && gross_sol > phase.max_buy_lamports {
+            require!(allow_partial, BondingCurveError::PhaseBuyLimitExceeded);
+            gross_sol = phase.max_buy_lamports;
+        }
+    }
+
+    // Split the payment into fees, the charity share and the part that backs the curve
+    let fee_bps = bonding_curve.buy_fee_bps(now, active_phase);
+    let charity_bps = bonding_curve.charity_bps;
+    let mut fee_lamports = calculate_fee(gross_sol, fee_bps)?;
+    let mut charity_lamports = calculate_fee(gross_sol, charity_bps)?;
+    let mut sol_to_reserves = gross_sol
+        .checked_sub(fee_lamports)
+        .and_then(|net| net.checked_sub(charity_lamports))
+        .ok_or(BondingCurveError::InsufficientSol)?;
+
+    // SOL paid at the phase's (possibly discounted) price buys as much as
+    // the equivalent amount of SOL at undiscounted curve prices
+    let curve_sol = match active_phase {
+        Some(phase) => phase.remove_price_multiplier(sol_to_reserves)?,
+        None => sol_to_reserves,
+    };
+
+    // Calculate how many tokens can be purchased with the given SOL
+    let mut tokens_to_mint = calculate_tokens_for_curve(
+        &bonding_curve.params(),
+        bonding_curve.current_supply,
+        curve_sol,
+    )?;
+
+    // Enforce the active sale phase, if a schedule was configured
+    if let Some(phase) = active_phase {
+        let supply_after = bonding_curve
+            .current_supply
+            .checked_add(tokens_to_mint)
+            .ok_or(BondingCurveError::SupplyOverflow)?;
+
+        // Partial fills stop at the phase's supply cap and pay only for what they get
+        if allow_partial && phase.supply_cap > 0 && supply_after > phase.supply_cap {
+            tokens_to_mint = phase.supply_cap.saturating_sub(bonding_curve.current_supply);
+            require!(tokens_to_mint > 0, BondingCurveError::PhaseSupplyCapReached);
+
+            let curve_cost = calculate_sol_for_curve_ceil(
+                &bonding_curve.params(),
+                tokens_to_mint,
+                bonding_curve.current_supply,
+            )?;
+            sol_to_reserves = phase.apply_price_multiplier_ceil(curve_cost)?;
+            gross_sol = gross_up_for_fee(sol_to_reserves, fee_bps + charity_bps)?;
+            require!(gross_sol <= sol_amount, BondingCurveError::InsufficientSol);
+            charity_lamports = calculate_fee(gross_sol, charity_bps)?;
+            fee_lamports = gross_sol - sol_to_reserves - charity_lamports;
+        }
+
+        let supply_after = bonding_curve
+            .current_supply
+            .checked_add(tokens_to_mint)
+            .ok_or(BondingCurveError::SupplyOverflow)?;
+        phase.check_buy(gross_sol, supply_after)?;
+    }
+
+    // Fixed-supply curves never mint past max_supply, and launch-window buys never
+    // past the wallet's allowance; partial fills take what's left, paying only
+    // for what they get
+    require!(
+        allow_partial || tokens_to_mint <= launch_allowance,
+        BondingCurveError::LaunchBuyLimitExceeded
+    );
+    let mint_limit = bonding_curve.remaining_supply().min(launch_allowance);
+    if tokens_to_mint > mint_limit {
+        require!(allow_partial, BondingCurveError::MaxSupplyExceeded);
+        tokens_to_mint = mint_limit;
+
+        let curve_cost = calculate_sol_for_curve_ceil(
+            &bonding_curve.params(),
+            tokens_to_mint,
+            bonding_curve.current_supply,
+        )?;
+        sol_to_reserves = match active_phase {
+            Some(phase) => phase.apply_price_multiplier_ceil(curve_cost)?,
+            None => curve_cost,
+        };
+        gross_sol = gross_up_for_fee(sol_to_reserves, fee_bps + charity_bps)?;
+        require!(gross_sol <= sol_amount, BondingCurveError::InsufficientSol);
+        charity_lamports = calculate_fee(gross_sol, charity_bps)?;
+        fee_lamports = gross_sol - sol_to_reserves - charity_lamports;
+    }
+    let sol_unfilled = sol_amount - gross_sol;
+    require!(
+        tokens_to_mint >= min_tokens_out.unwrap_or(0),
+        BondingCurveError::SlippageExceeded
+    );
+    let price_before = bonding_curve.current_price()?;
+    if let Some(tolerance_bps) = tolerance_bps {
+        require!(tolerance_bps <= 10_000, BondingCurveError::InvalidTolerance);
+        let filled_curve_sol = match active_phase {
+            Some(phase) => phase.remove_price_multiplier(sol_to_reserves)?,
+            None => sol_to_reserves,
+        };
+        let spot_tokens =
+            tokens_at_price(filled_curve_sol, price_before, bonding_curve.decimals)?;
+        let min_tokens = spot_tokens - calculate_fee(spot_tokens, tolerance_bps)?;
+        require!(tokens_to_mint >= min_tokens, BondingCurveError::SlippageExceeded);
+    }
+
+    // Session buys are capped per trade by the session
+    if let Some(session) = &ctx.accounts.session {
+        session.check_trade(slot, gross_sol)?;
+    }
+
+    // A referred buy hands part of the trading fee to the referrer
+    let referral_lamports = referral_reward(
+        &ctx.accounts.referral,
+        &ctx.accounts.global_config,
+        gross_sol,
+        fee_lamports,
+    )?;
+
+    // Quote-token curves are paid in the quote mint; they have no charity or referral share
+    if bonding_curve.has_quote_mint() {
+        transfer_quote_in(
+            &ctx.accounts.buyer_quote_account,
+            &ctx.accounts.quote_vault,
+            &ctx.accounts.quote_token_mint,
+            &ctx.accounts.quote_token_program,
+            &ctx.accounts.buyer,
+            gross_sol,
+        )?;
+    } else if let Some(session) = &ctx.accounts.session {
+        // Session buys spend the session's deposit, crediting each recipient directly
+        spend_session_deposit(session, gross_sol)?;
+        **ctx.accounts.sol_vault.try_borrow_mut_lamports()? +=
+            gross_sol - charity_lamports - referral_lamports;
+        if charity_lamports > 0 {
+            let charity = checked_charity_account(&ctx.accounts.charity, bonding_curve)?;
+            **charity.try_borrow_mut_lamports()? += charity_lamports;
+        }
+        if referral_lamports > 0 {
+            let referral = ctx.accounts.referral.as_ref().ok_or(BondingCurveError::InvalidReferral)?;
+            **referral.to_account_info().try_borrow_mut_lamports()? += referral_lamports;
+        }
+    } else {
+        // Transfer SOL to vault
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: ctx.accounts.sol_vault.to_account_info(),
+            },
+        );
+        system_program::transfer(cpi_context, gross_sol - charity_lamports - referral_lamports)?;
+
+        // The charity share goes straight from the buyer to the charity
+        if charity_lamports > 0 {
+            let charity = checked_charity_account(&ctx.accounts.charity, bonding_curve)?;
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.buyer.to_account_info(),
+                        to: charity,
+                    },
+                ),
+                charity_lamports,
+            )?;
+        }
+
+        // The referrer's share waits in the referral PDA for claim_referral_rewards
+        if referral_lamports > 0 {
+            let referral = ctx.accounts.referral.as_ref().ok_or(BondingCurveError::InvalidReferral)?;
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.buyer.to_account_info(),
+                        to: referral.to_account_info(),
+                    },
+                ),
+                referral_lamports,
+            )?;
+        }
+    }
+
+    // Mint tokens to buyer; session buys land with the session's owner, never the session key
+    let recipient_token_account = match &ctx.accounts.session {
+        Some(session) => {
+            let owner_token_account = ctx
+                .accounts
+                .owner_token_account
+                .as_ref()
+                .ok_or(BondingCurveError::InvalidSessionTokenAccount)?;
+            require!(
+                owner_token_account.owner == session.owner
+                    && owner_token_account.mint == ctx.accounts.token_mint.key(),
+                BondingCurveError::InvalidSessionTokenAccount
+            );
+            owner_token_account.to_account_info()
+        }
+        None => ctx.accounts.buyer_token_account.to_account_info(),
+    };
+    let cpi_context = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        token_interface::MintTo {
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: recipient_token_account,
+            authority: bonding_curve.to_account_info(),
+        },
+    );
+    token_interface::mint_to(
+        cpi_context.with_signer(&[&[
+            BONDING_CURVE_SEED,
+            ctx.accounts.token_mint.key().as_ref(),
+            &bonding_curve.salt_seed(),
+            &[bonding_curve.bump],
+        ]]),
+        tokens_to_mint,
+    )?;
+
+    // Update bonding curve state
+    let large_trade = bonding_curve.is_large_trade(sol_to_reserves, tokens_to_mint);
+    let (reserves_before, supply_before) =
+        (bonding_curve.sol_reserves, bonding_curve.current_supply);
+    bonding_curve.current_supply = bonding_curve.current_supply.checked_add(tokens_to_mint).unwrap();
+    bonding_curve.sol_reserves = bonding_curve.sol_reserves.checked_add(sol_to_reserves).unwrap();
+    let vault_fees = bonding_curve.accrue_fees(fee_lamports - referral_lamports)?;
+    if !bonding_curve.has_quote_mint() {
+        route_fees_to_vault(
+            &ctx.accounts.sol_vault,
+            &ctx.accounts.fee_vault,
+            &ctx.accounts.system_program,
+            bonding_curve.token_mint,
+            ctx.bumps.sol_vault,
+            vault_fees,
+        )?;
+    }
+
+    if bonding_curve.refresh_sold_out() {
+        emit!(CurveSoldOut {
+            bonding_curve: bonding_curve.key(),
+            token_mint: bonding_curve.token_mint,
+            max_supply: bonding_curve.max_supply,
+            sol_reserves: bonding_curve.sol_reserves,
+            timestamp: now,
+        });
+    }
+    latch_raise_target(bonding_curve, now);
+
+    // Calculate the new price after the purchase
+    let new_price = bonding_curve.current_price()?;
+    bonding_curve.check_price_impact(price_before, new_price)?;
+    if let Some(max_entry_price) = max_entry_price {
+        require!(
+            phase_price(new_price, active_phase)? <= max_entry_price,
+            BondingCurveError::PriceBoundExceeded
+        );
+    }
+
+    // Roll the trade into today's aggregate stats
+    bonding_curve.record_trade(now, gross_sol, fee_lamports, price_before, new_price)?;
+    record_protocol_trade(&mut ctx.accounts.protocol_stats, bonding_curve, gross_sol, fee_lamports)?;
+    record_price_observation(&mut ctx.accounts.price_observations, bonding_curve)?;
+    let first_buy = mark_buyer(
+        bonding_curve,
+        &ctx.accounts.curve_stats,
+        trader,
+        &ctx.accounts.buyer_mark,
+        ctx.accounts.buyer.to_account_info(),
+        ctx.accounts.system_program.to_account_info(),
+    )?;
+    record_curve_stats(&mut ctx.accounts.curve_stats, bonding_curve, gross_sol, true, first_buy)?;
+
+    // Start the buyer's hold period and count the buy against their launch allowance
+    record_buy_in_trade_state(
+        &mut ctx.accounts.user_trade_state,
+        bonding_curve,
+        trader,
+        now,
+        slot,
+        tokens_to_mint,
+    )?;
+
+    // Credit the referral to whoever currently holds its NFT
+    let referrer = credit_referral(
+        &mut ctx.accounts.referral,
+        &ctx.accounts.referrer_nft_account,
+        bonding_curve.key(),
+        trader,
+        gross_sol,
+        referral_lamports,
+    )?;
+
+    // Emit purchase event for tracking and analytics; compact curves keep only the key fields
+    if bonding_curve.compact_events {
+        emit!(CompactTrade {
+            trader,
+            bonding_curve: bonding_curve.key(),
+            is_buy: true,
+            sol_amount: gross_sol,
+            token_amount: tokens_to_mint,
+            new_price,
+        });
+    } else {
+        emit_trade!(ctx, TokensPurchased {
+            buyer: trader,
+            bonding_curve: bonding_curve.key(),
+            tokens_minted: tokens_to_mint,
+            sol_spent: gross_sol,
+            fee_lamports,
+            charity_lamports,
+            sol_to_reserves,
+            new_supply: bonding_curve.current_supply,
+            new_price,
+            referrer,
+            referral_lamports,
+            sol_unfilled,
+            price_before,
+            trade_index: bonding_curve.trade_index,
+            timestamp: now,
+            event_version: TRADE_EVENT_VERSION,
+        });
+    }
+
+    if large_trade {
+        emit!(LargeTrade {
+            trader,
+            bonding_curve: bonding_curve.key(),
+            is_buy: true,
+            sol_amount: sol_to_reserves,
+            token_amount: tokens_to_mint,
+            reserves_before,
+            supply_before,
+        });
+    }
+
+    // Log the purchase details
+    if !bonding_curve.compact_events {
+        msg!(
+            "Tokens purchased: {} tokens for {} lamports ({} fees, {} to reserves)",
+            tokens_to_mint,
+            gross_sol,
+            fee_lamports,
+            sol_to_reserves
+        );
+    }
+
+    Ok(BuyResult {
+        gross_sol,
+        fee_lamports,
+        charity_lamports,
+        sol_to_reserves,
+        tokens_minted: tokens_to_mint,
+        sol_unfilled,
+    })
+}
+
+/// Whether `proof` links `wallet`'s leaf to the allowlist `root`, hashing sorted pairs
+fn verify_whitelist_proof(root: &[u8; 32], wallet: &Pubkey, proof: &[[u8; 32]]) -> bool {
+    verify_merkle_proof(root, hashv(&[wallet.as_ref()]).to_bytes(), proof)
+}
+
+/// Whether `proof` leads from `leaf` to `root`, hashing each pair sorted
+fn verify_merkle_proof(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    let mut node = leaf;
+    for sibling in proof {
+        node = if node <= *sibling {
+            hashv(&[&node, sibling]).to_bytes()
+        } else {
+            hashv(&[sibling, &node]).to_bytes()
+        };
+    }
+    node == *root
+}
+
+/// SHA-256 of an account's key and the first `prefix_len` bytes of its data
+fn upgrade_state_hash(info: &AccountInfo, prefix_len: usize) -> Result<[u8; 32]> {
+    let data = info.try_borrow_data()?;
+    let prefix = &data[..prefix_len.min(data.len())];
+    Ok(hashv(&[info.key.as_ref(), prefix]).to_bytes())
+}
+
+/// Upgrade fingerprint of a bonding curve: its key and fixed prefix
+fn upgrade_curve_hash(info: &AccountInfo) -> Result<[u8; 32]> {
+    require_keys_eq!(*info.owner, crate::ID, BondingCurveError::UpgradeSampleMismatch);
+    require!(
+        info.try_borrow_data()?.starts_with(BondingCurve::DISCRIMINATOR),
+        BondingCurveError::UpgradeSampleMismatch
+    );
+    upgrade_state_hash(info, layout::bonding_curve::PREFIX_LEN)
+}
+
+/// Pull `amount` of the quote token from a trader into the curve's quote vault
+fn transfer_quote_in<'info>(
+    from: &Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    quote_vault: &Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    quote_mint: &Option<Box<InterfaceAccount<'info, Mint>>>,
+    quote_token_program: &Option<Interface<'info, TokenInterface>>,
+    authority: &Signer<'info>,
+    amount: u64,
+) -> Result<()> {
+    let (Some(from), Some(quote_vault), Some(quote_mint), Some(quote_token_program)) =
+        (from, quote_vault, quote_mint, quote_token_program)
+    else {
+        return err!(BondingCurveError::MissingQuoteAccount);
+    };
+    token_interface::transfer_checked(
+        CpiContext::new(
+            quote_token_program.to_account_info(),
+            token_interface::TransferChecked {
+                from: from.to_account_info(),
+                mint: quote_mint.to_account_info(),
+                to: quote_vault.to_account_info(),
+                authority: authority.to_account_info(),
+            },
+        ),
+        amount,
+        quote_mint.decimals,
+    )
+}
+
+/// Pay `amount` of the quote token out of the curve's quote vault, signed by the curve PDA
+fn transfer_quote_out<'info>(
+    quote_vault: &Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    to: &Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    quote_mint: &Option<Box<InterfaceAccount<'info, Mint>>>,
+    quote_token_program: &Option<Interface<'info, TokenInterface>>,
+    bonding_curve: &Account<'info, BondingCurve>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let (Some(quote_vault), Some(to), Some(quote_mint), Some(quote_token_program)) =
+        (quote_vault, to, quote_mint, quote_token_program)
+    else {
+        return err!(BondingCurveError::MissingQuoteAccount);
+    };
+    let salt = bonding_curve.salt_seed();
+    let seeds = &[
+        BONDING_CURVE_SEED,
+        bonding_curve.token_mint.as_ref(),
+        &salt,
+        &[bonding_curve.bump],
+    ];
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            quote_token_program.to_account_info(),
+            token_interface::TransferChecked {
+                from: quote_vault.to_account_info(),
+                mint: quote_mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: bonding_curve.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+        quote_mint.decimals,
+    )
+}
+
+/// Add the curve's post-trade price to its history, if the observations account was passed
+fn record_price_observation(
+    observations: &mut Option<Box<Account<PriceObservations>>>,
+    curve: &BondingCurve,
+) -> Result<()> {
+    match observations.as_mut() {
+        Some(observations) => {
+            observations.record(Clock::get()?.slot, curve.current_price()?, curve.current_supply)
+        }
+        None => require!(
+            !curve.price_observations_required,
+            BondingCurveError::PriceObservationsRequired
+        ),
+    }
+    Ok(())
+}
 
-    /// SOL vault to receive payment
-    /// CHECK: This is a PDA that holds SOL
-    #[account(
-        mut,
-        seeds = [b"sol_vault", token_mint.key().as_ref()],
-        bump
-    )]
-    pub sol_vault: AccountInfo<'info>,
+/// Count a trade in the curve's stats account; required once the curve has one
+fn record_curve_stats(
+    stats: &mut Option<Box<Account<CurveStats>>>,
+    curve: &BondingCurve,
+    volume: u64,
+    is_buy: bool,
+    first_buy: bool,
+) -> Result<()> {
+    match stats.as_mut() {
+        Some(stats) => {
+            stats.record(Clock::get()?.slot, curve.current_price()?, volume, is_buy, first_buy)?
+        }
+        None => require!(!curve.stats_required, BondingCurveError::CurveStatsRequired),
+    }
+    Ok(())
+}
 
-    // Required programs
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+/// Whether this is `trader`'s first buy on a curve whose stats were passed;
+/// a first buy turns their buyer mark, a data-less PDA, into a program
+/// account, with `payer` covering its rent
+fn mark_buyer<'info>(
+    curve: &Account<'info, BondingCurve>,
+    stats: &Option<Box<Account<'info, CurveStats>>>,
+    trader: Pubkey,
+    buyer_mark: &Option<UncheckedAccount<'info>>,
+    payer: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+) -> Result<bool> {
+    if stats.is_none() {
+        return Ok(false);
+    }
+
+    let buyer_mark = buyer_mark.as_ref().ok_or(BondingCurveError::InvalidBuyerMark)?;
+    let curve_key = curve.key();
+    let (address, bump) = pda::buyer_mark_address(&curve_key, &trader);
+    require_keys_eq!(buyer_mark.key(), address, BondingCurveError::InvalidBuyerMark);
+    if buyer_mark.owner == &crate::ID {
+        return Ok(false);
+    }
+
+    // Created like Anchor's init does it, including when someone sent the mark
+    // lamports ahead of time
+    let seeds: &[&[u8]] = &[BUYER_MARK_SEED, curve_key.as_ref(), trader.as_ref(), &[bump]];
+    let rent = Rent::get()?.minimum_balance(0);
+    if buyer_mark.lamports() == 0 {
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program,
+                system_program::CreateAccount {
+                    from: payer,
+                    to: buyer_mark.to_account_info(),
+                },
+                &[seeds],
+            ),
+            rent,
+            0,
+            &crate::ID,
+        )?;
+        return Ok(true);
+    }
+    let rent_due = rent.saturating_sub(buyer_mark.lamports());
+    if rent_due > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer,
+                    to: buyer_mark.to_account_info(),
+                },
+            ),
+            rent_due,
+        )?;
+    }
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program,
+            system_program::Assign {
+                account_to_assign: buyer_mark.to_account_info(),
+            },
+            &[seeds],
+        ),
+        &crate::ID,
+    )?;
+    Ok(true)
 }
 
-#[derive(Accounts)]
-#[instruction()]
-pub struct SellTokens<'info> {
-    /// The seller of tokens
-    #[account(mut)]
-    pub seller: Signer<'info>,
+/// Buy side of execute_limit_order: spend the order's escrowed SOL on the curve for its owner
+/// Returns (tokens_minted, gross_sol, fee_lamports)
+fn fill_limit_buy(
+    ctx: &mut Context<ExecuteLimitOrder>,
+    now: i64,
+    slot: u64,
+    active_phase: Option<SalePhase>,
+) -> Result<(u64, u64, u64)> {
+    let bonding_curve = &ctx.accounts.bonding_curve;
+    let owner = ctx.accounts.owner.key();
+    let budget = ctx.accounts.limit_order.amount;
+    require!(!bonding_curve.sold_out, BondingCurveError::SoldOut);
+    require!(!bonding_curve.raise_target_reached, BondingCurveError::RaiseTargetReached);
+    require!(!bonding_curve.whitelist_active(slot), BondingCurveError::WhitelistOnly);
+    let launch_allowance = launch_allowance(&ctx.accounts.user_trade_state, bonding_curve, owner, slot)?;
 
-    /// The bonding curve state
-    #[account(
-        mut,
-        seeds = [b"bonding_curve", token_mint.key().as_ref()],
-        bump = bonding_curve.bump
-    )]
-    pub bonding_curve: Account<'info, BondingCurve>,
+    // Size the fill from the budget net of fees, then price it exactly like buy_exact_tokens
+    let fee_bps = bonding_curve.buy_fee_bps(now, active_phase);
+    let net = budget
+        .checked_sub(calculate_fee(budget, fee_bps)?)
+        .and_then(|net| net.checked_sub(calculate_fee(budget, bonding_curve.charity_bps).ok()?))
+        .ok_or(BondingCurveError::InsufficientSol)?;
+    let mut curve_sol = match active_phase {
+        Some(phase) => phase.remove_price_multiplier(net)?,
+        None => net,
+    };
+    // Pricing rounds up, so trim the spend by any overshoot until the fill fits the budget
+    let (token_amount, (gross_sol, fee_lamports, charity_lamports, sol_to_reserves)) = loop {
+        let token_amount = calculate_tokens_for_curve(
+            &bonding_curve.params(),
+            bonding_curve.current_supply,
+            curve_sol,
+        )?
+        .min(bonding_curve.remaining_supply())
+        .min(launch_allowance);
+        require!(token_amount > 0, BondingCurveError::InvalidAmount);
+        let priced = price_exact_buy(bonding_curve, token_amount, now, active_phase)?;
+        if priced.0 <= budget {
+            break (token_amount, priced);
+        }
+        curve_sol = curve_sol
+            .checked_sub(priced.0 - budget)
+            .ok_or(BondingCurveError::InsufficientSol)?;
+    };
+    let min_tokens_out = ctx
+        .accounts
+        .limit_order
+        .min_tokens_out(sol_to_reserves, bonding_curve.params().unit())?;
+    require!(token_amount >= min_tokens_out, BondingCurveError::SlippageExceeded);
+    require!(
+        gross_sol <= bonding_curve.effective_max_sol_per_trade(),
+        BondingCurveError::TradeSizeExceeded
+    );
+    let supply_after = bonding_curve
+        .current_supply
+        .checked_add(token_amount)
+        .ok_or(BondingCurveError::SupplyOverflow)?;
+    if let Some(phase) = active_phase {
+        phase.check_buy(gross_sol, supply_after)?;
+    }
 
-    /// The token mint
-    #[account(mut)]
-    pub token_mint: Account<'info, Mint>,
+    // Pay the curve and the charity straight out of the order's escrow
+    let order_info = ctx.accounts.limit_order.to_account_info();
+    **order_info.try_borrow_mut_lamports()? -= gross_sol;
+    **ctx.accounts.sol_vault.try_borrow_mut_lamports()? += gross_sol - charity_lamports;
+    if charity_lamports > 0 {
+        let charity = checked_charity_account(&ctx.accounts.charity, bonding_curve)?;
+        **charity.try_borrow_mut_lamports()? += charity_lamports;
+    }
 
-    /// Seller's token account
-    #[account(mut)]
-    pub seller_token_account: Account<'info, TokenAccount>,
+    let token_mint_key = ctx.accounts.token_mint.key();
+    let salt = bonding_curve.salt_seed();
+    let seeds = &[
+        BONDING_CURVE_SEED,
+        token_mint_key.as_ref(),
+        &salt,
+        &[bonding_curve.bump],
+    ];
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token_interface::MintTo {
+                mint: ctx.accounts.token_mint.to_account_info(),
+                to: ctx.accounts.owner_token_account.to_account_info(),
+                authority: bonding_curve.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        token_amount,
+    )?;
 
-    /// SOL vault to send payment from
-    /// CHECK: This is a PDA that holds SOL
-    #[account(
-        mut,
-        seeds = [b"sol_vault", token_mint.key().as_ref()],
-        bump
-    )]
-    pub sol_vault: AccountInfo<'info>,
+    let bonding_curve = &mut ctx.accounts.bonding_curve;
+    let price_before = bonding_curve.current_price()?;
+    bonding_curve.current_supply = supply_after;
+    bonding_curve.sol_reserves = bonding_curve
+        .sol_reserves
+        .checked_add(sol_to_reserves)
+        .ok_or(BondingCurveError::ReservesOverflow)?;
+    let vault_fees = bonding_curve.accrue_fees(fee_lamports)?;
+    route_fees_to_vault(
+        &ctx.accounts.sol_vault,
+        &ctx.accounts.fee_vault,
+        &ctx.accounts.system_program,
+        token_mint_key,
+        ctx.bumps.sol_vault,
+        vault_fees,
+    )?;
+    bonding_curve.refresh_sold_out();
+    latch_raise_target(bonding_curve, now);
+    let new_price = bonding_curve.current_price()?;
+    bonding_curve.check_price_impact(price_before, new_price)?;
+    bonding_curve.record_trade(now, gross_sol, fee_lamports, price_before, new_price)?;
+    record_price_observation(&mut ctx.accounts.price_observations, bonding_curve)?;
+    let first_buy = mark_buyer(
+        bonding_curve,
+        &ctx.accounts.curve_stats,
+        owner,
+        &ctx.accounts.buyer_mark,
+        ctx.accounts.cranker.to_account_info(),
+        ctx.accounts.system_program.to_account_info(),
+    )?;
+    record_curve_stats(&mut ctx.accounts.curve_stats, bonding_curve, gross_sol, true, first_buy)?;
+    record_buy_in_trade_state(
+        &mut ctx.accounts.user_trade_state,
+        bonding_curve,
+        owner,
+        now,
+        slot,
+        token_amount,
+    )?;
 
-    // Required programs
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+    emit_trade!(ctx, TokensPurchased {
+        buyer: owner,
+        bonding_curve: bonding_curve.key(),
+        tokens_minted: token_amount,
+        sol_spent: gross_sol,
+        fee_lamports,
+        charity_lamports,
+        sol_to_reserves,
+        new_supply: bonding_curve.current_supply,
+        new_price,
+        referrer: None,
+        referral_lamports: 0,
+        sol_unfilled: budget - gross_sol,
+        price_before,
+        trade_index: bonding_curve.trade_index,
+        timestamp: now,
+        event_version: TRADE_EVENT_VERSION,
+    });
+    Ok((token_amount, gross_sol, fee_lamports))
 }
 
-#[derive(Accounts)]
-pub struct GetPrice<'info> {
-    /// The bonding curve to check price for
-    pub bonding_curve: Account<'info, BondingCurve>,
-}
+/// Sell side of execute_limit_order: sell the order's escrowed tokens back to the curve
+/// Returns (tokens_burned, gross_payout, fee_lamports)
+fn fill_limit_sell(
+    ctx: &mut Context<ExecuteLimitOrder>,
+    now: i64,
+    slot: u64,
+    active_phase: Option<SalePhase>,
+) -> Result<(u64, u64, u64)> {
+    let bonding_curve = &ctx.accounts.bonding_curve;
+    let owner = ctx.accounts.owner.key();
+    check_hold_period(&ctx.accounts.user_trade_state, bonding_curve, owner, now)?;
+    check_not_blacklisted(bonding_curve, owner, &ctx.accounts.blacklist_entry)?;
+    let escrowed = ctx.accounts.limit_order.amount;
+    require!(escrowed <= bonding_curve.current_supply, BondingCurveError::InsufficientSupply);
 
-/**
- * ACCOUNT DATA STRUCTURES
- */
+    // The sell burn tax comes out of the escrowed tokens; the rest is sold
+    let tax_tokens = calculate_fee(escrowed, bonding_curve.sell_burn_tax_bps)?;
+    let token_amount = escrowed - tax_tokens;
+    let curve_sol = calculate_sol_for_curve(
+        &bonding_curve.params(),
+        token_amount,
+        bonding_curve.current_supply - token_amount,
+    )?;
+    let sol_to_return = match active_phase {
+        Some(phase) => {
+            phase.check_sell()?;
+            phase.apply_price_multiplier(curve_sol)?
+        }
+        None => curve_sol,
+    };
+    let min_sol_out = ctx
+        .accounts
+        .limit_order
+        .min_sol_out(token_amount, bonding_curve.params().unit())?;
+    require!(sol_to_return >= min_sol_out, BondingCurveError::SlippageExceeded);
+    require!(
+        sol_to_return <= bonding_curve.effective_max_sol_per_trade(),
+        BondingCurveError::TradeSizeExceeded
+    );
+    let reserve_shortfall = sol_to_return.saturating_sub(bonding_curve.sellable_reserves());
+    require!(
+        reserve_shortfall <= bonding_curve.shortfall_cover(),
+        BondingCurveError::InsufficientReserves
+    );
+    let fee_lamports = calculate_fee(sol_to_return, bonding_curve.sell_fee_bps(slot, active_phase))?;
+    let charity_lamports = calculate_fee(sol_to_return, bonding_curve.charity_bps)?;
+    let sol_to_owner = sol_to_return
+        .checked_sub(fee_lamports)
+        .and_then(|net| net.checked_sub(charity_lamports))
+        .ok_or(BondingCurveError::MathOverflow)?;
+    let (sol_to_owner, fee_lamports) = apply_dust_policy(bonding_curve, sol_to_owner, fee_lamports)?;
 
-#[account]
-pub struct BondingCurve {
-    /// The creator/authority of the bonding curve
-    pub creator: Pubkey,
-    /// The token mint that this bonding curve manages
-    pub token_mint: Pubkey,
-    /// Current total supply of tokens
-    pub current_supply: u64,
-    /// Current SOL reserves
-    pub sol_reserves: u64,
-    /// Initial price in lamports
-    pub initial_price: u64,
-    /// Price slope in lamports
-    pub slope: u64,
-    /// PDA bump seed
-    pub bump: u8,
-    /// Token name
-    pub name: [u8; 32],
-    /// Token symbol
-    pub symbol: [u8; 8],
-}
+    // Burn the escrow and close it to the owner, signed by the order
+    let escrow = ctx
+        .accounts
+        .escrow_token_account
+        .as_ref()
+        .ok_or(BondingCurveError::MissingOrderEscrow)?;
+    let limit_order = &ctx.accounts.limit_order;
+    let order_id = limit_order.order_id.to_le_bytes();
+    let order_seeds = &[
+        LIMIT_ORDER_SEED,
+        limit_order.bonding_curve.as_ref(),
+        limit_order.owner.as_ref(),
+        &order_id,
+        &[limit_order.bump],
+    ];
+    token_interface::burn(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token_interface::Burn {
+                mint: ctx.accounts.token_mint.to_account_info(),
+                from: escrow.to_account_info(),
+                authority: limit_order.to_account_info(),
+            },
+            &[&order_seeds[..]],
+        ),
+        escrowed,
+    )?;
+    token_interface::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        token_interface::CloseAccount {
+            account: escrow.to_account_info(),
+            destination: ctx.accounts.owner.to_account_info(),
+            authority: limit_order.to_account_info(),
+        },
+        &[&order_seeds[..]],
+    ))?;
 
-impl BondingCurve {
-    pub const LEN: usize = 8 + // Discriminator
-        32 + // creator
-        32 + // token_mint
-        8 + // current_supply
-        8 + // sol_reserves
-        8 + // initial_price
-        8 + // slope
-        1 + // bump
-        32 + // name
-        8; // symbol
-}
+    // Pay the owner and the charity from the vault
+    let token_mint_key = ctx.accounts.token_mint.key();
+    let vault_seeds = &[
+        SOL_VAULT_SEED,
+        token_mint_key.as_ref(),
+        &[ctx.bumps.sol_vault],
+    ];
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.sol_vault.to_account_info(),
+                to: ctx.accounts.owner.to_account_info(),
+            },
+            &[&vault_seeds[..]],
+        ),
+        sol_to_owner,
+    )?;
+    if charity_lamports > 0 {
+        let charity = checked_charity_account(&ctx.accounts.charity, bonding_curve)?;
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.sol_vault.to_account_info(),
+                    to: charity,
+                },
+                &[&vault_seeds[..]],
+            ),
+            charity_lamports,
+        )?;
+    }
 
-/**
- * EVENTS
- * These events are emitted for tracking and analytics
- */
+    let bonding_curve = &mut ctx.accounts.bonding_curve;
+    let price_before = bonding_curve.current_price()?;
+    bonding_curve.current_supply -= escrowed;
+    bonding_curve.refresh_sold_out();
+    bonding_curve.draw_shortfall(reserve_shortfall);
+    bonding_curve.sol_reserves = (bonding_curve.sol_reserves + reserve_shortfall)
+        .checked_sub(sol_to_return)
+        .ok_or(BondingCurveError::InsufficientReserves)?;
+    let vault_fees = bonding_curve.accrue_fees(fee_lamports)?;
+    route_fees_to_vault(
+        &ctx.accounts.sol_vault,
+        &ctx.accounts.fee_vault,
+        &ctx.accounts.system_program,
+        token_mint_key,
+        ctx.bumps.sol_vault,
+        vault_fees,
+    )?;
+    let new_price = bonding_curve.current_price()?;
+    bonding_curve.record_trade(now, sol_to_return, fee_lamports, price_before, new_price)?;
+    record_price_observation(&mut ctx.accounts.price_observations, bonding_curve)?;
+    record_curve_stats(&mut ctx.accounts.curve_stats, bonding_curve, sol_to_return, false, false)?;
 
-#[event]
-pub struct BondingCurveInitialized {
-    pub bonding_curve: Pubkey,
-    pub token_mint: Pubkey,
-    pub creator: Pubkey,
-    pub initial_price: u64,
-    pub slope: u64,
+    emit_trade!(ctx, TokensSold {
+        seller: owner,
+        bonding_curve: bonding_curve.key(),
+        tokens_burned: token_amount,
+        tax_tokens_burned: tax_tokens,
+        sol_received: sol_to_owner,
+        fee_lamports,
+        charity_lamports,
+        new_supply: bonding_curve.current_supply,
+        new_price,
+        price_before,
+        trade_index: bonding_curve.trade_index,
+        timestamp: now,
+        event_version: TRADE_EVENT_VERSION,
+    });
+    Ok((escrowed, sol_to_return, fee_lamports))
 }
 
-#[event]
-pub struct TokensPurchased {
-    pub buyer: Pubkey,
-    pub bonding_curve: Pubkey,
-    pub tokens_minted: u64,
-    pub sol_spent: u64,
-    pub new_supply: u64,
-    pub new_price: u64,
+/// Record an event as a self-CPI signed by the event authority, which is
+/// what emit_cpi! does, but without borrowing the whole context
+#[cfg(feature = "cpi-events")]
+fn emit_event_cpi(
+    event_authority: &AccountInfo,
+    bump: u8,
+    event: &impl anchor_lang::Event,
+) -> Result<()> {
+    let data: Vec<u8> = anchor_lang::event::EVENT_IX_TAG_LE
+        .iter()
+        .copied()
+        .chain(event.data())
+        .collect();
+    let ix = anchor_lang::solana_program::instruction::Instruction::new_with_bytes(
+        crate::ID,
+        &data,
+        vec![AccountMeta::new_readonly(event_authority.key(), true)],
+    );
+    anchor_lang::solana_program::program::invoke_signed(
+        &ix,
+        std::slice::from_ref(event_authority),
+        &[&[EVENT_AUTHORITY_SEED, &[bump]]],
+    )?;
+    Ok(())
 }
 
-#[event]
-pub struct TokensSold {
-    pub seller: Pubkey,
-    pub bonding_curve: Pubkey,
-    pub tokens_burned: u64,
-    pub sol_received: u64,
-    pub new_supply: u64,
-    pub new_price: u64,
+/// Credit a wSOL account's token balance with the lamports just sent to it
+fn sync_wsol_balance<'info>(
+    wsol_account: AccountInfo<'info>,
+    wsol_token_program: &Option<Program<'info, Token>>,
+) -> Result<()> {
+    let wsol_token_program = wsol_token_program
+        .as_ref()
+        .ok_or(BondingCurveError::MissingWsolAccount)?;
+    anchor_spl::token::sync_native(CpiContext::new(
+        wsol_token_program.to_account_info(),
+        anchor_spl::token::SyncNative { account: wsol_account },
+    ))
 }
 
-/**
- * ERROR CODES
- * Custom error types for better error handling
- */
-
-#[error_code]
-pub enum BondingCurveError {
-    #[msg("Invalid price parameter")]
-    InvalidPrice,
-    #[msg("Invalid slope parameter")]
-    InvalidSlope,
-    #[msg("Invalid amount")]
-    InvalidAmount,
-    #[msg("Token name too long")]
-    NameTooLong,
-    #[msg("Token symbol too long")]
-    SymbolTooLong,
-    #[msg("Insufficient SOL for purchase")]
-    InsufficientSol,
-    #[msg("Insufficient token supply")]
-    InsufficientSupply,
-    #[msg("Insufficient SOL reserves")]
-    InsufficientReserves,
-    #[msg("Supply overflow")]
-    SupplyOverflow,
-    #[msg("Supply underflow")]
-    SupplyUnderflow,
-    #[msg("Reserves overflow")]
-    ReservesOverflow,
-    #[msg("Reserves underflow")]
-    ReservesUnderflow,
-    #[msg("Price calculation overflow")]
-    PriceOverflow,
-    #[msg("Math overflow in calculations")]
-    MathOverflow,
+/// Lamports a session holds above its rent floor, which its trades can spend
+fn session_deposit(session: &Account<TradingSession>) -> Result<u64> {
+    let info = session.to_account_info();
+    let rent_floor = Rent::get()?.minimum_balance(info.data_len());
+    Ok(info.lamports().saturating_sub(rent_floor))
 }
 
-/**
- * HELPER FUNCTIONS
- * Mathematical functions for bonding curve calculations
- */
+/// Take `lamports` out of a session's deposit, keeping the account rent-exempt
+fn spend_session_deposit(session: &Account<TradingSession>, lamports: u64) -> Result<()> {
+    require!(
+        session_deposit(session)? >= lamports,
+        BondingCurveError::InsufficientSessionDeposit
+    );
+    **session.to_account_info().try_borrow_mut_lamports()? -= lamports;
+    Ok(())
+}
 
-/// Calculate how many tokens can be bought with a given amount of SOL
-/// Solves the quadratic equation that arises from the bonding curve integral
-fn calculate_tokens_for_sol(
-    sol_amount: u64,
-    current_supply: u64,
-    initial_price: u64,
-    slope: u64,
+/// Pay a holder what their stake earned since their last settlement and
+/// checkpoint the index; rewards booked since the last settlement move out
+/// of the fee vault first. Creates the checkpoint on a holder's first call.
+fn settle_holder_rewards(
+    accounts: &mut HolderRewardsStake,
+    bumps: &HolderRewardsStakeBumps,
 ) -> Result<u64> {
-    // For a linear bonding curve: price = initial_price + supply * slope
-    // The integral gives us: sol_amount = initial_price * tokens + slope * (current_supply * tokens + tokens^2 / 2)
-    // Rearranging: (slope/2) * tokens^2 + (initial_price + slope * current_supply) * tokens - sol_amount = 0
-    
-    if slope == 0 {
-        // If slope is 0, it's a flat curve: sol_amount = initial_price * tokens
-        return sol_amount
-            .checked_div(initial_price)
-            .ok_or(BondingCurveError::MathOverflow.into());
-    }
-    
-    // Optimized calculation to reduce stack usage
-    // Calculate b = 2 * (initial_price + slope * current_supply)
-    let slope_times_supply = slope
-        .checked_mul(current_supply)
-        .ok_or(BondingCurveError::MathOverflow)?;
-    
-    let b = initial_price
-        .checked_add(slope_times_supply)
-        .ok_or(BondingCurveError::MathOverflow)?
-        .checked_mul(2)
-        .ok_or(BondingCurveError::MathOverflow)?;
-    
-    // Calculate 4ac where a = slope and c = -2 * sol_amount
-    let four_ac = slope
-        .checked_mul(sol_amount)
+    let bonding_curve = &mut accounts.bonding_curve;
+    let token_mint_key = bonding_curve.token_mint;
+
+    let pending = bonding_curve.holder_rewards_pending;
+    if pending > 0 {
+        let seeds = &[FEE_VAULT_SEED, token_mint_key.as_ref(), &[bumps.fee_vault]];
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: accounts.fee_vault.to_account_info(),
+                    to: accounts.rewards_vault.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            pending,
+        )?;
+        bonding_curve.holder_rewards_pending = 0;
+    }
+
+    let holder_rewards = &mut accounts.holder_rewards;
+    if holder_rewards.holder == Pubkey::default() {
+        holder_rewards.bonding_curve = bonding_curve.key();
+        holder_rewards.holder = accounts.holder.key();
+        holder_rewards.reward_index = bonding_curve.reward_per_token;
+        holder_rewards.bump = bumps.holder_rewards;
+    }
+    let owed = (holder_rewards.staked as u128)
+        .checked_mul(bonding_curve.reward_per_token - holder_rewards.reward_index)
         .ok_or(BondingCurveError::MathOverflow)?
-        .checked_mul(8) // 4 * 2 = 8
-        .ok_or(BondingCurveError::MathOverflow)?;
-    
-    // Calculate discriminant: b^2 + 4ac
-    let b_squared = b.checked_mul(b).ok_or(BondingCurveError::MathOverflow)?;
-    let discriminant = b_squared
-        .checked_add(four_ac)
+        / REWARD_INDEX_SCALE;
+    // Index rounding never lets claims outrun the vault; rent stays behind
+    let available = accounts
+        .rewards_vault
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(0));
+    let amount = u64::try_from(owed).unwrap_or(u64::MAX).min(available);
+
+    holder_rewards.reward_index = bonding_curve.reward_per_token;
+    holder_rewards.total_claimed = holder_rewards
+        .total_claimed
+        .checked_add(amount)
         .ok_or(BondingCurveError::MathOverflow)?;
-    
-    // Calculate sqrt(discriminant)
-    let sqrt_discriminant = integer_sqrt(discriminant);
-    
-    // Calculate tokens = (-b + sqrt(discriminant)) / (2a)
-    // Since b > 0 and we want positive result, we need sqrt_discriminant > b
-    if sqrt_discriminant <= b {
-        return Ok(0); // Not enough SOL to buy any tokens
+
+    if amount > 0 {
+        let seeds = &[REWARDS_VAULT_SEED, token_mint_key.as_ref(), &[bumps.rewards_vault]];
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: accounts.rewards_vault.to_account_info(),
+                    to: accounts.holder.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            amount,
+        )?;
     }
-    
-    let numerator = sqrt_discriminant.checked_sub(b).unwrap();
-    let denominator = slope.checked_mul(2).unwrap(); // 2a where a = slope
-    let tokens = numerator.checked_div(denominator).unwrap_or(0);
-    
-    Ok(tokens)
+    Ok(amount)
 }
 
-/// Integer square root approximation using binary search
-fn integer_sqrt(n: u64) -> u64 {
-    if n == 0 {
-        return 0;
+/// Tokens an exact-output buy of `token_amount` mints: all of them, or with
+/// BUY_FLAG_ALLOW_PARTIAL whatever max_supply still allows (never zero, as
+/// the curve isn't sold out)
+fn exact_buy_amount(curve: &BondingCurve, token_amount: u64, flags: u8) -> Result<u64> {
+    let remaining = curve.remaining_supply();
+    require!(
+        token_amount <= remaining || flags & BUY_FLAG_ALLOW_PARTIAL != 0,
+        BondingCurveError::MaxSupplyExceeded
+    );
+    Ok(token_amount.min(remaining))
+}
+
+/// Write `bonding_curve`'s entry at the next index of its creator's registry,
+/// creating the registry's fields on its first entry; returns the index
+fn register_in_curve_registry(
+    curve_registry: &mut CurveRegistry,
+    registry_bump: u8,
+    registry_entry: &mut CurveRegistryEntry,
+    entry_bump: u8,
+    bonding_curve: &mut Account<BondingCurve>,
+) -> Result<u64> {
+    if curve_registry.curve_count == 0 {
+        curve_registry.creator = bonding_curve.creator;
+        curve_registry.bump = registry_bump;
     }
-    
-    // Optimized binary search to reduce stack usage
-    let mut left = 1u64;
-    let mut right = n;
-    let mut result = 0u64;
-    
-    while left <= right {
-        let mid = left + (right - left) / 2;
-        
-        // Check for overflow and calculate mid_squared
-        if let Some(mid_squared) = mid.checked_mul(mid) {
-            if mid_squared == n {
-                return mid;
-            } else if mid_squared < n {
-                left = mid + 1;
-                result = mid;
-            } else {
-                right = mid - 1;
-            }
-        } else {
-            // Overflow occurred, reduce right boundary
-            right = mid - 1;
-        }
+    let index = curve_registry.curve_count;
+    registry_entry.index = index;
+    registry_entry.bonding_curve = bonding_curve.key();
+    registry_entry.token_mint = bonding_curve.token_mint;
+    registry_entry.creator = bonding_curve.creator;
+    registry_entry.created_ts = Clock::get()?.unix_timestamp;
+    registry_entry.bump = entry_bump;
+    curve_registry.curve_count = index.checked_add(1).ok_or(BondingCurveError::MathOverflow)?;
+    bonding_curve.registered = true;
+    Ok(index)
+}
+
+/// Grow a program account to `new_len`, the payer topping its rent up to
+/// the larger size; space grown within an instruction starts out zeroed
+fn grow_account<'info>(
+    info: &AccountInfo<'info>,
+    new_len: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    if info.data_len() >= new_len {
+        return Ok(());
+    }
+    let rent_due = Rent::get()?.minimum_balance(new_len).saturating_sub(info.lamports());
+    if rent_due > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: info.clone(),
+                },
+            ),
+            rent_due,
+        )?;
     }
-    
-    result
+    info.resize(new_len)?;
+    Ok(())
 }
 
-/// Calculate how much SOL is needed to buy a specific number of tokens
-/// This uses the integral of the linear bonding curve to calculate the area under the curve
-fn calculate_sol_for_tokens(
-    token_amount: u64,
-    current_supply: u64,
-    initial_price: u64,
-    slope: u64,
-) -> Result<u64> {
-    // For a linear bonding curve: price = initial_price + supply * slope
-    // To calculate the total cost for token_amount tokens, we need to integrate
-    // the price function from current_supply to current_supply + token_amount
-    
-    // The integral of (initial_price + (current_supply + x) * slope) dx from 0 to token_amount is:
-    // initial_price * token_amount + slope * (current_supply * token_amount + token_amount^2 / 2)
-    
-    // Optimized calculation to reduce stack usage
-    // Calculate base_cost = initial_price * token_amount
-    let base_cost = initial_price
-        .checked_mul(token_amount)
-        .ok_or(BondingCurveError::MathOverflow)?;
-    
-    // Calculate supply_cost = slope * current_supply * token_amount
-    let supply_cost = slope
-        .checked_mul(current_supply)
-        .ok_or(BondingCurveError::MathOverflow)?
-        .checked_mul(token_amount)
-        .ok_or(BondingCurveError::MathOverflow)?;
-    
-    // Calculate quadratic_cost = slope * token_amount^2 / 2
-    let token_squared = token_amount
-        .checked_mul(token_amount)
-        .ok_or(BondingCurveError::MathOverflow)?;
-    let quadratic_cost = slope
-        .checked_mul(token_squared)
-        .ok_or(BondingCurveError::MathOverflow)?
-        .checked_div(2)
-        .ok_or(BondingCurveError::MathOverflow)?;
-    
-    // Total cost = base_cost + supply_cost + quadratic_cost
-    let total_cost = base_cost
-        .checked_add(supply_cost)
-        .ok_or(BondingCurveError::MathOverflow)?
-        .checked_add(quadratic_cost)
+/// Whether the program has created the account at this address
+fn is_created(info: &AccountInfo) -> bool {
+    info.owner == &crate::ID && !info.data_is_empty()
+}
+
+/// Revoke the curve's mint authority for good, signing as the curve
+fn revoke_curve_mint_authority<'info>(
+    bonding_curve: &mut BondingCurve,
+    curve_info: &AccountInfo<'info>,
+    token_mint: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let salt = bonding_curve.salt_seed();
+    let seeds = &[
+        BONDING_CURVE_SEED,
+        bonding_curve.token_mint.as_ref(),
+        &salt,
+        &[bonding_curve.bump],
+    ];
+    token_interface::set_authority(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            token_interface::SetAuthority {
+                current_authority: curve_info.clone(),
+                account_or_mint: token_mint.clone(),
+            },
+            &[&seeds[..]],
+        ),
+        AuthorityType::MintTokens,
+        None,
+    )?;
+    bonding_curve.mint_authority_handed_off = true;
+    bonding_curve.mint_authority_target = None;
+    Ok(())
+}
+
+/// Close a program account, its lamports going to `destination`
+fn close_account<'info>(info: &AccountInfo<'info>, destination: &AccountInfo<'info>) -> Result<()> {
+    let credited = destination
+        .lamports()
+        .checked_add(info.lamports())
         .ok_or(BondingCurveError::MathOverflow)?;
-    
-    Ok(total_cost)
+    **info.try_borrow_mut_lamports()? = 0;
+    **destination.try_borrow_mut_lamports()? = credited;
+    info.assign(&system_program::ID);
+    info.resize(0)?;
+    Ok(())
+}
+
+/// Pay one launch bond out of the launch queue to `to`, never dipping into
+/// the queue's rent
+/// Returns the lamports paid
+fn release_launch_bond(launch_queue: &AccountInfo, to: &AccountInfo) -> Result<u64> {
+    let rent_floor = Rent::get()?.minimum_balance(launch_queue.data_len());
+    let bond = LAUNCH_BOND_LAMPORTS.min(launch_queue.lamports().saturating_sub(rent_floor));
+    **launch_queue.try_borrow_mut_lamports()? -= bond;
+    **to.try_borrow_mut_lamports()? += bond;
+    Ok(bond)
+}
+
+/// Seed taken from the SlotHashes entry for `slot`, if it is still there
+/// SlotHashes is a length prefix followed by (slot, hash) entries, newest first
+fn slot_hash_seed(slot_hashes: &AccountInfo, slot: u64) -> Result<Option<u64>> {
+    let data = slot_hashes.try_borrow_data()?;
+    let len = u64::from_le_bytes(data[..8].try_into().unwrap());
+    Ok(data[8..]
+        .chunks_exact(40)
+        .take(len as usize)
+        .find(|entry| u64::from_le_bytes(entry[..8].try_into().unwrap()) == slot)
+        .map(|entry| u64::from_le_bytes(entry[8..16].try_into().unwrap())))
+}
+
+/// The flat_threshold a curve of `curve_type` is created with
+/// The flat segment needs a threshold to start from; sigmoids use it as their
+/// midpoint and constant-product curves as their virtual token reserve
+fn curve_flat_threshold(curve_type: CurveType, flat_threshold: Option<u64>) -> Result<u64> {
+    match (curve_type, flat_threshold) {
+        (CurveType::Linear | CurveType::Exponential, _) => Ok(0),
+        (CurveType::LinearThenFlat | CurveType::ConstantProduct, threshold) => {
+            let threshold = threshold.unwrap_or(0);
+            require!(threshold > 0, BondingCurveError::InvalidThreshold);
+            Ok(threshold)
+        }
+        (CurveType::Sigmoid, threshold) => Ok(threshold.unwrap_or(0)),
+    }
+}
+
+/// Whether `mint` is a Token-2022 mint charging a fee on every transfer
+fn has_transfer_fee(mint: &AccountInfo) -> Result<bool> {
+    if mint.owner != &spl_token_2022::ID {
+        return Ok(false);
+    }
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    Ok(mint
+        .get_extension_types()?
+        .contains(&ExtensionType::TransferFeeConfig))
+}
+
+/// Latch the curve's raise target once its reserves reach it, emitting
+/// TargetReached on the call that does
+fn latch_raise_target(bonding_curve: &mut Account<BondingCurve>, now: i64) {
+    if bonding_curve.refresh_raise_target() {
+        emit!(TargetReached {
+            bonding_curve: bonding_curve.key(),
+            token_mint: bonding_curve.token_mint,
+            raise_target_lamports: bonding_curve.raise_target_lamports,
+            sol_reserves: bonding_curve.sol_reserves,
+            timestamp: now,
+        });
+    }
 }
@@ -0,0 +1,340 @@
+//! PDA seeds and address derivation helpers
+//!
+//! Integrators should derive program accounts through these functions instead
+//! of hardcoding seed strings, so their code keeps working if seeds evolve.
+//! Everything here is plain `Pubkey` math and works both on-chain and off-chain.
+
+use anchor_lang::prelude::*;
+
+/// Seed prefix of the bonding curve state account
+pub const BONDING_CURVE_SEED: &[u8] = b"bonding_curve";
+/// Seed prefix of the SOL vault backing a curve
+pub const SOL_VAULT_SEED: &[u8] = b"sol_vault";
+/// Seed prefix of the token account holding a quote-token curve's reserves
+pub const QUOTE_VAULT_SEED: &[u8] = b"quote_vault";
+/// Seed prefix of the vault collecting a curve's protocol and creator fees
+pub const FEE_VAULT_SEED: &[u8] = b"fee_vault";
+/// Seed of the protocol insurance fund state account
+pub const INSURANCE_FUND_SEED: &[u8] = b"insurance_fund";
+/// Seed of the SOL vault holding the insurance balance
+pub const INSURANCE_VAULT_SEED: &[u8] = b"insurance_vault";
+/// Seed prefix of attestor-issued eligibility records
+pub const ELIGIBILITY_SEED: &[u8] = b"eligibility";
+/// Seed prefix of referral code accounts, keyed by their NFT mint
+pub const REFERRAL_SEED: &[u8] = b"referral";
+/// Seed of the protocol-level launch queue
+pub const LAUNCH_QUEUE_SEED: &[u8] = b"launch_queue";
+/// Seed prefix of per-user replay-protection nonce windows
+pub const TRADE_NONCES_SEED: &[u8] = b"trade_nonces";
+/// Seed of the singleton protocol config account
+pub const GLOBAL_CONFIG_SEED: &[u8] = b"global_config";
+/// Seed prefix of per-user, per-curve trade state
+pub const USER_TRADE_STATE_SEED: &[u8] = b"user_trade_state";
+/// Seed prefix of per-depositor bootstrap allocation records
+pub const BOOTSTRAP_DEPOSIT_SEED: &[u8] = b"bootstrap_deposit";
+/// Seed prefix of launch raffle tickets, keyed by curve and ticket index
+pub const RAFFLE_TICKET_SEED: &[u8] = b"raffle_ticket";
+/// Seed prefix of queued sell claims, keyed by curve and queue position
+pub const EXIT_CLAIM_SEED: &[u8] = b"exit_claim";
+/// Seed prefix of a curve's market-maker vault
+pub const MM_VAULT_SEED: &[u8] = b"mm_vault";
+/// Seed of the protocol-wide statistics account
+pub const PROTOCOL_STATS_SEED: &[u8] = b"protocol_stats";
+/// Seed of the governance PDA that may receive graduated mint authorities
+pub const GOVERNANCE_SEED: &[u8] = b"governance";
+/// Seed of the PDA holding graduated liquidity until it seeds an AMM pool
+pub const MIGRATION_AUTHORITY_SEED: &[u8] = b"migration_authority";
+/// Seed prefix of a graduating curve's migration progress
+pub const MIGRATION_STATE_SEED: &[u8] = b"migration_state";
+/// Seed prefix of cross-curve price indexes, keyed by index id
+pub const CURVE_INDEX_SEED: &[u8] = b"curve_index";
+/// Seed prefix of a curve's creator allocation lockup
+pub const CREATOR_VESTING_SEED: &[u8] = b"creator_vesting";
+/// Seed prefix of a curve's price history ring buffer
+pub const PRICE_OBSERVATIONS_SEED: &[u8] = b"price_observations";
+/// Seed prefix of limit orders, keyed by curve, owner and order id
+pub const LIMIT_ORDER_SEED: &[u8] = b"limit_order";
+/// Seed prefix of a curve's holder rewards vault, keyed by mint
+pub const REWARDS_VAULT_SEED: &[u8] = b"rewards_vault";
+/// Seed prefix of holder reward checkpoints, keyed by curve and holder
+pub const HOLDER_REWARDS_SEED: &[u8] = b"holder_rewards";
+/// Seed of the authority signing self-CPI trade events (Anchor's event_cpi seed)
+pub const EVENT_AUTHORITY_SEED: &[u8] = b"__event_authority";
+/// Seed prefix of a creator's curve registry, keyed by creator
+pub const CURVE_REGISTRY_SEED: &[u8] = b"curve_registry";
+/// Seed prefix of curve registry entries, keyed by creator and index
+pub const CURVE_REGISTRY_ENTRY_SEED: &[u8] = b"curve_registry_entry";
+/// Seed prefix of trading sessions, keyed by owner and session key
+pub const SESSION_SEED: &[u8] = b"session";
+/// Seed prefix of the vault paying a curve's emergency redemptions
+pub const REDEMPTION_VAULT_SEED: &[u8] = b"redemption_vault";
+/// Seed prefix of a curve's holder rewards stake vault, keyed by mint
+pub const REWARDS_STAKE_SEED: &[u8] = b"rewards_stake";
+/// Seed prefix of blacklist entries, keyed by curve and holder
+pub const BLACKLIST_SEED: &[u8] = b"blacklist";
+/// Seed prefix of a curve's cumulative trade statistics
+pub const CURVE_STATS_SEED: &[u8] = b"curve_stats";
+/// Seed prefix of the data-less marks of wallets that have bought on a curve
+pub const BUYER_MARK_SEED: &[u8] = b"buyer_mark";
+/// Seed prefix of a curve's Merkle airdrop
+pub const AIRDROP_SEED: &[u8] = b"airdrop";
+/// Seed prefix of an airdrop claimant's claim receipt
+pub const AIRDROP_RECEIPT_SEED: &[u8] = b"airdrop_receipt";
+/// Seed of the pre-upgrade state snapshot
+pub const UPGRADE_SNAPSHOT_SEED: &[u8] = b"upgrade_snapshot";
+/// Seed prefix of Metaplex token metadata accounts (owned by the metadata program)
+pub const METADATA_SEED: &[u8] = b"metadata";
+
+/// Bonding curve state account for a mint
+pub fn bonding_curve_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BONDING_CURVE_SEED, mint.as_ref()], &crate::ID)
+}
+
+/// Bonding curve state account for a mint, initialized with a salt
+pub fn salted_bonding_curve_address(mint: &Pubkey, salt: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[BONDING_CURVE_SEED, mint.as_ref(), &salt_seed(salt)],
+        &crate::ID,
+    )
+}
+
+/// Salt seed of a bonding curve PDA; salt 0 contributes no bytes, so unsalted
+/// curves keep their plain mint-derived address
+pub fn salt_seed(salt: u64) -> Vec<u8> {
+    match salt {
+        0 => Vec::new(),
+        salt => salt.to_le_bytes().to_vec(),
+    }
+}
+
+/// SOL vault holding a curve's reserves
+pub fn sol_vault_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SOL_VAULT_SEED, mint.as_ref()], &crate::ID)
+}
+
+/// Quote-token vault of a quote-token curve's mint
+pub fn quote_vault_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[QUOTE_VAULT_SEED, mint.as_ref()], &crate::ID)
+}
+
+/// Metaplex metadata account of a mint
+pub fn metadata_address(mint: &Pubkey) -> (Pubkey, u8) {
+    let program_id = anchor_spl::metadata::ID;
+    Pubkey::find_program_address(&[METADATA_SEED, program_id.as_ref(), mint.as_ref()], &program_id)
+}
+
+/// Fee vault of the curve for a mint
+pub fn fee_vault_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FEE_VAULT_SEED, mint.as_ref()], &crate::ID)
+}
+
+/// Protocol insurance fund state account
+pub fn insurance_fund_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[INSURANCE_FUND_SEED], &crate::ID)
+}
+
+/// SOL vault holding the insurance balance
+pub fn insurance_vault_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[INSURANCE_VAULT_SEED], &crate::ID)
+}
+
+/// Eligibility record issued by `attestor` for `wallet`
+pub fn eligibility_record_address(attestor: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[ELIGIBILITY_SEED, attestor.as_ref(), wallet.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Referral code account backing a referral NFT mint
+pub fn referral_address(nft_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REFERRAL_SEED, nft_mint.as_ref()], &crate::ID)
+}
+
+/// Protocol-level launch queue
+pub fn launch_queue_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LAUNCH_QUEUE_SEED], &crate::ID)
+}
+
+/// Replay-protection nonce window for a user
+pub fn trade_nonces_address(user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TRADE_NONCES_SEED, user.as_ref()], &crate::ID)
+}
+
+/// Singleton protocol config account
+pub fn global_config_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GLOBAL_CONFIG_SEED], &crate::ID)
+}
+
+/// Governance PDA that may receive a graduated curve's mint authority
+pub fn governance_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GOVERNANCE_SEED], &crate::ID)
+}
+
+/// PDA holding a graduated curve's migrated liquidity
+pub fn migration_authority_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MIGRATION_AUTHORITY_SEED], &crate::ID)
+}
+
+/// Migration progress of a graduating curve
+pub fn migration_state_address(bonding_curve: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MIGRATION_STATE_SEED, bonding_curve.as_ref()], &crate::ID)
+}
+
+/// A depositor's bootstrap allocation record on a curve
+pub fn bootstrap_deposit_address(bonding_curve: &Pubkey, depositor: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[BOOTSTRAP_DEPOSIT_SEED, bonding_curve.as_ref(), depositor.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// A user's trade state on a curve
+pub fn user_trade_state_address(bonding_curve: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[USER_TRADE_STATE_SEED, bonding_curve.as_ref(), user.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Launch raffle ticket number `index` on a curve
+pub fn raffle_ticket_address(bonding_curve: &Pubkey, index: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[RAFFLE_TICKET_SEED, bonding_curve.as_ref(), &index.to_le_bytes()],
+        &crate::ID,
+    )
+}
+
+/// Protocol-wide statistics account
+pub fn protocol_stats_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROTOCOL_STATS_SEED], &crate::ID)
+}
+
+/// Cross-curve price index number `index_id`
+pub fn curve_index_address(index_id: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CURVE_INDEX_SEED, &index_id.to_le_bytes()], &crate::ID)
+}
+
+/// Queued sell claim at a position in a curve's exit queue
+pub fn exit_claim_address(bonding_curve: &Pubkey, position: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[EXIT_CLAIM_SEED, bonding_curve.as_ref(), &position.to_le_bytes()],
+        &crate::ID,
+    )
+}
+
+/// Market-maker vault quoting around a curve
+pub fn mm_vault_address(bonding_curve: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MM_VAULT_SEED, bonding_curve.as_ref()], &crate::ID)
+}
+
+/// Pre-upgrade state snapshot
+pub fn upgrade_snapshot_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[UPGRADE_SNAPSHOT_SEED], &crate::ID)
+}
+
+/// Creator allocation lockup of a curve
+pub fn creator_vesting_address(bonding_curve: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CREATOR_VESTING_SEED, bonding_curve.as_ref()], &crate::ID)
+}
+
+/// Price history ring buffer of a curve
+pub fn price_observations_address(bonding_curve: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PRICE_OBSERVATIONS_SEED, bonding_curve.as_ref()], &crate::ID)
+}
+
+/// Merkle airdrop of a curve
+pub fn airdrop_address(bonding_curve: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[AIRDROP_SEED, bonding_curve.as_ref()], &crate::ID)
+}
+
+/// Claim receipt of a claimant on an airdrop
+pub fn airdrop_receipt_address(airdrop: &Pubkey, claimant: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[AIRDROP_RECEIPT_SEED, airdrop.as_ref(), claimant.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Cumulative trade statistics of a curve
+pub fn curve_stats_address(bonding_curve: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CURVE_STATS_SEED, bonding_curve.as_ref()], &crate::ID)
+}
+
+/// Mark of a wallet's first buy on a curve, counted in its unique_buyers
+pub fn buyer_mark_address(bonding_curve: &Pubkey, buyer: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[BUYER_MARK_SEED, bonding_curve.as_ref(), buyer.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Limit order `order_id` of an owner on a curve
+pub fn limit_order_address(bonding_curve: &Pubkey, owner: &Pubkey, order_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[LIMIT_ORDER_SEED, bonding_curve.as_ref(), owner.as_ref(), &order_id.to_le_bytes()],
+        &crate::ID,
+    )
+}
+
+/// Blacklist entry of a holder on a curve; sellers pass it even when it doesn't exist
+pub fn blacklist_address(bonding_curve: &Pubkey, holder: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[BLACKLIST_SEED, bonding_curve.as_ref(), holder.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Holder rewards vault of the curve for a mint
+pub fn rewards_vault_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REWARDS_VAULT_SEED, mint.as_ref()], &crate::ID)
+}
+
+/// Stake and reward checkpoint of a holder on a curve
+pub fn holder_rewards_address(bonding_curve: &Pubkey, holder: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[HOLDER_REWARDS_SEED, bonding_curve.as_ref(), holder.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Authority signing the self-CPI trade events of cpi-events builds
+pub fn event_authority_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], &crate::ID)
+}
+
+/// Curve registry of a creator
+pub fn curve_registry_address(creator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CURVE_REGISTRY_SEED, creator.as_ref()], &crate::ID)
+}
+
+/// Entry of the curve a creator registered `index`-th, from 0
+pub fn curve_registry_entry_address(creator: &Pubkey, index: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[CURVE_REGISTRY_ENTRY_SEED, creator.as_ref(), &index.to_le_bytes()],
+        &crate::ID,
+    )
+}
+
+/// Trading session of an owner for a session key
+pub fn session_address(owner: &Pubkey, authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SESSION_SEED, owner.as_ref(), authority.as_ref()], &crate::ID)
+}
+
+/// This program's data account, which records its upgrade authority
+pub fn program_data_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[crate::ID.as_ref()],
+        &anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+    )
+}
+
+/// Vault paying a curve's emergency redemptions
+pub fn redemption_vault_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REDEMPTION_VAULT_SEED, mint.as_ref()], &crate::ID)
+}
+
+/// Token account holding the tokens staked for holder rewards on the curve for a mint
+pub fn rewards_stake_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REWARDS_STAKE_SEED, mint.as_ref()], &crate::ID)
+}
@@ -0,0 +1,196 @@
+//! Account validation of the trade instructions
+//!
+//! Each test runs an instruction's Anchor account checks (`try_accounts`)
+//! against the accounts of a real trade with one of them swapped for an
+//! attacker's: a vault of another mint, a curve of another mint, a curve
+//! owned by another program, and so on.
+
+mod common;
+
+use std::collections::BTreeSet;
+
+use anchor_lang::prelude::*;
+use anchor_lang::{system_program, Accounts};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use bonding_curve_program::{
+    BondingCurve, BondingCurveError, BuyTokens, BuyTokensBumps, GlobalConfig, SellTokens,
+    SellTokensBumps, ID,
+};
+use common::*;
+
+// Positions in the account lists built by Market
+const TRADER: usize = 0;
+const CURVE: usize = 1;
+const MINT: usize = 2;
+const TOKEN_ACCOUNT: usize = 3;
+const SOL_VAULT: usize = 4;
+const FEE_VAULT: usize = 5;
+const SELL_TOKEN_PROGRAM: usize = 6;
+const BUY_GLOBAL_CONFIG: usize = 6;
+
+fn validate_sale(accounts: &[AccountInfo<'static>]) -> Result<()> {
+    SellTokens::try_accounts(
+        &ID,
+        &mut with_optional_accounts_omitted(accounts),
+        &[],
+        &mut SellTokensBumps::default(),
+        &mut BTreeSet::new(),
+    )
+    .map(|_| ())
+}
+
+fn validate_purchase(accounts: &[AccountInfo<'static>]) -> Result<()> {
+    BuyTokens::try_accounts(
+        &ID,
+        &mut with_optional_accounts_omitted(accounts),
+        &[],
+        &mut BuyTokensBumps::default(),
+        &mut BTreeSet::new(),
+    )
+    .map(|_| ())
+}
+
+fn sale() -> Vec<AccountInfo<'static>> {
+    let market = Market::new();
+    market.sell_accounts(&market.trader())
+}
+
+fn purchase() -> Vec<AccountInfo<'static>> {
+    let market = Market::new();
+    market.buy_accounts(&market.trader())
+}
+
+/// Anchor error number of a failed validation
+fn code(error: Error) -> u32 {
+    match error {
+        Error::AnchorError(error) => error.error_code_number,
+        Error::ProgramError(error) => panic!("expected an Anchor error, got {error:?}"),
+    }
+}
+
+#[test]
+fn sale_accounts_validate() {
+    validate_sale(&sale()).unwrap();
+}
+
+#[test]
+fn sale_rejects_an_unsigned_seller() {
+    let mut accounts = sale();
+    let seller = *accounts[TRADER].key;
+    accounts[TRADER] = account(seller, system_program::ID, 1, vec![], false, false);
+    assert_eq!(code(validate_sale(&accounts).unwrap_err()), ErrorCode::AccountNotSigner as u32);
+}
+
+#[test]
+fn sale_rejects_another_mints_sol_vault() {
+    let mut accounts = sale();
+    accounts[SOL_VAULT] = sale()[SOL_VAULT].clone();
+    assert_eq!(code(validate_sale(&accounts).unwrap_err()), ErrorCode::ConstraintSeeds as u32);
+}
+
+#[test]
+fn sale_rejects_a_look_alike_fee_vault() {
+    let mut accounts = sale();
+    accounts[FEE_VAULT] = vault(Pubkey::new_unique());
+    assert_eq!(code(validate_sale(&accounts).unwrap_err()), ErrorCode::ConstraintSeeds as u32);
+}
+
+#[test]
+fn sale_rejects_a_curve_of_another_mint() {
+    // A real curve, but not the one the passed mint derives
+    let mut accounts = sale();
+    accounts[CURVE] = sale()[CURVE].clone();
+    assert_eq!(code(validate_sale(&accounts).unwrap_err()), ErrorCode::ConstraintSeeds as u32);
+}
+
+#[test]
+fn sale_rejects_a_mint_other_than_the_curves() {
+    // Vaults follow the substituted mint, so only the curve seeds catch it
+    let mut accounts = sale();
+    let other = sale();
+    accounts[MINT] = other[MINT].clone();
+    accounts[SOL_VAULT] = other[SOL_VAULT].clone();
+    accounts[FEE_VAULT] = other[FEE_VAULT].clone();
+    assert_eq!(code(validate_sale(&accounts).unwrap_err()), ErrorCode::ConstraintSeeds as u32);
+}
+
+#[test]
+fn sale_rejects_a_curve_owned_by_another_program() {
+    let mut accounts = sale();
+    let curve = &accounts[CURVE];
+    let forged = owned(*curve.key, Pubkey::new_unique(), curve.data.borrow().to_vec());
+    accounts[CURVE] = forged;
+    assert_eq!(
+        code(validate_sale(&accounts).unwrap_err()),
+        ErrorCode::AccountOwnedByWrongProgram as u32
+    );
+}
+
+#[test]
+fn sale_rejects_another_account_type_as_the_curve() {
+    let mut accounts = sale();
+    let data = zeroed::<GlobalConfig>(BondingCurve::LEN, |_| {});
+    accounts[CURVE] = owned(*accounts[CURVE].key, ID, data);
+    assert_eq!(
+        code(validate_sale(&accounts).unwrap_err()),
+        ErrorCode::AccountDiscriminatorMismatch as u32
+    );
+}
+
+#[test]
+fn sale_rejects_a_token_program_other_than_the_curves() {
+    let mut accounts = sale();
+    accounts[SELL_TOKEN_PROGRAM] = program(anchor_spl::token_2022::ID);
+    assert_eq!(
+        code(validate_sale(&accounts).unwrap_err()),
+        code(BondingCurveError::TokenProgramMismatch.into())
+    );
+}
+
+#[test]
+fn purchase_accounts_validate() {
+    validate_purchase(&purchase()).unwrap();
+}
+
+#[test]
+fn purchase_rejects_another_mints_sol_vault() {
+    let mut accounts = purchase();
+    accounts[SOL_VAULT] = purchase()[SOL_VAULT].clone();
+    assert_eq!(code(validate_purchase(&accounts).unwrap_err()), ErrorCode::ConstraintSeeds as u32);
+}
+
+#[test]
+fn purchase_rejects_someone_elses_token_account() {
+    // Minting into another holder's account of the right mint
+    let mut accounts = purchase();
+    let mint = *accounts[MINT].key;
+    let stranger = Pubkey::new_unique();
+    accounts[TOKEN_ACCOUNT] = owned(
+        get_associated_token_address(&stranger, &mint),
+        spl_token::ID,
+        token_account_data(mint, stranger, 0),
+    );
+    assert!(validate_purchase(&accounts).is_err());
+}
+
+#[test]
+fn purchase_rejects_a_session_of_another_key() {
+    let market = Market::new();
+    let buyer = market.trader();
+    let session = session(Pubkey::new_unique(), Pubkey::new_unique(), SLOT, 1, 1);
+    let nonces = trade_nonces(*buyer.wallet.key);
+    let accounts = market.session_buy_accounts(&buyer, &session, &nonces, &buyer);
+    assert_eq!(
+        code(validate_purchase(&accounts).unwrap_err()),
+        code(BondingCurveError::SessionKeyMismatch.into())
+    );
+}
+
+#[test]
+fn purchase_rejects_a_forged_global_config() {
+    // Right layout and owner, but not at the config PDA
+    let mut accounts = purchase();
+    accounts[BUY_GLOBAL_CONFIG] = owned(Pubkey::new_unique(), ID, global_config_data());
+    assert_eq!(code(validate_purchase(&accounts).unwrap_err()), ErrorCode::ConstraintSeeds as u32);
+}
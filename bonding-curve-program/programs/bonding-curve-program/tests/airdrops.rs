@@ -0,0 +1,200 @@
+//! Merkle airdrops run end to end through the program's entrypoint
+//!
+//! Each airdrop lists two allotments, so a claimant's proof is the other
+//! claimant's leaf. As in `trades`, a failed instruction is not rolled back,
+//! so tests only look at state after successful ones.
+
+mod common;
+
+use anchor_lang::prelude::{AccountInfo, ProgramError};
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::{system_program, AccountDeserialize, AccountSerialize};
+use anchor_spl::token::spl_token;
+use bonding_curve_program::{
+    instruction, pda, Airdrop, AirdropReceipt, BondingCurveError, CurveStatus,
+};
+use common::*;
+
+/// Scheduled launch of the market's curve, an hour after the test clock
+const LAUNCH_TS: i64 = UNIX_TIMESTAMP + 3_600;
+
+const ALLOTMENT: u64 = 5_000_000;
+
+fn leaf(claimant: &Trader, amount: u64) -> [u8; 32] {
+    hashv(&[claimant.wallet.key.as_ref(), &amount.to_le_bytes()]).to_bytes()
+}
+
+/// Root over two leaves, hashed sorted as the program verifies them
+fn root(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    let (low, high) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[&low, &high]).to_bytes()
+}
+
+/// A market whose launch is scheduled for LAUNCH_TS, and its creator
+fn scheduled_market() -> (Market, Trader) {
+    let market = Market::new();
+    let creator = market.trader();
+    market.set_state(|curve| {
+        curve.status = CurveStatus::PreLaunch;
+        curve.launch_ts = LAUNCH_TS;
+        curve.creator = *creator.wallet.key;
+    });
+    (market, creator)
+}
+
+fn create_airdrop(
+    market: &Market,
+    creator: &Trader,
+    merkle_root: [u8; 32],
+    claim_deadline_ts: i64,
+) -> std::result::Result<AccountInfo<'static>, ProgramError> {
+    let airdrop = uncreated(pda::airdrop_address(market.curve.key).0, Airdrop::LEN);
+    run(
+        &[
+            creator.wallet.clone(),
+            market.curve.clone(),
+            airdrop.clone(),
+            program(system_program::ID),
+        ],
+        instruction::CreateAirdrop {
+            merkle_root,
+            total_amount: 2 * ALLOTMENT,
+            claim_deadline_ts,
+        },
+    )?;
+    Ok(airdrop)
+}
+
+/// The not yet created claim receipt of `claimant`
+fn receipt(airdrop: &AccountInfo, claimant: &Trader) -> AccountInfo<'static> {
+    let key = pda::airdrop_receipt_address(airdrop.key, claimant.wallet.key).0;
+    uncreated(key, AirdropReceipt::LEN)
+}
+
+fn claim(
+    market: &Market,
+    airdrop: &AccountInfo<'static>,
+    receipt: &AccountInfo<'static>,
+    claimant: &Trader,
+    proof: [u8; 32],
+    amount: u64,
+) -> std::result::Result<(), ProgramError> {
+    run(
+        &[
+            claimant.wallet.clone(),
+            market.curve.clone(),
+            airdrop.clone(),
+            receipt.clone(),
+            market.token_mint.clone(),
+            claimant.token_account.clone(),
+            program(spl_token::ID),
+            program(anchor_spl::associated_token::ID),
+            program(system_program::ID),
+        ],
+        instruction::ClaimAirdrop {
+            proof: vec![proof],
+            amount,
+        },
+    )
+}
+
+#[test]
+fn claims_mint_the_proven_allotment_once() {
+    let (market, creator) = scheduled_market();
+    let (alice, bob) = (market.trader(), market.trader());
+    let (alice_leaf, bob_leaf) = (leaf(&alice, ALLOTMENT), leaf(&bob, 2 * ALLOTMENT));
+    let airdrop = create_airdrop(&market, &creator, root(alice_leaf, bob_leaf), LAUNCH_TS).unwrap();
+
+    let alice_receipt = receipt(&airdrop, &alice);
+    claim(&market, &airdrop, &alice_receipt, &alice, bob_leaf, ALLOTMENT).unwrap();
+
+    assert_eq!(alice.tokens(), ALLOTMENT);
+    assert_eq!(market.state().current_supply, ALLOTMENT);
+    assert_eq!(market.state().sol_reserves, 0);
+    let recorded = Airdrop::try_deserialize(&mut &airdrop.data.borrow()[..]).unwrap();
+    assert_eq!((recorded.claimed_amount, recorded.claim_count), (ALLOTMENT, 1));
+
+    // The receipt exists now, so claiming again fails to create it with the
+    // System program's AccountAlreadyInUse
+    assert_eq!(
+        claim(&market, &airdrop, &alice_receipt, &alice, bob_leaf, ALLOTMENT),
+        Err(ProgramError::Custom(0))
+    );
+    assert_eq!(alice.tokens(), ALLOTMENT);
+    assert_eq!(market.state().current_supply, ALLOTMENT);
+}
+
+#[test]
+fn claims_need_a_proof_of_the_claimants_own_leaf() {
+    let (market, creator) = scheduled_market();
+    let (alice, bob, mallory) = (market.trader(), market.trader(), market.trader());
+    let (alice_leaf, bob_leaf) = (leaf(&alice, ALLOTMENT), leaf(&bob, ALLOTMENT));
+    let airdrop = create_airdrop(&market, &creator, root(alice_leaf, bob_leaf), LAUNCH_TS).unwrap();
+    let invalid = Err(program_error(BondingCurveError::InvalidAirdropProof));
+
+    // More than the allotment
+    let alice_receipt = receipt(&airdrop, &alice);
+    assert_eq!(claim(&market, &airdrop, &alice_receipt, &alice, bob_leaf, 2 * ALLOTMENT), invalid);
+    // Someone else's allotment, with either leaf as the proof; each attempt
+    // gets a fresh receipt since failed ones are not rolled back here
+    for proof in [alice_leaf, bob_leaf] {
+        let mallory_receipt = receipt(&airdrop, &mallory);
+        assert_eq!(claim(&market, &airdrop, &mallory_receipt, &mallory, proof, ALLOTMENT), invalid);
+    }
+
+    assert_eq!(market.state().current_supply, 0);
+}
+
+#[test]
+fn airdrops_close_before_trading_starts() {
+    let (market, creator) = scheduled_market();
+    let alice = market.trader();
+    let sibling = [7u8; 32];
+    let merkle_root = root(leaf(&alice, ALLOTMENT), sibling);
+
+    // Claims must close by launch
+    assert_eq!(
+        create_airdrop(&market, &creator, merkle_root, LAUNCH_TS + 1).unwrap_err(),
+        program_error(BondingCurveError::InvalidAirdropDeadline)
+    );
+    let airdrop = create_airdrop(&market, &creator, merkle_root, UNIX_TIMESTAMP + 1).unwrap();
+
+    // Once the curve trades, no more claims
+    market.set_state(|curve| curve.status = CurveStatus::Active);
+    assert_eq!(
+        claim(&market, &airdrop, &receipt(&airdrop, &alice), &alice, sibling, ALLOTMENT),
+        Err(program_error(BondingCurveError::AirdropClosed))
+    );
+}
+
+#[test]
+fn airdrops_cannot_be_created_after_launch() {
+    let market = Market::new();
+    let creator = market.trader();
+    market.set_state(|curve| curve.creator = *creator.wallet.key);
+    assert_eq!(
+        create_airdrop(&market, &creator, [0; 32], UNIX_TIMESTAMP + 1).unwrap_err(),
+        program_error(BondingCurveError::AirdropClosed)
+    );
+}
+
+#[test]
+fn claims_stop_at_the_deadline() {
+    let (market, creator) = scheduled_market();
+    let alice = market.trader();
+    let sibling = [7u8; 32];
+    let merkle_root = root(leaf(&alice, ALLOTMENT), sibling);
+    let airdrop = create_airdrop(&market, &creator, merkle_root, LAUNCH_TS).unwrap();
+
+    // Move the deadline to the test clock's time
+    let mut state = Airdrop::try_deserialize(&mut &airdrop.data.borrow()[..]).unwrap();
+    state.claim_deadline_ts = UNIX_TIMESTAMP;
+    let mut data = Vec::new();
+    state.try_serialize(&mut data).unwrap();
+    airdrop.data.borrow_mut()[..data.len()].copy_from_slice(&data);
+
+    assert_eq!(
+        claim(&market, &airdrop, &receipt(&airdrop, &alice), &alice, sibling, ALLOTMENT),
+        Err(program_error(BondingCurveError::AirdropClosed))
+    );
+}
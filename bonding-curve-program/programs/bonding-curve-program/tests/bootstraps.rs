@@ -0,0 +1,102 @@
+//! Deposit bootstraps buying the curve's opening supply as one order
+//!
+//! The test clock never moves, so the deposit window is closed by rewriting
+//! the curve once deposits are in.
+
+mod common;
+
+use anchor_lang::prelude::AccountInfo;
+use anchor_lang::{system_program, AccountDeserialize};
+use bonding_curve_program::{
+    calculate_sol_for_curve_ceil, calculate_tokens_for_curve, instruction, pda, BootstrapDeposit,
+    CurveStatus, ProtocolStats, ID,
+};
+use common::*;
+
+const SOL: u64 = 1_000_000_000;
+
+fn deposit_record(market: &Market, depositor: &Trader) -> AccountInfo<'static> {
+    let key = pda::bootstrap_deposit_address(market.curve.key, depositor.wallet.key).0;
+    uncreated(key, BootstrapDeposit::LEN)
+}
+
+fn deposit(market: &Market, depositor: &Trader, record: &AccountInfo<'static>, amount: u64) {
+    run(
+        &[
+            depositor.wallet.clone(),
+            market.curve.clone(),
+            record.clone(),
+            market.sol_vault.clone(),
+            program(system_program::ID),
+        ],
+        instruction::DepositBootstrap { amount },
+    )
+    .unwrap();
+}
+
+fn claim(market: &Market, depositor: &Trader, record: &AccountInfo<'static>) {
+    run(
+        &[
+            depositor.wallet.clone(),
+            market.curve.clone(),
+            record.clone(),
+            market.sol_vault.clone(),
+            market.token_mint.clone(),
+            depositor.token_account.clone(),
+            program(anchor_spl::token::spl_token::ID),
+            program(anchor_spl::associated_token::ID),
+            program(system_program::ID),
+        ],
+        instruction::ClaimBootstrapAllocation {},
+    )
+    .unwrap();
+}
+
+#[test]
+fn deposits_past_max_supply_buy_it_out_and_refund_the_rest() {
+    let market = Market::new();
+    market.set_state(|curve| {
+        curve.status = CurveStatus::Bootstrap;
+        curve.bootstrap_end_ts = UNIX_TIMESTAMP + 1;
+    });
+    let (first, second) = (market.trader(), market.trader());
+    let (first_record, second_record) = (deposit_record(&market, &first), deposit_record(&market, &second));
+    deposit(&market, &first, &first_record, 3 * SOL);
+    deposit(&market, &second, &second_record, SOL);
+    let deposited = [first.lamports(), second.lamports()];
+
+    // Cap the supply at half of what the deposits would buy
+    let params = market.state().params();
+    let max_supply = calculate_tokens_for_curve(&params, 0, 4 * SOL).unwrap() / 2;
+    market.set_state(|curve| {
+        curve.bootstrap_end_ts = UNIX_TIMESTAMP;
+        curve.max_supply = max_supply;
+    });
+    let (stats_key, stats_bump) = pda::protocol_stats_address();
+    let stats = owned(
+        stats_key,
+        ID,
+        zeroed::<ProtocolStats>(ProtocolStats::LEN, |stats| stats.bump = stats_bump),
+    );
+    run(&[market.curve.clone(), stats.clone()], instruction::ActivateBootstrap {}).unwrap();
+
+    let spent = calculate_sol_for_curve_ceil(&params, max_supply, 0).unwrap();
+    let state = market.state();
+    assert_eq!(state.status, CurveStatus::Active);
+    assert_eq!(state.current_supply, max_supply);
+    assert!(state.sold_out);
+    assert_eq!(state.sol_reserves, spent);
+    assert_eq!(state.bootstrap_refund_lamports, 4 * SOL - spent);
+    let totals = ProtocolStats::try_deserialize(&mut &stats.data.borrow()[..]).unwrap().totals;
+    assert_eq!(totals.volume_lamports, spent);
+
+    // Each claim mints its share of the supply and refunds its share of the rest
+    claim(&market, &first, &first_record);
+    claim(&market, &second, &second_record);
+    let refund = 4 * SOL - spent;
+    assert_eq!(first.tokens(), max_supply * 3 / 4);
+    assert_eq!(second.tokens(), max_supply / 4);
+    assert_eq!(first.lamports(), deposited[0] + refund * 3 / 4);
+    assert_eq!(second.lamports(), deposited[1] + refund / 4);
+    assert!(market.vault_balance() >= spent);
+}
@@ -0,0 +1,147 @@
+//! Closing curves along with the accounts that point at them
+//!
+//! As in `trades`, a failed instruction is not rolled back, so each test
+//! runs its failing close before anything is changed. Closes pass their
+//! accounts exactly, since the indexes to clean up are remaining accounts.
+
+mod common;
+
+use anchor_lang::prelude::{AccountInfo, ProgramError, Pubkey};
+use anchor_lang::{system_program, AccountDeserialize, InstructionData};
+use anchor_spl::token::spl_token;
+use anchor_spl::token::spl_token::solana_program::program_option::COption;
+use anchor_spl::token::spl_token::solana_program::program_pack::Pack;
+use bonding_curve_program::{
+    instruction, layout, pda, BondingCurveError, CurveIndex, CurveRegistryEntry, CurveStatus,
+    LaunchEntry, LaunchQueue, MmVault, ID,
+};
+use common::*;
+
+/// The market's curve, made closable by its creator
+fn closable_market() -> (Market, Trader) {
+    let market = Market::new();
+    let creator = market.trader();
+    market.set_state(|curve| curve.creator = *creator.wallet.key);
+    (market, creator)
+}
+
+/// Close the market's curve, passing `trailing` after the required accounts
+fn close(
+    market: &Market,
+    creator: &Trader,
+    mm_vault: &AccountInfo<'static>,
+    trailing: impl IntoIterator<Item = AccountInfo<'static>>,
+) -> Result<(), ProgramError> {
+    let mut accounts = vec![
+        creator.wallet.clone(),
+        market.curve.clone(),
+        market.token_mint.clone(),
+        market.sol_vault.clone(),
+        market.fee_vault.clone(),
+        vault(pda::rewards_vault_address(&market.mint).0),
+        mm_vault.clone(),
+        program(spl_token::ID),
+        program(system_program::ID),
+    ];
+    accounts.extend(trailing);
+    bonding_curve_program::entry(
+        &ID,
+        Box::leak(accounts.into_boxed_slice()),
+        &instruction::CloseBondingCurve {}.data(),
+    )
+}
+
+fn mint_authority(market: &Market) -> COption<Pubkey> {
+    spl_token::state::Mint::unpack(&market.token_mint.data.borrow()).unwrap().mint_authority
+}
+
+#[test]
+fn closing_takes_the_curve_out_of_everything_pointing_at_it() {
+    let (market, creator) = closable_market();
+    market.set_state(|curve| curve.registered = true);
+    let curve = *market.curve.key;
+    let (first, last) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    let mm_vault = owned(
+        pda::mm_vault_address(&curve).0,
+        ID,
+        zeroed::<MmVault>(MmVault::LEN, |vault| vault.bonding_curve = curve),
+    );
+    let entry = owned(
+        pda::curve_registry_entry_address(creator.wallet.key, 0).0,
+        ID,
+        zeroed::<CurveRegistryEntry>(CurveRegistryEntry::LEN, |entry| entry.bonding_curve = curve),
+    );
+    let (queue_key, queue_bump) = pda::launch_queue_address();
+    let queue = owned(
+        queue_key,
+        ID,
+        zeroed::<LaunchQueue>(LaunchQueue::LEN, |queue| {
+            queue.entries[0] = LaunchEntry { bonding_curve: curve, launch_ts: 1 };
+            queue.entries[1] = LaunchEntry { bonding_curve: first, launch_ts: 2 };
+            queue.len = 2;
+            queue.bump = queue_bump;
+        }),
+    );
+    let index = owned(
+        pda::curve_index_address(0).0,
+        ID,
+        zeroed::<CurveIndex>(CurveIndex::LEN, |index| {
+            index.curves[..3].copy_from_slice(&[first, curve, last]);
+            index.len = 3;
+        }),
+    );
+
+    // A registered curve can't leave its registry entry behind
+    assert_eq!(
+        close(&market, &creator, &mm_vault, [program(ID), queue.clone(), index.clone()]),
+        Err(program_error(BondingCurveError::RegistryEntryRequired))
+    );
+
+    close(&market, &creator, &mm_vault, [entry.clone(), queue.clone(), index.clone()]).unwrap();
+
+    for closed in [&market.curve, &mm_vault, &entry] {
+        assert_eq!((closed.lamports(), *closed.owner), (0, system_program::ID));
+    }
+    assert_eq!(mint_authority(&market), COption::None);
+    let queue = LaunchQueue::try_deserialize(&mut &queue.data.borrow()[..]).unwrap();
+    assert_eq!((queue.len, queue.entries[0].bonding_curve), (1, first));
+    let index = CurveIndex::try_deserialize(&mut &index.data.borrow()[..]).unwrap();
+    assert_eq!(index.len, 2);
+    assert_eq!(index.curves[..2], [first, last]);
+}
+
+#[test]
+fn shrunk_archived_curves_still_close() {
+    let (market, creator) = closable_market();
+    market.set_state(|curve| {
+        curve.status = CurveStatus::Graduated;
+        curve.creator_fees_unclaimed = 1;
+    });
+    let archive = |with_mint: bool| {
+        run(
+            &[
+                creator.wallet.clone(),
+                creator.wallet.clone(),
+                market.curve.clone(),
+                program(ID),
+                if with_mint { market.token_mint.clone() } else { program(ID) },
+                if with_mint { program(spl_token::ID) } else { program(ID) },
+            ],
+            instruction::ArchiveCurve { shrink: true },
+        )
+    };
+
+    // Nothing may be owed once the rest of the state is gone, and the curve
+    // must be able to give up its mint authority
+    assert_eq!(archive(true), Err(program_error(BondingCurveError::FeesUnclaimed)));
+    market.set_state(|curve| curve.creator_fees_unclaimed = 0);
+    assert_eq!(archive(false), Err(program_error(BondingCurveError::MissingMintAccount)));
+    archive(true).unwrap();
+    assert_eq!(market.curve.data_len(), layout::bonding_curve::PREFIX_LEN);
+    assert_eq!(mint_authority(&market), COption::None);
+
+    let mm_vault = uncreated(pda::mm_vault_address(market.curve.key).0, 0);
+    close(&market, &creator, &mm_vault, [program(ID), program(ID)]).unwrap();
+    assert_eq!((market.curve.lamports(), *market.curve.owner), (0, system_program::ID));
+}
@@ -0,0 +1,699 @@
+//! In-memory accounts and a minimal runtime for running the program's
+//! instructions natively
+//!
+//! Accounts are leaked so they live as long as the test process, which is
+//! what Anchor's `'info` lifetime asks for. The runtime answers the clock and
+//! rent sysvars and carries out the CPIs a trade makes: System transfers and
+//! account creation, and SPL Token instructions, run through the token
+//! program's own processor.
+//! Anything else a handler invokes fails with IncorrectProgramId, so a test
+//! touching a new CPI says so instead of silently passing.
+
+// Each test file uses its own subset of the helpers
+#![allow(dead_code)]
+
+use std::sync::Once;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::{system_program, AccountDeserialize, Discriminator, InstructionData};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use anchor_spl::token::spl_token::solana_program::program_option::COption;
+use anchor_spl::token::spl_token::solana_program::program_pack::Pack;
+use bonding_curve_program::{
+    instruction, pda, BondingCurve, GlobalConfig, TradeNonces, TradingSession, ID,
+    VAULT_RENT_BUFFER,
+};
+
+/// Slot and timestamp every instruction runs at
+pub const SLOT: u64 = 1_000;
+pub const UNIX_TIMESTAMP: i64 = 1_700_000_000;
+
+/// Lamports a fresh wallet holds
+pub const WALLET_LAMPORTS: u64 = 100_000_000_000;
+
+const SUCCESS: u64 = 0;
+
+struct Runtime;
+
+impl SyscallStubs for Runtime {
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            slot: SLOT,
+            unix_timestamp: UNIX_TIMESTAMP,
+            ..Clock::default()
+        };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> std::result::Result<(), ProgramError> {
+        let signers = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &ID))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let accounts = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                let mut info = account_infos
+                    .iter()
+                    .find(|info| *info.key == meta.pubkey)
+                    .ok_or(ProgramError::NotEnoughAccountKeys)?
+                    .clone();
+                info.is_signer |= signers.contains(info.key);
+                Ok(info)
+            })
+            .collect::<std::result::Result<Vec<_>, ProgramError>>()?;
+        match instruction.program_id {
+            system_program::ID => system_instruction(&accounts, &instruction.data),
+            spl_token::ID => {
+                spl_token::processor::Processor::process(&spl_token::ID, &accounts, &instruction.data)
+            }
+            _ => Err(ProgramError::IncorrectProgramId),
+        }
+    }
+}
+
+/// The System instructions the program makes: Transfer, and the
+/// CreateAccount, Allocate and Assign behind Anchor's `init`
+fn system_instruction(
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> std::result::Result<(), ProgramError> {
+    const CREATE_ACCOUNT: u32 = 0;
+    const ASSIGN: u32 = 1;
+    const TRANSFER: u32 = 2;
+    const ALLOCATE: u32 = 8;
+    // SystemError::AccountAlreadyInUse
+    const ACCOUNT_ALREADY_IN_USE: ProgramError = ProgramError::Custom(0);
+
+    let tag = data.get(..4).ok_or(ProgramError::InvalidInstructionData)?;
+    let tag = u32::from_le_bytes(tag.try_into().unwrap());
+    let u64_at = |offset: usize| -> std::result::Result<u64, ProgramError> {
+        let bytes = data.get(offset..offset + 8).ok_or(ProgramError::InvalidInstructionData)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    match tag {
+        TRANSFER => transfer_lamports(&accounts[0], &accounts[1], u64_at(4)?),
+        CREATE_ACCOUNT => {
+            let (from, to) = (&accounts[0], &accounts[1]);
+            let owner = data.get(20..52).ok_or(ProgramError::InvalidInstructionData)?;
+            if to.lamports() > 0 || *to.owner != system_program::ID {
+                return Err(ACCOUNT_ALREADY_IN_USE);
+            }
+            if !to.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            // In-memory accounts can't grow: tests hand in new accounts at their final size
+            if to.data_len() as u64 != u64_at(12)? {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            transfer_lamports(from, to, u64_at(4)?)?;
+            to.assign(&Pubkey::try_from(owner).unwrap());
+            Ok(())
+        }
+        // Anchor's init only allocates and assigns accounts that already hold
+        // lamports; in these tests those are always accounts created before
+        ASSIGN | ALLOCATE => Err(ACCOUNT_ALREADY_IN_USE),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn transfer_lamports(
+    from: &AccountInfo,
+    to: &AccountInfo,
+    lamports: u64,
+) -> std::result::Result<(), ProgramError> {
+    if !from.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let balance = from.lamports();
+    **from.try_borrow_mut_lamports()? = balance
+        .checked_sub(lamports)
+        .ok_or(ProgramError::InsufficientFunds)?;
+    **to.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
+
+/// Route the program's syscalls through the runtime above; safe to call from every test
+pub fn install_runtime() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        set_syscall_stubs(Box::new(Runtime));
+    });
+}
+
+/// An account's key preceded by its original data length, where
+/// AccountInfo::resize looks for it in the runtime's serialized input
+#[repr(C)]
+struct KeyWithDataLen {
+    original_data_len: u32,
+    key: Pubkey,
+}
+
+/// An account kept alive for the whole test run
+///
+/// Its data is laid out as the runtime serializes it: the current length
+/// just before the data and room to grow by MAX_PERMITTED_DATA_INCREASE
+/// after it, so handlers may resize or close the account.
+pub fn account(
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    is_signer: bool,
+    executable: bool,
+) -> AccountInfo<'static> {
+    let len = data.len();
+    let key = Box::leak(Box::new(KeyWithDataLen {
+        original_data_len: len as u32,
+        key,
+    }));
+    // Whole words keep the length prefix aligned
+    let words = (len + MAX_PERMITTED_DATA_INCREASE).div_ceil(8);
+    let buffer = Box::leak(vec![0u64; 1 + words].into_boxed_slice());
+    buffer[0] = len as u64;
+    let data_slice = unsafe {
+        let start = buffer.as_mut_ptr().add(1) as *mut u8;
+        std::slice::from_raw_parts_mut(start, len)
+    };
+    data_slice.copy_from_slice(&data);
+    AccountInfo::new(
+        &key.key,
+        is_signer,
+        !executable,
+        Box::leak(Box::new(lamports)),
+        data_slice,
+        Box::leak(Box::new(owner)),
+        executable,
+        0,
+    )
+}
+
+/// A rent-exempt account of `owner` holding `data`
+pub fn owned(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> AccountInfo<'static> {
+    let lamports = Rent::default().minimum_balance(data.len());
+    account(key, owner, lamports, data, false, false)
+}
+
+pub fn wallet(key: Pubkey) -> AccountInfo<'static> {
+    account(key, system_program::ID, WALLET_LAMPORTS, vec![], true, false)
+}
+
+pub fn program(key: Pubkey) -> AccountInfo<'static> {
+    account(key, Pubkey::default(), 1, vec![], false, true)
+}
+
+/// An account not created yet, sized for the `len` bytes its creation allocates
+pub fn uncreated(key: Pubkey, len: usize) -> AccountInfo<'static> {
+    account(key, system_program::ID, 0, vec![0; len], false, false)
+}
+
+/// System-owned vault holding just its rent floor, as initialization leaves it
+pub fn vault(key: Pubkey) -> AccountInfo<'static> {
+    let lamports = Rent::default().minimum_balance(0) + VAULT_RENT_BUFFER;
+    account(key, system_program::ID, lamports, vec![], false, false)
+}
+
+/// The required accounts followed by the program id in every optional slot,
+/// which is how a client leaves an optional account out
+pub fn with_optional_accounts_omitted(
+    required: &[AccountInfo<'static>],
+) -> &'static [AccountInfo<'static>] {
+    let mut accounts = required.to_vec();
+    accounts.resize(required.len() + 32, program(ID));
+    Box::leak(accounts.into_boxed_slice())
+}
+
+/// The required accounts, `skipped` optional ones left out, then `passed`
+/// in the optional slots that follow
+pub fn with_optional_accounts(
+    required: Vec<AccountInfo<'static>>,
+    skipped: usize,
+    passed: impl IntoIterator<Item = AccountInfo<'static>>,
+) -> Vec<AccountInfo<'static>> {
+    let mut accounts = required;
+    accounts.extend(std::iter::repeat_n(program(ID), skipped));
+    accounts.extend(passed);
+    accounts
+}
+
+/// Program account data starting from all-zero fields: every enum at its first
+/// variant, every option None and the sale phase list empty
+pub fn zeroed<T: AccountDeserialize + AccountSerialize + Discriminator>(
+    len: usize,
+    edit: impl FnOnce(&mut T),
+) -> Vec<u8> {
+    let mut state = T::try_deserialize_unchecked(&mut &vec![0u8; len][..]).unwrap();
+    edit(&mut state);
+    let mut data = Vec::with_capacity(len);
+    state.try_serialize(&mut data).unwrap();
+    data.resize(len, 0);
+    data
+}
+
+/// A linear curve of a fresh mint as initialize_bonding_curve leaves it
+pub fn curve_data(token_mint: Pubkey, token_program: Pubkey) -> Vec<u8> {
+    zeroed::<BondingCurve>(BondingCurve::LEN, |curve| {
+        curve.token_mint = token_mint;
+        curve.bump = pda::bonding_curve_address(&token_mint).1;
+        curve.token_program = token_program;
+        curve.initial_price = 100;
+        curve.slope = 1;
+        curve.decimals = 6;
+        curve.protocol_fee_bps = 100;
+        curve.graduation_threshold = u64::MAX;
+        curve.version = bonding_curve_program::CURVE_VERSION;
+    })
+}
+
+pub fn global_config_data() -> Vec<u8> {
+    zeroed::<GlobalConfig>(GlobalConfig::LEN, |config| {
+        config.bump = pda::global_config_address().1;
+    })
+}
+
+pub fn mint_data(authority: Pubkey, supply: u64) -> Vec<u8> {
+    let mut data = vec![0u8; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: COption::Some(authority),
+        supply,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    data
+}
+
+pub fn token_account_data(mint: Pubkey, owner: Pubkey, amount: u64) -> Vec<u8> {
+    let mut data = vec![0u8; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint,
+        owner,
+        amount,
+        delegate: COption::None,
+        state: spl_token::state::AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    data
+}
+
+/// A wSOL associated token account of `owner`, holding only its rent reserve
+pub fn wsol_account(owner: Pubkey) -> AccountInfo<'static> {
+    let mut data = vec![0u8; spl_token::state::Account::LEN];
+    let reserve = Rent::default().minimum_balance(data.len());
+    spl_token::state::Account {
+        mint: spl_token::native_mint::ID,
+        owner,
+        amount: 0,
+        delegate: COption::None,
+        state: spl_token::state::AccountState::Initialized,
+        is_native: COption::Some(reserve),
+        delegated_amount: 0,
+        close_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    let key = get_associated_token_address(&owner, &spl_token::native_mint::ID);
+    account(key, spl_token::ID, reserve, data, false, false)
+}
+
+/// A trading session of `owner` for the session key `authority`, holding
+/// `deposit` lamports above its rent
+pub fn session(
+    owner: Pubkey,
+    authority: Pubkey,
+    expiry_slot: u64,
+    max_sol_per_trade: u64,
+    deposit: u64,
+) -> AccountInfo<'static> {
+    let (key, bump) = pda::session_address(&owner, &authority);
+    let data = zeroed::<TradingSession>(TradingSession::LEN, |session| {
+        session.owner = owner;
+        session.authority = authority;
+        session.expiry_slot = expiry_slot;
+        session.max_sol_per_trade = max_sol_per_trade;
+        session.bump = bump;
+    });
+    let lamports = Rent::default().minimum_balance(data.len()) + deposit;
+    account(key, ID, lamports, data, false, false)
+}
+
+/// `user`'s nonce window as initialize_trade_nonces leaves it
+pub fn trade_nonces(user: Pubkey) -> AccountInfo<'static> {
+    let (key, bump) = pda::trade_nonces_address(&user);
+    let data = zeroed::<TradeNonces>(TradeNonces::LEN, |nonces| {
+        nonces.user = user;
+        nonces.bump = bump;
+    });
+    owned(key, ID, data)
+}
+
+/// Lamports a session holds above its rent
+pub fn session_deposit(session: &AccountInfo) -> u64 {
+    session.lamports() - Rent::default().minimum_balance(TradingSession::LEN)
+}
+
+/// Rent sysvar account with mainnet parameters, bincode-encoded
+pub fn rent_sysvar() -> AccountInfo<'static> {
+    let rent = Rent::default();
+    let mut data = rent.lamports_per_byte_year.to_le_bytes().to_vec();
+    data.extend_from_slice(&rent.exemption_threshold.to_le_bytes());
+    data.push(rent.burn_percent);
+    account(sysvar::rent::ID, sysvar::ID, 1, data, false, false)
+}
+
+/// Index of the seller's token account in sell_accounts
+pub const SELL_TOKEN_ACCOUNT: usize = 3;
+
+/// A live curve of a fresh SPL Token mint, with its vaults and the global config
+pub struct Market {
+    pub mint: Pubkey,
+    pub curve: AccountInfo<'static>,
+    pub token_mint: AccountInfo<'static>,
+    pub sol_vault: AccountInfo<'static>,
+    pub fee_vault: AccountInfo<'static>,
+    pub global_config: AccountInfo<'static>,
+}
+
+/// A wallet trading on a market, with its associated token account
+pub struct Trader {
+    pub wallet: AccountInfo<'static>,
+    pub token_account: AccountInfo<'static>,
+}
+
+impl Market {
+    pub fn new() -> Self {
+        install_runtime();
+        let mint = Pubkey::new_unique();
+        let curve = pda::bonding_curve_address(&mint).0;
+        Self {
+            mint,
+            curve: owned(curve, ID, curve_data(mint, spl_token::ID)),
+            token_mint: owned(mint, spl_token::ID, mint_data(curve, 0)),
+            sol_vault: vault(pda::sol_vault_address(&mint).0),
+            fee_vault: vault(pda::fee_vault_address(&mint).0),
+            global_config: owned(pda::global_config_address().0, ID, global_config_data()),
+        }
+    }
+
+    /// A funded wallet holding an empty token account of this mint
+    pub fn trader(&self) -> Trader {
+        let key = Pubkey::new_unique();
+        Trader {
+            wallet: wallet(key),
+            token_account: owned(
+                get_associated_token_address(&key, &self.mint),
+                spl_token::ID,
+                token_account_data(self.mint, key, 0),
+            ),
+        }
+    }
+
+    pub fn state(&self) -> BondingCurve {
+        BondingCurve::try_deserialize(&mut &self.curve.data.borrow()[..]).unwrap()
+    }
+
+    /// Edit the curve account in place
+    pub fn set_state(&self, edit: impl FnOnce(&mut BondingCurve)) {
+        let mut curve = self.state();
+        edit(&mut curve);
+        let mut data = Vec::new();
+        curve.try_serialize(&mut data).unwrap();
+        self.curve.data.borrow_mut()[..data.len()].copy_from_slice(&data);
+    }
+
+    /// Lamports in the SOL vault above its rent floor
+    pub fn vault_balance(&self) -> u64 {
+        self.sol_vault.lamports() - Rent::default().minimum_balance(0) - VAULT_RENT_BUFFER
+    }
+
+    pub fn buy_accounts(&self, trader: &Trader) -> Vec<AccountInfo<'static>> {
+        vec![
+            trader.wallet.clone(),
+            self.curve.clone(),
+            self.token_mint.clone(),
+            trader.token_account.clone(),
+            self.sol_vault.clone(),
+            self.fee_vault.clone(),
+            self.global_config.clone(),
+            program(spl_token::ID),
+            program(anchor_spl::associated_token::ID),
+            program(system_program::ID),
+            rent_sysvar(),
+        ]
+    }
+
+    pub fn sell_accounts(&self, trader: &Trader) -> Vec<AccountInfo<'static>> {
+        vec![
+            trader.wallet.clone(),
+            self.curve.clone(),
+            self.token_mint.clone(),
+            trader.token_account.clone(),
+            self.sol_vault.clone(),
+            self.fee_vault.clone(),
+            program(spl_token::ID),
+            program(system_program::ID),
+        ]
+    }
+
+    /// Sell accounts passing the wSOL settlement accounts
+    pub fn sell_as_wsol_accounts(
+        &self,
+        trader: &Trader,
+        wsol: &AccountInfo<'static>,
+    ) -> Vec<AccountInfo<'static>> {
+        const OPTIONAL_ACCOUNTS_BEFORE_WSOL: usize = 12;
+        with_optional_accounts(
+            self.sell_accounts(trader),
+            OPTIONAL_ACCOUNTS_BEFORE_WSOL,
+            [
+                owned(spl_token::native_mint::ID, spl_token::ID, mint_data(Pubkey::default(), 0)),
+                wsol.clone(),
+                program(spl_token::ID),
+                program(anchor_spl::associated_token::ID),
+            ],
+        )
+    }
+
+    /// Buy accounts of a session key trading for `session`, minting to
+    /// `recipient`'s token account
+    pub fn session_buy_accounts(
+        &self,
+        trader: &Trader,
+        session: &AccountInfo<'static>,
+        nonces: &AccountInfo<'static>,
+        recipient: &Trader,
+    ) -> Vec<AccountInfo<'static>> {
+        const OPTIONAL_ACCOUNTS_BEFORE_SESSION: usize = 13;
+        with_optional_accounts(
+            self.buy_accounts(trader),
+            OPTIONAL_ACCOUNTS_BEFORE_SESSION,
+            [session.clone(), recipient.token_account.clone(), program(ID), nonces.clone()],
+        )
+    }
+
+    /// Sell accounts of a session key trading for `session`, burning from
+    /// `holder`'s token account
+    pub fn session_sell_accounts(
+        &self,
+        trader: &Trader,
+        session: &AccountInfo<'static>,
+        nonces: &AccountInfo<'static>,
+        holder: &Trader,
+    ) -> Vec<AccountInfo<'static>> {
+        const OPTIONAL_ACCOUNTS_BEFORE_SESSION: usize = 16;
+        let mut accounts = self.sell_accounts(trader);
+        accounts[SELL_TOKEN_ACCOUNT] = holder.token_account.clone();
+        with_optional_accounts(
+            accounts,
+            OPTIONAL_ACCOUNTS_BEFORE_SESSION,
+            [session.clone(), nonces.clone()],
+        )
+    }
+
+    pub fn buy(
+        &self,
+        trader: &Trader,
+        sol_amount: u64,
+        min_tokens_out: Option<u64>,
+    ) -> std::result::Result<(), ProgramError> {
+        run(
+            &self.buy_accounts(trader),
+            instruction::BuyTokens {
+                sol_amount,
+                flags: 0,
+                max_entry_price: None,
+                min_tokens_out,
+                tolerance_bps: None,
+                nonce: None,
+            },
+        )
+    }
+
+    pub fn sell(
+        &self,
+        trader: &Trader,
+        token_amount: u64,
+        min_sol_out: Option<u64>,
+    ) -> std::result::Result<(), ProgramError> {
+        run(
+            &self.sell_accounts(trader),
+            instruction::SellTokens {
+                token_amount,
+                min_exit_price: None,
+                min_sol_out,
+                settle_as_wsol: None,
+                nonce: None,
+            },
+        )
+    }
+
+    /// Buy signed by `trader` as the session key of `session`, minting to
+    /// `recipient` and consuming `nonce` from `nonces`
+    pub fn session_buy(
+        &self,
+        trader: &Trader,
+        session: &AccountInfo<'static>,
+        nonces: &AccountInfo<'static>,
+        recipient: &Trader,
+        sol_amount: u64,
+        nonce: Option<u64>,
+    ) -> std::result::Result<(), ProgramError> {
+        run(
+            &self.session_buy_accounts(trader, session, nonces, recipient),
+            instruction::BuyTokens {
+                sol_amount,
+                flags: 0,
+                max_entry_price: None,
+                min_tokens_out: None,
+                tolerance_bps: None,
+                nonce,
+            },
+        )
+    }
+
+    /// Sell signed by `trader` as the session key of `session`, burning from
+    /// `holder` and consuming `nonce` from `nonces`
+    pub fn session_sell(
+        &self,
+        trader: &Trader,
+        session: &AccountInfo<'static>,
+        nonces: &AccountInfo<'static>,
+        holder: &Trader,
+        token_amount: u64,
+        nonce: Option<u64>,
+    ) -> std::result::Result<(), ProgramError> {
+        run(
+            &self.session_sell_accounts(trader, session, nonces, holder),
+            instruction::SellTokens {
+                token_amount,
+                min_exit_price: None,
+                min_sol_out: None,
+                settle_as_wsol: None,
+                nonce,
+            },
+        )
+    }
+
+    /// Sell with the proceeds paid into `wsol`, the trader's wSOL account
+    pub fn sell_as_wsol(
+        &self,
+        trader: &Trader,
+        wsol: &AccountInfo<'static>,
+        token_amount: u64,
+    ) -> std::result::Result<(), ProgramError> {
+        run(
+            &self.sell_as_wsol_accounts(trader, wsol),
+            instruction::SellTokens {
+                token_amount,
+                min_exit_price: None,
+                min_sol_out: None,
+                settle_as_wsol: Some(true),
+                nonce: None,
+            },
+        )
+    }
+}
+
+impl Trader {
+    pub fn tokens(&self) -> u64 {
+        spl_token::state::Account::unpack(&self.token_account.data.borrow())
+            .unwrap()
+            .amount
+    }
+
+    pub fn lamports(&self) -> u64 {
+        self.wallet.lamports()
+    }
+
+    /// Let `delegate` move up to `amount` of this trader's tokens
+    pub fn approve(&self, delegate: &Trader, amount: u64) -> std::result::Result<(), ProgramError> {
+        let ix = spl_token::instruction::approve(
+            &spl_token::ID,
+            self.token_account.key,
+            delegate.wallet.key,
+            self.wallet.key,
+            &[],
+            amount,
+        )?;
+        spl_token::processor::Processor::process(
+            &spl_token::ID,
+            &[self.token_account.clone(), delegate.wallet.clone(), self.wallet.clone()],
+            &ix.data,
+        )
+    }
+
+    /// Transfer `amount` of this trader's tokens to `to`, signed by `authority`
+    pub fn transfer_signed_by(
+        &self,
+        authority: &Trader,
+        to: &Trader,
+        amount: u64,
+    ) -> std::result::Result<(), ProgramError> {
+        let ix = spl_token::instruction::transfer(
+            &spl_token::ID,
+            self.token_account.key,
+            to.token_account.key,
+            authority.wallet.key,
+            &[],
+            amount,
+        )?;
+        spl_token::processor::Processor::process(
+            &spl_token::ID,
+            &[self.token_account.clone(), to.token_account.clone(), authority.wallet.clone()],
+            &ix.data,
+        )
+    }
+}
+
+/// Run one instruction of the program through its entrypoint
+pub fn run(
+    accounts: &[AccountInfo<'static>],
+    data: impl InstructionData,
+) -> std::result::Result<(), ProgramError> {
+    bonding_curve_program::entry(&ID, with_optional_accounts_omitted(accounts), &data.data())
+}
+
+/// The program error a handler failing with `error` returns
+pub fn program_error(error: impl Into<Error>) -> ProgramError {
+    error.into().into()
+}
@@ -0,0 +1,141 @@
+//! Sampling curve indexes and keeping their baskets up to date
+//!
+//! As in `trades`, a failed instruction is not rolled back, so failing
+//! cranks run before anything they would change. Updates pass their
+//! accounts exactly, since the basket is remaining accounts.
+
+mod common;
+
+use anchor_lang::prelude::{AccountInfo, ProgramError, Pubkey};
+use anchor_lang::{AccountDeserialize, InstructionData};
+use bonding_curve_program::{
+    instruction, layout, pda, BondingCurveError, CurveIndex, InsuranceFund, PriceObservation,
+    PriceObservations, ID,
+};
+use common::*;
+
+const TWAP: u64 = 5_000;
+
+/// An index holding `curves`, in order
+fn curve_index(curves: &[Pubkey]) -> AccountInfo<'static> {
+    let (key, bump) = pda::curve_index_address(0);
+    owned(
+        key,
+        ID,
+        zeroed::<CurveIndex>(CurveIndex::LEN, |index| {
+            index.curves[..curves.len()].copy_from_slice(curves);
+            index.len = curves.len() as u8;
+            index.bump = bump;
+        }),
+    )
+}
+
+fn index_state(index: &AccountInfo) -> CurveIndex {
+    CurveIndex::try_deserialize(&mut &index.data.borrow()[..]).unwrap()
+}
+
+/// Observations that have held the market's price at TWAP for a while
+fn price_observations(market: &Market) -> AccountInfo<'static> {
+    let (key, bump) = pda::price_observations_address(market.curve.key);
+    owned(
+        key,
+        ID,
+        zeroed::<PriceObservations>(PriceObservations::LEN, |history| {
+            history.bonding_curve = *market.curve.key;
+            history.observations[0] = PriceObservation { slot: SLOT - 100, price: TWAP, supply: 0 };
+            history.head = 1;
+            history.count = 1;
+            history.bump = bump;
+        }),
+    )
+}
+
+fn update(index: &AccountInfo<'static>, basket: &[AccountInfo<'static>]) -> Result<(), ProgramError> {
+    let mut accounts = vec![index.clone()];
+    accounts.extend_from_slice(basket);
+    bonding_curve_program::entry(
+        &ID,
+        Box::leak(accounts.into_boxed_slice()),
+        &instruction::UpdateCurveIndex {}.data(),
+    )
+}
+
+#[test]
+fn updates_sample_twaps_and_skip_closed_curves() {
+    let market = Market::new();
+    let observations = price_observations(&market);
+    // The spot price is far from the TWAP, as if pushed within the crank's transaction
+    market.set_state(|curve| curve.current_supply = 1_000_000_000_000);
+    assert_ne!(market.state().current_price().unwrap(), TWAP);
+
+    let closed = uncreated(Pubkey::new_unique(), 0);
+    let shrunk_curve = Market::new();
+    let shrunk = owned(
+        Pubkey::new_unique(),
+        ID,
+        shrunk_curve.curve.data.borrow()[..layout::bonding_curve::PREFIX_LEN].to_vec(),
+    );
+    let index = curve_index(&[*closed.key, *market.curve.key, *shrunk.key]);
+
+    // Every curve brings its observations along
+    assert_eq!(
+        update(&index, &[closed.clone(), program(ID), market.curve.clone(), observations.clone()]),
+        Err(program_error(BondingCurveError::IndexBasketMismatch))
+    );
+    assert_eq!(
+        update(
+            &index,
+            &[
+                closed.clone(),
+                program(ID),
+                market.curve.clone(),
+                program(ID),
+                shrunk.clone(),
+                program(ID),
+            ],
+        ),
+        Err(program_error(BondingCurveError::PriceObservationsRequired))
+    );
+
+    update(
+        &index,
+        &[closed.clone(), program(ID), market.curve.clone(), observations, shrunk, program(ID)],
+    )
+    .unwrap();
+    let state = index_state(&index);
+    assert_eq!((state.last_price, state.last_update_ts), (TWAP, UNIX_TIMESTAMP));
+}
+
+#[test]
+fn the_admin_removes_curves_from_the_basket() {
+    let authority = wallet(Pubkey::new_unique());
+    let (fund_key, fund_bump) = pda::insurance_fund_address();
+    let fund = owned(
+        fund_key,
+        ID,
+        zeroed::<InsuranceFund>(InsuranceFund::LEN, |fund| {
+            fund.authority = *authority.key;
+            fund.bump = fund_bump;
+        }),
+    );
+    let (first, gone, last) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let index = curve_index(&[first, gone, last]);
+    let remove = |signer: &AccountInfo<'static>, bonding_curve: Pubkey| {
+        run(
+            &[signer.clone(), fund.clone(), index.clone()],
+            instruction::RemoveIndexCurve { bonding_curve },
+        )
+    };
+
+    assert_eq!(
+        remove(&wallet(Pubkey::new_unique()), gone),
+        Err(program_error(BondingCurveError::Unauthorized))
+    );
+    remove(&authority, gone).unwrap();
+    let state = index_state(&index);
+    assert_eq!(state.curves[..state.len as usize], [first, last]);
+    assert_eq!(
+        remove(&authority, gone),
+        Err(program_error(BondingCurveError::CurveNotIndexed))
+    );
+}
@@ -0,0 +1,162 @@
+//! Pricing invariants every curve type must keep
+//!
+//! Rounding has to favour the curve: tokens bought for some lamports can
+//! never be sold back for more, and buying in pieces never beats one buy.
+
+use bonding_curve_program::{
+    calculate_fee, calculate_sol_for_curve, calculate_sol_for_curve_ceil, calculate_tokens_for_curve,
+    integer_sqrt, CurveParams, CurveType,
+};
+
+const SOL: u64 = 1_000_000_000;
+
+fn curves() -> [CurveParams; 5] {
+    let params = |curve_type, initial_price, slope, flat_threshold| CurveParams {
+        curve_type,
+        initial_price,
+        slope,
+        flat_threshold,
+        decimals: 6,
+    };
+    [
+        params(CurveType::Linear, 100, 1, 0),
+        params(CurveType::LinearThenFlat, 100, 1, 500_000_000_000),
+        params(CurveType::Exponential, 1_000, 1_000_000, 0),
+        params(CurveType::Sigmoid, 1_000_000, 100_000, 500_000_000_000),
+        params(CurveType::ConstantProduct, 28, 0, 1_073_000_000_000_000),
+    ]
+}
+
+/// Deterministic pseudo-random amounts in 1..=bound
+fn amounts(seed: u64, bound: u64) -> impl Iterator<Item = u64> {
+    let mut seed = seed;
+    std::iter::repeat_with(move || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        1 + (seed >> 16) % bound
+    })
+}
+
+#[test]
+fn tokens_bought_never_sell_back_for_more() {
+    for curve in curves() {
+        for (supply, sol) in amounts(1, 1_000_000_000_000).zip(amounts(2, 10 * SOL)).take(200) {
+            let tokens = calculate_tokens_for_curve(&curve, supply, sol).unwrap();
+            let back = calculate_sol_for_curve(&curve, tokens, supply).unwrap();
+            assert!(back <= sol, "{:?}: {sol} lamports at {supply} sell back for {back}", curve.curve_type);
+        }
+    }
+}
+
+#[test]
+fn round_trips_at_lamport_scale_prices_never_pay_out_more() {
+    // Prices of thousands of lamports per token and supplies in the hundreds
+    // of millions of 9-decimal tokens, where a u64 quadratic solver overflowed
+    let curve = CurveParams {
+        curve_type: CurveType::Linear,
+        initial_price: 1_000,
+        slope: 10,
+        flat_threshold: 0,
+        decimals: 9,
+    };
+    let mut reserves = 0u64;
+    let mut supply = 0u64;
+    for (sol, _) in amounts(6, 1_000 * SOL).zip(0..500) {
+        let tokens = calculate_tokens_for_curve(&curve, supply, sol).unwrap();
+        let back = calculate_sol_for_curve(&curve, tokens, supply).unwrap();
+        assert!(back <= sol, "{sol} lamports at {supply} sell back for {back}");
+
+        // Keep half of each buy so the supply climbs across rounds
+        let kept = tokens / 2;
+        let refund = calculate_sol_for_curve(&curve, tokens - kept, supply + kept).unwrap();
+        reserves += sol - refund;
+        supply += kept;
+        assert!(
+            reserves >= calculate_sol_for_curve(&curve, supply, 0).unwrap(),
+            "reserves {reserves} can't buy back the {supply} tokens out"
+        );
+    }
+}
+
+#[test]
+fn split_buys_never_beat_one_buy() {
+    for curve in curves() {
+        for sol in amounts(3, 10 * SOL).take(100) {
+            let whole = calculate_tokens_for_curve(&curve, 0, sol).unwrap();
+            let first = calculate_tokens_for_curve(&curve, 0, sol / 2).unwrap();
+            let second = calculate_tokens_for_curve(&curve, first, sol - sol / 2).unwrap();
+            assert!(first + second <= whole, "{:?}: split {sol} lamports", curve.curve_type);
+        }
+    }
+}
+
+#[test]
+fn later_buys_get_fewer_tokens() {
+    for curve in curves() {
+        let mut supply = 0;
+        let mut last = u64::MAX;
+        for _ in 0..20 {
+            let tokens = calculate_tokens_for_curve(&curve, supply, SOL).unwrap();
+            assert!(tokens <= last, "{:?}: price fell at {supply}", curve.curve_type);
+            (supply, last) = (supply + tokens, tokens);
+        }
+    }
+}
+
+#[test]
+fn overflowing_amounts_error_instead_of_wrapping() {
+    for curve in curves() {
+        assert!(calculate_sol_for_curve(&curve, u64::MAX, u64::MAX).is_err());
+    }
+    // The largest fee is the whole amount, without overflowing on the way
+    assert_eq!(calculate_fee(u64::MAX, 10_000).unwrap(), u64::MAX);
+}
+
+#[test]
+fn exact_buys_pay_the_cost_rounded_up() {
+    // A single base unit is worth a fraction of a lamport: sells get nothing
+    // for it, but minting it is never free
+    let linear = curves()[0];
+    assert_eq!(calculate_sol_for_curve(&linear, 1, 0).unwrap(), 0);
+    assert_eq!(calculate_sol_for_curve_ceil(&linear, 1, 0).unwrap(), 1);
+
+    for curve in curves() {
+        for (supply, tokens) in amounts(4, 1_000_000_000_000).zip(amounts(5, 1_000_000_000)).take(100) {
+            let value = calculate_sol_for_curve(&curve, tokens, supply).unwrap();
+            let cost = calculate_sol_for_curve_ceil(&curve, tokens, supply).unwrap();
+            assert!(
+                (value..=value + 2).contains(&cost),
+                "{:?}: {tokens} at {supply} cost {cost}, worth {value}",
+                curve.curve_type
+            );
+        }
+        assert_eq!(calculate_sol_for_curve_ceil(&curve, 0, 1_000).unwrap(), 0);
+    }
+}
+
+#[test]
+fn wide_products_divide_without_truncating() {
+    // Virtual reserves of 10^20 lamports and 10^19 tokens put k past 2^128,
+    // so the area under the curve needs the 256-bit product
+    let curve = CurveParams {
+        curve_type: CurveType::ConstantProduct,
+        initial_price: 10,
+        slope: 0,
+        flat_threshold: 10_000_000_000_000_000_000,
+        decimals: 0,
+    };
+    // k / (T - s) at s = T / 10 is 10^39 / (9 * 10^18), less the 10^20 at s = 0
+    let cost = calculate_sol_for_curve(&curve, 1_000_000_000_000_000_000, 0).unwrap();
+    assert_eq!(cost, 11_111_111_111_111_111_111);
+}
+
+#[test]
+fn square_roots_round_down_at_every_width() {
+    for root in [1u128, 2, 3, 1_000, 4_294_967_295, 4_294_967_296, 1 << 40, (1 << 64) - 1] {
+        let square = root * root;
+        assert_eq!(integer_sqrt(square), root);
+        assert_eq!(integer_sqrt(square - 1), root - 1);
+        assert_eq!(integer_sqrt(square + 1), root);
+    }
+    assert_eq!(integer_sqrt(0), 0);
+    assert_eq!(integer_sqrt(u128::MAX), u64::MAX as u128);
+}
@@ -0,0 +1,155 @@
+//! Curve trade stats counted on every trade path once a curve has them
+//!
+//! As in `trades`, a failed instruction is not rolled back, so the trades
+//! that leave the stats out run last, sells before buys.
+
+mod common;
+
+use anchor_lang::prelude::{AccountInfo, ProgramError};
+use anchor_lang::{system_program, AccountDeserialize};
+use bonding_curve_program::{
+    instruction, pda, BondingCurveError, CurveStats, PriceObservations, CURVE_VERSION, ID,
+};
+use common::*;
+
+const SOL: u64 = 1_000_000_000;
+
+/// Optional BuyTokens accounts ahead of curve_stats
+const BUY_OPTIONAL_ACCOUNTS_BEFORE_STATS: usize = 11;
+/// Optional SellTokens accounts ahead of curve_stats
+const SELL_OPTIONAL_ACCOUNTS_BEFORE_STATS: usize = 9;
+
+/// The market's curve with its trade stats created
+fn market_with_stats() -> (Market, AccountInfo<'static>) {
+    let market = Market::new();
+    let payer = market.trader();
+    let stats = uncreated(pda::curve_stats_address(market.curve.key).0, CurveStats::LEN);
+    run(
+        &[
+            payer.wallet.clone(),
+            market.curve.clone(),
+            stats.clone(),
+            program(system_program::ID),
+        ],
+        instruction::InitializeCurveStats {},
+    )
+    .unwrap();
+    (market, stats)
+}
+
+/// The not yet created buyer mark of `trader`
+fn buyer_mark(market: &Market, trader: &Trader) -> AccountInfo<'static> {
+    uncreated(pda::buyer_mark_address(market.curve.key, trader.wallet.key).0, 0)
+}
+
+fn buy(
+    market: &Market,
+    trader: &Trader,
+    stats: &AccountInfo<'static>,
+    mark: &AccountInfo<'static>,
+) -> Result<(), ProgramError> {
+    run(
+        &with_optional_accounts(
+            market.buy_accounts(trader),
+            BUY_OPTIONAL_ACCOUNTS_BEFORE_STATS,
+            [stats.clone(), program(ID), program(ID), program(ID), mark.clone()],
+        ),
+        instruction::BuyTokens {
+            sol_amount: SOL / 10,
+            flags: 0,
+            max_entry_price: None,
+            min_tokens_out: None,
+            tolerance_bps: None,
+            nonce: None,
+        },
+    )
+}
+
+fn sell(market: &Market, trader: &Trader, stats: &AccountInfo<'static>) -> Result<(), ProgramError> {
+    run(
+        &with_optional_accounts(
+            market.sell_accounts(trader),
+            SELL_OPTIONAL_ACCOUNTS_BEFORE_STATS,
+            [stats.clone()],
+        ),
+        instruction::SellTokens {
+            token_amount: trader.tokens(),
+            min_exit_price: None,
+            min_sol_out: None,
+            settle_as_wsol: None,
+            nonce: None,
+        },
+    )
+}
+
+fn recorded(stats: &AccountInfo) -> CurveStats {
+    CurveStats::try_deserialize(&mut &stats.data.borrow()[..]).unwrap()
+}
+
+#[test]
+fn buyers_are_counted_once_without_their_trade_state() {
+    let (market, stats) = market_with_stats();
+    let (alice, bob) = (market.trader(), market.trader());
+    let (alice_mark, bob_mark) = (buyer_mark(&market, &alice), buyer_mark(&market, &bob));
+
+    buy(&market, &alice, &stats, &alice_mark).unwrap();
+    assert_eq!(*alice_mark.owner, ID);
+    buy(&market, &alice, &stats, &alice_mark).unwrap();
+    buy(&market, &bob, &stats, &bob_mark).unwrap();
+    sell(&market, &bob, &stats).unwrap();
+
+    let counted = recorded(&stats);
+    assert_eq!((counted.buy_count, counted.sell_count), (3, 1));
+    assert_eq!(counted.unique_buyers, 2);
+    assert!(counted.volume_lamports > 0);
+}
+
+#[test]
+fn trades_must_count_once_a_curve_has_stats() {
+    let (market, stats) = market_with_stats();
+    let (trader, someone_else) = (market.trader(), market.trader());
+    buy(&market, &trader, &stats, &buyer_mark(&market, &trader)).unwrap();
+
+    assert_eq!(
+        market.sell(&trader, trader.tokens(), None),
+        Err(program_error(BondingCurveError::CurveStatsRequired))
+    );
+    assert_eq!(
+        market.buy(&trader, SOL / 10, None),
+        Err(program_error(BondingCurveError::CurveStatsRequired))
+    );
+    assert_eq!(
+        buy(&market, &trader, &stats, &buyer_mark(&market, &someone_else)),
+        Err(program_error(BondingCurveError::InvalidBuyerMark))
+    );
+    let counted = recorded(&stats);
+    assert_eq!((counted.buy_count, counted.sell_count), (1, 0));
+}
+
+#[test]
+fn migrating_a_curve_with_stats_starts_requiring_them() {
+    let (market, stats) = market_with_stats();
+    // As a version 3 curve left it, from before the requirement existed
+    market.set_state(|curve| {
+        curve.version = 3;
+        curve.stats_required = false;
+    });
+    let observations = pda::price_observations_address(market.curve.key).0;
+
+    run(
+        &[
+            market.trader().wallet,
+            market.curve.clone(),
+            uncreated(observations, PriceObservations::LEN),
+            stats.clone(),
+            program(system_program::ID),
+        ],
+        instruction::MigrateCurve {},
+    )
+    .unwrap();
+
+    let state = market.state();
+    assert_eq!(state.version, CURVE_VERSION);
+    assert!(state.stats_required);
+    assert!(!state.price_observations_required);
+}
@@ -0,0 +1,88 @@
+//! Handing a curve over to a new creator, and the global config to a new
+//! admin, in two steps
+
+mod common;
+
+use anchor_lang::prelude::{ProgramError, Pubkey};
+use anchor_lang::AccountDeserialize;
+use bonding_curve_program::{instruction, pda, BondingCurveError, GlobalConfig, ID};
+use common::*;
+
+fn transfer(market: &Market, creator: &Trader, new_creator: Pubkey) -> Result<(), ProgramError> {
+    run(
+        &[creator.wallet.clone(), market.curve.clone()],
+        instruction::TransferCurveAuthority { new_creator },
+    )
+}
+
+#[test]
+fn handovers_wait_for_claimed_fees_and_keep_the_inactivity_clock() {
+    let market = Market::new();
+    let (creator, successor) = (market.trader(), market.trader());
+    market.set_state(|curve| {
+        curve.creator = *creator.wallet.key;
+        curve.creator_fees_unclaimed = 1;
+    });
+
+    assert_eq!(
+        transfer(&market, &creator, *successor.wallet.key),
+        Err(program_error(BondingCurveError::FeesUnclaimed))
+    );
+    // Cancelling needs nothing claimed
+    transfer(&market, &creator, Pubkey::default()).unwrap();
+
+    market.set_state(|curve| curve.creator_fees_unclaimed = 0);
+    transfer(&market, &creator, *successor.wallet.key).unwrap();
+    // The creator's last action was a while before the handover completes
+    market.set_state(|curve| curve.creator_last_action_ts = UNIX_TIMESTAMP - 100);
+    run(
+        &[successor.wallet.clone(), market.curve.clone()],
+        instruction::AcceptCurveAuthority {},
+    )
+    .unwrap();
+
+    let state = market.state();
+    assert_eq!((state.creator, state.pending_creator), (*successor.wallet.key, Pubkey::default()));
+    assert_eq!(state.creator_last_action_ts, UNIX_TIMESTAMP - 100);
+}
+
+#[test]
+fn the_global_config_changes_admin_only_once_the_new_one_accepts() {
+    let market = Market::new();
+    let (admin, successor) = (market.trader(), market.trader());
+    let (key, bump) = pda::global_config_address();
+    let config = owned(
+        key,
+        ID,
+        zeroed::<GlobalConfig>(GlobalConfig::LEN, |config| {
+            config.admin = *admin.wallet.key;
+            config.bump = bump;
+        }),
+    );
+    let state = || GlobalConfig::try_deserialize(&mut &config.data.borrow()[..]).unwrap();
+    let accept = |signer: &Trader| {
+        run(
+            &[signer.wallet.clone(), config.clone()],
+            instruction::AcceptGlobalConfigAdmin {},
+        )
+    };
+
+    assert_eq!(
+        run(
+            &[successor.wallet.clone(), config.clone()],
+            instruction::TransferGlobalConfigAdmin { new_admin: *successor.wallet.key },
+        ),
+        Err(program_error(BondingCurveError::Unauthorized))
+    );
+    run(
+        &[admin.wallet.clone(), config.clone()],
+        instruction::TransferGlobalConfigAdmin { new_admin: *successor.wallet.key },
+    )
+    .unwrap();
+    assert_eq!(state().admin, *admin.wallet.key);
+    assert_eq!(accept(&admin), Err(program_error(BondingCurveError::Unauthorized)));
+
+    accept(&successor).unwrap();
+    let state = state();
+    assert_eq!((state.admin, state.pending_admin), (*successor.wallet.key, Pubkey::default()));
+}
@@ -0,0 +1,144 @@
+//! Scheduled launches going through the launch queue
+//!
+//! The test clock never moves, so launches are scheduled for the future and
+//! then moved into the past by rewriting the queue. As in `trades`, a failed
+//! instruction is not rolled back, so failing cranks run before anything
+//! they would change.
+
+mod common;
+
+use anchor_lang::prelude::{AccountInfo, ProgramError};
+use anchor_lang::{system_program, AccountDeserialize, AccountSerialize};
+use bonding_curve_program::{
+    instruction, pda, BondingCurveError, CurveStatus, LaunchQueue, ID, LAUNCH_BOND_LAMPORTS,
+    LAUNCH_EXPIRY_SECONDS,
+};
+use common::*;
+
+/// A fresh market and its creator
+fn market_with_creator() -> (Market, Trader) {
+    let market = Market::new();
+    let creator = market.trader();
+    market.set_state(|curve| curve.creator = *creator.wallet.key);
+    (market, creator)
+}
+
+fn launch_queue() -> AccountInfo<'static> {
+    let (key, bump) = pda::launch_queue_address();
+    owned(key, ID, zeroed::<LaunchQueue>(LaunchQueue::LEN, |queue| queue.bump = bump))
+}
+
+fn queue_state(queue: &AccountInfo) -> LaunchQueue {
+    LaunchQueue::try_deserialize(&mut &queue.data.borrow()[..]).unwrap()
+}
+
+/// Move every queued launch `seconds` earlier
+fn rewind(queue: &AccountInfo, seconds: i64) {
+    let mut state = queue_state(queue);
+    for entry in &mut state.entries[..state.len as usize] {
+        entry.launch_ts -= seconds;
+    }
+    let mut data = Vec::new();
+    state.try_serialize(&mut data).unwrap();
+    queue.data.borrow_mut()[..data.len()].copy_from_slice(&data);
+}
+
+fn schedule(market: &Market, creator: &Trader, queue: &AccountInfo<'static>) {
+    run(
+        &[
+            creator.wallet.clone(),
+            market.curve.clone(),
+            queue.clone(),
+            program(system_program::ID),
+        ],
+        instruction::ScheduleLaunch { launch_ts: UNIX_TIMESTAMP + 1 },
+    )
+    .unwrap();
+}
+
+fn activate(
+    market: &Market,
+    creator: &Trader,
+    queue: &AccountInfo<'static>,
+) -> Result<(), ProgramError> {
+    run(
+        &[market.curve.clone(), queue.clone(), creator.wallet.clone()],
+        instruction::ActivateNextLaunch {},
+    )
+}
+
+fn expire(
+    market: &Market,
+    cranker: &Trader,
+    queue: &AccountInfo<'static>,
+    creator: &Trader,
+) -> Result<(), ProgramError> {
+    run(
+        &[cranker.wallet.clone(), market.curve.clone(), queue.clone(), creator.wallet.clone()],
+        instruction::ExpireLaunch {},
+    )
+}
+
+#[test]
+fn activating_a_launch_refunds_its_bond() {
+    let (market, creator) = market_with_creator();
+    let queue = launch_queue();
+    let queue_rent = queue.lamports();
+
+    schedule(&market, &creator, &queue);
+    assert_eq!(creator.lamports(), WALLET_LAMPORTS - LAUNCH_BOND_LAMPORTS);
+    assert_eq!(queue.lamports(), queue_rent + LAUNCH_BOND_LAMPORTS);
+
+    assert_eq!(
+        activate(&market, &creator, &queue),
+        Err(program_error(BondingCurveError::LaunchNotDue))
+    );
+    rewind(&queue, 1);
+    // The bond can only go back to the curve's creator
+    assert_eq!(
+        activate(&market, &market.trader(), &queue),
+        Err(program_error(BondingCurveError::Unauthorized))
+    );
+    activate(&market, &creator, &queue).unwrap();
+
+    assert_eq!(market.state().status, CurveStatus::Active);
+    assert_eq!(queue_state(&queue).len, 0);
+    assert_eq!(creator.lamports(), WALLET_LAMPORTS);
+    assert_eq!(queue.lamports(), queue_rent);
+}
+
+#[test]
+fn launches_nobody_activates_expire_and_refund_the_bond() {
+    let (market, creator) = market_with_creator();
+    let (later, later_creator) = market_with_creator();
+    let cranker = market.trader();
+    let queue = launch_queue();
+    let queue_rent = queue.lamports();
+    schedule(&market, &creator, &queue);
+    schedule(&later, &later_creator, &queue);
+
+    rewind(&queue, LAUNCH_EXPIRY_SECONDS);
+    assert_eq!(
+        expire(&market, &cranker, &queue, &creator),
+        Err(program_error(BondingCurveError::LaunchNotExpired))
+    );
+    rewind(&queue, 1);
+    // The bond can only go back to the curve's creator, never to the cranker
+    assert_eq!(
+        expire(&market, &cranker, &queue, &cranker),
+        Err(program_error(BondingCurveError::Unauthorized))
+    );
+    expire(&market, &cranker, &queue, &creator).unwrap();
+
+    assert_eq!(market.state().status, CurveStatus::Active);
+    assert_eq!(cranker.lamports(), WALLET_LAMPORTS);
+    assert_eq!(creator.lamports(), WALLET_LAMPORTS);
+    // The other launch keeps its slot and its bond
+    let state = queue_state(&queue);
+    assert_eq!((state.len, state.entries[0].bonding_curve), (1, *later.curve.key));
+    assert_eq!(queue.lamports(), queue_rent + LAUNCH_BOND_LAMPORTS);
+    assert_eq!(
+        expire(&market, &cranker, &queue, &creator),
+        Err(program_error(BondingCurveError::LaunchNotScheduled))
+    );
+}
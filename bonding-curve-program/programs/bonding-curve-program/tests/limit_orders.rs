@@ -0,0 +1,155 @@
+//! Limit orders placed and filled through the program's entrypoint
+//!
+//! Only buy orders run here: a sell order's token escrow is created through
+//! the associated token program, which the runtime in `common` doesn't
+//! carry out. As in `trades`, a failed instruction is not rolled back, so
+//! every failing fill gets its own order.
+
+mod common;
+
+use anchor_lang::prelude::{AccountInfo, ProgramError};
+use anchor_lang::{system_program, AccountDeserialize};
+use anchor_spl::token::spl_token;
+use bonding_curve_program::{
+    instruction, pda, BondingCurveError, LimitOrder, OrderSide, UserTradeState, ID,
+};
+use common::*;
+
+const SOL: u64 = 1_000_000_000;
+
+/// Small enough a budget to stay within twice the starting price
+const BUDGET: u64 = 10_000;
+
+/// Place a buy order spending `amount` lamports once the spot price is at
+/// or below `trigger_price`
+fn place_buy(
+    market: &Market,
+    owner: &Trader,
+    order_id: u64,
+    trigger_price: u64,
+    amount: u64,
+) -> AccountInfo<'static> {
+    let key = pda::limit_order_address(market.curve.key, owner.wallet.key, order_id).0;
+    let limit_order = uncreated(key, LimitOrder::LEN);
+    run(
+        &[
+            owner.wallet.clone(),
+            market.curve.clone(),
+            limit_order.clone(),
+            market.token_mint.clone(),
+            owner.token_account.clone(),
+            program(ID),
+            program(spl_token::ID),
+            program(anchor_spl::associated_token::ID),
+            program(system_program::ID),
+        ],
+        instruction::PlaceLimitOrder {
+            order_id,
+            side: OrderSide::Buy,
+            trigger_price,
+            amount,
+        },
+    )
+    .unwrap();
+    limit_order
+}
+
+/// Fill `limit_order` of `owner`, passing `trade_state` if any
+fn execute(
+    market: &Market,
+    owner: &Trader,
+    limit_order: &AccountInfo<'static>,
+    trade_state: Option<&AccountInfo<'static>>,
+) -> Result<(), ProgramError> {
+    run(
+        &[
+            market.trader().wallet,
+            owner.wallet.clone(),
+            market.curve.clone(),
+            limit_order.clone(),
+            market.token_mint.clone(),
+            owner.token_account.clone(),
+            program(ID),
+            market.sol_vault.clone(),
+            market.fee_vault.clone(),
+            program(ID),
+            trade_state.cloned().unwrap_or_else(|| program(ID)),
+            program(ID),
+            program(spl_token::ID),
+            program(system_program::ID),
+        ],
+        instruction::ExecuteLimitOrder {},
+    )
+}
+
+/// The owner's trade state on the market's curve, as init_user_trade_state leaves it
+fn trade_state(market: &Market, owner: &Trader) -> AccountInfo<'static> {
+    let (key, bump) = pda::user_trade_state_address(market.curve.key, owner.wallet.key);
+    owned(
+        key,
+        ID,
+        zeroed::<UserTradeState>(UserTradeState::LEN, |state| {
+            state.bonding_curve = *market.curve.key;
+            state.user = *owner.wallet.key;
+            state.bump = bump;
+        }),
+    )
+}
+
+#[test]
+fn buy_fills_average_no_worse_than_the_trigger() {
+    let market = Market::new();
+    let owner = market.trader();
+    let spot = market.state().current_price().unwrap();
+
+    // The spot price is at the trigger, but filling walks the price above it
+    let at_spot = place_buy(&market, &owner, 0, spot, SOL / 10);
+    assert_eq!(
+        execute(&market, &owner, &at_spot, None),
+        Err(program_error(BondingCurveError::SlippageExceeded))
+    );
+
+    let with_room = place_buy(&market, &owner, 1, 2 * spot, BUDGET);
+    execute(&market, &owner, &with_room, None).unwrap();
+    let state = market.state();
+    assert_eq!(owner.tokens(), state.current_supply);
+    assert!(state.current_supply > 0);
+    assert!(state.floor_price() <= 2 * spot);
+}
+
+#[test]
+fn buy_fills_restart_the_owners_hold_period() {
+    let market = Market::new();
+    let owner = market.trader();
+    market.set_state(|curve| curve.min_hold_seconds = 60);
+    let spot = market.state().current_price().unwrap();
+    let state = trade_state(&market, &owner);
+
+    let order = place_buy(&market, &owner, 0, 2 * spot, BUDGET);
+    execute(&market, &owner, &order, Some(&state)).unwrap();
+    let recorded = UserTradeState::try_deserialize(&mut &state.data.borrow()[..]).unwrap();
+    assert_eq!(recorded.last_buy_ts, UNIX_TIMESTAMP);
+
+    // Without its trade state the hold period would go unrecorded
+    let order = place_buy(&market, &owner, 1, 2 * spot, BUDGET);
+    assert_eq!(
+        execute(&market, &owner, &order, None),
+        Err(program_error(BondingCurveError::InvalidTradeState))
+    );
+}
+
+#[test]
+fn strict_mode_covers_limit_order_fills() {
+    let market = Market::new();
+    let owner = market.trader();
+    let spot = market.state().current_price().unwrap();
+    let order = place_buy(&market, &owner, 0, 2 * spot, BUDGET);
+    market.set_state(|curve| curve.strict_mode = true);
+
+    // Fills are trades like any other: without the instructions sysvar they are refused
+    assert_eq!(
+        execute(&market, &owner, &order, None),
+        Err(program_error(BondingCurveError::BundleRejected))
+    );
+    assert_eq!(owner.tokens(), 0);
+}
@@ -0,0 +1,124 @@
+//! Buying on several curves at once with buy_tokens_multi
+//!
+//! Each leg passes the full BuyTokens account list, so legs are built
+//! exactly rather than padded by `run`. As in `trades`, a failed instruction
+//! is not rolled back, so failing buys are only checked for their error.
+
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::{AccountInfo, ProgramError};
+use anchor_lang::InstructionData;
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use bonding_curve_program::{instruction, BuyLeg, ID};
+use common::*;
+
+const SOL: u64 = 1_000_000_000;
+
+/// Optional BuyTokens accounts, each left out with the program id
+const BUY_OPTIONAL_ACCOUNTS: usize = 17;
+
+/// `trader`'s wallet with an empty token account of `market`'s mint
+fn trader_on(market: &Market, trader: &Trader) -> Trader {
+    let key = *trader.wallet.key;
+    Trader {
+        wallet: trader.wallet.clone(),
+        token_account: owned(
+            get_associated_token_address(&key, &market.mint),
+            spl_token::ID,
+            token_account_data(market.mint, key, 0),
+        ),
+    }
+}
+
+fn leg(sol_amount: u64) -> BuyLeg {
+    BuyLeg {
+        sol_amount,
+        flags: 0,
+        max_entry_price: None,
+        min_tokens_out: None,
+        tolerance_bps: None,
+        nonce: None,
+    }
+}
+
+/// One buy_tokens_multi by `buyer`, one leg per account group
+fn buy_multi(
+    buyer: &Trader,
+    groups: Vec<Vec<AccountInfo<'static>>>,
+    legs: Vec<BuyLeg>,
+) -> Result<(), ProgramError> {
+    let mut accounts = vec![buyer.wallet.clone()];
+    for group in groups {
+        accounts.extend(with_optional_accounts(group, BUY_OPTIONAL_ACCOUNTS, []));
+    }
+    bonding_curve_program::entry(
+        &ID,
+        Box::leak(accounts.into_boxed_slice()),
+        &instruction::BuyTokensMulti { legs }.data(),
+    )
+}
+
+#[test]
+fn each_leg_buys_on_its_own_curve() {
+    let (first, second) = (Market::new(), Market::new());
+    let buyer = first.trader();
+    let buyer_on_second = trader_on(&second, &buyer);
+
+    buy_multi(
+        &buyer,
+        vec![first.buy_accounts(&buyer), second.buy_accounts(&buyer_on_second)],
+        vec![leg(SOL / 10), leg(SOL / 5)],
+    )
+    .unwrap();
+
+    // The same as two separate buys
+    let (alone, alone_buyer) = (Market::new(), Market::new());
+    alone.buy(&alone.trader(), SOL / 10, None).unwrap();
+    alone_buyer.buy(&alone_buyer.trader(), SOL / 5, None).unwrap();
+    assert_eq!(buyer.tokens(), alone.state().current_supply);
+    assert_eq!(buyer_on_second.tokens(), alone_buyer.state().current_supply);
+    assert_eq!(first.vault_balance(), alone.vault_balance());
+    assert_eq!(second.vault_balance(), alone_buyer.vault_balance());
+    assert_eq!(buyer.lamports(), WALLET_LAMPORTS - SOL / 10 - SOL / 5);
+}
+
+#[test]
+fn a_later_leg_with_another_curves_vault_fails_the_buy() {
+    let (first, second) = (Market::new(), Market::new());
+    let buyer = first.trader();
+    let mut second_accounts = second.buy_accounts(&trader_on(&second, &buyer));
+    second_accounts[4] = first.sol_vault.clone();
+
+    assert_eq!(
+        buy_multi(
+            &buyer,
+            vec![first.buy_accounts(&buyer), second_accounts],
+            vec![leg(SOL / 10), leg(SOL / 10)],
+        ),
+        Err(program_error(ErrorCode::ConstraintSeeds))
+    );
+}
+
+#[test]
+fn legs_on_the_same_curve_price_after_each_other() {
+    let market = Market::new();
+    let buyer = market.trader();
+
+    buy_multi(
+        &buyer,
+        vec![market.buy_accounts(&buyer), market.buy_accounts(&buyer)],
+        vec![leg(SOL / 10), leg(SOL / 10)],
+    )
+    .unwrap();
+
+    // The second leg sees the first one's supply, as a second buy would
+    let sequential = Market::new();
+    let trader = sequential.trader();
+    sequential.buy(&trader, SOL / 10, None).unwrap();
+    sequential.buy(&trader, SOL / 10, None).unwrap();
+    assert_eq!(buyer.tokens(), trader.tokens());
+    assert_eq!(market.state().current_supply, sequential.state().current_supply);
+    assert_eq!(market.state().trade_index, 2);
+}
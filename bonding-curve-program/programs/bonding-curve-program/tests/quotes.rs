@@ -0,0 +1,44 @@
+//! Quoting buys against hypothetical curve parameters
+
+mod common;
+
+use anchor_lang::prelude::ProgramError;
+use anchor_lang::system_program;
+use bonding_curve_program::{instruction, BondingCurveError, CurveType};
+use common::*;
+
+fn quote(curve_type: CurveType, slope: u64, flat_threshold: u64) -> Result<(), ProgramError> {
+    run(
+        &[program(system_program::ID)],
+        instruction::QuoteWithParams {
+            curve_type,
+            price: 1_000_000,
+            slope,
+            flat_threshold,
+            supply: 0,
+            input: 1_000_000_000,
+            decimals: 6,
+        },
+    )
+}
+
+#[test]
+fn quotes_take_only_shapes_a_curve_could_be_created_with() {
+    // Exponential and sigmoid curves divide by their width
+    for curve_type in [CurveType::Exponential, CurveType::Sigmoid] {
+        assert_eq!(
+            quote(curve_type, 0, 500_000_000_000),
+            Err(program_error(BondingCurveError::InvalidSlope))
+        );
+    }
+    for curve_type in [CurveType::LinearThenFlat, CurveType::ConstantProduct] {
+        assert_eq!(
+            quote(curve_type, 1, 0),
+            Err(program_error(BondingCurveError::InvalidThreshold))
+        );
+    }
+
+    quote(CurveType::Sigmoid, 100_000, 500_000_000_000).unwrap();
+    // A linear curve ignores its threshold, as it does when created
+    quote(CurveType::Linear, 1, 500_000_000_000).unwrap();
+}
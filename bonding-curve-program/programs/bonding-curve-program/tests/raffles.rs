@@ -0,0 +1,142 @@
+//! Drawing launch raffles from the hash of a slot fixed after sales close
+//!
+//! The test clock never moves, so draws that need their slot behind them
+//! have it moved into the past by rewriting the curve. As in `trades`, a
+//! failed instruction is not rolled back, so failing cranks run before
+//! anything they would change.
+
+mod common;
+
+use anchor_lang::prelude::{AccountInfo, ProgramError};
+use anchor_lang::solana_program::sysvar;
+use bonding_curve_program::{
+    calculate_tokens_for_curve, instruction, BondingCurveError, CurveStatus, LaunchRaffle,
+    RAFFLE_DRAW_DELAY_SLOTS,
+};
+use common::*;
+
+const TICKETS: u32 = 5;
+
+/// A market whose raffle sold TICKETS tickets and closed at the current time
+fn closed_raffle() -> Market {
+    let market = Market::new();
+    market.set_state(|curve| {
+        curve.status = CurveStatus::Raffle;
+        curve.raffle = LaunchRaffle {
+            end_ts: UNIX_TIMESTAMP,
+            ticket_price: 1_000_000,
+            max_winners: 2,
+            ticket_count: TICKETS,
+            ..LaunchRaffle::default()
+        };
+    });
+    market
+}
+
+/// SlotHashes holding `entries` of (slot, first hash word), newest first
+fn slot_hashes(entries: &[(u64, u64)]) -> AccountInfo<'static> {
+    let mut data = (entries.len() as u64).to_le_bytes().to_vec();
+    for (slot, word) in entries {
+        data.extend_from_slice(&slot.to_le_bytes());
+        data.extend_from_slice(&word.to_le_bytes());
+        data.extend_from_slice(&[0; 24]);
+    }
+    account(sysvar::slot_hashes::ID, sysvar::ID, 1, data, false, false)
+}
+
+fn commit(market: &Market, hashes: &AccountInfo<'static>) -> Result<(), ProgramError> {
+    run(&[market.curve.clone(), hashes.clone()], instruction::CommitRaffleDraw {})
+}
+
+fn draw(market: &Market, hashes: &AccountInfo<'static>) -> Result<(), ProgramError> {
+    run(&[market.curve.clone(), hashes.clone()], instruction::DrawRaffle {})
+}
+
+#[test]
+fn draws_wait_for_a_slot_committed_after_sales_close() {
+    let market = closed_raffle();
+    let hashes = slot_hashes(&[(SLOT - 1, 7)]);
+
+    // Nothing to draw from until a slot is committed
+    assert_eq!(
+        draw(&market, &hashes),
+        Err(program_error(BondingCurveError::RaffleDrawNotReady))
+    );
+    commit(&market, &hashes).unwrap();
+    assert_eq!(market.state().raffle_draw_slot, SLOT + RAFFLE_DRAW_DELAY_SLOTS);
+
+    // Its hash doesn't exist yet, and the slot can't be swapped for another
+    assert_eq!(
+        draw(&market, &hashes),
+        Err(program_error(BondingCurveError::RaffleDrawNotReady))
+    );
+    assert_eq!(
+        commit(&market, &hashes),
+        Err(program_error(BondingCurveError::RaffleDrawAlreadyCommitted))
+    );
+    assert_eq!(market.state().status, CurveStatus::Raffle);
+}
+
+#[test]
+fn the_committed_slot_hash_picks_the_winners() {
+    let market = closed_raffle();
+    let hashes = slot_hashes(&[(SLOT - 1, 7), (SLOT - 5, 13)]);
+
+    // A skipped slot has no hash; only then may another be committed
+    market.set_state(|curve| curve.raffle_draw_slot = SLOT - 3);
+    assert_eq!(
+        draw(&market, &hashes),
+        Err(program_error(BondingCurveError::RaffleDrawSlotUnavailable))
+    );
+    commit(&market, &hashes).unwrap();
+    assert_eq!(market.state().raffle_draw_slot, SLOT + RAFFLE_DRAW_DELAY_SLOTS);
+
+    // Once the committed slot's hash is in, the draw uses it and no other
+    market.set_state(|curve| curve.raffle_draw_slot = SLOT - 5);
+    assert_eq!(
+        commit(&market, &hashes),
+        Err(program_error(BondingCurveError::RaffleDrawAlreadyCommitted))
+    );
+    draw(&market, &hashes).unwrap();
+
+    let state = market.state();
+    assert_eq!(state.status, CurveStatus::Active);
+    assert!(state.raffle.drawn);
+    assert_eq!(state.raffle.offset, 13 % TICKETS);
+    assert_eq!(state.raffle.winner_count, 2);
+}
+
+#[test]
+fn a_raffle_without_tickets_has_nothing_to_draw() {
+    let market = closed_raffle();
+    let hashes = slot_hashes(&[(SLOT - 1, 7)]);
+    market.set_state(|curve| {
+        curve.raffle.ticket_count = 0;
+        curve.raffle_draw_slot = SLOT - 1;
+    });
+
+    assert_eq!(
+        draw(&market, &hashes),
+        Err(program_error(BondingCurveError::RaffleNoTickets))
+    );
+    assert_eq!(market.state().status, CurveStatus::Raffle);
+}
+
+#[test]
+fn a_draw_buying_out_max_supply_sells_the_curve_out() {
+    let market = closed_raffle();
+    let hashes = slot_hashes(&[(SLOT - 1, 7)]);
+    let params = market.state().params();
+    let tokens = calculate_tokens_for_curve(&params, 0, 2 * 1_000_000).unwrap();
+    market.set_state(|curve| {
+        curve.max_supply = tokens / 2 * 2;
+        curve.raffle_draw_slot = SLOT - 1;
+    });
+
+    draw(&market, &hashes).unwrap();
+
+    let state = market.state();
+    assert_eq!(state.current_supply, state.max_supply);
+    assert_eq!(state.sol_reserves, 2 * 1_000_000);
+    assert!(state.sold_out);
+}
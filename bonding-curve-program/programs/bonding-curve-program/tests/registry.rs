@@ -0,0 +1,63 @@
+//! Backfilling curves into their creators' curve registries
+//!
+//! As in `trades`, a failed instruction is not rolled back, so the failing
+//! registration runs last.
+
+mod common;
+
+use anchor_lang::prelude::{AccountInfo, ProgramError};
+use anchor_lang::{system_program, AccountDeserialize};
+use bonding_curve_program::{
+    instruction, pda, BondingCurveError, CurveRegistry, CurveRegistryEntry,
+};
+use common::*;
+
+fn register(
+    market: &Market,
+    payer: &Trader,
+    registry: &AccountInfo<'static>,
+    entry: &AccountInfo<'static>,
+) -> Result<(), ProgramError> {
+    run(
+        &[
+            payer.wallet.clone(),
+            market.curve.clone(),
+            registry.clone(),
+            entry.clone(),
+            program(system_program::ID),
+        ],
+        instruction::RegisterCurve {},
+    )
+}
+
+#[test]
+fn curves_register_once_in_their_creators_registry() {
+    let market = Market::new();
+    let (creator, payer) = (market.trader(), market.trader());
+    market.set_state(|curve| curve.creator = *creator.wallet.key);
+    let creator_key = creator.wallet.key;
+    let registry = uncreated(pda::curve_registry_address(creator_key).0, CurveRegistry::LEN);
+    let first = uncreated(
+        pda::curve_registry_entry_address(creator_key, 0).0,
+        CurveRegistryEntry::LEN,
+    );
+
+    // Anyone can backfill the curve
+    register(&market, &payer, &registry, &first).unwrap();
+    let counted = CurveRegistry::try_deserialize(&mut &registry.data.borrow()[..]).unwrap();
+    assert_eq!((counted.creator, counted.curve_count), (*creator_key, 1));
+    let entry = CurveRegistryEntry::try_deserialize(&mut &first.data.borrow()[..]).unwrap();
+    assert_eq!((entry.index, entry.bonding_curve), (0, *market.curve.key));
+    assert_eq!((entry.creator, entry.created_ts), (*creator_key, UNIX_TIMESTAMP));
+    assert!(market.state().registered);
+
+    // But only once
+    let second = uncreated(
+        pda::curve_registry_entry_address(creator_key, 1).0,
+        CurveRegistryEntry::LEN,
+    );
+    assert_eq!(
+        register(&market, &payer, &registry, &second),
+        Err(program_error(BondingCurveError::CurveAlreadyRegistered))
+    );
+}
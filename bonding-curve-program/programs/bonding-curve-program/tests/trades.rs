@@ -0,0 +1,865 @@
+//! Buys and sells run end to end through the program's entrypoint
+//!
+//! Unlike the TypeScript suite these need no validator: the accounts live in
+//! memory and the CPIs a trade makes are carried out by the runtime in
+//! `common`. A failed instruction is not rolled back here, so tests only look
+//! at state after successful ones.
+
+mod common;
+
+use anchor_spl::token::spl_token;
+use anchor_spl::token::spl_token::solana_program::program_pack::Pack;
+use anchor_lang::prelude::{AccountInfo, ProgramError, Pubkey};
+use anchor_lang::{system_program, AccountDeserialize};
+use bonding_curve_program::{
+    calculate_sol_for_curve, instruction, pda, BlacklistEntry, BondingCurveError, DustPolicy,
+    ExitClaim, InsuranceFund, ReferralCode, SalePhase, SalePhaseKind, BUY_FLAG_ALLOW_PARTIAL, ID,
+    MAX_DUST_THRESHOLD_LAMPORTS,
+};
+use common::*;
+
+const SOL: u64 = 1_000_000_000;
+
+#[test]
+fn buy_mints_tokens_and_books_the_reserves() {
+    let market = Market::new();
+    let buyer = market.trader();
+
+    market.buy(&buyer, SOL / 10, None).unwrap();
+
+    let curve = market.state();
+    assert!(buyer.tokens() > 0);
+    assert_eq!(curve.current_supply, buyer.tokens());
+    assert!(curve.sol_reserves > 0);
+    assert_eq!(market.vault_balance(), curve.sol_reserves);
+    assert_eq!(buyer.lamports(), WALLET_LAMPORTS - SOL / 10);
+    assert_eq!(curve.trade_index, 1);
+}
+
+#[test]
+fn sell_burns_tokens_and_pays_the_seller() {
+    let market = Market::new();
+    let trader = market.trader();
+    market.buy(&trader, SOL / 10, None).unwrap();
+    let (tokens, lamports) = (trader.tokens(), trader.lamports());
+
+    market.sell(&trader, tokens / 2, None).unwrap();
+
+    let curve = market.state();
+    assert_eq!(trader.tokens(), tokens - tokens / 2);
+    assert_eq!(curve.current_supply, trader.tokens());
+    assert!(trader.lamports() > lamports);
+    assert_eq!(market.vault_balance(), curve.sol_reserves);
+    assert_eq!(curve.trade_index, 2);
+}
+
+#[test]
+fn sell_can_settle_in_wsol() {
+    let market = Market::new();
+    let trader = market.trader();
+    market.buy(&trader, SOL / 10, None).unwrap();
+    let lamports = trader.lamports();
+    let wsol = wsol_account(*trader.wallet.key);
+    let reserve = wsol.lamports();
+
+    market.sell_as_wsol(&trader, &wsol, trader.tokens()).unwrap();
+
+    // The proceeds are wrapped: lamports and token balance of the wSOL account agree
+    let wrapped = spl_token::state::Account::unpack(&wsol.data.borrow()).unwrap().amount;
+    assert!(wrapped > 0);
+    assert_eq!(wsol.lamports(), reserve + wrapped);
+    assert_eq!(trader.lamports(), lamports);
+    assert_eq!(market.vault_balance(), market.state().sol_reserves);
+}
+
+#[test]
+fn session_trades_spend_and_refill_the_deposit() {
+    let market = Market::new();
+    let (owner, session_key) = (market.trader(), market.trader());
+    let session = session(*owner.wallet.key, *session_key.wallet.key, SLOT, SOL, SOL);
+    let nonces = trade_nonces(*owner.wallet.key);
+
+    market.session_buy(&session_key, &session, &nonces, &owner, SOL / 10, Some(0)).unwrap();
+
+    // The buy is paid from the deposit and the tokens go to the owner, not the session key
+    assert!(owner.tokens() > 0);
+    assert_eq!(session_key.tokens(), 0);
+    assert_eq!(session_deposit(&session), SOL - SOL / 10);
+    assert_eq!(session_key.lamports(), WALLET_LAMPORTS);
+
+    owner.approve(&session_key, owner.tokens()).unwrap();
+    market
+        .session_sell(&session_key, &session, &nonces, &owner, owner.tokens(), Some(1))
+        .unwrap();
+
+    // And the sale, burning from the owner as delegate, pays back into it
+    assert_eq!(owner.tokens(), 0);
+    assert!(session_deposit(&session) > SOL - SOL / 10);
+    assert_eq!(session_key.lamports(), WALLET_LAMPORTS);
+    assert_eq!(market.vault_balance(), market.state().sol_reserves);
+}
+
+#[test]
+fn session_keys_cannot_move_the_owners_tokens() {
+    let market = Market::new();
+    let (owner, session_key) = (market.trader(), market.trader());
+    let session = session(*owner.wallet.key, *session_key.wallet.key, SLOT, SOL, SOL);
+    let nonces = trade_nonces(*owner.wallet.key);
+    market.session_buy(&session_key, &session, &nonces, &owner, SOL / 10, Some(0)).unwrap();
+    let bought = owner.tokens();
+
+    // Minting into the session key's own account is refused
+    assert_eq!(
+        market.session_buy(&session_key, &session, &nonces, &session_key, SOL / 10, Some(1)),
+        Err(program_error(BondingCurveError::InvalidSessionTokenAccount))
+    );
+    // Without the owner's approval it can neither sell nor transfer them
+    assert!(market
+        .session_sell(&session_key, &session, &nonces, &owner, bought, Some(2))
+        .is_err());
+    assert!(owner.transfer_signed_by(&session_key, &session_key, bought).is_err());
+
+    // An approval lets it sell, but still not take the tokens for itself
+    owner.approve(&session_key, bought).unwrap();
+    assert_eq!(
+        market.session_sell(&session_key, &session, &nonces, &session_key, bought, Some(3)),
+        Err(program_error(BondingCurveError::InvalidSessionTokenAccount))
+    );
+    assert_eq!(owner.tokens(), bought);
+    assert_eq!(session_key.tokens(), 0);
+}
+
+#[test]
+fn session_trades_consume_a_nonce_once() {
+    let market = Market::new();
+    let (owner, session_key) = (market.trader(), market.trader());
+    let session = session(*owner.wallet.key, *session_key.wallet.key, SLOT, SOL, SOL);
+    let nonces = trade_nonces(*owner.wallet.key);
+
+    // A relayed buy without a nonce is refused outright
+    assert_eq!(
+        market.session_buy(&session_key, &session, &nonces, &owner, SOL / 10, None),
+        Err(program_error(BondingCurveError::TradeNonceRequired))
+    );
+
+    market.session_buy(&session_key, &session, &nonces, &owner, SOL / 10, Some(7)).unwrap();
+    let bought = owner.tokens();
+
+    // Replaying the same request fails without buying again
+    assert_eq!(
+        market.session_buy(&session_key, &session, &nonces, &owner, SOL / 10, Some(7)),
+        Err(program_error(BondingCurveError::NonceAlreadyUsed))
+    );
+    assert_eq!(owner.tokens(), bought);
+
+    // Nor can a sale reuse it, or take another owner's window
+    owner.approve(&session_key, bought).unwrap();
+    assert_eq!(
+        market.session_sell(&session_key, &session, &nonces, &owner, bought, Some(7)),
+        Err(program_error(BondingCurveError::NonceAlreadyUsed))
+    );
+    let strangers = trade_nonces(Pubkey::new_unique());
+    assert_eq!(
+        market.session_sell(&session_key, &session, &strangers, &owner, bought, Some(8)),
+        Err(program_error(BondingCurveError::Unauthorized))
+    );
+    market.session_sell(&session_key, &session, &nonces, &owner, bought, Some(8)).unwrap();
+    assert_eq!(owner.tokens(), 0);
+}
+
+#[test]
+fn expired_sessions_cannot_trade() {
+    let market = Market::new();
+    let (owner, session_key) = (market.trader(), market.trader());
+    let session = session(*owner.wallet.key, *session_key.wallet.key, SLOT - 1, SOL, SOL);
+    let nonces = trade_nonces(*owner.wallet.key);
+    assert_eq!(
+        market.session_buy(&session_key, &session, &nonces, &owner, SOL / 10, Some(0)),
+        Err(program_error(BondingCurveError::SessionExpired))
+    );
+}
+
+#[test]
+fn sessions_cap_each_trade_and_their_deposit() {
+    let market = Market::new();
+    let (owner, session_key) = (market.trader(), market.trader());
+    let capped = session(*owner.wallet.key, *session_key.wallet.key, SLOT, SOL / 100, SOL);
+    let nonces = trade_nonces(*owner.wallet.key);
+    assert_eq!(
+        market.session_buy(&session_key, &capped, &nonces, &owner, SOL / 10, Some(0)),
+        Err(program_error(BondingCurveError::SessionTradeLimitExceeded))
+    );
+
+    let underfunded = session(*owner.wallet.key, *session_key.wallet.key, SLOT, SOL, SOL / 100);
+    assert_eq!(
+        market.session_buy(&session_key, &underfunded, &nonces, &owner, SOL / 10, Some(1)),
+        Err(program_error(BondingCurveError::InsufficientSessionDeposit))
+    );
+}
+
+#[test]
+fn paused_curves_refuse_buys_but_keep_sells_open() {
+    let market = Market::new();
+    let trader = market.trader();
+    market.buy(&trader, SOL / 10, None).unwrap();
+
+    market.set_state(|curve| curve.paused = true);
+    assert_eq!(
+        market.buy(&trader, SOL / 10, None),
+        Err(program_error(BondingCurveError::CurvePaused))
+    );
+    market.sell(&trader, trader.tokens(), None).unwrap();
+    assert_eq!(market.state().current_supply, 0);
+}
+
+#[test]
+fn creator_pauses_lapse_on_their_own() {
+    let market = Market::new();
+    let buyer = market.trader();
+    market.set_state(|curve| {
+        curve.paused = true;
+        curve.pause_expires_ts = UNIX_TIMESTAMP;
+    });
+
+    market.buy(&buyer, SOL / 10, None).unwrap();
+    assert!(buyer.tokens() > 0);
+}
+
+#[test]
+fn an_ended_schedule_stops_buys_but_not_sells() {
+    let market = Market::new();
+    let trader = market.trader();
+    market.buy(&trader, SOL / 10, None).unwrap();
+    market.set_state(|curve| curve.trading_end_ts = UNIX_TIMESTAMP);
+
+    assert_eq!(
+        market.buy(&trader, SOL / 10, None),
+        Err(program_error(BondingCurveError::TradingClosed))
+    );
+    market.sell(&trader, trader.tokens(), None).unwrap();
+    assert_eq!(trader.tokens(), 0);
+}
+
+/// Set the curve's schedule to `phases` of (kind, start relative to now, multiplier)
+fn schedule(market: &Market, phases: &[(SalePhaseKind, i64, u16)]) {
+    market.set_state(|curve| {
+        for (slot, &(kind, start, price_multiplier_bps)) in curve.sale_phases.iter_mut().zip(phases) {
+            *slot = SalePhase {
+                kind,
+                start_ts: UNIX_TIMESTAMP + start,
+                price_multiplier_bps,
+                ..SalePhase::default()
+            };
+        }
+        curve.phase_count = phases.len() as u8;
+    });
+}
+
+#[test]
+fn discounted_buys_sell_back_for_no_more_than_they_paid() {
+    let market = Market::new();
+    let trader = market.trader();
+    schedule(&market, &[(SalePhaseKind::Presale, -100, 5_000), (SalePhaseKind::Public, 100, 5_000)]);
+    market.state().check_phase_multipliers().unwrap();
+    market.buy(&trader, SOL / 10, None).unwrap();
+
+    // The public phase opens and the presale buyer sells out
+    schedule(&market, &[(SalePhaseKind::Presale, -200, 5_000), (SalePhaseKind::Public, -100, 5_000)]);
+    market.sell(&trader, trader.tokens(), None).unwrap();
+    assert!(trader.lamports() <= WALLET_LAMPORTS);
+    let curve = market.state();
+    assert_eq!(curve.current_supply, 0);
+    assert_eq!(market.vault_balance(), curve.sol_reserves);
+
+    // A public phase pricing above the presale, or above the curve, would pay out
+    // more than the presale put into reserves
+    for phases in [
+        [(SalePhaseKind::Presale, -200, 5_000), (SalePhaseKind::Public, -100, 10_000)],
+        [(SalePhaseKind::Public, -200, 20_000), (SalePhaseKind::Public, -100, 20_000)],
+    ] {
+        schedule(&market, &phases);
+        assert_eq!(
+            market.state().check_phase_multipliers(),
+            Err(BondingCurveError::InvalidPriceMultiplier.into())
+        );
+    }
+}
+
+#[test]
+fn the_price_impact_cap_binds_buys_only() {
+    let market = Market::new();
+    let trader = market.trader();
+    market.buy(&trader, SOL / 10, None).unwrap();
+    market.set_state(|curve| curve.max_price_impact_bps = 500);
+
+    market.sell(&trader, trader.tokens(), None).unwrap();
+    assert_eq!(market.state().current_supply, 0);
+    assert_eq!(
+        market.buy(&trader, SOL / 10, None),
+        Err(program_error(BondingCurveError::PriceImpactExceeded))
+    );
+}
+
+#[test]
+fn raise_targets_are_set_once_before_launch_and_close_buys() {
+    let market = Market::new();
+    let creator = market.trader();
+    market.set_state(|curve| curve.creator = *creator.wallet.key);
+    let set_target = |market: &Market, raise_target_lamports| {
+        run(
+            &[creator.wallet.clone(), market.curve.clone()],
+            instruction::SetRaiseTarget { raise_target_lamports },
+        )
+    };
+
+    set_target(&market, SOL / 20).unwrap();
+    assert_eq!(
+        set_target(&market, SOL),
+        Err(program_error(BondingCurveError::RaiseTargetAlreadySet))
+    );
+    // The buy that reaches the target still fills
+    market.buy(&creator, SOL / 10, None).unwrap();
+    assert!(market.state().raise_target_reached);
+    assert_eq!(
+        market.buy(&creator, SOL / 10, None),
+        Err(program_error(BondingCurveError::RaiseTargetReached))
+    );
+
+    let launched = Market::new();
+    launched.set_state(|curve| curve.creator = *creator.wallet.key);
+    launched.buy(&launched.trader(), SOL / 10, None).unwrap();
+    assert_eq!(
+        set_target(&launched, SOL),
+        Err(program_error(BondingCurveError::CurveAlreadyLaunched))
+    );
+}
+
+#[test]
+fn curves_with_price_history_refuse_trades_that_skip_it() {
+    let market = Market::new();
+    let trader = market.trader();
+    market.buy(&trader, SOL / 10, None).unwrap();
+    market.set_state(|curve| curve.price_observations_required = true);
+
+    // The failed sell is not rolled back here, so the buy goes second
+    let skipped = Err(program_error(BondingCurveError::PriceObservationsRequired));
+    assert_eq!(market.sell(&trader, trader.tokens(), None), skipped);
+    assert_eq!(market.buy(&trader, SOL / 10, None), skipped);
+}
+
+/// `trader`'s blacklist address on `market`, holding an entry if `listed`
+fn blacklist_entry(market: &Market, trader: &Trader, listed: bool) -> AccountInfo<'static> {
+    let (key, bump) = pda::blacklist_address(market.curve.key, trader.wallet.key);
+    if !listed {
+        return uncreated(key, 0);
+    }
+    owned(
+        key,
+        ID,
+        zeroed::<BlacklistEntry>(BlacklistEntry::LEN, |entry| {
+            entry.bonding_curve = *market.curve.key;
+            entry.holder = *trader.wallet.key;
+            entry.bump = bump;
+        }),
+    )
+}
+
+fn sell_with_blacklist_entry(
+    market: &Market,
+    trader: &Trader,
+    entry: &AccountInfo<'static>,
+) -> Result<(), ProgramError> {
+    const OPTIONAL_ACCOUNTS_BEFORE_BLACKLIST: usize = 11;
+    run(
+        &with_optional_accounts(
+            market.sell_accounts(trader),
+            OPTIONAL_ACCOUNTS_BEFORE_BLACKLIST,
+            [entry.clone()],
+        ),
+        instruction::SellTokens {
+            token_amount: trader.tokens(),
+            min_exit_price: None,
+            min_sol_out: None,
+            settle_as_wsol: None,
+            nonce: None,
+        },
+    )
+}
+
+/// Queue the sale of all of `trader`'s tokens, passing `entry` as their
+/// blacklist address; returns the exit claim
+fn queue_sell(
+    market: &Market,
+    trader: &Trader,
+    entry: &AccountInfo<'static>,
+) -> Result<AccountInfo<'static>, ProgramError> {
+    let key = pda::exit_claim_address(market.curve.key, market.state().exit_queue_tail).0;
+    let exit_claim = uncreated(key, ExitClaim::LEN);
+    run(
+        &[
+            trader.wallet.clone(),
+            market.curve.clone(),
+            exit_claim.clone(),
+            market.token_mint.clone(),
+            trader.token_account.clone(),
+            program(spl_token::ID),
+            program(system_program::ID),
+            program(ID),
+            program(ID),
+            entry.clone(),
+        ],
+        instruction::QueueSell {
+            token_amount: trader.tokens(),
+            min_sol_out: None,
+        },
+    )?;
+    Ok(exit_claim)
+}
+
+#[test]
+fn blacklisted_holders_cannot_sell_on_any_path() {
+    let market = Market::new();
+    let (listed, unlisted) = (market.trader(), market.trader());
+    market.buy(&listed, SOL / 10, None).unwrap();
+    market.buy(&unlisted, SOL / 10, None).unwrap();
+    market.set_state(|curve| curve.blacklisted_holders = 1);
+    let blacklisted = Err(program_error(BondingCurveError::Blacklisted));
+
+    // Direct sells need the blacklist address, and it must be empty
+    assert_eq!(market.sell(&listed, listed.tokens(), None), blacklisted);
+    let entry = blacklist_entry(&market, &listed, true);
+    assert_eq!(sell_with_blacklist_entry(&market, &listed, &entry), blacklisted);
+
+    // With the reserves short, sells go through the exit queue, which checks the same
+    market.set_state(|curve| curve.sol_reserves = 0);
+    assert_eq!(queue_sell(&market, &listed, &entry).map(|_| ()), blacklisted);
+    queue_sell(&market, &unlisted, &blacklist_entry(&market, &unlisted, false)).unwrap();
+    assert_eq!(unlisted.tokens(), 0);
+    assert_eq!(market.state().exit_queue_len(), 1);
+}
+
+#[test]
+fn strict_mode_covers_the_exit_queue() {
+    let market = Market::new();
+    let seller = market.trader();
+    market.buy(&seller, SOL / 10, None).unwrap();
+    market.set_state(|curve| {
+        curve.sol_reserves = 0;
+        curve.strict_mode = true;
+    });
+
+    // Without the instructions sysvar the queued sale can't be checked, so it is refused
+    assert_eq!(
+        queue_sell(&market, &seller, &blacklist_entry(&market, &seller, false)).unwrap_err(),
+        program_error(BondingCurveError::BundleRejected)
+    );
+    assert_eq!(market.state().exit_queue_len(), 0);
+}
+
+#[test]
+fn dust_exits_are_refused_or_swept_into_the_fees() {
+    let market = Market::new();
+    let (refused, swept) = (market.trader(), market.trader());
+    market.buy(&refused, SOL / 10_000, None).unwrap();
+    market.buy(&swept, SOL / 10_000, None).unwrap();
+    market.set_state(|curve| {
+        curve.sol_reserves = 0;
+        curve.dust_threshold = MAX_DUST_THRESHOLD_LAMPORTS;
+    });
+
+    // Queued exits go by the same dust policy as direct sells
+    assert_eq!(
+        queue_sell(&market, &refused, &blacklist_entry(&market, &refused, false)).unwrap_err(),
+        program_error(BondingCurveError::DustTrade)
+    );
+    market.set_state(|curve| curve.dust_policy = DustPolicy::SweepToFees);
+    let claim = queue_sell(&market, &swept, &blacklist_entry(&market, &swept, false)).unwrap();
+    let claim = ExitClaim::try_deserialize(&mut &claim.data.borrow()[..]).unwrap();
+    assert!(claim.sol_amount > 0);
+    assert_eq!(claim.fee_lamports, claim.sol_amount);
+}
+
+#[test]
+fn emergency_withdrawals_leave_queued_exits_what_they_are_owed() {
+    let market = Market::new();
+    let (queued, holder) = (market.trader(), market.trader());
+    market.buy(&queued, SOL / 10, None).unwrap();
+    market.buy(&holder, SOL / 10, None).unwrap();
+    market.set_state(|curve| curve.sol_reserves = 0);
+    let claim = queue_sell(&market, &queued, &blacklist_entry(&market, &queued, false)).unwrap();
+    let owed = market.state().exit_queue_lamports;
+
+    let authority = wallet(Pubkey::new_unique());
+    let (fund_key, fund_bump) = pda::insurance_fund_address();
+    let fund = owned(
+        fund_key,
+        ID,
+        zeroed::<InsuranceFund>(InsuranceFund::LEN, |fund| {
+            fund.authority = *authority.key;
+            fund.bump = fund_bump;
+        }),
+    );
+    let redemption_vault = uncreated(pda::redemption_vault_address(&market.mint).0, 0);
+    market.set_state(|curve| {
+        curve.paused = true;
+        curve.emergency_withdraw_ts = UNIX_TIMESTAMP;
+    });
+    run(
+        &[
+            authority,
+            fund,
+            market.curve.clone(),
+            market.sol_vault.clone(),
+            redemption_vault.clone(),
+            program(system_program::ID),
+        ],
+        instruction::EmergencyWithdraw {},
+    )
+    .unwrap();
+    assert_eq!(market.state().sol_reserves, owed);
+    assert_eq!(market.vault_balance(), owed);
+    assert_eq!(market.state().redemption_supply, holder.tokens());
+
+    run(
+        &[
+            market.curve.clone(),
+            claim,
+            queued.wallet.clone(),
+            market.sol_vault.clone(),
+            market.fee_vault.clone(),
+            program(system_program::ID),
+        ],
+        instruction::SettleExitClaim {},
+    )
+    .unwrap();
+    let curve = market.state();
+    assert_eq!((curve.exit_queue_len(), curve.sol_reserves), (0, 0));
+}
+
+/// Expire `market` as a simulation of a fresh creator, returned
+fn expire_simulation(market: &Market) -> Trader {
+    let creator = market.trader();
+    market.set_state(|curve| {
+        curve.creator = *creator.wallet.key;
+        curve.simulation_expiry_ts = UNIX_TIMESTAMP;
+    });
+    creator
+}
+
+fn close_simulation(market: &Market, creator: &Trader) -> Result<(), ProgramError> {
+    run(
+        &[
+            market.trader().wallet,
+            market.curve.clone(),
+            creator.wallet.clone(),
+            market.sol_vault.clone(),
+            program(system_program::ID),
+        ],
+        instruction::CloseSimulation {},
+    )
+}
+
+/// Refund `holder`'s tokens, signed by `authority`
+fn claim_simulation_refund(
+    market: &Market,
+    authority: &Trader,
+    holder: &Trader,
+) -> Result<(), ProgramError> {
+    run(
+        &[
+            authority.wallet.clone(),
+            market.curve.clone(),
+            market.token_mint.clone(),
+            holder.token_account.clone(),
+            holder.wallet.clone(),
+            market.sol_vault.clone(),
+            program(spl_token::ID),
+            program(system_program::ID),
+        ],
+        instruction::ClaimSimulationRefund {},
+    )
+}
+
+#[test]
+fn closing_a_simulation_refunds_holders_pro_rata() {
+    let market = Market::new();
+    let (alice, bob, relayer) = (market.trader(), market.trader(), market.trader());
+    market.buy(&alice, SOL / 10, None).unwrap();
+    market.buy(&bob, SOL / 20, None).unwrap();
+    let creator = expire_simulation(&market);
+    let (refundable, supply) = (market.vault_balance(), market.state().current_supply);
+    let (alice_before, bob_before) = (alice.lamports(), bob.lamports());
+    let share = |tokens: u64| (refundable as u128 * tokens as u128 / supply as u128) as u64;
+    let (alice_share, bob_share) = (share(alice.tokens()), share(bob.tokens()));
+
+    assert_eq!(
+        claim_simulation_refund(&market, &alice, &alice),
+        Err(program_error(BondingCurveError::SimulationRefundsNotOpen))
+    );
+    close_simulation(&market, &creator).unwrap();
+    let state = market.state();
+    assert!(state.paused);
+    assert_eq!((state.redemption_lamports, state.redemption_supply), (refundable, supply));
+
+    // Each holder claims in a transaction of their own, or a delegate for them
+    claim_simulation_refund(&market, &alice, &alice).unwrap();
+    assert_eq!(
+        close_simulation(&market, &creator),
+        Err(program_error(BondingCurveError::SimulationRefundsOutstanding))
+    );
+    bob.approve(&relayer, bob.tokens()).unwrap();
+    claim_simulation_refund(&market, &relayer, &bob).unwrap();
+    assert_eq!(alice.lamports() - alice_before, alice_share);
+    assert_eq!(bob.lamports() - bob_before, bob_share);
+    assert_eq!((alice.tokens(), bob.tokens()), (0, 0));
+
+    // With every token refunded, the vault's remainder and the curve go to the creator
+    let vault_lamports = market.sol_vault.lamports();
+    let creator_before = creator.lamports() + market.curve.lamports();
+    close_simulation(&market, &creator).unwrap();
+    assert_eq!(market.sol_vault.lamports(), 0);
+    assert_eq!(market.curve.lamports(), 0);
+    assert_eq!(creator.lamports(), creator_before + vault_lamports);
+}
+
+#[test]
+fn buy_below_min_tokens_out_fails() {
+    let market = Market::new();
+    let quote = market.trader();
+    market.buy(&quote, SOL / 10, None).unwrap();
+
+    // The same buy on a fresh curve, asking for one token more than it gives
+    let market = Market::new();
+    let buyer = market.trader();
+    assert_eq!(
+        market.buy(&buyer, SOL / 10, Some(quote.tokens() + 1)),
+        Err(program_error(BondingCurveError::SlippageExceeded))
+    );
+}
+
+#[test]
+fn buy_at_exactly_min_tokens_out_succeeds() {
+    let market = Market::new();
+    let quote = market.trader();
+    market.buy(&quote, SOL / 10, None).unwrap();
+
+    let market = Market::new();
+    let buyer = market.trader();
+    market.buy(&buyer, SOL / 10, Some(quote.tokens())).unwrap();
+    assert_eq!(buyer.tokens(), quote.tokens());
+}
+
+#[test]
+fn sell_below_min_sol_out_fails() {
+    let market = Market::new();
+    let trader = market.trader();
+    market.buy(&trader, SOL / 10, None).unwrap();
+    let tokens = trader.tokens();
+
+    assert_eq!(
+        market.sell(&trader, tokens, Some(SOL / 10)),
+        Err(program_error(BondingCurveError::SlippageExceeded))
+    );
+}
+
+#[test]
+fn zero_amounts_are_rejected() {
+    let market = Market::new();
+    let trader = market.trader();
+    assert_eq!(
+        market.buy(&trader, 0, None),
+        Err(program_error(BondingCurveError::InvalidAmount))
+    );
+    assert_eq!(
+        market.sell(&trader, 0, None),
+        Err(program_error(BondingCurveError::InvalidAmount))
+    );
+}
+
+#[test]
+fn oversized_amounts_fail_without_panicking() {
+    let market = Market::new();
+    let trader = market.trader();
+    market.buy(&trader, SOL / 10, None).unwrap();
+
+    assert!(market.buy(&trader, u64::MAX, None).is_err());
+    assert!(market.sell(&trader, u64::MAX, None).is_err());
+    assert!(market.sell(&trader, market.state().current_supply + 1, None).is_err());
+}
+
+#[test]
+fn round_trips_never_drain_the_reserves() {
+    let market = Market::new();
+    let traders = [market.trader(), market.trader(), market.trader()];
+
+    // Small deterministic LCG so failures are reproducible
+    let mut seed = 1293u64;
+    let mut next = |bound: u64| {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 16) % bound
+    };
+
+    for _ in 0..60 {
+        let trader = &traders[next(3) as usize];
+        if trader.tokens() == 0 || next(2) == 0 {
+            market.buy(trader, 1_000_000 + next(SOL), None).unwrap();
+        } else {
+            market.sell(trader, 1 + next(trader.tokens()), None).unwrap();
+        }
+
+        // The vault can always buy back every outstanding token at the curve price
+        let curve = market.state();
+        let owed = calculate_sol_for_curve(&curve.params(), curve.current_supply, 0).unwrap();
+        assert!(curve.sol_reserves >= owed, "reserves {} < owed {owed}", curve.sol_reserves);
+        assert_eq!(market.vault_balance(), curve.sol_reserves);
+    }
+
+    // And everyone can actually sell out
+    for trader in &traders {
+        if trader.tokens() > 0 {
+            market.sell(trader, trader.tokens(), None).unwrap();
+        }
+    }
+    assert_eq!(market.state().current_supply, 0);
+}
+
+fn buy_exact(
+    market: &Market,
+    trader: &Trader,
+    token_amount: u64,
+    flags: u8,
+) -> std::result::Result<(), ProgramError> {
+    run(
+        &market.buy_accounts(trader),
+        instruction::BuyExactTokens {
+            token_amount,
+            max_sol_in: SOL,
+            flags,
+        },
+    )
+}
+
+#[test]
+fn buys_past_max_supply_fill_the_rest_only_when_allowed() {
+    let market = Market::new();
+    let trader = market.trader();
+    market.set_state(|curve| curve.max_supply = 5_000_000);
+
+    // The failing attempts come first: a failed instruction is not rolled back
+    let exceeded = Err(program_error(BondingCurveError::MaxSupplyExceeded));
+    assert_eq!(buy_exact(&market, &trader, 6_000_000, 0), exceeded);
+    assert_eq!(market.buy(&trader, SOL, None), exceeded);
+    assert_eq!(market.state().current_supply, 0);
+
+    buy_exact(&market, &trader, 6_000_000, BUY_FLAG_ALLOW_PARTIAL).unwrap();
+    let state = market.state();
+    assert_eq!((state.current_supply, trader.tokens()), (5_000_000, 5_000_000));
+    assert!(state.sold_out);
+}
+
+#[test]
+fn buys_for_a_recipient_credit_the_referral() {
+    const OPTIONAL_ACCOUNTS_BEFORE_REFERRAL: usize = 8;
+    let market = Market::new();
+    let (payer, recipient, referrer) = (market.trader(), market.trader(), market.trader());
+    let nft_mint = Pubkey::new_unique();
+    let referral = owned(
+        Pubkey::new_unique(),
+        ID,
+        zeroed::<ReferralCode>(ReferralCode::LEN, |code| code.nft_mint = nft_mint),
+    );
+    let nft_account = owned(
+        Pubkey::new_unique(),
+        spl_token::ID,
+        token_account_data(nft_mint, *referrer.wallet.key, 1),
+    );
+
+    run(
+        &with_optional_accounts(
+            vec![
+                payer.wallet.clone(),
+                recipient.wallet.clone(),
+                market.curve.clone(),
+                market.token_mint.clone(),
+                recipient.token_account.clone(),
+                market.sol_vault.clone(),
+                market.fee_vault.clone(),
+                program(spl_token::ID),
+                program(anchor_spl::associated_token::ID),
+                program(system_program::ID),
+            ],
+            OPTIONAL_ACCOUNTS_BEFORE_REFERRAL,
+            [referral.clone(), nft_account, market.global_config.clone()],
+        ),
+        instruction::BuyExactTokensFor {
+            recipient: *recipient.wallet.key,
+            token_amount: 1_000_000,
+            max_sol_cost: SOL,
+            flags: 0,
+        },
+    )
+    .unwrap();
+
+    // The recipient holds the tokens and the referral is credited with what the payer spent
+    assert_eq!((recipient.tokens(), payer.tokens()), (1_000_000, 0));
+    let code = ReferralCode::try_deserialize(&mut &referral.data.borrow()[..]).unwrap();
+    assert_eq!(code.referral_count, 1);
+    assert_eq!(code.referred_volume, WALLET_LAMPORTS - payer.lamports());
+}
+
+fn burn(market: &Market, trader: &Trader, amount: u64) -> std::result::Result<(), ProgramError> {
+    run(
+        &[
+            trader.wallet.clone(),
+            market.curve.clone(),
+            market.token_mint.clone(),
+            trader.token_account.clone(),
+            program(spl_token::ID),
+        ],
+        instruction::BurnTokens { amount },
+    )
+}
+
+#[test]
+fn burns_raise_the_floor_without_cheapening_the_curve() {
+    let market = Market::new();
+    let (burner, buyer) = (market.trader(), market.trader());
+    market.buy(&burner, SOL / 10, None).unwrap();
+    let before = market.state();
+
+    burn(&market, &burner, burner.tokens() / 2).unwrap();
+    let after = market.state();
+    assert_eq!(after.circulating_supply(), burner.tokens());
+    assert_eq!(after.current_price().unwrap(), before.current_price().unwrap());
+    assert!(after.floor_price() > before.floor_price());
+
+    // The next buyer pays for the stretch above the burned tokens, not for
+    // the cheap one they used to occupy
+    let cost = calculate_sol_for_curve(&after.params(), 1_000_000, after.current_supply).unwrap();
+    let reserves = after.sol_reserves;
+    market.buy(&buyer, cost, None).unwrap();
+    assert!(buyer.tokens() <= 1_000_000);
+    let paid = calculate_sol_for_curve(&after.params(), buyer.tokens(), after.current_supply).unwrap();
+    assert!(market.state().sol_reserves - reserves >= paid);
+
+    // Once everyone has sold, the burned tokens' SOL is still in the reserves
+    market.sell(&burner, burner.tokens(), None).unwrap();
+    market.sell(&buyer, buyer.tokens(), None).unwrap();
+    let state = market.state();
+    assert_eq!(state.circulating_supply(), 0);
+    let owed = calculate_sol_for_curve(&state.params(), state.current_supply, 0).unwrap();
+    assert!(state.sol_reserves >= owed);
+}
+
+#[test]
+fn burns_stop_while_the_curve_is_paused() {
+    let market = Market::new();
+    let trader = market.trader();
+    market.buy(&trader, SOL / 10, None).unwrap();
+    let held = trader.tokens();
+
+    market.set_state(|curve| curve.paused = true);
+    assert_eq!(burn(&market, &trader, held), Err(program_error(BondingCurveError::CurvePaused)));
+    assert_eq!(trader.tokens(), held);
+    assert_eq!(market.state().burned_supply, 0);
+}
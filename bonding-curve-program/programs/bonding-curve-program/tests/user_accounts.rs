@@ -0,0 +1,101 @@
+//! Sweeping a wallet's empty auxiliary accounts with close_user_accounts
+
+mod common;
+
+use anchor_lang::prelude::{AccountInfo, ProgramError, Pubkey, Rent};
+use anchor_lang::system_program;
+use bonding_curve_program::{
+    instruction, pda, BondingCurveError, HolderRewards, LimitOrder, OrderSide, ID,
+};
+use common::*;
+
+/// Optional CloseUserAccounts accounts ahead of holder_rewards
+const OPTIONAL_ACCOUNTS_BEFORE_HOLDER_REWARDS: usize = 2;
+
+fn holder_rewards(market: &Market, holder: &Trader, staked: u64) -> AccountInfo<'static> {
+    let (key, bump) = pda::holder_rewards_address(market.curve.key, holder.wallet.key);
+    owned(
+        key,
+        ID,
+        zeroed::<HolderRewards>(HolderRewards::LEN, |rewards| {
+            rewards.bonding_curve = *market.curve.key;
+            rewards.holder = *holder.wallet.key;
+            rewards.staked = staked;
+            rewards.bump = bump;
+        }),
+    )
+}
+
+fn limit_order(market: &Market, owner: &Trader, side: OrderSide, escrow: u64) -> AccountInfo<'static> {
+    let (key, bump) = pda::limit_order_address(market.curve.key, owner.wallet.key, 0);
+    let data = zeroed::<LimitOrder>(LimitOrder::LEN, |order| {
+        order.bonding_curve = *market.curve.key;
+        order.owner = *owner.wallet.key;
+        order.side = side;
+        order.amount = escrow;
+        order.bump = bump;
+    });
+    let lamports = Rent::default().minimum_balance(data.len()) + escrow;
+    account(key, ID, lamports, data, false, false)
+}
+
+/// Close whichever of `passed` are empty; they fill the optional slots from
+/// holder_rewards on
+fn close(
+    market: &Market,
+    user: &Trader,
+    passed: impl IntoIterator<Item = AccountInfo<'static>>,
+) -> Result<(), ProgramError> {
+    run(
+        &with_optional_accounts(
+            vec![user.wallet.clone(), market.curve.clone()],
+            OPTIONAL_ACCOUNTS_BEFORE_HOLDER_REWARDS,
+            passed,
+        ),
+        instruction::CloseUserAccounts {},
+    )
+}
+
+fn is_closed(account: &AccountInfo) -> bool {
+    account.lamports() == 0 && *account.owner == system_program::ID
+}
+
+#[test]
+fn empty_accounts_close_and_the_rest_stay() {
+    let market = Market::new();
+    let user = market.trader();
+    let staked = holder_rewards(&market, &user, 1);
+    let sell_order = limit_order(&market, &user, OrderSide::Sell, 5);
+    let live_session = session(*user.wallet.key, Pubkey::new_unique(), SLOT, 1, 0);
+
+    // Nothing here is empty
+    assert_eq!(
+        close(
+            &market,
+            &user,
+            [staked.clone(), program(ID), program(ID), sell_order.clone(), live_session.clone()],
+        ),
+        Err(program_error(BondingCurveError::NoEmptyAccounts))
+    );
+    for account in [&staked, &sell_order, &live_session] {
+        assert!(!is_closed(account));
+    }
+
+    let unstaked = holder_rewards(&market, &user, 0);
+    let buy_order = limit_order(&market, &user, OrderSide::Buy, 1_000);
+    let expired_session = session(*user.wallet.key, Pubkey::new_unique(), SLOT - 1, 1, 2_000);
+    let reclaimed = unstaked.lamports() + buy_order.lamports() + expired_session.lamports();
+    let before = user.wallet.lamports();
+
+    close(
+        &market,
+        &user,
+        [unstaked.clone(), program(ID), program(ID), buy_order.clone(), expired_session.clone()],
+    )
+    .unwrap();
+    for account in [&unstaked, &buy_order, &expired_session] {
+        assert!(is_closed(account));
+    }
+    // The buy order's escrow and the session's deposit come back with the rent
+    assert_eq!(user.wallet.lamports(), before + reclaimed);
+}
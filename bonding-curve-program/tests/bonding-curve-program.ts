@@ -2,6 +2,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { BondingCurveProgram } from "../target/types/bonding_curve_program";
 import { Keypair } from "@solana/web3.js";
+import { ensureGlobalConfig, nextRegistryEntry } from "./global-config";
 
 describe("bonding-curve-program", () => {
   // Configure the client to use the local cluster.
@@ -12,12 +13,29 @@ describe("bonding-curve-program", () => {
 
   it("Is initialized!", async () => {
     // Add your test here.
+    const feeRecipient = await ensureGlobalConfig(program);
+    const registryEntry = await nextRegistryEntry(program, program.provider.publicKey);
     const tx = await program.methods
       .initializeBondingCurve(
         new anchor.BN(100), // initial price
         new anchor.BN(1),   // slope
         "TestCoin",        // name
-        "TEST"            // symbol
+        "TEST",           // symbol
+        "",               // metadata URI
+        { linear: {} },   // curve type
+        {
+          flatThreshold: null,             // unused for linear curves
+          salePhases: [],                  // none = always public
+          graduationMetric: { solRaised: {} },
+          graduationThreshold: null,       // null = default SOL target
+          salt: null,                      // null = plain mint-derived curve address
+          maxSupply: null,                 // null = uncapped
+          dust: null,                      // null = no sell payout floor
+          decimals: null,                  // null = 0
+          initialBuyLamports: null,        // null = no dev buy
+          quoteMint: null,                 // null = SOL
+          sellTaxRamp: null,               // null = no ramp
+        }
       )
       .accounts({
         creator: program.provider.publicKey,
@@ -30,9 +48,11 @@ describe("bonding-curve-program", () => {
           [Buffer.from("sol_vault"), tokenMint.publicKey.toBuffer()],
           program.programId
         ))[0],
+        feeRecipient,
         tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
         systemProgram: anchor.web3.SystemProgram.programId,
         rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        registryEntry,
       } as any)
       .signers([tokenMint])
       .rpc();
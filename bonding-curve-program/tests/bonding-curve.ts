@@ -4,6 +4,19 @@ import { BondingCurveProgram } from "../target/types/bonding_curve_program";
 import { PublicKey, Keypair, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
 import { TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID, getAssociatedTokenAddress } from "@solana/spl-token";
 import { expect } from "chai";
+import * as fs from "fs";
+import * as path from "path";
+import { ensureGlobalConfig, nextRegistryEntry } from "./global-config";
+
+/**
+ * Compute units measured for the buy/sell hot paths, keyed by instruction.
+ * The first run against a local validator records any missing entry; commit
+ * the file, and delete an entry to re-record it after a deliberate change.
+ */
+const CU_BASELINE_PATH = path.join(__dirname, "compute-units.json");
+
+/** Headroom over the measured baseline before the compute budget test fails */
+const CU_HEADROOM = 1.2;
 
 /**
  * Comprehensive test suite for the Bonding Curve SPL Token Program
@@ -35,6 +48,7 @@ describe("Bonding Curve SPL Token Program", () => {
   let solVaultPda: PublicKey;
   let bondingCurveBump: number;
   let solVaultBump: number;
+  let feeRecipient: PublicKey;
 
   // Token parameters for testing
   const TOKEN_NAME = "TestCoin";
@@ -63,6 +77,9 @@ describe("Bonding Curve SPL Token Program", () => {
       program.programId
     );
 
+    // Curve creation is validated against the protocol's global config
+    feeRecipient = await ensureGlobalConfig(program);
+
     // Check balance and fund only if needed
     const MINIMUM_BALANCE_NEEDED = 0.05 * LAMPORTS_PER_SOL; // 0.05 SOL minimum
     const AIRDROP_AMOUNT = 2 * LAMPORTS_PER_SOL; // Request 2 SOL when needed
@@ -101,21 +118,39 @@ describe("Bonding Curve SPL Token Program", () => {
     console.log("\n🧪 Test 1: Initializing bonding curve...");
 
     try {
+      const registryEntry = await nextRegistryEntry(program, creator.publicKey);
       const tx = await program.methods
         .initializeBondingCurve(
           INITIAL_PRICE,
           SLOPE,
           TOKEN_NAME,
-          TOKEN_SYMBOL
+          TOKEN_SYMBOL,
+          "",
+          { linear: {} },
+          {
+            flatThreshold: null,
+            salePhases: [],
+            graduationMetric: { solRaised: {} },
+            graduationThreshold: null,
+            salt: null,
+            maxSupply: null,
+            dust: null,
+            decimals: null,
+            initialBuyLamports: null,
+            quoteMint: null,
+            sellTaxRamp: null,
+          }
         )
         .accounts({
           creator: creator.publicKey,
           tokenMint: tokenMint.publicKey,
           bondingCurve: bondingCurvePda,
           solVault: solVaultPda,
+          feeRecipient,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
           rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          registryEntry,
         } as any)
         .signers([creator, tokenMint])
         .rpc();
@@ -171,7 +206,7 @@ describe("Bonding Curve SPL Token Program", () => {
 
       // Execute buy_tokens instruction
       const tx = await program.methods
-        .buyTokens(solAmountToPay)
+        .buyTokens(solAmountToPay, 0, null, null, null, null)
         .accounts({
           buyer: user1.publicKey,
           bondingCurve: bondingCurvePda,
@@ -237,7 +272,7 @@ describe("Bonding Curve SPL Token Program", () => {
 
       // Execute second purchase
       const tx = await program.methods
-        .buyTokens(solAmountToPay)
+        .buyTokens(solAmountToPay, 0, null, null, null, null)
         .accounts({
           buyer: user2.publicKey,
           bondingCurve: bondingCurvePda,
@@ -303,7 +338,7 @@ describe("Bonding Curve SPL Token Program", () => {
 
       // Execute sell_tokens instruction
       const tx = await program.methods
-        .sellTokens(new anchor.BN(tokenAmountToSell))
+        .sellTokens(new anchor.BN(tokenAmountToSell), null, null, null, null)
         .accounts({
           seller: user1.publicKey,
           bondingCurve: bondingCurvePda,
@@ -383,7 +418,7 @@ describe("Bonding Curve SPL Token Program", () => {
         );
 
         await program.methods
-          .buyTokens(new anchor.BN(0))
+          .buyTokens(new anchor.BN(0), 0, null, null, null, null)
           .accounts({
             buyer: user1.publicKey,
             bondingCurve: bondingCurvePda,
@@ -414,7 +449,7 @@ describe("Bonding Curve SPL Token Program", () => {
         );
 
         await program.methods
-          .sellTokens(new anchor.BN(0))
+          .sellTokens(new anchor.BN(0), null, null, null, null)
           .accounts({
             seller: user1.publicKey,
             bondingCurve: bondingCurvePda,
@@ -439,6 +474,77 @@ describe("Bonding Curve SPL Token Program", () => {
     }
   });
 
+  it("Keeps a plain buy and sell within the compute budget", async () => {
+    console.log("\n🧪 Test 7: Measuring compute units...");
+
+    const user2TokenAccount = await getAssociatedTokenAddress(
+      tokenMint.publicKey,
+      user2.publicKey
+    );
+    const computeUnits = async (signature: string) => {
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      return tx!.meta!.computeUnitsConsumed!;
+    };
+
+    const buyTx = await program.methods
+      .buyTokens(new anchor.BN(100_000), 0, null, null, null, null)
+      .accounts({
+        buyer: user2.publicKey,
+        bondingCurve: bondingCurvePda,
+        tokenMint: tokenMint.publicKey,
+        buyerTokenAccount: user2TokenAccount,
+        solVault: solVaultPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      } as any)
+      .signers([user2])
+      .rpc({ commitment: "confirmed" });
+    const buyUnits = await computeUnits(buyTx);
+
+    const balance = await provider.connection.getTokenAccountBalance(user2TokenAccount);
+    const sellTx = await program.methods
+      .sellTokens(new anchor.BN(balance.value.amount), null, null, null, null)
+      .accounts({
+        seller: user2.publicKey,
+        bondingCurve: bondingCurvePda,
+        tokenMint: tokenMint.publicKey,
+        sellerTokenAccount: user2TokenAccount,
+        solVault: solVaultPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      } as any)
+      .signers([user2])
+      .rpc({ commitment: "confirmed" });
+    const sellUnits = await computeUnits(sellTx);
+
+    console.log(`⛽ Buy: ${buyUnits} CU, sell: ${sellUnits} CU`);
+
+    // Regression guard: limits are the measured baseline plus headroom, so
+    // they only move when the baseline is deliberately re-recorded
+    const baseline: Record<string, number> = fs.existsSync(CU_BASELINE_PATH)
+      ? JSON.parse(fs.readFileSync(CU_BASELINE_PATH, "utf8"))
+      : {};
+    let recorded = false;
+    for (const [name, units] of Object.entries({ buyTokens: buyUnits, sellTokens: sellUnits })) {
+      if (baseline[name] === undefined) {
+        baseline[name] = units;
+        recorded = true;
+        console.log(`   📝 Recorded ${name} baseline: ${units} CU`);
+        continue;
+      }
+      const limit = Math.ceil(baseline[name] * CU_HEADROOM);
+      expect(units, `${name} compute units`).to.be.at.most(limit);
+    }
+    if (recorded) {
+      fs.writeFileSync(CU_BASELINE_PATH, JSON.stringify(baseline, null, 2) + "\n");
+    }
+  });
+
   after(async () => {
     console.log("\n📊 TEST SUMMARY");
     console.log("═".repeat(50));
@@ -0,0 +1,74 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { BondingCurveProgram } from "../target/types/bonding_curve_program";
+import { PublicKey } from "@solana/web3.js";
+
+const U64_MAX = new anchor.BN("18446744073709551615");
+const BPF_LOADER_UPGRADEABLE_ID = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+
+/**
+ * Creates the program's singleton GlobalConfig on first use, with the
+ * provider wallet (the upgrade authority of a local deploy) as admin and
+ * fee recipient and permissive curve ranges.
+ * Returns the fee recipient initialize_bonding_curve must be passed.
+ */
+export async function ensureGlobalConfig(program: Program<BondingCurveProgram>): Promise<PublicKey> {
+  const [globalConfigPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("global_config")],
+    program.programId
+  );
+
+  const existing = await program.account.globalConfig.fetchNullable(globalConfigPda);
+  if (existing) {
+    return existing.feeRecipient;
+  }
+
+  const admin = program.provider.publicKey!;
+  const [programData] = PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    BPF_LOADER_UPGRADEABLE_ID
+  );
+
+  await program.methods
+    .initializeGlobalConfig({
+      feeRecipient: admin,
+      creationFeeLamports: new anchor.BN(0),
+      protocolFeeBps: 100,
+      referralFeeBps: 0,
+      maxNameLen: 32,
+      maxSymbolLen: 10,
+      minInitialPrice: new anchor.BN(1),
+      maxInitialPrice: U64_MAX,
+      minSlope: new anchor.BN(1),
+      maxSlope: U64_MAX,
+      minGraduationThreshold: new anchor.BN(1_000_000_000),
+      defaultGraduationThreshold: new anchor.BN(85_000_000_000),
+    })
+    .accounts({ payer: admin, programData } as any)
+    .rpc();
+
+  return admin;
+}
+
+/**
+ * Returns the registry entry the creator's next initialize_bonding_curve
+ * must be passed; their CurveRegistry is created with their first curve.
+ */
+export async function nextRegistryEntry(
+  program: Program<BondingCurveProgram>,
+  creator: PublicKey
+): Promise<PublicKey> {
+  const [curveRegistryPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("curve_registry"), creator.toBuffer()],
+    program.programId
+  );
+
+  const registry = await program.account.curveRegistry.fetchNullable(curveRegistryPda);
+  const curveCount = registry ? registry.curveCount : new anchor.BN(0);
+
+  const [registryEntryPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("curve_registry_entry"), creator.toBuffer(), curveCount.toArrayLike(Buffer, "le", 8)],
+    program.programId
+  );
+  return registryEntryPda;
+}